@@ -0,0 +1,373 @@
+//! ## Cli
+//!
+//! `cli` contains the definition of the Pyc command line options, shared by the options parser and by the completion scripts generator
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+extern crate ansi_term;
+extern crate getopts;
+
+use crate::config::Config;
+use crate::translator::lang::LANGUAGE_CODES;
+
+use ansi_term::Colour;
+use getopts::{Matches, Options};
+use std::path::PathBuf;
+
+/// ### OptionArg
+///
+/// OptionArg describes the argument taken by a command line option
+#[derive(Copy, Clone, PartialEq, std::fmt::Debug)]
+pub enum OptionArg {
+    None,                            //Option is a flag
+    Text,                            //Free text
+    File,                            //Path to a file
+    Command,                         //Executable
+    Values(&'static [&'static str]), //One of the provided values
+}
+
+/// ### CliOption
+///
+/// CliOption describes a command line option
+pub struct CliOption {
+    pub short: &'static str, //Empty if option has no short name
+    pub long: &'static str,
+    pub desc: &'static str,
+    pub hint: &'static str,
+    pub arg: OptionArg,
+    pub multi: bool, //Option can be repeated
+}
+
+/// ### CompletionShell
+///
+/// CompletionShell represents the shells a completion script can be generated for
+#[derive(Copy, Clone, PartialEq, std::fmt::Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Name of the Pyc executable
+const PROGRAM: &str = "pyc";
+
+/// Pyc command line options
+pub const CLI_OPTIONS: [CliOption; 16] = [
+    CliOption { short: "c", long: "command", desc: "Specify command to run. Shell returns after running the command", hint: "<command>", arg: OptionArg::Text, multi: false },
+    CliOption { short: "C", long: "config", desc: "Specify YAML configuration file (can be repeated)", hint: "<config>", arg: OptionArg::File, multi: true },
+    CliOption { short: "l", long: "lang", desc: "Specify shell language", hint: "<ru|рус>", arg: OptionArg::Values(&LANGUAGE_CODES), multi: false },
+    CliOption { short: "s", long: "shell", desc: "Force the shell binary path", hint: "</bin/bash>", arg: OptionArg::Command, multi: false },
+    CliOption { short: "", long: "set", desc: "Override configuration value (can be repeated)", hint: "<key=value>", arg: OptionArg::Text, multi: true },
+    CliOption { short: "", long: "asciicast", desc: "Record the session to an asciicast file", hint: "<file>", arg: OptionArg::File, multi: false },
+    CliOption { short: "", long: "trace", desc: "Log the raw I/O exchanged with the shell to file", hint: "<file>", arg: OptionArg::File, multi: false },
+    CliOption { short: "", long: "path", desc: "Prepend directory to the shell PATH (can be repeated)", hint: "<dir>", arg: OptionArg::File, multi: true },
+    CliOption { short: "T", long: "no-translate-output", desc: "Don't translate the shell output", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "", long: "restricted", desc: "Reject the commands in the restricted denylist (e.g. cd)", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "", long: "check-config", desc: "Check the configuration, print the resolved values and exit", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "", long: "init-config", desc: "Write the default configuration file and exit", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "", long: "force", desc: "Overwrite the existing configuration file with --init-config", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "", long: "completion", desc: "Print the completion script of Pyc options for shell", hint: "<bash|zsh|fish>", arg: OptionArg::Values(&["bash", "zsh", "fish"]), multi: false },
+    CliOption { short: "v", long: "version", desc: "", hint: "", arg: OptionArg::None, multi: false },
+    CliOption { short: "h", long: "help", desc: "Print this menu", hint: "", arg: OptionArg::None, multi: false },
+];
+
+/// ### options
+///
+/// Build the getopts options from the command line options definitions
+pub fn options() -> Options {
+    let mut opts = Options::new();
+    for opt in cli_options() {
+        match (opt.arg, opt.multi) {
+            (OptionArg::None, _) => opts.optflag(opt.short, opt.long, opt.desc),
+            (_, true) => opts.optmulti(opt.short, opt.long, opt.desc, opt.hint),
+            (_, false) => opts.optopt(opt.short, opt.long, opt.desc, opt.hint),
+        };
+    }
+    opts
+}
+
+/// ### parse_completion_shell
+///
+/// Parse the name of the shell to generate the completion script for; None if the shell is not supported
+pub fn parse_completion_shell(shell: &str) -> Option<CompletionShell> {
+    match shell {
+        "bash" => Some(CompletionShell::Bash),
+        "zsh" => Some(CompletionShell::Zsh),
+        "fish" => Some(CompletionShell::Fish),
+        _ => None,
+    }
+}
+
+/// ### completion
+///
+/// Generate the completion script of Pyc command line options for the provided shell
+pub fn completion(shell: CompletionShell) -> String {
+    match shell {
+        CompletionShell::Bash => bash_completion(),
+        CompletionShell::Zsh => zsh_completion(),
+        CompletionShell::Fish => fish_completion(),
+    }
+}
+
+/// ### check_config
+///
+/// Parse the configuration files (`--check-config`): if the configuration is valid, the resolved values are printed,
+/// otherwise the error is reported with the key which caused it.
+/// Returns the exit code of Pyc
+pub fn check_config(config_files: &[PathBuf]) -> i32 {
+    match Config::parse_config_files(config_files) {
+        Ok(config) => {
+            println!("{}", config.describe());
+            0
+        }
+        Err(err) => {
+            match err.key {
+                Some(ref key) => eprintln!("{}", Colour::Red.paint(format!("{}: {}", key, err))),
+                None => eprintln!("{}", Colour::Red.paint(err.to_string())),
+            }
+            1
+        }
+    }
+}
+
+/// ### apply_overrides
+///
+/// Apply to the configuration the values overridden by command line options:
+/// `--path` directories are prepended to the configured ones, `-T` disables the output translation and `--restricted` enables the restricted mode
+pub fn apply_overrides(config: &mut Config, matches: &Matches) {
+    //Prepend CLI path directories to configured ones
+    let mut path: Vec<String> = matches.opt_strs("path");
+    path.append(&mut config.shell_config.path);
+    config.shell_config.path = path;
+    //Output translation
+    if matches.opt_present("no-translate-output") {
+        config.output_config.translate_output = false;
+    }
+    //Restricted mode
+    if matches.opt_present("restricted") {
+        config.shell_config.restricted = true;
+    }
+}
+
+/// ### cli_options
+///
+/// Iterate over the defined command line options
+fn cli_options() -> impl Iterator<Item = &'static CliOption> {
+    CLI_OPTIONS.iter()
+}
+
+/// ### option_names
+///
+/// Returns the names of the option, as typed on the command line (e.g. `-c`, `--command`)
+fn option_names(opt: &CliOption) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(2);
+    if !opt.short.is_empty() {
+        names.push(format!("-{}", opt.short));
+    }
+    names.push(format!("--{}", opt.long));
+    names
+}
+
+/// ### bash_completion
+///
+/// Generate bash completion script
+fn bash_completion() -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut cases: String = String::new();
+    for opt in cli_options() {
+        let names: Vec<String> = option_names(opt);
+        let reply: String = match opt.arg {
+            OptionArg::None => {
+                words.extend(names);
+                continue;
+            }
+            OptionArg::Text => String::new(),
+            OptionArg::File => String::from("COMPREPLY=( $(compgen -f -- \"${cur}\") )\n            "),
+            OptionArg::Command => String::from("COMPREPLY=( $(compgen -c -- \"${cur}\") )\n            "),
+            OptionArg::Values(values) => format!("COMPREPLY=( $(compgen -W \"{}\" -- \"${{cur}}\") )\n            ", values.join(" ")),
+        };
+        cases.push_str(format!("        {})\n            {}return 0\n            ;;\n", names.join("|"), reply).as_str());
+        words.extend(names);
+    }
+    format!(
+        "_{prog}() {{\n    local cur prev\n    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    case \"${{prev}}\" in\n{cases}    esac\n    if [[ \"${{cur}}\" == -* ]]; then\n        COMPREPLY=( $(compgen -W \"{words}\" -- \"${{cur}}\") )\n        return 0\n    fi\n    COMPREPLY=( $(compgen -f -- \"${{cur}}\") )\n}}\ncomplete -F _{prog} {prog}\n",
+        prog = PROGRAM,
+        cases = cases,
+        words = words.join(" ")
+    )
+}
+
+/// ### zsh_completion
+///
+/// Generate zsh completion script
+fn zsh_completion() -> String {
+    let mut specs: Vec<String> = Vec::new();
+    for opt in cli_options() {
+        let action: String = match opt.arg {
+            OptionArg::None => String::new(),
+            OptionArg::Text => format!(":{}: ", opt.long),
+            OptionArg::File => format!(":{}:_files", opt.long),
+            OptionArg::Command => format!(":{}:_command_names", opt.long),
+            OptionArg::Values(values) => format!(":{}:({})", opt.long, values.join(" ")),
+        };
+        let desc: String = opt.desc.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]");
+        let names: Vec<String> = option_names(opt);
+        let spec: String = match (opt.multi, names.len()) {
+            (true, _) => format!("'*{}[{}]{}'", names.join(","), desc, action),
+            (false, 1) => format!("'{}[{}]{}'", names[0], desc, action),
+            (false, _) => format!("'({})'{{{}}}'[{}]{}'", names.join(" "), names.join(","), desc, action),
+        };
+        specs.push(spec);
+    }
+    format!("#compdef {prog}\n\n_arguments \\\n  {specs} \\\n  '*:file:_files'\n", prog = PROGRAM, specs = specs.join(" \\\n  "))
+}
+
+/// ### fish_completion
+///
+/// Generate fish completion script
+fn fish_completion() -> String {
+    let mut script: String = String::new();
+    for opt in cli_options() {
+        let mut line: String = format!("complete -c {}", PROGRAM);
+        if !opt.short.is_empty() {
+            line.push_str(format!(" -s {}", opt.short).as_str());
+        }
+        line.push_str(format!(" -l {}", opt.long).as_str());
+        match opt.arg {
+            OptionArg::None => {}
+            OptionArg::Text => line.push_str(" -x"),
+            OptionArg::File => line.push_str(" -r -F"),
+            OptionArg::Command => line.push_str(" -x -a '(__fish_complete_command)'"),
+            OptionArg::Values(values) => line.push_str(format!(" -x -a '{}'", values.join(" ")).as_str()),
+        }
+        if !opt.desc.is_empty() {
+            line.push_str(format!(" -d '{}'", opt.desc.replace('\\', "\\\\").replace('\'', "\\'")).as_str());
+        }
+        script.push_str(line.as_str());
+        script.push('\n');
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_cli_options() {
+        let opts: Options = options();
+        let args: Vec<&str> = vec!["-c", "ls", "--set", "a=1", "--set", "b=2", "-v", "--completion", "zsh", "file.sh"];
+        let matches = opts.parse(&args).unwrap();
+        assert_eq!(matches.opt_str("c"), Some(String::from("ls")));
+        assert_eq!(matches.opt_strs("set"), vec![String::from("a=1"), String::from("b=2")]);
+        assert!(matches.opt_present("v"));
+        assert_eq!(matches.opt_str("completion"), Some(String::from("zsh")));
+        assert_eq!(matches.free, vec![String::from("file.sh")]);
+        //Flags
+        let matches = opts.parse(&["-T", "--restricted"]).unwrap();
+        assert!(matches.opt_present("no-translate-output"));
+        assert!(matches.opt_present("restricted"));
+        assert!(opts.parse(&["--no-translate-output"]).unwrap().opt_present("T"));
+        //Option which takes a value can't be repeated
+        assert!(opts.parse(&["-c", "ls", "-c", "pwd"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_check_config() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let config_files: Vec<PathBuf> = vec![tmpdir.path().join("pyc.yml")];
+        std::fs::write(config_files[0].as_path(), "language: ru\noutput:\n  translate: false\n").unwrap();
+        assert_eq!(check_config(&config_files), 0);
+        //Bad value
+        std::fs::write(config_files[0].as_path(), "output:\n  translate: foobar\n").unwrap();
+        assert_eq!(check_config(&config_files), 1);
+        //Missing file
+        assert_eq!(check_config(&[tmpdir.path().join("missing.yml")]), 1);
+    }
+
+    #[test]
+    fn test_cli_apply_overrides() {
+        let opts: Options = options();
+        //Nothing to override
+        let mut config: Config = Config::default();
+        config.shell_config.path = vec![String::from("/opt/bin")];
+        apply_overrides(&mut config, &opts.parse(&["file.sh"]).unwrap());
+        assert_eq!(config.shell_config.path, vec![String::from("/opt/bin")]);
+        assert!(config.output_config.translate_output);
+        assert!(!config.shell_config.restricted);
+        //Override values
+        let matches = opts.parse(&["-T", "--restricted", "--path", "/usr/local/bin", "--path", "/home/user/bin"]).unwrap();
+        apply_overrides(&mut config, &matches);
+        assert_eq!(
+            config.shell_config.path,
+            vec![String::from("/usr/local/bin"), String::from("/home/user/bin"), String::from("/opt/bin")]
+        );
+        assert!(!config.output_config.translate_output);
+        assert!(config.shell_config.restricted);
+    }
+
+    #[test]
+    fn test_cli_parse_completion_shell() {
+        assert_eq!(parse_completion_shell("bash"), Some(CompletionShell::Bash));
+        assert_eq!(parse_completion_shell("zsh"), Some(CompletionShell::Zsh));
+        assert_eq!(parse_completion_shell("fish"), Some(CompletionShell::Fish));
+        assert!(parse_completion_shell("tcsh").is_none());
+    }
+
+    #[test]
+    fn test_cli_bash_completion() {
+        let script: String = completion(CompletionShell::Bash);
+        assert!(script.ends_with("complete -F _pyc pyc\n"));
+        let line: &str = script.lines().find(|l| l.contains("COMPREPLY=( $(compgen -W \"-c ")).unwrap();
+        let words: String = format!(" {} ", line.split('"').nth(1).unwrap());
+        for opt in CLI_OPTIONS.iter() {
+            if !opt.short.is_empty() {
+                assert!(words.contains(format!(" -{} ", opt.short).as_str()));
+            }
+            assert!(words.contains(format!(" --{}", opt.long).as_str()));
+        }
+        //Option values
+        assert!(script.contains("        -l|--lang)\n            COMPREPLY=( $(compgen -W \"ru by bg mk rs ua nil\" -- \"${cur}\") )\n"));
+        assert!(script.contains("        -C|--config)\n            COMPREPLY=( $(compgen -f -- \"${cur}\") )\n"));
+    }
+
+    #[test]
+    fn test_cli_zsh_completion() {
+        let script: String = completion(CompletionShell::Zsh);
+        assert!(script.starts_with("#compdef pyc\n"));
+        assert!(script.contains("'(-c --command)'{-c,--command}'[Specify command to run. Shell returns after running the command]:command: '"));
+        assert!(script.contains("'*--set[Override configuration value (can be repeated)]:set: '"));
+        assert!(script.contains("'(-l --lang)'{-l,--lang}'[Specify shell language]:lang:(ru by bg mk rs ua nil)'"));
+        assert!(script.contains("'--trace[Log the raw I/O exchanged with the shell to file]:trace:_files'"));
+    }
+
+    #[test]
+    fn test_cli_fish_completion() {
+        let script: String = completion(CompletionShell::Fish);
+        assert_eq!(script.lines().count(), CLI_OPTIONS.len());
+        assert!(script.contains("complete -c pyc -s C -l config -r -F -d 'Specify YAML configuration file (can be repeated)'\n"));
+        assert!(script.contains("complete -c pyc -s v -l version\n"));
+        assert!(script.contains("complete -c pyc -l completion -x -a 'bash zsh fish' -d 'Print the completion script of Pyc options for shell'\n"));
+    }
+}
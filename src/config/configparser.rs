@@ -40,6 +40,7 @@ impl ConfigParser {
             true => Err(ConfigError {
                 code: ConfigErrorCode::YamlSyntaxError,
                 message: String::from(format!("Missing key '{}'", child)),
+                key: Some(child.clone()),
             }),
             false => Ok(&yaml_doc[child.as_str()]),
         }
@@ -55,6 +56,7 @@ impl ConfigParser {
                 None => Err(ConfigError {
                     code: ConfigErrorCode::YamlSyntaxError,
                     message: String::from(format!("'{}' is not a bool", key)),
+                    key: Some(key),
                 }),
             },
             Err(err) => Err(err),
@@ -71,6 +73,7 @@ impl ConfigParser {
                 None => Err(ConfigError {
                     code: ConfigErrorCode::YamlSyntaxError,
                     message: String::from(format!("'{}' is not a number", key)),
+                    key: Some(key),
                 }),
             },
             Err(err) => Err(err),
@@ -87,6 +90,7 @@ impl ConfigParser {
                 None => Err(ConfigError {
                     code: ConfigErrorCode::YamlSyntaxError,
                     message: String::from(format!("'{}' is not a string", key)),
+                    key: Some(key),
                 }),
             },
             Err(err) => Err(err),
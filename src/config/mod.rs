@@ -23,16 +23,43 @@
 *
 */
 
+extern crate ansi_term;
+extern crate regex;
 extern crate yaml_rust;
 
 mod configparser;
 
+use ansi_term::Colour;
 use configparser::ConfigParser;
+use crate::translator::ioprocessor::{IOProcessor, DEFAULT_MAX_EXPRESSION_DEPTH};
+use crate::translator::lang::{GostSystem, Language, LANGUAGE_CODES};
+use crate::translator::new_gost_translator;
+use regex::Regex;
 use std::collections::HashMap;
 use std::fmt;
 use yaml_rust::{Yaml, YamlLoader};
 
-use std::path::PathBuf;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+//Commands rejected in restricted mode, if no denylist is configured: cd, PATH/SHELL/ENV changes, commands with '/' and exec
+const RESTRICTED_DENYLIST: [&str; 5] = [
+    r"(^|[;&|(`])\s*cd(\s|$)",
+    r"(^|[;&|(`\s])(PATH|SHELL|ENV)=",
+    r"(^|[;&|(`])\s*unset\s.*\b(PATH|SHELL|ENV)\b",
+    r"(^|[;&|(`])\s*[^\s;&|()`]*/",
+    r"(^|[;&|(`])\s*exec(\s|$)",
+];
+
+//Maximum number of nested 'include' levels
+const CONFIG_INCLUDE_MAX_DEPTH: usize = 8;
+
+//Prompt modules which can be disabled
+pub const PROMPT_MODULE_DURATION: &str = "duration";
+pub const PROMPT_MODULE_GIT: &str = "git";
+pub const PROMPT_MODULE_RC: &str = "rc";
+const PROMPT_MODULES: [&str; 3] = [PROMPT_MODULE_DURATION, PROMPT_MODULE_GIT, PROMPT_MODULE_RC];
 
 //Types
 #[derive(Clone)]
@@ -40,35 +67,137 @@ pub struct Config {
     pub language: String,
     pub shell_config: ShellConfig,
     pub alias: HashMap<String, String>,
+    pub transliteration: HashMap<char, String>, //Overrides of the transliteration of single characters
+    pub input_config: InputConfig,
     pub output_config: OutputConfig,
     pub prompt_config: PromptConfig,
+    pub translit_config: TranslitConfig,
+    pub history_config: HistoryConfig,
+    pub config_file: Option<PathBuf>, //Path of the file the configuration has been loaded from
+    pub config_layers: Vec<PathBuf>,  //Files merged under config_file (-C repeated)
+    pub trace_file: Option<PathBuf>,  //File the shell I/O is traced to (--trace)
 }
 
 #[derive(Clone)]
 pub struct ShellConfig {
     pub exec: String,
-    pub args: Vec<String>
+    pub args: Vec<String>,
+    pub path: Vec<String>,
+    pub suggest_commands: bool,
+    pub auto_cd: bool,                    //Change directory when a directory is typed as a command
+    pub layout_autocorrect: LayoutAutocorrect,
+    pub restricted: bool,                 //Reject the commands matching the denylist
+    pub restricted_denylist: Vec<Regex>,  //Regex of the commands rejected in restricted mode
+    pub logout_file: Option<String>,      //Script run when the interactive session ends
+    pub clear_mode: ClearMode,
+    pub stop_grace_ms: usize,             //Time given to the shell to exit after SIGTERM, before it is killed
+    pub read_timeout_ms: usize,           //Time waited for the shell output on each read; 0 just polls
+    pub read_buffer_size: usize,          //Initial size of the buffer the shell output is read into
+}
+
+#[derive(Copy, Clone, PartialEq, fmt::Debug)]
+pub enum LayoutAutocorrect {
+    Off,
+    Suggest,
+    Run,
+}
+
+#[derive(Copy, Clone, PartialEq, fmt::Debug)]
+pub enum ClearMode {
+    Erase,  //Erase the screen
+    Purge,  //Erase the screen and the scrollback
+    Scroll, //Scroll the screen content into the scrollback
+}
+
+#[derive(Clone)]
+pub struct InputConfig {
+    pub filter_control_chars: bool,
+    pub word_chars: Option<String>, //None if words are separated by whitespaces only
+    pub translit_toggle_key: Option<u8>, //Control code of the key which suspends and resumes transliteration; None if disabled
+    pub recall_on_failure: bool,
+    pub translate_heredoc: bool, //Transliterate the body of heredocs as text
 }
 
 #[derive(Clone)]
 pub struct OutputConfig {
     pub translate_output: bool,
+    pub normalize: OutputNormalization,
+    pub error_color: Option<Colour>, //None if errors mustn't be colored
+    pub squelch_repeats: bool,
+    pub realign_tabs: bool,
+    pub max_lines_per_sec: Option<usize>, //None if output rate is not limited
+    pub collapse_progress: bool,          //Keep only the last update of the lines redrawn with carriage return
+    pub echo_typed: bool,                 //Echo the input typed to subprocesses as typed (otherwise as it is sent)
+    pub buffering: OutputBuffering,
+}
+
+#[derive(Copy, Clone, PartialEq, fmt::Debug)]
+pub enum OutputNormalization {
+    None,
+    Nfc,
+}
+
+#[derive(Copy, Clone, PartialEq, fmt::Debug)]
+pub enum OutputBuffering {
+    Raw,  //Partial lines are printed as soon as no more output is available
+    Line, //Partial lines are printed once complete, or when the command terminates
+}
+
+#[derive(Clone)]
+pub struct TranslitConfig {
+    pub gost_system: GostSystem,
+    pub max_expression_depth: usize,
+    pub translate_args: bool, //Translate the command arguments too, not only the command token
+}
+
+#[derive(Clone)]
+pub struct HistoryConfig {
+    pub store_original: bool, //Store input as typed by the user, instead of the transliterated one
+    pub format: HistoryFormat,
+    pub autosave: bool, //Append each command to the history file as soon as it's entered
+}
+
+#[derive(Copy, Clone, PartialEq, fmt::Debug)]
+pub enum HistoryFormat {
+    Plain, //One command per line
+    Json,  //One JSON object per line, with the command metadata
 }
 
 #[derive(Clone)]
 pub struct PromptConfig {
     pub prompt_line: String,
+    pub first_prompt_line: Option<String>, //Prompt line used for the first prompt of the session
+    pub rprompt_line: Option<String>, //Prompt line aligned to the right of the terminal
     pub history_size: usize,
+    pub history_ignore_dups: bool, //Collapse consecutive duplicates in history
     pub translate: bool,
     pub break_enabled: bool,
     pub break_str: String,
     pub min_duration: usize,
+    pub duration_label: String,
+    pub duration_separator: String,
+    pub duration_unit_ms: Option<String>,
+    pub duration_unit_s: String,
+    pub duration_unit_m: Option<String>,
     pub rc_ok: String,
     pub rc_err: String,
     pub git_branch: String,
     pub git_commit_ref: usize,
     pub git_commit_prepend: Option<String>,
-    pub git_commit_append: Option<String>
+    pub git_commit_append: Option<String>,
+    pub git_dirty: String,
+    pub git_ahead: String,
+    pub git_behind: String,
+    pub bottom_pinned: bool,
+    pub lang_flag_emoji: bool,
+    pub disabled_modules: Vec<String>,
+    pub rev_search_label: String,
+    pub rev_search_glyph: Option<String>,
+    pub rev_search_color: Option<Colour>,
+    pub translit_off: String,
+    pub clock_format: String, //strftime format of ${CLOCK}
+    pub wrkdir_home_tilde: bool, //Replace the home directory with '~' in ${WRKDIR}
+    pub wrkdir_max_components: usize, //Show only the last components of ${WRKDIR} (0: unlimited)
 }
 
 #[derive(Copy, Clone, PartialEq, fmt::Debug)]
@@ -76,11 +205,13 @@ pub enum ConfigErrorCode {
     NoSuchFileOrDirectory,
     CouldNotReadFile,
     YamlSyntaxError,
+    IncludeError,
 }
 
 pub struct ConfigError {
     pub code: ConfigErrorCode,
     pub message: String,
+    pub key: Option<String>, //Dotted path of the key which caused the error (e.g. prompt.rc.ok), if known
 }
 
 impl fmt::Display for ConfigErrorCode {
@@ -89,6 +220,7 @@ impl fmt::Display for ConfigErrorCode {
             ConfigErrorCode::NoSuchFileOrDirectory => "NoSuchFileOrDirectory",
             ConfigErrorCode::CouldNotReadFile => "CouldNotReadFile",
             ConfigErrorCode::YamlSyntaxError => "YamlSyntaxError",
+            ConfigErrorCode::IncludeError => "IncludeError",
         };
         write!(f, "{}", code_str)
     }
@@ -100,6 +232,19 @@ impl fmt::Display for ConfigError {
     }
 }
 
+impl ConfigError {
+    /// ### in_section
+    ///
+    /// Prefix the key of the error with the section it has been found in
+    pub(crate) fn in_section(mut self, section: &str) -> ConfigError {
+        self.key = Some(match self.key {
+            Some(key) => format!("{}.{}", section, key),
+            None => String::from(section),
+        });
+        self
+    }
+}
+
 impl Config {
     /// ### default
     ///
@@ -110,47 +255,201 @@ impl Config {
             language: String::from("ru"),
             shell_config: ShellConfig::default(),
             alias: alias_config,
+            transliteration: HashMap::new(),
+            input_config: InputConfig::default(),
             output_config: OutputConfig::default(),
             prompt_config: PromptConfig::default(),
+            translit_config: TranslitConfig::default(),
+            history_config: HistoryConfig::default(),
+            config_file: None,
+            config_layers: Vec::new(),
+            trace_file: None,
         }
     }
 
     /// ### parse_config
     ///
     /// `parse_config` parse a YAML configuration file and return a Config struct
+    #[cfg(test)]
     pub fn parse_config(config_file: PathBuf) -> Result<Config, ConfigError> {
-        //Read configuration file
-        let config_str: String = match std::fs::read_to_string(config_file.clone()) {
-            Ok(config) => config,
+        Config::parse_config_files(&[config_file])
+    }
+
+    /// ### parse_config_files
+    ///
+    /// Parse configuration from several files; each file is merged over the previous ones:
+    /// scalars and arrays are overridden, while maps (e.g. alias) are merged.
+    /// Errors of a single file report the file name
+    pub fn parse_config_files(config_files: &[PathBuf]) -> Result<Config, ConfigError> {
+        let mut merged: Option<Yaml> = None;
+        for config_file in config_files.iter() {
+            let yaml: Yaml = Config::load_config_file(config_file, &mut Vec::new())?;
+            merged = Some(match merged {
+                Some(base) => Config::merge_yaml(base, yaml),
+                None => yaml,
+            });
+        }
+        let yaml: Yaml = match merged {
+            Some(yaml) => yaml,
+            None => return Err(ConfigError {
+                code: ConfigErrorCode::NoSuchFileOrDirectory,
+                message: String::from("No configuration file provided"),
+                key: None,
+            }),
+        };
+        let mut config: Config = Config::parse_yaml(&yaml)?;
+        config.config_file = config_files.last().cloned();
+        config.config_layers = config_files[..config_files.len() - 1].to_vec();
+        Ok(config)
+    }
+
+    /// ### load_config_file
+    ///
+    /// Load the YAML document of a configuration file, with its included files merged over it.
+    /// includes is the chain of the files being loaded, used to detect include cycles
+    fn load_config_file(config_file: &PathBuf, includes: &mut Vec<PathBuf>) -> Result<Yaml, ConfigError> {
+        let canonical: PathBuf = fs::canonicalize(config_file).unwrap_or(config_file.clone());
+        if includes.contains(&canonical) {
+            return Err(ConfigError {
+                code: ConfigErrorCode::IncludeError,
+                message: format!("Include cycle: {} is already being included", config_file.display()),
+                key: Some(String::from("include")),
+            });
+        }
+        if includes.len() > CONFIG_INCLUDE_MAX_DEPTH {
+            return Err(ConfigError {
+                code: ConfigErrorCode::IncludeError,
+                message: format!("Could not include {}: too many nested includes", config_file.display()),
+                key: Some(String::from("include")),
+            });
+        }
+        let yaml: Yaml = match Config::load_yaml(Config::read_config_file(config_file)?) {
+            Ok(yaml) => yaml,
+            Err(err) => return Err(ConfigError {
+                code: err.code,
+                message: format!("{}: {}", config_file.display(), err.message),
+                key: None,
+            }),
+        };
+        let dir: PathBuf = match config_file.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::new(),
+        };
+        includes.push(canonical);
+        let yaml: Result<Yaml, ConfigError> = Config::resolve_includes(yaml, dir.as_path(), includes);
+        includes.pop();
+        yaml
+    }
+
+    /// ### resolve_includes
+    ///
+    /// Merge the files listed in the 'include' key over the document, in order.
+    /// Included paths are relative to dir, the directory of the including file
+    fn resolve_includes(yaml: Yaml, dir: &Path, includes: &mut Vec<PathBuf>) -> Result<Yaml, ConfigError> {
+        let mut doc = match yaml {
+            Yaml::Hash(doc) => doc,
+            yaml => return Ok(yaml),
+        };
+        let paths: Vec<Yaml> = match doc.remove(&Yaml::String(String::from("include"))) {
+            None => return Ok(Yaml::Hash(doc)),
+            Some(Yaml::Array(paths)) => paths,
+            Some(_) => return Err(ConfigError {
+                code: ConfigErrorCode::IncludeError,
+                message: String::from("'include' key is not an array"),
+                key: Some(String::from("include")),
+            }),
+        };
+        let mut yaml: Yaml = Yaml::Hash(doc);
+        for path in paths.iter() {
+            let path: PathBuf = match path.as_str() {
+                Some(path) => dir.join(path),
+                None => return Err(ConfigError {
+                    code: ConfigErrorCode::IncludeError,
+                    message: String::from("Included path is not a string"),
+                    key: Some(String::from("include")),
+                }),
+            };
+            //Errors of included files are reported as include errors
+            let included: Yaml = Config::load_config_file(&path, includes).map_err(|err| ConfigError {
+                code: ConfigErrorCode::IncludeError,
+                message: err.message,
+                key: Some(String::from("include")),
+            })?;
+            yaml = Config::merge_yaml(yaml, included);
+        }
+        Ok(yaml)
+    }
+
+    /// ### read_config_file
+    ///
+    /// Read configuration file to string
+    fn read_config_file(config_file: &PathBuf) -> Result<String, ConfigError> {
+        match std::fs::read_to_string(config_file) {
             Err(err) => match err.kind() {
                 std::io::ErrorKind::NotFound => {
                     return Err(ConfigError {
                         code: ConfigErrorCode::NoSuchFileOrDirectory,
                         message: format!("No such file or directory: {}", config_file.display()),
+                        key: None,
                     })
                 }
                 _ => {
                     return Err(ConfigError {
                         code: ConfigErrorCode::CouldNotReadFile,
-                        message: format!("Could not read file {}", config_file.display())
+                        message: format!("Could not read file {}", config_file.display()),
+                        key: None,
                     })
                 }
             },
-        };
-        Config::parse_config_str(config_str)
+            Ok(config) => Ok(config),
+        }
+    }
+
+    /// ### merge_yaml
+    ///
+    /// Merge overlay YAML over base. Maps are merged recursively, aliases are joined, any other value is replaced
+    fn merge_yaml(base: Yaml, overlay: Yaml) -> Yaml {
+        match (base, overlay) {
+            (Yaml::Hash(mut base), Yaml::Hash(overlay)) => {
+                for (key, value) in overlay.into_iter() {
+                    let merged: Yaml = match (base.remove(&key), value) {
+                        //Aliases are a list; the latest definition of an alias wins
+                        (Some(Yaml::Array(mut aliases)), Yaml::Array(mut overlay_aliases)) if key.as_str() == Some("alias") => {
+                            aliases.append(&mut overlay_aliases);
+                            Yaml::Array(aliases)
+                        }
+                        (Some(base_value), value) => Config::merge_yaml(base_value, value),
+                        (None, value) => value,
+                    };
+                    base.insert(key, merged);
+                }
+                Yaml::Hash(base)
+            }
+            (_, overlay) => overlay,
+        }
     }
 
     /// ### parse_config_str
     ///
     /// Parse configuration as string
+    #[cfg(test)]
     fn parse_config_str(config: String) -> Result<Config, ConfigError> {
+        let yaml: Yaml = Config::resolve_includes(Config::load_yaml(config)?, Path::new("."), &mut Vec::new())?;
+        Config::parse_yaml(&yaml)
+    }
+
+    /// ### load_yaml
+    ///
+    /// Load the first YAML document of the configuration
+    fn load_yaml(config: String) -> Result<Yaml, ConfigError> {
         //Parse YAML file
-        let yaml_docs: Vec<Yaml> = match YamlLoader::load_from_str(config.as_str()) {
+        let mut yaml_docs: Vec<Yaml> = match YamlLoader::load_from_str(config.as_str()) {
             Ok(doc) => doc,
             Err(_) => {
                 return Err(ConfigError {
                     code: ConfigErrorCode::YamlSyntaxError,
                     message: String::from("Configuration is not a valid YAML"),
+                    key: None,
                 });
             }
         };
@@ -159,9 +458,16 @@ impl Config {
             return Err(ConfigError {
                 code: ConfigErrorCode::YamlSyntaxError,
                 message: String::from("File does not contain any YAML document"),
+                key: None,
             });
         };
-        let yaml_doc: &Yaml = &yaml_docs[0];
+        Ok(yaml_docs.swap_remove(0))
+    }
+
+    /// ### parse_yaml
+    ///
+    /// Parse configuration from YAML document
+    fn parse_yaml(yaml_doc: &Yaml) -> Result<Config, ConfigError> {
         //Look for keys and get configuration parts
         //Get language
         let language: String = match ConfigParser::get_child(&yaml_doc, String::from("language")) {
@@ -179,19 +485,31 @@ impl Config {
                 },
                 Err(_) => HashMap::new(),
         };
+        //Get transliteration overrides
+        let transliteration: HashMap<char, String> =
+            match ConfigParser::get_child(yaml_doc, String::from("transliteration")) {
+                Ok(node) => Config::parse_transliteration(node)?,
+                Err(_) => HashMap::new(),
+            };
         let shell_config: ShellConfig = match ConfigParser::get_child(&yaml_doc, String::from("shell")) {
             Ok(node) => match ShellConfig::parse_config(&node) {
                 Ok(cfg) => cfg,
-                Err(err) => return Err(err)
+                Err(err) => return Err(err.in_section("shell"))
             },
             Err(_) => ShellConfig::default()
         };
+        //Get input config
+        let input_config: InputConfig =
+            match ConfigParser::get_child(yaml_doc, String::from("input")) {
+                Ok(node) => InputConfig::parse_config(node).map_err(|err| err.in_section("input"))?,
+                Err(_) => InputConfig::default(),
+            };
         //Get output config
         let output_config: OutputConfig =
             match ConfigParser::get_child(&yaml_doc, String::from("output")) {
                 Ok(node) => match OutputConfig::parse_config(&node) {
                     Ok(config) => config,
-                    Err(err) => return Err(err),
+                    Err(err) => return Err(err.in_section("output")),
                 },
                 Err(_) => OutputConfig::default(),
             };
@@ -200,16 +518,36 @@ impl Config {
             match ConfigParser::get_child(&yaml_doc, String::from("prompt")) {
                 Ok(node) => match PromptConfig::parse_config(&node) {
                     Ok(config) => config,
-                    Err(err) => return Err(err),
+                    Err(err) => return Err(err.in_section("prompt")),
                 },
                 Err(_) => PromptConfig::default(),
             };
+        //Get translit config
+        let translit_config: TranslitConfig =
+            match ConfigParser::get_child(yaml_doc, String::from("translit")) {
+                Ok(node) => TranslitConfig::parse_config(node).map_err(|err| err.in_section("translit"))?,
+                Err(_) => TranslitConfig::default(),
+            };
+        //Get history config
+        let history_config: HistoryConfig =
+            match ConfigParser::get_child(yaml_doc, String::from("history")) {
+                Ok(node) => HistoryConfig::parse_config(node).map_err(|err| err.in_section("history"))?,
+                Err(_) => HistoryConfig::default(),
+            };
+        Config::check_language(language.as_str(), translit_config.gost_system, &transliteration)?;
         Ok(Config {
             language: language,
             shell_config: shell_config,
             alias: alias_config,
-            output_config: output_config,
-            prompt_config: prompt_config,
+            transliteration,
+            input_config,
+            output_config,
+            prompt_config,
+            translit_config,
+            history_config,
+            config_file: None,
+            config_layers: Vec::new(),
+            trace_file: None,
         })
     }
 
@@ -223,6 +561,328 @@ impl Config {
         }
     }
 
+    /// ### describe
+    ///
+    /// Describe the resolved configuration, one `key: value` per line
+    pub fn describe(&self) -> String {
+        let optional = |value: &Option<String>| -> String {
+            match value {
+                Some(value) => format!("\"{}\"", value),
+                None => String::from("none"),
+            }
+        };
+        let lines: Vec<String> = vec![
+            format!("language: {}", self.language),
+            format!("alias: {}", self.alias.len()),
+            format!("transliteration: {}", self.transliteration.len()),
+            format!("shell.exec: {}", self.shell_config.exec),
+            format!("shell.args: {}", self.shell_config.args.join(" ")),
+            format!("shell.restricted: {}", self.shell_config.restricted),
+            format!("output.translate: {}", self.output_config.translate_output),
+            format!("prompt.prompt_line: \"{}\"", self.prompt_config.prompt_line),
+            format!("prompt.first_prompt_line: {}", optional(&self.prompt_config.first_prompt_line)),
+            format!("prompt.rprompt_line: {}", optional(&self.prompt_config.rprompt_line)),
+            format!("prompt.history_size: {}", self.prompt_config.history_size),
+            format!("prompt.translate: {}", self.prompt_config.translate),
+            format!("prompt.break.enabled: {}", self.prompt_config.break_enabled),
+            format!("prompt.break.with: \"{}\"", self.prompt_config.break_str),
+            format!("prompt.duration.min_elapsed_time: {}", self.prompt_config.min_duration),
+            format!("prompt.rc.ok: \"{}\"", self.prompt_config.rc_ok),
+            format!("prompt.rc.error: \"{}\"", self.prompt_config.rc_err),
+            format!("prompt.git.branch: \"{}\"", self.prompt_config.git_branch),
+            format!("prompt.git.commit_ref_len: {}", self.prompt_config.git_commit_ref),
+            format!("prompt.disabled_modules: {}", self.prompt_config.disabled_modules.join(" ")),
+        ];
+        lines.join("\n")
+    }
+
+    /// ### default_yaml
+    ///
+    /// Returns the default configuration as a commented YAML document.
+    /// Required keys are set to their default value; optional keys are commented out, with their default value
+    pub fn default_yaml() -> String {
+        let config: Config = Config::default();
+        let shell: &ShellConfig = &config.shell_config;
+        let input: &InputConfig = &config.input_config;
+        let output: &OutputConfig = &config.output_config;
+        let prompt: &PromptConfig = &config.prompt_config;
+        let translit: &TranslitConfig = &config.translit_config;
+        let history: &HistoryConfig = &config.history_config;
+        let lines: Vec<String> = vec![
+            String::from("# Pyc configuration; see https://github.com/veeso/pyc for all the options"),
+            format!("# Language of the shell ({})", LANGUAGE_CODES.join(", ")),
+            format!("language: {}", config.language),
+            String::from("shell:"),
+            String::from("  # Shell binary (absolute or in PATH) and its arguments"),
+            format!("  exec: {}", yaml_string(shell.exec.as_str())),
+            String::from("  # args:"),
+            String::from("  #   - \"-l\""),
+            String::from("  # Directories prepended to the shell PATH"),
+            String::from("  # path:"),
+            String::from("  #   - \"/opt/tools/bin\""),
+            format!("  # suggest_commands: {}", shell.suggest_commands),
+            format!("  # auto_cd: {}", shell.auto_cd),
+            String::from("  # Fix commands typed with the russian keyboard layout (off, suggest, run)"),
+            String::from("  # layout_autocorrect: \"off\""),
+            format!("  # restricted: {}", shell.restricted),
+            String::from("  # restricted_denylist:"),
+            RESTRICTED_DENYLIST.iter().map(|p| format!("  #   - '{}'", p.replace('\'', "''"))).collect::<Vec<String>>().join("\n"),
+            String::from("  # logout_file: \"~/.pyc_logout\""),
+            String::from("  # How the screen is cleared (erase, purge, scroll)"),
+            String::from("  # clear_mode: erase"),
+            format!("  # stop_grace_ms: {}", shell.stop_grace_ms),
+            format!("  # read_timeout_ms: {}", shell.read_timeout_ms),
+            format!("  # read_buffer_size: {}", shell.read_buffer_size),
+            String::from("# Commands replaced with the associated latin expression"),
+            String::from("# alias:"),
+            String::from("#   - чд: cd"),
+            String::from("#   - гцо: \"git checkout $1\""),
+            String::from("# Overrides of the transliteration of single characters"),
+            String::from("# transliteration:"),
+            String::from("#   ж: zh"),
+            String::from("# input:"),
+            format!("#   filter_control_chars: {}", input.filter_control_chars),
+            String::from("#   word_chars: \"-_\""),
+            String::from("#   translit_toggle_key: \"CTRL+T\""),
+            format!("#   recall_on_failure: {}", input.recall_on_failure),
+            format!("#   translate_heredoc: {}", input.translate_heredoc),
+            String::from("output:"),
+            String::from("  # Translate the shell output to cyrillic"),
+            format!("  translate: {}", output.translate_output),
+            String::from("  # normalize: none"),
+            String::from("  # error_color: red"),
+            format!("  # squelch_repeats: {}", output.squelch_repeats),
+            format!("  # realign_tabs: {}", output.realign_tabs),
+            String::from("  # max_lines_per_sec: 0"),
+            format!("  # collapse_progress: {}", output.collapse_progress),
+            format!("  # echo_typed: {}", output.echo_typed),
+            String::from("  # buffering: raw"),
+            String::from("prompt:"),
+            String::from("  # Prompt line; see the README for the available keys"),
+            format!("  prompt_line: {}", yaml_string(prompt.prompt_line.as_str())),
+            String::from("  # first_prompt_line: \"${USER}@${HOSTNAME}:${WRKDIR} ${LANG}$\""),
+            String::from("  # rprompt_line: \"${CLOCK}\""),
+            format!("  history_size: {}", prompt.history_size),
+            format!("  # history_ignore_dups: {}", prompt.history_ignore_dups),
+            String::from("  # Translate the prompt line to cyrillic"),
+            format!("  translate: {}", prompt.translate),
+            format!("  # bottom_pinned: {}", prompt.bottom_pinned),
+            format!("  # lang_flag_emoji: {}", prompt.lang_flag_emoji),
+            String::from("  # Prompt modules to disable (duration, git, rc)"),
+            String::from("  # disabled_modules: []"),
+            format!("  # translit_off: {}", yaml_string(prompt.translit_off.as_str())),
+            format!("  # clock_format: {}", yaml_string(prompt.clock_format.as_str())),
+            format!("  # wrkdir_home_tilde: {}", prompt.wrkdir_home_tilde),
+            format!("  # wrkdir_max_components: {}", prompt.wrkdir_max_components),
+            String::from("  # rev_search:"),
+            format!("  #   label: {}", yaml_string(prompt.rev_search_label.as_str())),
+            String::from("  #   glyph: \"🔍\""),
+            String::from("  #   color: cyan"),
+            String::from("  break:"),
+            format!("    enabled: {}", prompt.break_enabled),
+            format!("    with: {}", yaml_string(prompt.break_str.as_str())),
+            String::from("  duration:"),
+            format!("    min_elapsed_time: {}", prompt.min_duration),
+            format!("    # label: {}", yaml_string(prompt.duration_label.as_str())),
+            format!("    # decimal_separator: {}", yaml_string(prompt.duration_separator.as_str())),
+            String::from("    # units:"),
+            String::from("    #   ms: \"ms\""),
+            format!("    #   s: {}", yaml_string(prompt.duration_unit_s.as_str())),
+            String::from("    #   m: \"m\""),
+            String::from("  rc:"),
+            format!("    ok: {}", yaml_string(prompt.rc_ok.as_str())),
+            format!("    error: {}", yaml_string(prompt.rc_err.as_str())),
+            String::from("  git:"),
+            format!("    branch: {}", yaml_string(prompt.git_branch.as_str())),
+            format!("    commit_ref_len: {}", prompt.git_commit_ref),
+            String::from("    # commit_prepend: \"(\""),
+            String::from("    # commit_append: \")\""),
+            format!("    # dirty: {}", yaml_string(prompt.git_dirty.as_str())),
+            format!("    # ahead: {}", yaml_string(prompt.git_ahead.as_str())),
+            format!("    # behind: {}", yaml_string(prompt.git_behind.as_str())),
+            String::from("# translit:"),
+            String::from("#   gost_system: b"),
+            format!("#   max_expression_depth: {}", translit.max_expression_depth),
+            format!("#   translate_args: {}", translit.translate_args),
+            String::from("# history:"),
+            format!("#   store_original: {}", history.store_original),
+            String::from("#   format: plain"),
+            format!("#   autosave: {}", history.autosave),
+        ];
+        let mut yaml: String = lines.join("\n");
+        yaml.push('\n');
+        yaml
+    }
+
+    /// ### init_config_file
+    ///
+    /// Write the default configuration to config_file, creating its directory if necessary.
+    /// An existing file is overwritten only if force is true
+    pub fn init_config_file(config_file: &Path, force: bool) -> io::Result<()> {
+        if let Some(dir) = config_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file: fs::File = match force {
+            true => OpenOptions::new().create(true).write(true).truncate(true).open(config_file)?,
+            false => OpenOptions::new().create_new(true).write(true).open(config_file)?,
+        };
+        file.write_all(Config::default_yaml().as_bytes())
+    }
+
+    /// ### set_value
+    ///
+    /// Override a configuration value by its dotted key path (e.g. `prompt.translate`).
+    /// Returns error if the key is unknown or if the value is not valid for the key
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "language" => {
+                Config::check_language(value, self.translit_config.gost_system, &self.transliteration)?;
+                self.language = String::from(value);
+            }
+            "shell.exec" => self.shell_config.exec = String::from(value),
+            "shell.suggest_commands" => self.shell_config.suggest_commands = Config::parse_bool_value(key, value)?,
+            "shell.auto_cd" => self.shell_config.auto_cd = Config::parse_bool_value(key, value)?,
+            "shell.restricted" => self.shell_config.restricted = Config::parse_bool_value(key, value)?,
+            "shell.clear_mode" => self.shell_config.clear_mode = match ShellConfig::parse_clear_mode(value) {
+                Ok(m) => m,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "shell.logout_file" => self.shell_config.logout_file = Some(String::from(value)),
+            "shell.stop_grace_ms" => self.shell_config.stop_grace_ms = Config::parse_usize_value(key, value)?,
+            "shell.read_timeout_ms" => self.shell_config.read_timeout_ms = Config::parse_usize_value(key, value)?,
+            "shell.read_buffer_size" => match Config::parse_usize_value(key, value)? {
+                0 => return Err(Config::bad_value(key, value)),
+                size => self.shell_config.read_buffer_size = size,
+            },
+            "shell.layout_autocorrect" => self.shell_config.layout_autocorrect = match ShellConfig::parse_layout_autocorrect(value) {
+                Ok(l) => l,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "input.filter_control_chars" => self.input_config.filter_control_chars = Config::parse_bool_value(key, value)?,
+            "input.word_chars" => self.input_config.word_chars = Some(String::from(value)),
+            "input.recall_on_failure" => self.input_config.recall_on_failure = Config::parse_bool_value(key, value)?,
+            "input.translate_heredoc" => self.input_config.translate_heredoc = Config::parse_bool_value(key, value)?,
+            "input.translit_toggle_key" => self.input_config.translit_toggle_key = match InputConfig::parse_ctrl_key(value) {
+                Ok(k) => k,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "output.translate" => self.output_config.translate_output = Config::parse_bool_value(key, value)?,
+            "output.normalize" => self.output_config.normalize = match OutputConfig::parse_normalization(value) {
+                Ok(n) => n,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "output.error_color" => self.output_config.error_color = match OutputConfig::parse_color(value) {
+                Ok(c) => c,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "output.squelch_repeats" => self.output_config.squelch_repeats = Config::parse_bool_value(key, value)?,
+            "output.realign_tabs" => self.output_config.realign_tabs = Config::parse_bool_value(key, value)?,
+            "output.collapse_progress" => self.output_config.collapse_progress = Config::parse_bool_value(key, value)?,
+            "output.buffering" => self.output_config.buffering = match OutputConfig::parse_buffering(value) {
+                Ok(b) => b,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "output.echo_typed" => self.output_config.echo_typed = Config::parse_bool_value(key, value)?,
+            "output.max_lines_per_sec" => self.output_config.max_lines_per_sec = match Config::parse_usize_value(key, value)? {
+                0 => None,
+                m => Some(m),
+            },
+            "prompt.prompt_line" => self.prompt_config.prompt_line = String::from(value),
+            "prompt.first_prompt_line" => self.prompt_config.first_prompt_line = Some(String::from(value)),
+            "prompt.rprompt_line" => self.prompt_config.rprompt_line = Some(String::from(value)),
+            "prompt.history_size" => self.prompt_config.history_size = Config::parse_usize_value(key, value)?,
+            "prompt.history_ignore_dups" => self.prompt_config.history_ignore_dups = Config::parse_bool_value(key, value)?,
+            "prompt.translate" => self.prompt_config.translate = Config::parse_bool_value(key, value)?,
+            "prompt.break.enabled" => self.prompt_config.break_enabled = Config::parse_bool_value(key, value)?,
+            "prompt.break.with" => self.prompt_config.break_str = String::from(value),
+            "prompt.duration.min_elapsed_time" => self.prompt_config.min_duration = Config::parse_usize_value(key, value)?,
+            "prompt.duration.label" => self.prompt_config.duration_label = String::from(value),
+            "prompt.duration.decimal_separator" => self.prompt_config.duration_separator = String::from(value),
+            "prompt.duration.units.ms" => self.prompt_config.duration_unit_ms = Some(String::from(value)),
+            "prompt.duration.units.s" => self.prompt_config.duration_unit_s = String::from(value),
+            "prompt.duration.units.m" => self.prompt_config.duration_unit_m = Some(String::from(value)),
+            "prompt.rc.ok" => self.prompt_config.rc_ok = String::from(value),
+            "prompt.rc.error" => self.prompt_config.rc_err = String::from(value),
+            "prompt.git.branch" => self.prompt_config.git_branch = String::from(value),
+            "prompt.git.commit_ref_len" => self.prompt_config.git_commit_ref = Config::parse_usize_value(key, value)?,
+            "prompt.git.commit_prepend" => self.prompt_config.git_commit_prepend = Some(String::from(value)),
+            "prompt.git.commit_append" => self.prompt_config.git_commit_append = Some(String::from(value)),
+            "prompt.git.dirty" => self.prompt_config.git_dirty = String::from(value),
+            "prompt.git.ahead" => self.prompt_config.git_ahead = String::from(value),
+            "prompt.git.behind" => self.prompt_config.git_behind = String::from(value),
+            "prompt.bottom_pinned" => self.prompt_config.bottom_pinned = Config::parse_bool_value(key, value)?,
+            "prompt.lang_flag_emoji" => self.prompt_config.lang_flag_emoji = Config::parse_bool_value(key, value)?,
+            "prompt.rev_search.label" => self.prompt_config.rev_search_label = String::from(value),
+            "prompt.translit_off" => self.prompt_config.translit_off = String::from(value),
+            "prompt.clock_format" => self.prompt_config.clock_format = String::from(value),
+            "prompt.wrkdir_home_tilde" => self.prompt_config.wrkdir_home_tilde = Config::parse_bool_value(key, value)?,
+            "prompt.wrkdir_max_components" => self.prompt_config.wrkdir_max_components = Config::parse_usize_value(key, value)?,
+            "prompt.rev_search.glyph" => self.prompt_config.rev_search_glyph = Some(String::from(value)),
+            "prompt.rev_search.color" => self.prompt_config.rev_search_color = match OutputConfig::parse_color(value) {
+                Ok(c) => c,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            "translit.gost_system" | "translit.standard" => {
+                let gost_system: Result<GostSystem, ()> = match key {
+                    "translit.standard" => TranslitConfig::parse_standard(value),
+                    _ => TranslitConfig::parse_gost_system(value),
+                };
+                let gost_system: GostSystem = match gost_system {
+                    Ok(g) => g,
+                    Err(_) => return Err(Config::bad_value(key, value)),
+                };
+                Config::check_language(self.language.as_str(), gost_system, &self.transliteration)?;
+                self.translit_config.gost_system = gost_system;
+            }
+            "translit.max_expression_depth" => self.translit_config.max_expression_depth = Config::parse_usize_value(key, value)?,
+            "translit.translate_args" => self.translit_config.translate_args = Config::parse_bool_value(key, value)?,
+            "history.store_original" => self.history_config.store_original = Config::parse_bool_value(key, value)?,
+            "history.autosave" => self.history_config.autosave = Config::parse_bool_value(key, value)?,
+            "history.format" => self.history_config.format = match HistoryConfig::parse_format(value) {
+                Ok(f) => f,
+                Err(_) => return Err(Config::bad_value(key, value)),
+            },
+            _ => return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: format!("Unknown configuration key '{}'", key),
+                key: Some(String::from(key)),
+            }),
+        };
+        Ok(())
+    }
+
+    /// ### parse_bool_value
+    ///
+    /// Parse the value of a boolean key provided as string
+    fn parse_bool_value(key: &str, value: &str) -> Result<bool, ConfigError> {
+        match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(Config::bad_value(key, value)),
+        }
+    }
+
+    /// ### parse_usize_value
+    ///
+    /// Parse the value of a numeric key provided as string
+    fn parse_usize_value(key: &str, value: &str) -> Result<usize, ConfigError> {
+        match value.parse::<usize>() {
+            Ok(v) => Ok(v),
+            Err(_) => Err(Config::bad_value(key, value)),
+        }
+    }
+
+    /// ### bad_value
+    ///
+    /// Make the error for a bad value provided for key
+    fn bad_value(key: &str, value: &str) -> ConfigError {
+        ConfigError {
+            code: ConfigErrorCode::YamlSyntaxError,
+            message: format!("Invalid value '{}' for '{}'", value, key),
+            key: Some(String::from(key)),
+        }
+    }
+
     /// ### parse_alias
     ///
     /// Parse alias in Pyc configuration file
@@ -231,6 +891,7 @@ impl Config {
             return Err(ConfigError {
                 code: ConfigErrorCode::YamlSyntaxError,
                 message: String::from("'alias' key is not an array"),
+                key: Some(String::from("alias")),
             });
         }
         let mut alias_table: HashMap<String, String> = HashMap::new();
@@ -245,6 +906,48 @@ impl Config {
         Ok(alias_table)
     }
 
+    /// ### parse_transliteration
+    ///
+    /// Parse the transliteration overrides: a map between a single character and the sequence it must be converted into
+    fn parse_transliteration(transliteration_yaml: &Yaml) -> Result<HashMap<char, String>, ConfigError> {
+        let map = match transliteration_yaml.as_hash() {
+            Some(map) => map,
+            None => {
+                return Err(ConfigError {
+                    code: ConfigErrorCode::YamlSyntaxError,
+                    message: String::from("'transliteration' key is not a map"),
+                    key: Some(String::from("transliteration")),
+                })
+            }
+        };
+        let mut overrides: HashMap<char, String> = HashMap::new();
+        for (key, value) in map.iter() {
+            let key: &str = key.as_str().unwrap_or("");
+            let mut chars = key.chars();
+            let ch: char = match (chars.next(), chars.next()) {
+                (Some(ch), None) => ch,
+                _ => {
+                    return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("'transliteration' keys must be single characters, found '{}'", key),
+                        key: Some(String::from("transliteration")),
+                    })
+                }
+            };
+            match value.as_str() {
+                Some(value) => overrides.insert(ch, String::from(value)),
+                None => {
+                    return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("'transliteration.{}' is not a string", key),
+                        key: Some(format!("transliteration.{}", key)),
+                    })
+                }
+            };
+        }
+        Ok(overrides)
+    }
+
     /// ### parse_language
     ///
     /// Parse language YAML object
@@ -254,19 +957,67 @@ impl Config {
             None => Err(ConfigError {
                 code: ConfigErrorCode::YamlSyntaxError,
                 message: String::from("'language' is not a string"),
+                key: Some(String::from("language")),
+            }),
+        }
+    }
+
+    /// ### check_language
+    ///
+    /// Check whether the language is supported and whether an IOProcessor can be built for it
+    /// with the provided GOST system
+    fn check_language(language: &str, gost_system: GostSystem, transliteration: &HashMap<char, String>) -> Result<(), ConfigError> {
+        let lang: Language = match Language::from_code(language) {
+            Some(l) => l,
+            None => return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: format!("Unknown language '{}'", language),
+                key: Some(String::from("language")),
+            }),
+        };
+        match IOProcessor::new(lang, new_gost_translator(lang, gost_system), transliteration.clone()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: format!("Could not build translator for language '{}': {}", language, err),
+                key: None,
             }),
         }
     }
 }
 
+/// ### yaml_string
+///
+/// Quote a string as a YAML double quoted scalar
+fn yaml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 impl ShellConfig {
+    /// ### default
+    ///
+    /// Instantiate a default ShellConfig struct
     pub fn default() -> ShellConfig {
         ShellConfig {
             exec: String::from("bash"),
-            args: vec![]
+            args: vec![],
+            path: vec![],
+            suggest_commands: false,
+            auto_cd: false,
+            layout_autocorrect: LayoutAutocorrect::Off,
+            restricted: false,
+            restricted_denylist: RESTRICTED_DENYLIST.iter().map(|p| Regex::new(p).unwrap()).collect(),
+            logout_file: None,
+            clear_mode: ClearMode::Erase,
+            stop_grace_ms: 500,
+            read_timeout_ms: 50,
+            read_buffer_size: 8192,
         }
     }
 
+    /// ### parse_config
+    ///
+    /// Parse a ShellConfig from YAML configuration file
     pub fn parse_config(shell_yaml: &Yaml) -> Result<ShellConfig, ConfigError> {
         let exec: String = match ConfigParser::get_string(&shell_yaml, String::from("exec")) {
             Ok(s) => s,
@@ -280,24 +1031,229 @@ impl ShellConfig {
                 for arg in args_yaml.as_vec().unwrap() {
                     args.push(match arg.as_str() {
                         Some(s) => String::from(s),
-                        None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("Shell arg is not a string")})
+                        None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("Shell arg is not a string"), key: Some(String::from("args"))})
                     });
                 }
                 args
             },
             Err(_) => Vec::new()
         };
+        //Directories to prepend to PATH (optional)
+        let path: Vec<String> = match ConfigParser::get_child(shell_yaml, String::from("path")) {
+            Ok(path_yaml) => {
+                let mut path: Vec<String> = Vec::new();
+                let dirs: &Vec<Yaml> = match path_yaml.as_vec() {
+                    Some(dirs) => dirs,
+                    None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("'path' key is not an array"), key: Some(String::from("path"))})
+                };
+                for dir in dirs.iter() {
+                    path.push(match dir.as_str() {
+                        Some(s) => String::from(s),
+                        None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("Shell path is not a string"), key: Some(String::from("path"))})
+                    });
+                }
+                path
+            },
+            Err(_) => Vec::new()
+        };
+        //Suggest commands when not found (optional)
+        let suggest_commands: bool = ConfigParser::get_bool(shell_yaml, String::from("suggest_commands")).unwrap_or_default();
+        //Auto cd (optional)
+        let auto_cd: bool = ConfigParser::get_bool(shell_yaml, String::from("auto_cd")).unwrap_or_default();
+        //Fix commands typed with the wrong keyboard layout (optional)
+        let layout_autocorrect: LayoutAutocorrect = match ConfigParser::get_string(shell_yaml, String::from("layout_autocorrect")) {
+            Ok(l) => match ShellConfig::parse_layout_autocorrect(l.as_str()) {
+                Ok(l) => l,
+                Err(_) => return Err(ConfigError {
+                    code: ConfigErrorCode::YamlSyntaxError,
+                    message: format!("'layout_autocorrect' must be 'off', 'suggest' or 'run', found '{}'", l),
+                    key: Some(String::from("layout_autocorrect")),
+                }),
+            },
+            Err(_) => LayoutAutocorrect::Off
+        };
+        //Restricted mode (optional)
+        let restricted: bool = ConfigParser::get_bool(shell_yaml, String::from("restricted")).unwrap_or_default();
+        //Commands denied in restricted mode (optional)
+        let restricted_denylist: Vec<Regex> = match ConfigParser::get_child(shell_yaml, String::from("restricted_denylist")) {
+            Ok(denylist_yaml) => {
+                let mut denylist: Vec<Regex> = Vec::new();
+                let patterns: &Vec<Yaml> = match denylist_yaml.as_vec() {
+                    Some(patterns) => patterns,
+                    None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("'restricted_denylist' key is not an array"), key: Some(String::from("restricted_denylist"))})
+                };
+                for pattern in patterns.iter() {
+                    let pattern: &str = match pattern.as_str() {
+                        Some(s) => s,
+                        None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("Denylist pattern is not a string"), key: Some(String::from("restricted_denylist"))})
+                    };
+                    //Patterns are compiled once here, so that an invalid one is reported as a configuration error
+                    match Regex::new(pattern) {
+                        Ok(re) => denylist.push(re),
+                        Err(err) => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: format!("Invalid denylist pattern '{}': {}", pattern, err), key: Some(String::from("restricted_denylist"))})
+                    }
+                }
+                denylist
+            },
+            Err(_) => RESTRICTED_DENYLIST.iter().map(|p| Regex::new(p).unwrap()).collect()
+        };
+        //Logout script (optional)
+        let logout_file: Option<String> = ConfigParser::get_string(shell_yaml, String::from("logout_file")).ok();
+        //Clear mode (optional)
+        let clear_mode: ClearMode = match ConfigParser::get_string(shell_yaml, String::from("clear_mode")) {
+            Ok(m) => match ShellConfig::parse_clear_mode(m.as_str()) {
+                Ok(m) => m,
+                Err(_) => return Err(ConfigError {
+                    code: ConfigErrorCode::YamlSyntaxError,
+                    message: format!("'clear_mode' must be 'erase', 'purge' or 'scroll', found '{}'", m),
+                    key: Some(String::from("clear_mode")),
+                }),
+            },
+            Err(_) => ClearMode::Erase
+        };
+        //Grace period before killing the shell (optional)
+        let stop_grace_ms: usize = ConfigParser::get_usize(shell_yaml, String::from("stop_grace_ms")).unwrap_or(500);
+        //Timeout of shell reads (optional)
+        let read_timeout_ms: usize = ConfigParser::get_usize(shell_yaml, String::from("read_timeout_ms")).unwrap_or(50);
+        //Read buffer size (optional)
+        let read_buffer_size: usize = match ConfigParser::get_usize(shell_yaml, String::from("read_buffer_size")) {
+            Ok(0) => return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: String::from("'read_buffer_size' must be greater than 0"),
+                key: Some(String::from("read_buffer_size")),
+            }),
+            Ok(size) => size,
+            Err(_) => 8192,
+        };
         Ok(ShellConfig {
-            exec: exec,
-            args: args
+            exec,
+            args,
+            path,
+            suggest_commands,
+            auto_cd,
+            layout_autocorrect,
+            restricted,
+            restricted_denylist,
+            logout_file,
+            clear_mode,
+            stop_grace_ms,
+            read_timeout_ms,
+            read_buffer_size,
+        })
+    }
+
+    /// ### parse_clear_mode
+    ///
+    /// Parse clear mode
+    fn parse_clear_mode(mode: &str) -> Result<ClearMode, ()> {
+        match mode {
+            "erase" => Ok(ClearMode::Erase),
+            "purge" => Ok(ClearMode::Purge),
+            "scroll" => Ok(ClearMode::Scroll),
+            _ => Err(()),
+        }
+    }
+
+    /// ### parse_layout_autocorrect
+    ///
+    /// Parse layout autocorrect mode
+    fn parse_layout_autocorrect(mode: &str) -> Result<LayoutAutocorrect, ()> {
+        match mode {
+            "off" => Ok(LayoutAutocorrect::Off),
+            "suggest" => Ok(LayoutAutocorrect::Suggest),
+            "run" => Ok(LayoutAutocorrect::Run),
+            _ => Err(()),
+        }
+    }
+}
+
+impl InputConfig {
+    /// ### default
+    ///
+    /// Instantiate a default InputConfig struct
+    pub fn default() -> InputConfig {
+        InputConfig {
+            filter_control_chars: true,
+            word_chars: None,
+            translit_toggle_key: Some(20),
+            recall_on_failure: false,
+            translate_heredoc: true,
+        }
+    }
+
+    /// ### parse_config
+    ///
+    /// Parse an InputConfig from YAML configuration file
+    pub fn parse_config(input_yaml: &Yaml) -> Result<InputConfig, ConfigError> {
+        if input_yaml.as_hash().is_none() {
+            return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: String::from("'input' key is not a map"),
+                key: None,
+            });
+        }
+        //Filter control chars (optional)
+        let filter_control_chars: bool =
+            ConfigParser::get_bool(input_yaml, String::from("filter_control_chars")).unwrap_or(true);
+        //Word chars (optional)
+        let word_chars: Option<String> =
+            ConfigParser::get_string(input_yaml, String::from("word_chars")).ok();
+        //Transliteration toggle key (optional)
+        let translit_toggle_key: Option<u8> = match ConfigParser::get_string(input_yaml, String::from("translit_toggle_key")) {
+            Ok(k) => match InputConfig::parse_ctrl_key(k.as_str()) {
+                Ok(k) => k,
+                Err(_) => return Err(ConfigError {
+                    code: ConfigErrorCode::YamlSyntaxError,
+                    message: format!("'translit_toggle_key' must be 'CTRL+<letter>' or 'none', found '{}'", k),
+                    key: Some(String::from("translit_toggle_key")),
+                }),
+            },
+            Err(_) => Some(20),
+        };
+        //Recall on failure (optional)
+        let recall_on_failure: bool =
+            ConfigParser::get_bool(input_yaml, String::from("recall_on_failure")).unwrap_or(false);
+        //Translate heredoc (optional)
+        let translate_heredoc: bool =
+            ConfigParser::get_bool(input_yaml, String::from("translate_heredoc")).unwrap_or(true);
+        Ok(InputConfig {
+            filter_control_chars,
+            word_chars,
+            translit_toggle_key,
+            recall_on_failure,
+            translate_heredoc,
         })
     }
+
+    /// ### parse_ctrl_key
+    ///
+    /// Parse a key in the 'CTRL+<letter>' format (case insensitive) into its control code.
+    /// 'none' disables the key
+    fn parse_ctrl_key(key: &str) -> Result<Option<u8>, ()> {
+        let key: String = key.to_ascii_uppercase();
+        if key == "NONE" {
+            return Ok(None);
+        }
+        let letter: &[u8] = key.strip_prefix("CTRL+").ok_or(())?.as_bytes();
+        match letter {
+            [l] if l.is_ascii_uppercase() => Ok(Some(l - b'A' + 1)),
+            _ => Err(()),
+        }
+    }
 }
 
 impl OutputConfig {
     pub fn default() -> OutputConfig {
         OutputConfig {
             translate_output: true,
+            normalize: OutputNormalization::None,
+            error_color: Some(Colour::Red),
+            squelch_repeats: false,
+            realign_tabs: false,
+            max_lines_per_sec: None,
+            collapse_progress: true,
+            echo_typed: true,
+            buffering: OutputBuffering::Raw,
         }
     }
 
@@ -307,50 +1263,341 @@ impl OutputConfig {
                 Ok(t) => t,
                 Err(err) => return Err(err),
             };
+        //Normalize (optional)
+        let normalize: OutputNormalization =
+            match ConfigParser::get_string(output_yaml, String::from("normalize")) {
+                Ok(n) => match OutputConfig::parse_normalization(n.as_str()) {
+                    Ok(n) => n,
+                    Err(_) => return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("'normalize' must be 'none' or 'nfc', found '{}'", n),
+                        key: Some(String::from("normalize")),
+                    }),
+                },
+                Err(_) => OutputNormalization::None,
+            };
+        //Error color (optional)
+        let error_color: Option<Colour> =
+            match ConfigParser::get_string(output_yaml, String::from("error_color")) {
+                Ok(color) => match OutputConfig::parse_color(color.as_str()) {
+                    Ok(c) => c,
+                    Err(_) => return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("Unknown color '{}' for 'error_color'", color),
+                        key: Some(String::from("error_color")),
+                    }),
+                },
+                Err(_) => Some(Colour::Red),
+            };
+        //Squelch repeats (optional)
+        let squelch_repeats: bool =
+            ConfigParser::get_bool(output_yaml, String::from("squelch_repeats")).unwrap_or_default();
+        //Realign tabs (optional)
+        let realign_tabs: bool =
+            ConfigParser::get_bool(output_yaml, String::from("realign_tabs")).unwrap_or_default();
+        //Max lines per second (optional; 0 means unlimited)
+        let max_lines_per_sec: Option<usize> =
+            match ConfigParser::get_usize(output_yaml, String::from("max_lines_per_sec")) {
+                Ok(0) => None,
+                Ok(m) => Some(m),
+                Err(_) => None,
+            };
+        //Collapse progress lines (optional)
+        let collapse_progress: bool =
+            ConfigParser::get_bool(output_yaml, String::from("collapse_progress")).unwrap_or(true);
+        //Echo typed input (optional)
+        let echo_typed: bool =
+            ConfigParser::get_bool(output_yaml, String::from("echo_typed")).unwrap_or(true);
+        //Buffering (optional)
+        let buffering: OutputBuffering =
+            match ConfigParser::get_string(output_yaml, String::from("buffering")) {
+                Ok(b) => match OutputConfig::parse_buffering(b.as_str()) {
+                    Ok(b) => b,
+                    Err(_) => return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("'buffering' must be 'raw' or 'line', found '{}'", b),
+                        key: Some(String::from("buffering")),
+                    }),
+                },
+                Err(_) => OutputBuffering::Raw,
+            };
         Ok(OutputConfig {
-            translate_output: translate_output,
+            translate_output,
+            normalize,
+            error_color,
+            squelch_repeats,
+            realign_tabs,
+            max_lines_per_sec,
+            collapse_progress,
+            echo_typed,
+            buffering,
         })
     }
+
+    /// ### parse_buffering
+    ///
+    /// Parse output buffering mode
+    fn parse_buffering(buffering: &str) -> Result<OutputBuffering, ()> {
+        match buffering {
+            "raw" => Ok(OutputBuffering::Raw),
+            "line" => Ok(OutputBuffering::Line),
+            _ => Err(()),
+        }
+    }
+
+    /// ### parse_normalization
+    ///
+    /// Parse normalization name
+    fn parse_normalization(normalization: &str) -> Result<OutputNormalization, ()> {
+        match normalization {
+            "none" => Ok(OutputNormalization::None),
+            "nfc" => Ok(OutputNormalization::Nfc),
+            _ => Err(()),
+        }
+    }
+
+    /// ### parse_color
+    ///
+    /// Parse color name; 'none' means no color
+    fn parse_color(color: &str) -> Result<Option<Colour>, ()> {
+        match color.to_lowercase().as_str() {
+            "none" => Ok(None),
+            "black" => Ok(Some(Colour::Black)),
+            "red" => Ok(Some(Colour::Red)),
+            "green" => Ok(Some(Colour::Green)),
+            "yellow" => Ok(Some(Colour::Yellow)),
+            "blue" => Ok(Some(Colour::Blue)),
+            "purple" | "magenta" => Ok(Some(Colour::Purple)),
+            "cyan" => Ok(Some(Colour::Cyan)),
+            "white" => Ok(Some(Colour::White)),
+            _ => Err(()),
+        }
+    }
 }
 
-impl PromptConfig {
+impl TranslitConfig {
     /// ### default
     ///
-    /// Instantiate a default PromptConfig struct
-    pub fn default() -> PromptConfig {
-        PromptConfig {
-            prompt_line: String::from("${USER}@${HOSTNAME}:${WRKDIR}$"),
-            history_size: 256,
-            translate: false,
-            break_enabled: false,
-            break_str: String::from("❯"),
-            min_duration: 2000,
-            rc_ok: String::from("✔"),
-            rc_err: String::from("✖"),
-            git_branch: String::from("on "),
-            git_commit_ref: 8,
-            git_commit_append: None,
-            git_commit_prepend: None
+    /// Instantiate a default TranslitConfig struct
+    pub fn default() -> TranslitConfig {
+        TranslitConfig {
+            gost_system: GostSystem::B,
+            max_expression_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
+            translate_args: true,
         }
     }
 
     /// ### parse_config
     ///
-    /// Parse a PromptConfig from YAML configuration file
-    pub fn parse_config(prompt_config_yaml: &Yaml) -> Result<PromptConfig, ConfigError> {
-        //Prompt line
-        let prompt_line: String =
-            match ConfigParser::get_string(&prompt_config_yaml, String::from("prompt_line")) {
-                Ok(ret) => ret,
-                Err(err) => return Err(err),
-            };
-        //History size
-        let history_size: usize =
-            match ConfigParser::get_usize(&prompt_config_yaml, String::from("history_size")) {
+    /// Parse a TranslitConfig from YAML configuration file
+    pub fn parse_config(translit_yaml: &Yaml) -> Result<TranslitConfig, ConfigError> {
+        if translit_yaml.as_hash().is_none() {
+            return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: String::from("'translit' key is not a map"),
+                key: None,
+            });
+        }
+        //GOST system (optional)
+        let gost_system: GostSystem =
+            match ConfigParser::get_string(translit_yaml, String::from("gost_system")) {
+                Ok(g) => match TranslitConfig::parse_gost_system(g.as_str()) {
+                    Ok(g) => g,
+                    Err(_) => return Err(ConfigError {
+                        code: ConfigErrorCode::YamlSyntaxError,
+                        message: format!("'gost_system' must be 'a' or 'b', found '{}'", g),
+                        key: Some(String::from("gost_system")),
+                    }),
+                },
+                Err(_) => GostSystem::B,
+            };
+        //Transliteration standard (optional); an alternative way to select the GOST system
+        let gost_system: GostSystem =
+            match ConfigParser::get_string(translit_yaml, String::from("standard")) {
+                Ok(s) => {
+                    if ConfigParser::get_string(translit_yaml, String::from("gost_system")).is_ok() {
+                        return Err(ConfigError {
+                            code: ConfigErrorCode::YamlSyntaxError,
+                            message: String::from("'standard' and 'gost_system' can't be both set"),
+                            key: Some(String::from("standard")),
+                        });
+                    }
+                    match TranslitConfig::parse_standard(s.as_str()) {
+                        Ok(g) => g,
+                        Err(_) => return Err(ConfigError {
+                            code: ConfigErrorCode::YamlSyntaxError,
+                            message: format!("'standard' must be 'gost' or 'iso9', found '{}'", s),
+                            key: Some(String::from("standard")),
+                        }),
+                    }
+                }
+                Err(_) => gost_system,
+            };
+        //Maximum expression depth (optional)
+        let max_expression_depth: usize =
+            ConfigParser::get_usize(translit_yaml, String::from("max_expression_depth")).unwrap_or(DEFAULT_MAX_EXPRESSION_DEPTH);
+        //Translate args (optional)
+        let translate_args: bool = ConfigParser::get_bool(translit_yaml, String::from("translate_args")).unwrap_or(true);
+        Ok(TranslitConfig {
+            gost_system,
+            max_expression_depth,
+            translate_args,
+        })
+    }
+
+    /// ### parse_standard
+    ///
+    /// Parse transliteration standard name into the GOST 7.79-2000 system implementing it:
+    /// ISO 9 is the same as System A, while 'gost' is the command-oriented System B
+    fn parse_standard(standard: &str) -> Result<GostSystem, ()> {
+        match standard.to_lowercase().as_str() {
+            "gost" => Ok(GostSystem::B),
+            "iso9" => Ok(GostSystem::A),
+            _ => Err(()),
+        }
+    }
+
+    /// ### parse_gost_system
+    ///
+    /// Parse GOST 7.79-2000 system name
+    fn parse_gost_system(system: &str) -> Result<GostSystem, ()> {
+        match system.to_lowercase().as_str() {
+            "a" => Ok(GostSystem::A),
+            "b" => Ok(GostSystem::B),
+            _ => Err(()),
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// ### default
+    ///
+    /// Instantiate a default HistoryConfig struct
+    pub fn default() -> HistoryConfig {
+        HistoryConfig {
+            store_original: false,
+            format: HistoryFormat::Plain,
+            autosave: false,
+        }
+    }
+
+    /// ### parse_config
+    ///
+    /// Parse a HistoryConfig from YAML configuration file
+    pub fn parse_config(history_yaml: &Yaml) -> Result<HistoryConfig, ConfigError> {
+        if history_yaml.as_hash().is_none() {
+            return Err(ConfigError {
+                code: ConfigErrorCode::YamlSyntaxError,
+                message: String::from("'history' key is not a map"),
+                key: None,
+            });
+        }
+        //Store original input (optional)
+        let store_original: bool =
+            ConfigParser::get_bool(history_yaml, String::from("store_original")).unwrap_or_default();
+        //History file format (optional)
+        let format: HistoryFormat = match ConfigParser::get_string(history_yaml, String::from("format")) {
+            Ok(f) => match HistoryConfig::parse_format(f.as_str()) {
+                Ok(f) => f,
+                Err(_) => return Err(ConfigError {
+                    code: ConfigErrorCode::YamlSyntaxError,
+                    message: format!("'format' must be 'plain' or 'json', found '{}'", f),
+                    key: Some(String::from("format")),
+                }),
+            },
+            Err(_) => HistoryFormat::Plain,
+        };
+        //Autosave (optional)
+        let autosave: bool =
+            ConfigParser::get_bool(history_yaml, String::from("autosave")).unwrap_or(false);
+        Ok(HistoryConfig {
+            store_original,
+            format,
+            autosave,
+        })
+    }
+
+    /// ### parse_format
+    ///
+    /// Parse history file format
+    fn parse_format(format: &str) -> Result<HistoryFormat, ()> {
+        match format {
+            "plain" => Ok(HistoryFormat::Plain),
+            "json" => Ok(HistoryFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+impl PromptConfig {
+    /// ### is_module_disabled
+    ///
+    /// Returns whether the provided prompt module has been disabled by the user
+    pub fn is_module_disabled(&self, module: &str) -> bool {
+        self.disabled_modules.iter().any(|m| m == module)
+    }
+
+    /// ### default
+    ///
+    /// Instantiate a default PromptConfig struct
+    pub fn default() -> PromptConfig {
+        PromptConfig {
+            prompt_line: String::from("${USER}@${HOSTNAME}:${WRKDIR}$"),
+            first_prompt_line: None,
+            rprompt_line: None,
+            history_size: 256,
+            history_ignore_dups: true,
+            translate: false,
+            break_enabled: false,
+            break_str: String::from("❯"),
+            min_duration: 2000,
+            duration_label: String::from("took"),
+            duration_separator: String::from("."),
+            duration_unit_ms: None,
+            duration_unit_s: String::from("s"),
+            duration_unit_m: None,
+            rc_ok: String::from("✔"),
+            rc_err: String::from("✖"),
+            git_branch: String::from("on "),
+            git_commit_ref: 8,
+            git_commit_append: None,
+            git_commit_prepend: None,
+            git_dirty: String::from("*"),
+            git_ahead: String::from("↑"),
+            git_behind: String::from("↓"),
+            bottom_pinned: false,
+            lang_flag_emoji: true,
+            disabled_modules: Vec::new(),
+            rev_search_label: String::from("(reverse-i-search)"),
+            rev_search_glyph: None,
+            rev_search_color: None,
+            translit_off: String::from("(translit off)"),
+            clock_format: String::from("%H:%M:%S"),
+            wrkdir_home_tilde: false,
+            wrkdir_max_components: 0,
+        }
+    }
+
+    /// ### parse_config
+    ///
+    /// Parse a PromptConfig from YAML configuration file
+    pub fn parse_config(prompt_config_yaml: &Yaml) -> Result<PromptConfig, ConfigError> {
+        //Prompt line
+        let prompt_line: String =
+            match ConfigParser::get_string(&prompt_config_yaml, String::from("prompt_line")) {
                 Ok(ret) => ret,
                 Err(err) => return Err(err),
             };
         //History size
+        let history_size: usize =
+            match ConfigParser::get_usize(&prompt_config_yaml, String::from("history_size")) {
+                Ok(ret) => ret,
+                Err(err) => return Err(err),
+            };
+        //Ignore consecutive duplicates in history (optional)
+        let history_ignore_dups: bool =
+            ConfigParser::get_bool(prompt_config_yaml, String::from("history_ignore_dups")).unwrap_or(true);
+        //Translate
         let translate: bool =
             match ConfigParser::get_bool(&prompt_config_yaml, String::from("translate")) {
                 Ok(ret) => ret,
@@ -364,12 +1611,12 @@ impl PromptConfig {
         //Break enabled
         let break_enabled: bool = match ConfigParser::get_bool(&brk, String::from("enabled")) {
             Ok(ret) => ret,
-            Err(err) => return Err(err),
+            Err(err) => return Err(err.in_section("break")),
         };
         //Break with
         let break_str: String = match ConfigParser::get_string(&brk, String::from("with")) {
             Ok(ret) => ret,
-            Err(err) => return Err(err),
+            Err(err) => return Err(err.in_section("break")),
         };
         //Duration
         let duration: &Yaml =
@@ -381,8 +1628,31 @@ impl PromptConfig {
         let min_duration: usize =
             match ConfigParser::get_usize(&duration, String::from("min_elapsed_time")) {
                 Ok(ret) => ret,
-                Err(err) => return Err(err),
+                Err(err) => return Err(err.in_section("duration")),
             };
+        //Label (optional)
+        let duration_label: String = ConfigParser::get_string(duration, String::from("label")).unwrap_or_else(|_| String::from("took"));
+        //Decimal separator (optional)
+        let duration_separator: String =
+            match ConfigParser::get_string(duration, String::from("decimal_separator")) {
+                Ok(ret) => ret,
+                Err(_) => String::from("."),
+            };
+        //Unit labels (optional)
+        let units: Option<&Yaml> = ConfigParser::get_child(duration, String::from("units")).ok();
+        //Milliseconds and minutes are used only if their label is configured
+        let duration_unit_ms: Option<String> = match units.map(|u| ConfigParser::get_string(u, String::from("ms"))) {
+            Some(Ok(ret)) => Some(ret),
+            _ => None,
+        };
+        let duration_unit_s: String = match units.map(|u| ConfigParser::get_string(u, String::from("s"))) {
+            Some(Ok(ret)) => ret,
+            _ => String::from("s"),
+        };
+        let duration_unit_m: Option<String> = match units.map(|u| ConfigParser::get_string(u, String::from("m"))) {
+            Some(Ok(ret)) => Some(ret),
+            _ => None,
+        };
         //Rc
         let rc: &Yaml = match ConfigParser::get_child(&prompt_config_yaml, String::from("rc")) {
             Ok(ret) => ret,
@@ -391,12 +1661,12 @@ impl PromptConfig {
         //Rc_ok
         let rc_ok: String = match ConfigParser::get_string(&rc, String::from("ok")) {
             Ok(ret) => ret,
-            Err(err) => return Err(err),
+            Err(err) => return Err(err.in_section("rc")),
         };
         //Rc err
         let rc_err: String = match ConfigParser::get_string(&rc, String::from("error")) {
             Ok(ret) => ret,
-            Err(err) => return Err(err),
+            Err(err) => return Err(err.in_section("rc")),
         };
         //Git
         let git: &Yaml = match ConfigParser::get_child(&prompt_config_yaml, String::from("git")) {
@@ -406,13 +1676,13 @@ impl PromptConfig {
         //Git branch
         let git_branch: String = match ConfigParser::get_string(&git, String::from("branch")) {
             Ok(ret) => ret,
-            Err(err) => return Err(err),
+            Err(err) => return Err(err.in_section("git")),
         };
         //Git commit ref
         let git_commit_ref: usize =
             match ConfigParser::get_usize(&git, String::from("commit_ref_len")) {
                 Ok(ret) => ret,
-                Err(err) => return Err(err),
+                Err(err) => return Err(err.in_section("git")),
             };
         //Git commit prepend
         let git_commit_prepend: Option<String> =
@@ -426,19 +1696,111 @@ impl PromptConfig {
                 Ok(ret) => Some(ret),
                 Err(_) => None,
             };
+        //Git dirty indicator (optional)
+        let git_dirty: String = ConfigParser::get_string(git, String::from("dirty")).unwrap_or_else(|_| String::from("*"));
+        //Git ahead/behind labels (optional)
+        let git_ahead: String = ConfigParser::get_string(git, String::from("ahead")).unwrap_or_else(|_| String::from("↑"));
+        let git_behind: String = ConfigParser::get_string(git, String::from("behind")).unwrap_or_else(|_| String::from("↓"));
+        //First prompt line (optional)
+        let first_prompt_line: Option<String> =
+            ConfigParser::get_string(prompt_config_yaml, String::from("first_prompt_line")).ok();
+        //Right prompt line (optional)
+        let rprompt_line: Option<String> =
+            ConfigParser::get_string(prompt_config_yaml, String::from("rprompt_line")).ok();
+        //Bottom pinned (optional)
+        let bottom_pinned: bool =
+            ConfigParser::get_bool(prompt_config_yaml, String::from("bottom_pinned")).unwrap_or_default();
+        //Lang flag emoji (optional)
+        let lang_flag_emoji: bool =
+            ConfigParser::get_bool(prompt_config_yaml, String::from("lang_flag_emoji")).unwrap_or(true);
+        //Disabled modules (optional)
+        let disabled_modules: Vec<String> = match ConfigParser::get_child(prompt_config_yaml, String::from("disabled_modules")) {
+            Ok(modules_yaml) => {
+                let mut disabled_modules: Vec<String> = Vec::new();
+                let modules: &Vec<Yaml> = match modules_yaml.as_vec() {
+                    Some(modules) => modules,
+                    None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("'disabled_modules' key is not an array"), key: Some(String::from("disabled_modules"))})
+                };
+                for module in modules.iter() {
+                    let module: String = match module.as_str() {
+                        Some(s) => String::from(s),
+                        None => return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: String::from("Prompt module is not a string"), key: Some(String::from("disabled_modules"))})
+                    };
+                    if ! PROMPT_MODULES.contains(&module.as_str()) {
+                        return Err(ConfigError {code: ConfigErrorCode::YamlSyntaxError, message: format!("Unknown prompt module '{}'", module), key: Some(String::from("disabled_modules"))});
+                    }
+                    disabled_modules.push(module);
+                }
+                disabled_modules
+            },
+            Err(_) => Vec::new()
+        };
+        //Reverse search (optional)
+        let (rev_search_label, rev_search_glyph, rev_search_color): (String, Option<String>, Option<Colour>) = match ConfigParser::get_child(prompt_config_yaml, String::from("rev_search")) {
+            Ok(rev_search) => {
+                let label: String = match ConfigParser::get_string(rev_search, String::from("label")) {
+                    Ok(ret) => ret,
+                    Err(_) => String::from("(reverse-i-search)"),
+                };
+                let glyph: Option<String> = ConfigParser::get_string(rev_search, String::from("glyph")).ok();
+                let color: Option<Colour> = match ConfigParser::get_string(rev_search, String::from("color")) {
+                    Ok(color) => match OutputConfig::parse_color(color.as_str()) {
+                        Ok(c) => c,
+                        Err(_) => return Err(ConfigError {
+                            code: ConfigErrorCode::YamlSyntaxError,
+                            message: format!("Unknown color '{}' for 'rev_search.color'", color),
+                            key: Some(String::from("rev_search.color")),
+                        }),
+                    },
+                    Err(_) => None,
+                };
+                (label, glyph, color)
+            },
+            Err(_) => (String::from("(reverse-i-search)"), None, None)
+        };
+        //Transliteration bypass indicator (optional)
+        let translit_off: String = ConfigParser::get_string(prompt_config_yaml, String::from("translit_off")).unwrap_or_else(|_| String::from("(translit off)"));
+        //Clock format (optional)
+        let clock_format: String = ConfigParser::get_string(prompt_config_yaml, String::from("clock_format")).unwrap_or_else(|_| String::from("%H:%M:%S"));
+        //Working directory format (optional)
+        let wrkdir_home_tilde: bool =
+            ConfigParser::get_bool(prompt_config_yaml, String::from("wrkdir_home_tilde")).unwrap_or(false);
+        let wrkdir_max_components: usize =
+            ConfigParser::get_usize(prompt_config_yaml, String::from("wrkdir_max_components")).unwrap_or(0);
         Ok(PromptConfig {
-            prompt_line: prompt_line,
-            history_size: history_size,
-            translate: translate,
-            break_enabled: break_enabled,
-            break_str: break_str,
-            min_duration: min_duration,
-            rc_ok: rc_ok,
-            rc_err: rc_err,
-            git_branch: git_branch,
-            git_commit_ref: git_commit_ref,
-            git_commit_append: git_commit_append,
-            git_commit_prepend: git_commit_prepend
+            prompt_line,
+            first_prompt_line,
+            rprompt_line,
+            history_size,
+            history_ignore_dups,
+            translate,
+            break_enabled,
+            break_str,
+            min_duration,
+            duration_label,
+            duration_separator,
+            duration_unit_ms,
+            duration_unit_s,
+            duration_unit_m,
+            rc_ok,
+            rc_err,
+            git_branch,
+            git_commit_ref,
+            git_commit_append,
+            git_commit_prepend,
+            git_dirty,
+            git_ahead,
+            git_behind,
+            bottom_pinned,
+            lang_flag_emoji,
+            disabled_modules,
+            rev_search_label,
+            rev_search_glyph,
+            rev_search_color,
+            translit_off,
+            clock_format,
+            wrkdir_home_tilde,
+            wrkdir_max_components,
         })
     }
 }
@@ -453,22 +1815,50 @@ mod tests {
         let config: Config = Config::default();
         assert!(config.get_alias(&String::from("чд")).is_none());
         assert_eq!(config.output_config.translate_output, true);
+        assert_eq!(config.output_config.normalize, OutputNormalization::None);
+        assert!(config.output_config.echo_typed);
         assert_eq!(config.language, String::from("ru"));
         let prompt_config: PromptConfig = config.prompt_config;
         assert_eq!(prompt_config.prompt_line, String::from("${USER}@${HOSTNAME}:${WRKDIR}$"));
+        assert_eq!(prompt_config.first_prompt_line, None);
+        assert_eq!(prompt_config.rprompt_line, None);
         assert_eq!(prompt_config.break_enabled, false);
         assert_eq!(prompt_config.break_str, String::from("❯"));
         assert_eq!(prompt_config.git_branch, String::from("on "));
         assert_eq!(prompt_config.git_commit_ref, 8);
         assert_eq!(prompt_config.git_commit_prepend, None);
         assert_eq!(prompt_config.git_commit_append, None);
+        assert_eq!(prompt_config.git_dirty, String::from("*"));
+        assert_eq!(prompt_config.git_ahead, String::from("↑"));
+        assert_eq!(prompt_config.git_behind, String::from("↓"));
         assert_eq!(prompt_config.history_size, 256);
         assert_eq!(prompt_config.min_duration, 2000);
+        assert_eq!(prompt_config.duration_label, String::from("took"));
+        assert_eq!(prompt_config.duration_separator, String::from("."));
+        assert_eq!(prompt_config.duration_unit_ms, None);
+        assert_eq!(prompt_config.duration_unit_s, String::from("s"));
+        assert_eq!(prompt_config.duration_unit_m, None);
         assert_eq!(prompt_config.rc_err, String::from("✖"));
         assert_eq!(prompt_config.rc_ok, String::from("✔"));
-        assert_eq!(prompt_config.translate, false);
+        assert!(!prompt_config.translate);
+        assert!(!prompt_config.bottom_pinned);
+        assert!(prompt_config.lang_flag_emoji);
+        assert!(prompt_config.history_ignore_dups);
+        assert_eq!(prompt_config.rev_search_label, String::from("(reverse-i-search)"));
+        assert_eq!(prompt_config.rev_search_glyph, None);
+        assert_eq!(prompt_config.rev_search_color, None);
+        assert_eq!(prompt_config.translit_off, String::from("(translit off)"));
+        assert_eq!(prompt_config.clock_format, String::from("%H:%M:%S"));
+        assert!(!prompt_config.wrkdir_home_tilde);
+        assert_eq!(prompt_config.wrkdir_max_components, 0);
+        assert_eq!(config.input_config.translit_toggle_key, Some(20));
         assert_eq!(config.shell_config.exec, String::from("bash"));
         assert_eq!(config.shell_config.args.len(), 0);
+        assert_eq!(config.shell_config.stop_grace_ms, 500);
+        assert_eq!(config.translit_config.gost_system, GostSystem::B);
+        assert!(!config.history_config.store_original);
+        assert_eq!(config.history_config.format, HistoryFormat::Plain);
+        assert!(config.config_file.is_none());
     }
 
     #[test]
@@ -477,9 +1867,10 @@ mod tests {
         let config_file: tempfile::NamedTempFile = write_config_file_en();
         let config_file_path: PathBuf = PathBuf::from(config_file.path().to_str().unwrap());
         println!("Generated config file: {}", config_file_path.display());
-        let config: Result<Config, ConfigError> =Config::parse_config(config_file_path);
+        let config: Result<Config, ConfigError> =Config::parse_config(config_file_path.clone());
         assert!(config.is_ok());
         let config: Config = config.ok().unwrap();
+        assert_eq!(config.config_file, Some(config_file_path));
         // Verify parameters
         assert!(config.get_alias(&String::from("чд")).is_some());
         assert_eq!(config.output_config.translate_output, true);
@@ -492,6 +1883,9 @@ mod tests {
         assert_eq!(prompt_config.git_commit_ref, 8);
         assert_eq!(prompt_config.git_commit_prepend, None);
         assert_eq!(prompt_config.git_commit_append, None);
+        assert_eq!(prompt_config.git_dirty, String::from("*"));
+        assert_eq!(prompt_config.git_ahead, String::from("↑"));
+        assert_eq!(prompt_config.git_behind, String::from("↓"));
         assert_eq!(prompt_config.history_size, 256);
         assert_eq!(prompt_config.min_duration, 2000);
         assert_eq!(prompt_config.rc_err, String::from("✖"));
@@ -502,6 +1896,111 @@ mod tests {
         
     }
 
+    #[test]
+    fn test_config_files_merge() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let base: PathBuf = tmpdir.path().join("base.yml");
+        let overrides: PathBuf = tmpdir.path().join("overrides.yml");
+        std::fs::write(
+            base.as_path(),
+            "language: ru\nalias:\n  - чд: \"cd\"\n  - пвд: \"pwd\"\nshell:\n  exec: \"bash\"\n  args:\n    - \"-l\"\n  suggest_commands: true\noutput:\n  translate: true\n",
+        ).unwrap();
+        std::fs::write(
+            overrides.as_path(),
+            "language: by\nalias:\n  - пвд: \"pwd -P\"\n  - лс: \"ls\"\nshell:\n  exec: \"zsh\"\n",
+        ).unwrap();
+        let config: Config = Config::parse_config_files(&[base.clone(), overrides.clone()]).ok().unwrap();
+        //Scalars are overridden
+        assert_eq!(config.language, String::from("by"));
+        assert_eq!(config.shell_config.exec, String::from("zsh"));
+        //Values not set in overrides are kept
+        assert_eq!(config.shell_config.args, vec![String::from("-l")]);
+        assert!(config.shell_config.suggest_commands);
+        assert!(config.output_config.translate_output);
+        //Aliases are joined
+        assert_eq!(config.alias.len(), 3);
+        assert_eq!(config.get_alias(&String::from("чд")), Some(String::from("cd")));
+        assert_eq!(config.get_alias(&String::from("пвд")), Some(String::from("pwd -P")));
+        assert_eq!(config.get_alias(&String::from("лс")), Some(String::from("ls")));
+        //Files
+        assert_eq!(config.config_file, Some(overrides.clone()));
+        assert_eq!(config.config_layers, vec![base.clone()]);
+        //Single file
+        let config: Config = Config::parse_config(base.clone()).ok().unwrap();
+        assert_eq!(config.language, String::from("ru"));
+        assert_eq!(config.config_layers.len(), 0);
+        //Missing layer
+        let missing: PathBuf = tmpdir.path().join("missing.yml");
+        let err: ConfigError = Config::parse_config_files(&[base.clone(), missing.clone()]).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::NoSuchFileOrDirectory);
+        assert!(err.message.contains(missing.to_str().unwrap()));
+        //Invalid layer
+        let invalid: PathBuf = tmpdir.path().join("invalid.yml");
+        std::fs::write(invalid.as_path(), "language: [ru\n").unwrap();
+        let err: ConfigError = Config::parse_config_files(&[base, invalid.clone()]).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::YamlSyntaxError);
+        assert!(err.message.starts_with(invalid.to_str().unwrap()));
+        assert_eq!(Config::parse_config_files(&[]).err().unwrap().code, ConfigErrorCode::NoSuchFileOrDirectory);
+    }
+
+    #[test]
+    fn test_config_include() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmpdir.path().join("machines")).unwrap();
+        let base: PathBuf = tmpdir.path().join("pyc.yml");
+        let child: PathBuf = tmpdir.path().join("machines").join("laptop.yml");
+        std::fs::write(
+            base.as_path(),
+            "language: ru\ninclude:\n  - \"machines/laptop.yml\"\nalias:\n  - чд: \"cd\"\n  - пвд: \"pwd\"\nshell:\n  exec: \"bash\"\n",
+        ).unwrap();
+        std::fs::write(
+            child.as_path(),
+            "language: by\nalias:\n  - пвд: \"pwd -P\"\n  - лс: \"ls\"\n",
+        ).unwrap();
+        let config: Config = Config::parse_config(base.clone()).ok().unwrap();
+        //Included file overrides scalars and adds aliases
+        assert_eq!(config.language, String::from("by"));
+        assert_eq!(config.shell_config.exec, String::from("bash"));
+        assert_eq!(config.alias.len(), 3);
+        assert_eq!(config.get_alias(&String::from("чд")), Some(String::from("cd")));
+        assert_eq!(config.get_alias(&String::from("пвд")), Some(String::from("pwd -P")));
+        assert_eq!(config.get_alias(&String::from("лс")), Some(String::from("ls")));
+        assert_eq!(config.config_file, Some(base.clone()));
+        //Include cycle
+        std::fs::write(child.as_path(), "language: by\ninclude:\n  - \"../pyc.yml\"\n").unwrap();
+        let err: ConfigError = Config::parse_config(base.clone()).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::IncludeError);
+        assert_eq!(err.key, Some(String::from("include")));
+        assert!(err.message.contains("cycle"));
+        //Self include
+        std::fs::write(child.as_path(), "include:\n  - \"laptop.yml\"\n").unwrap();
+        assert_eq!(Config::parse_config(base.clone()).err().unwrap().code, ConfigErrorCode::IncludeError);
+        //Too many nested includes
+        for i in 0..CONFIG_INCLUDE_MAX_DEPTH + 1 {
+            std::fs::write(tmpdir.path().join(format!("{}.yml", i)), format!("include:\n  - \"{}.yml\"\n", i + 1)).unwrap();
+        }
+        std::fs::write(tmpdir.path().join(format!("{}.yml", CONFIG_INCLUDE_MAX_DEPTH + 1)), "language: by\n").unwrap();
+        let err: ConfigError = Config::parse_config(tmpdir.path().join("0.yml")).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::IncludeError);
+        assert!(err.message.contains("too many nested includes"));
+        std::fs::write(tmpdir.path().join(format!("{}.yml", CONFIG_INCLUDE_MAX_DEPTH)), "language: by\n").unwrap();
+        assert_eq!(Config::parse_config(tmpdir.path().join("0.yml")).ok().unwrap().language, String::from("by"));
+        //Missing include
+        std::fs::write(child.as_path(), "include:\n  - \"missing.yml\"\n").unwrap();
+        let err: ConfigError = Config::parse_config(base.clone()).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::IncludeError);
+        assert!(err.message.contains("missing.yml"));
+        //Invalid include
+        assert_eq!(
+            Config::parse_config_str(String::from("include: \"pyc.yml\"\n")).err().unwrap().code,
+            ConfigErrorCode::IncludeError
+        );
+        assert_eq!(
+            Config::parse_config_str(String::from("include:\n  - 1\n")).err().unwrap().code,
+            ConfigErrorCode::IncludeError
+        );
+    }
+
     #[test]
     fn test_config_no_file() {
         assert_eq!(
@@ -564,6 +2063,16 @@ mod tests {
         assert!(config.get_alias(&String::from("чд")).is_none());
     }
 
+    #[test]
+    fn test_config_unknown_language() {
+        let config: String = String::from("language: xx\n");
+        let err: ConfigError = Config::parse_config_str(config).err().unwrap();
+        assert_eq!(err.code, ConfigErrorCode::YamlSyntaxError);
+        assert_eq!(err.message, String::from("Unknown language 'xx'"));
+        let config: String = String::from("language: бг\n");
+        assert!(Config::parse_config_str(config).is_ok());
+    }
+
     #[test]
     fn test_config_alias_not_array() {
         let config: String = String::from("alias: 5\n");
@@ -573,6 +2082,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_transliteration() {
+        let config: String = String::from("language: ru\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.transliteration.is_empty());
+        let config: String = String::from("transliteration:\n  ж: \"zh\"\n  j: \"дж\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.transliteration.len(), 2);
+        assert_eq!(*config.transliteration.get(&'ж').unwrap(), String::from("zh"));
+        assert_eq!(*config.transliteration.get(&'j').unwrap(), String::from("дж"));
+    }
+
+    #[test]
+    fn test_config_transliteration_bad() {
+        //Not a map
+        let config: String = String::from("transliteration: 5\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+        //Key is not a single character
+        let config: String = String::from("transliteration:\n  жж: \"zh\"\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+        //Value is not a string
+        let config: String = String::from("transliteration:\n  ж: [z, h]\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
     #[test]
     fn test_config_shell_config() {
         let config: String = String::from("shell:\n  exec: \"sh\"\n  args:\n    - \"-l\"\n    - \"-h\"\n");
@@ -581,6 +2124,154 @@ mod tests {
         assert_eq!(config.shell_config.args, vec![String::from("-l"), String::from("-h")]);
     }
 
+    #[test]
+    fn test_config_shell_config_zsh() {
+        let config: String = String::from("shell:\n  exec: /bin/zsh\n  args: [-i]\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.exec, String::from("/bin/zsh"));
+        assert_eq!(config.shell_config.args, vec![String::from("-i")]);
+    }
+
+    #[test]
+    fn test_config_shell_config_path() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  path:\n    - \"/opt/tools/bin\"\n    - \"./bin\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.path, vec![String::from("/opt/tools/bin"), String::from("./bin")]);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.path.len(), 0);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  path: 5\n");
+        assert!(Config::parse_config_str(config).is_err());
+    }
+
+    #[test]
+    fn test_config_shell_config_auto_cd() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.shell_config.auto_cd);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  auto_cd: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.shell_config.auto_cd);
+    }
+
+    #[test]
+    fn test_config_shell_config_suggest_commands() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.shell_config.suggest_commands);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  suggest_commands: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.shell_config.suggest_commands);
+    }
+
+    #[test]
+    fn test_config_shell_config_layout_autocorrect() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.layout_autocorrect, LayoutAutocorrect::Off);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  layout_autocorrect: suggest\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.layout_autocorrect, LayoutAutocorrect::Suggest);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  layout_autocorrect: run\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.layout_autocorrect, LayoutAutocorrect::Run);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  layout_autocorrect: always\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_shell_config_restricted() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.shell_config.restricted);
+        assert_eq!(config.shell_config.restricted_denylist.len(), RESTRICTED_DENYLIST.len());
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  restricted: true\n  restricted_denylist:\n    - '^\\s*rm\\s'\n    - '^sudo'\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.shell_config.restricted);
+        let patterns: Vec<&str> = config.shell_config.restricted_denylist.iter().map(|re| re.as_str()).collect();
+        assert_eq!(patterns, vec!["^\\s*rm\\s", "^sudo"]);
+        //Bad denylist
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  restricted_denylist: rm\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  restricted_denylist:\n    - '(rm'\n");
+        assert!(Config::parse_config_str(config).is_err());
+    }
+
+    #[test]
+    fn test_config_shell_config_clear_mode() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.clear_mode, ClearMode::Erase);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  clear_mode: purge\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.clear_mode, ClearMode::Purge);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  clear_mode: scroll\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.clear_mode, ClearMode::Scroll);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  clear_mode: wipe\n");
+        assert!(Config::parse_config_str(config).is_err());
+    }
+
+    #[test]
+    fn test_config_shell_config_logout_file() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.shell_config.logout_file.is_none());
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  logout_file: \"~/.pyc_logout\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.logout_file, Some(String::from("~/.pyc_logout")));
+    }
+
+    #[test]
+    fn test_config_shell_config_stop_grace() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.stop_grace_ms, 500);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  stop_grace_ms: 2000\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.stop_grace_ms, 2000);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  stop_grace_ms: 0\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.stop_grace_ms, 0);
+    }
+
+    #[test]
+    fn test_config_shell_config_read_timeout() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.read_timeout_ms, 50);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  read_timeout_ms: 200\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.read_timeout_ms, 200);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  read_timeout_ms: 0\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.read_timeout_ms, 0);
+    }
+
+    #[test]
+    fn test_config_shell_config_read_buffer_size() {
+        let config: String = String::from("shell:\n  exec: \"sh\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.read_buffer_size, 8192);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  read_buffer_size: 65536\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.read_buffer_size, 65536);
+        let config: String = String::from("shell:\n  exec: \"sh\"\n  read_buffer_size: 0\n");
+        assert_eq!(Config::parse_config_str(config).err().unwrap().code, ConfigErrorCode::YamlSyntaxError);
+    }
+
+    #[test]
+    fn test_config_shell_config_missing() {
+        let config: String = String::from("language: ru\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.shell_config.exec, String::from("bash"));
+        assert_eq!(config.shell_config.args.len(), 0);
+        assert_eq!(config.shell_config.path.len(), 0);
+    }
+
     #[test]
     fn test_config_shell_config_bad() {
         let config: String = String::from("shell:\n  args:\n    - \"-l\"\n    - \"-h\"\n");
@@ -589,6 +2280,44 @@ mod tests {
         assert!(Config::parse_config_str(config).is_err());
     }
 
+    #[test]
+    fn test_config_input_config() {
+        let config: String = String::from("language: ru\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.input_config.filter_control_chars);
+        let config: String = String::from("input:\n  filter_control_chars: false\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.input_config.filter_control_chars);
+        assert!(config.input_config.word_chars.is_none());
+        let config: String = String::from("input:\n  word_chars: \"-_\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.input_config.word_chars, Some(String::from("-_")));
+        assert_eq!(config.input_config.translit_toggle_key, Some(20));
+        let config: String = String::from("input:\n  translit_toggle_key: \"ctrl+x\"\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.input_config.translit_toggle_key, Some(24));
+        let config: String = String::from("input:\n  translit_toggle_key: none\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.input_config.translit_toggle_key, None);
+        assert!(!config.input_config.recall_on_failure);
+        let config: String = String::from("input:\n  recall_on_failure: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.input_config.recall_on_failure);
+        assert!(config.input_config.translate_heredoc);
+        let config: String = String::from("input:\n  translate_heredoc: false\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.input_config.translate_heredoc);
+        let config: String = String::from("input:\n  translit_toggle_key: \"ALT+T\"\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("input:\n  translit_toggle_key: \"CTRL+TAB\"\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("input: 5\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
     #[test]
     fn test_config_output_config() {
         let config: String =
@@ -601,6 +2330,309 @@ mod tests {
         assert!(!config.output_config.translate_output);
     }
 
+    #[test]
+    fn test_config_output_normalize() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.normalize, OutputNormalization::None);
+        let config: String = String::from("output:\n  translate: true\n  normalize: nfc\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.normalize, OutputNormalization::Nfc);
+        let config: String = String::from("output:\n  translate: true\n  normalize: none\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.normalize, OutputNormalization::None);
+        let config: String = String::from("output:\n  translate: true\n  normalize: nfkd\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_output_buffering() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.buffering, OutputBuffering::Raw);
+        let config: String = String::from("output:\n  translate: true\n  buffering: line\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.buffering, OutputBuffering::Line);
+        let config: String = String::from("output:\n  translate: true\n  buffering: block\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_output_error_color() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.error_color, Some(Colour::Red));
+        let config: String = String::from("output:\n  translate: true\n  error_color: yellow\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.error_color, Some(Colour::Yellow));
+        let config: String = String::from("output:\n  translate: true\n  error_color: Magenta\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.error_color, Some(Colour::Purple));
+        let config: String = String::from("output:\n  translate: true\n  error_color: none\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.error_color, None);
+        let config: String = String::from("output:\n  translate: true\n  error_color: orange\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_output_squelch_repeats() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.output_config.squelch_repeats);
+        let config: String = String::from("output:\n  translate: true\n  squelch_repeats: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.output_config.squelch_repeats);
+    }
+
+    #[test]
+    fn test_config_output_realign_tabs() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.output_config.realign_tabs);
+        let config: String = String::from("output:\n  translate: true\n  realign_tabs: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.output_config.realign_tabs);
+    }
+
+    #[test]
+    fn test_config_output_collapse_progress() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.output_config.collapse_progress);
+        let config: String = String::from("output:\n  translate: true\n  collapse_progress: false\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.output_config.collapse_progress);
+    }
+
+    #[test]
+    fn test_config_output_echo_typed() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.output_config.echo_typed);
+        let config: String = String::from("output:\n  translate: true\n  echo_typed: false\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.output_config.echo_typed);
+    }
+
+    #[test]
+    fn test_config_output_max_lines_per_sec() {
+        let config: String = String::from("output:\n  translate: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.max_lines_per_sec, None);
+        let config: String = String::from("output:\n  translate: true\n  max_lines_per_sec: 500\n");
+        let mut config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.output_config.max_lines_per_sec, Some(500));
+        assert!(config.set_value("output.max_lines_per_sec", "0").is_ok());
+        assert_eq!(config.output_config.max_lines_per_sec, None);
+        assert!(config.set_value("output.max_lines_per_sec", "100").is_ok());
+        assert_eq!(config.output_config.max_lines_per_sec, Some(100));
+        assert!(config.set_value("output.max_lines_per_sec", "fast").is_err());
+    }
+
+    #[test]
+    fn test_config_history_config() {
+        let config: String = String::from("language: ru\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.history_config.store_original);
+        let config: String = String::from("history:\n  store_original: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.history_config.store_original);
+        assert_eq!(config.history_config.format, HistoryFormat::Plain);
+        let config: String = String::from("history:\n  format: json\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.history_config.format, HistoryFormat::Json);
+        assert!(!config.history_config.autosave);
+        let config: String = String::from("history:\n  autosave: true\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.history_config.autosave);
+        let config: String = String::from("history:\n  format: xml\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("history: 5\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_translit_config() {
+        let config: String = String::from("language: ru\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.gost_system, GostSystem::B);
+        let config: String = String::from("translit:\n  gost_system: a\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.gost_system, GostSystem::A);
+        assert_eq!(config.translit_config.max_expression_depth, 64);
+        let config: String = String::from("translit:\n  max_expression_depth: 16\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.max_expression_depth, 16);
+        assert!(config.translit_config.translate_args);
+        let config: String = String::from("translit:\n  translate_args: false\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.translit_config.translate_args);
+        let config: String = String::from("translit:\n  gost_system: B\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.gost_system, GostSystem::B);
+        let config: String = String::from("translit:\n  gost_system: c\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+        //Standard
+        let config: String = String::from("translit:\n  standard: iso9\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.gost_system, GostSystem::A);
+        let config: String = String::from("translit:\n  standard: gost\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.translit_config.gost_system, GostSystem::B);
+        let config: String = String::from("translit:\n  standard: din\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("translit:\n  standard: iso9\n  gost_system: a\n");
+        assert!(Config::parse_config_str(config).is_err());
+        let config: String = String::from("translit: 5\n");
+        assert_eq!(
+            Config::parse_config_str(config).err().unwrap().code,
+            ConfigErrorCode::YamlSyntaxError
+        );
+    }
+
+    #[test]
+    fn test_config_set_value() {
+        let mut config: Config = Config::default();
+        //Boolean
+        assert!(config.set_value("output.translate", "false").is_ok());
+        assert!(!config.output_config.translate_output);
+        assert!(config.set_value("prompt.translate", "true").is_ok());
+        assert!(config.prompt_config.translate);
+        //String
+        assert!(config.set_value("prompt.prompt_line", "${USER} ~>").is_ok());
+        assert_eq!(config.prompt_config.prompt_line, String::from("${USER} ~>"));
+        assert!(config.set_value("prompt.first_prompt_line", "${USER} on ${HOSTNAME} ~>").is_ok());
+        assert_eq!(config.prompt_config.first_prompt_line, Some(String::from("${USER} on ${HOSTNAME} ~>")));
+        assert!(config.set_value("prompt.rprompt_line", "${GIT_BRANCH}").is_ok());
+        assert_eq!(config.prompt_config.rprompt_line, Some(String::from("${GIT_BRANCH}")));
+        assert!(config.set_value("shell.exec", "zsh").is_ok());
+        assert_eq!(config.shell_config.exec, String::from("zsh"));
+        //Number
+        assert!(config.set_value("prompt.history_size", "64").is_ok());
+        assert_eq!(config.prompt_config.history_size, 64);
+        assert!(config.set_value("prompt.history_ignore_dups", "false").is_ok());
+        assert!(!config.prompt_config.history_ignore_dups);
+        assert!(config.set_value("prompt.wrkdir_home_tilde", "true").is_ok());
+        assert!(config.prompt_config.wrkdir_home_tilde);
+        assert!(config.set_value("prompt.wrkdir_max_components", "3").is_ok());
+        assert_eq!(config.prompt_config.wrkdir_max_components, 3);
+        assert!(config.set_value("prompt.clock_format", "%H:%M").is_ok());
+        assert_eq!(config.prompt_config.clock_format, String::from("%H:%M"));
+        assert!(config.set_value("prompt.wrkdir_max_components", "-1").is_err());
+        //Enums
+        assert!(config.set_value("output.normalize", "nfc").is_ok());
+        assert_eq!(config.output_config.normalize, OutputNormalization::Nfc);
+        assert!(config.set_value("output.error_color", "none").is_ok());
+        assert_eq!(config.output_config.error_color, None);
+        assert!(config.set_value("translit.gost_system", "a").is_ok());
+        assert!(config.set_value("translit.max_expression_depth", "8").is_ok());
+        assert_eq!(config.translit_config.max_expression_depth, 8);
+        assert!(config.set_value("translit.translate_args", "false").is_ok());
+        assert!(!config.translit_config.translate_args);
+        assert!(config.set_value("language", "xx").is_err());
+        assert_eq!(config.language, String::from("ru"));
+        assert!(config.set_value("language", "ua").is_ok());
+        assert_eq!(config.language, String::from("ua"));
+        assert_eq!(config.translit_config.gost_system, GostSystem::A);
+        assert!(config.set_value("translit.standard", "gost").is_ok());
+        assert_eq!(config.translit_config.gost_system, GostSystem::B);
+        assert!(config.set_value("translit.standard", "iso9").is_ok());
+        assert_eq!(config.translit_config.gost_system, GostSystem::A);
+        assert!(config.set_value("translit.standard", "b").is_err());
+        assert!(config.set_value("input.word_chars", "-_.").is_ok());
+        assert_eq!(config.input_config.word_chars, Some(String::from("-_.")));
+        assert!(config.set_value("input.translit_toggle_key", "CTRL+Y").is_ok());
+        assert_eq!(config.input_config.translit_toggle_key, Some(25));
+        assert!(config.set_value("input.translit_toggle_key", "none").is_ok());
+        assert_eq!(config.input_config.translit_toggle_key, None);
+        assert!(config.set_value("input.translit_toggle_key", "F1").is_err());
+        assert!(config.set_value("input.recall_on_failure", "true").is_ok());
+        assert!(config.input_config.recall_on_failure);
+        assert!(config.set_value("input.translate_heredoc", "false").is_ok());
+        assert!(!config.input_config.translate_heredoc);
+        assert!(config.set_value("prompt.git.dirty", "±").is_ok());
+        assert_eq!(config.prompt_config.git_dirty, String::from("±"));
+        assert!(config.set_value("prompt.git.ahead", "⇡").is_ok());
+        assert_eq!(config.prompt_config.git_ahead, String::from("⇡"));
+        assert!(config.set_value("prompt.git.behind", "⇣").is_ok());
+        assert_eq!(config.prompt_config.git_behind, String::from("⇣"));
+        assert!(config.set_value("prompt.translit_off", "LAT").is_ok());
+        assert_eq!(config.prompt_config.translit_off, String::from("LAT"));
+        assert!(config.set_value("prompt.duration.decimal_separator", ",").is_ok());
+        assert_eq!(config.prompt_config.duration_separator, String::from(","));
+        assert!(config.set_value("prompt.duration.units.s", "с").is_ok());
+        assert_eq!(config.prompt_config.duration_unit_s, String::from("с"));
+        assert!(config.set_value("prompt.duration.units.m", "м").is_ok());
+        assert_eq!(config.prompt_config.duration_unit_m, Some(String::from("м")));
+        assert!(config.set_value("prompt.duration.label", "за").is_ok());
+        assert_eq!(config.prompt_config.duration_label, String::from("за"));
+        assert!(config.set_value("output.collapse_progress", "false").is_ok());
+        assert!(!config.output_config.collapse_progress);
+        assert!(config.set_value("output.buffering", "line").is_ok());
+        assert_eq!(config.output_config.buffering, OutputBuffering::Line);
+        assert!(config.set_value("output.buffering", "full").is_err());
+        assert!(config.set_value("output.echo_typed", "false").is_ok());
+        assert!(!config.output_config.echo_typed);
+        assert!(config.set_value("shell.clear_mode", "scroll").is_ok());
+        assert_eq!(config.shell_config.clear_mode, ClearMode::Scroll);
+        assert!(config.set_value("shell.clear_mode", "wipe").is_err());
+        assert!(config.set_value("shell.logout_file", "~/.pyc_logout").is_ok());
+        assert_eq!(config.shell_config.logout_file, Some(String::from("~/.pyc_logout")));
+        assert!(config.set_value("shell.stop_grace_ms", "1000").is_ok());
+        assert_eq!(config.shell_config.stop_grace_ms, 1000);
+        assert!(config.set_value("shell.stop_grace_ms", "soon").is_err());
+        assert!(config.set_value("shell.read_timeout_ms", "10").is_ok());
+        assert_eq!(config.shell_config.read_timeout_ms, 10);
+        assert!(config.set_value("shell.read_timeout_ms", "-1").is_err());
+        assert!(config.set_value("shell.read_buffer_size", "1024").is_ok());
+        assert_eq!(config.shell_config.read_buffer_size, 1024);
+        assert!(config.set_value("shell.read_buffer_size", "0").is_err());
+        assert_eq!(config.shell_config.read_buffer_size, 1024);
+        assert!(config.set_value("shell.auto_cd", "true").is_ok());
+        assert!(config.shell_config.auto_cd);
+        assert!(config.set_value("shell.restricted", "true").is_ok());
+        assert!(config.shell_config.restricted);
+        assert!(config.set_value("history.store_original", "true").is_ok());
+        assert!(config.history_config.store_original);
+        assert!(config.set_value("history.format", "json").is_ok());
+        assert_eq!(config.history_config.format, HistoryFormat::Json);
+        assert!(config.set_value("history.format", "xml").is_err());
+        assert!(config.set_value("history.autosave", "true").is_ok());
+        assert!(config.history_config.autosave);
+        assert!(config.set_value("prompt.rev_search.label", "search:").is_ok());
+        assert_eq!(config.prompt_config.rev_search_label, String::from("search:"));
+        assert!(config.set_value("prompt.rev_search.glyph", "🔍").is_ok());
+        assert_eq!(config.prompt_config.rev_search_glyph, Some(String::from("🔍")));
+        assert!(config.set_value("prompt.rev_search.color", "cyan").is_ok());
+        assert_eq!(config.prompt_config.rev_search_color, Some(Colour::Cyan));
+        assert!(config.set_value("prompt.rev_search.color", "rainbow").is_err());
+        //Bad values
+        assert!(config.set_value("output.translate", "yes").is_err());
+        assert!(config.set_value("prompt.history_size", "many").is_err());
+        assert!(config.set_value("output.normalize", "nfkd").is_err());
+        //Unknown key
+        assert!(config.set_value("prompt.foobar", "true").is_err());
+        //Values didn't change
+        assert!(!config.output_config.translate_output);
+        assert_eq!(config.prompt_config.history_size, 64);
+    }
+
     #[test]
     fn test_config_bad_output_config() {
         let config: String = String::from("output: 5\n");
@@ -620,6 +2652,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_error_key() {
+        //Missing key in a prompt sub-section
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let err: ConfigError = Config::parse_config_str(config).err().unwrap();
+        assert_eq!(err.key, Some(String::from("prompt.rc.ok")));
+        assert_eq!(err.message, String::from("Missing key 'ok'"));
+        //Bad value
+        let config: String = String::from("output:\n  translate: foobar\n");
+        assert_eq!(Config::parse_config_str(config).err().unwrap().key, Some(String::from("output.translate")));
+        let config: String = String::from("shell:\n  exec: \"bash\"\n  clear_mode: fade\n");
+        assert_eq!(Config::parse_config_str(config).err().unwrap().key, Some(String::from("shell.clear_mode")));
+        //Sections without a specific key
+        let config: String = String::from("history: 5\n");
+        assert_eq!(Config::parse_config_str(config).err().unwrap().key, Some(String::from("history")));
+        let config: String = String::from("language: 5\n");
+        assert_eq!(Config::parse_config_str(config).err().unwrap().key, Some(String::from("language")));
+        //Not related to a key
+        let config: String = String::from("language: [\n");
+        assert!(Config::parse_config_str(config).err().unwrap().key.is_none());
+        //Set value
+        let mut config: Config = Config::default();
+        assert_eq!(config.set_value("prompt.translate", "maybe").err().unwrap().key, Some(String::from("prompt.translate")));
+        assert_eq!(config.set_value("prompt.foo", "bar").err().unwrap().key, Some(String::from("prompt.foo")));
+    }
+
+    #[test]
+    fn test_config_describe() {
+        let config_file: tempfile::NamedTempFile = write_config_file_en();
+        let config: Config = Config::parse_config(PathBuf::from(config_file.path())).ok().unwrap();
+        let description: String = config.describe();
+        let lines: Vec<&str> = description.lines().collect();
+        assert!(lines.contains(&"language: ru"));
+        assert!(lines.contains(&format!("alias: {}", config.alias.len()).as_str()));
+        assert!(lines.contains(&"shell.exec: bash"));
+        assert!(lines.contains(&"prompt.prompt_line: \"${USER}@${HOSTNAME}:${WRKDIR}$\""));
+        assert!(lines.contains(&"prompt.first_prompt_line: none"));
+        assert!(lines.contains(&"prompt.rc.ok: \"✔\""));
+        assert!(lines.contains(&"prompt.git.commit_ref_len: 8"));
+    }
+
+    #[test]
+    fn test_config_init_config_file() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        //Directory is created
+        let config_file: PathBuf = tmpdir.path().join("pyc").join("pyc.yml");
+        assert!(Config::init_config_file(config_file.as_path(), false).is_ok());
+        //Generated configuration has the default values
+        let config: Config = Config::parse_config(config_file.clone()).ok().unwrap();
+        let default: Config = Config::default();
+        assert_eq!(config.describe(), default.describe());
+        assert_eq!(config.prompt_config.prompt_line, default.prompt_config.prompt_line);
+        assert_eq!(config.prompt_config.clock_format, default.prompt_config.clock_format);
+        assert_eq!(config.shell_config.restricted_denylist.len(), default.shell_config.restricted_denylist.len());
+        //Commented keys are valid too
+        let uncommented: String = Config::default_yaml()
+            .lines()
+            .map(|line| line.replacen("# ", "", 1))
+            .filter(|line| !line.trim_start().starts_with(char::is_uppercase))
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert!(Config::parse_config_str(uncommented).is_ok());
+        assert!(Config::default_yaml().contains("# Language of the shell (ru, by, bg, mk, rs, ua, nil)\n"));
+        //Existing file is not overwritten, unless forced
+        assert!(fs::write(config_file.as_path(), "language: by\n").is_ok());
+        assert_eq!(Config::init_config_file(config_file.as_path(), false).err().unwrap().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(Config::parse_config(config_file.clone()).ok().unwrap().language, String::from("by"));
+        assert!(Config::init_config_file(config_file.as_path(), true).is_ok());
+        assert_eq!(Config::parse_config(config_file.clone()).ok().unwrap().language, String::from("ru"));
+    }
+
     #[test]
     fn test_config_language() {
         let config: String = String::from("language: bg\n");
@@ -660,7 +2763,7 @@ mod tests {
 
     #[test]
     fn test_config_prompt() {
-        let config: String = String::from("prompt:\n  prompt_line: \"${USER} on ${HOSTNAME} in ${WRKDIR} ${GIT_BRANCH} (${GIT_COMMIT}) ${CMD_TIME}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n    commit_prepend: \"(\"\n    commit_append: \")\"\n");
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER} on ${HOSTNAME} in ${WRKDIR} ${GIT_BRANCH} (${GIT_COMMIT}) ${CMD_TIME}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n    commit_prepend: \"(\"\n    commit_append: \")\"\n    dirty: \"!\"\n    ahead: \"+\"\n    behind: \"-\"\n");
         let config: Config = Config::parse_config_str(config).ok().unwrap();
         //Verify config parameters
         let prompt_config: PromptConfig = config.prompt_config;
@@ -671,11 +2774,113 @@ mod tests {
         assert_eq!(prompt_config.git_commit_ref, 4);
         assert_eq!(prompt_config.git_commit_prepend, Some(String::from("(")));
         assert_eq!(prompt_config.git_commit_append, Some(String::from(")")));
+        assert_eq!(prompt_config.git_dirty, String::from("!"));
+        assert_eq!(prompt_config.git_ahead, String::from("+"));
+        assert_eq!(prompt_config.git_behind, String::from("-"));
         assert_eq!(prompt_config.history_size, 1024);
         assert_eq!(prompt_config.min_duration, 5000);
         assert_eq!(prompt_config.rc_err, String::from("x_x"));
         assert_eq!(prompt_config.rc_ok, String::from("^_^"));
-        assert_eq!(prompt_config.translate, true);
+        assert!(prompt_config.translate);
+        assert!(!prompt_config.bottom_pinned);
+    }
+
+    #[test]
+    fn test_config_prompt_bottom_pinned() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  bottom_pinned: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.prompt_config.bottom_pinned);
+    }
+
+    #[test]
+    fn test_config_prompt_first_prompt_line() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.first_prompt_line, None);
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  first_prompt_line: \"${USER} on ${HOSTNAME}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.first_prompt_line, Some(String::from("${USER} on ${HOSTNAME}")));
+    }
+
+    #[test]
+    fn test_config_prompt_rprompt_line() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  rprompt_line: \"${TIME:%H:%M}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.rprompt_line, Some(String::from("${TIME:%H:%M}")));
+    }
+
+    #[test]
+    fn test_config_prompt_lang_flag_emoji() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${LANG_FLAG}\"\n  history_size: 1024\n  translate: true\n  lang_flag_emoji: false\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.prompt_config.lang_flag_emoji);
+    }
+
+    #[test]
+    fn test_config_prompt_history_ignore_dups() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  history_ignore_dups: false\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(!config.prompt_config.history_ignore_dups);
+    }
+
+    #[test]
+    fn test_config_prompt_wrkdir() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${WRKDIR}\"\n  history_size: 1024\n  translate: true\n  wrkdir_home_tilde: true\n  wrkdir_max_components: 2\n  clock_format: \"%T\"\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert!(config.prompt_config.wrkdir_home_tilde);
+        assert_eq!(config.prompt_config.wrkdir_max_components, 2);
+        assert_eq!(config.prompt_config.clock_format, String::from("%T"));
+    }
+
+    #[test]
+    fn test_config_prompt_disabled_modules() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${GIT_BRANCH}\"\n  history_size: 1024\n  translate: true\n  disabled_modules:\n    - git\n    - rc\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.disabled_modules, vec![String::from("git"), String::from("rc")]);
+        assert!(config.prompt_config.is_module_disabled("git"));
+        assert!(config.prompt_config.is_module_disabled("rc"));
+        assert!(!config.prompt_config.is_module_disabled("duration"));
+        //Default
+        assert!(PromptConfig::default().disabled_modules.is_empty());
+        //Unknown module
+        let config: String = String::from("prompt:\n  prompt_line: \"${GIT_BRANCH}\"\n  history_size: 1024\n  translate: true\n  disabled_modules:\n    - foobar\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        assert!(Config::parse_config_str(config).is_err());
+        //Not an array
+        let config: String = String::from("prompt:\n  prompt_line: \"${GIT_BRANCH}\"\n  history_size: 1024\n  translate: true\n  disabled_modules: git\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        assert!(Config::parse_config_str(config).is_err());
+    }
+
+    #[test]
+    fn test_config_prompt_duration_format() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${CMD_TIME}\"\n  history_size: 1024\n  translate: true\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n    label: \"за\"\n    decimal_separator: \",\"\n    units:\n      ms: \"мс\"\n      s: \"с\"\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.duration_label, String::from("за"));
+        assert_eq!(config.prompt_config.duration_separator, String::from(","));
+        assert_eq!(config.prompt_config.duration_unit_ms, Some(String::from("мс")));
+        assert_eq!(config.prompt_config.duration_unit_s, String::from("с"));
+        assert_eq!(config.prompt_config.duration_unit_m, None);
+    }
+
+    #[test]
+    fn test_config_prompt_rev_search() {
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  rev_search:\n    label: \"(поиск)\"\n    glyph: \"🔍\"\n    color: yellow\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.rev_search_label, String::from("(поиск)"));
+        assert_eq!(config.prompt_config.rev_search_glyph, Some(String::from("🔍")));
+        assert_eq!(config.prompt_config.rev_search_color, Some(Colour::Yellow));
+        assert_eq!(config.prompt_config.translit_off, String::from("(translit off)"));
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER} ${TRANSLIT}\"\n  history_size: 1024\n  translate: true\n  translit_off: \"LAT\"\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.translit_off, String::from("LAT"));
+        //Only label
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  rev_search:\n    label: \"search\"\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        let config: Config = Config::parse_config_str(config).ok().unwrap();
+        assert_eq!(config.prompt_config.rev_search_label, String::from("search"));
+        assert_eq!(config.prompt_config.rev_search_glyph, None);
+        assert_eq!(config.prompt_config.rev_search_color, None);
+        //Bad color
+        let config: String = String::from("prompt:\n  prompt_line: \"${USER}\"\n  history_size: 1024\n  translate: true\n  rev_search:\n    color: rainbow\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n");
+        assert!(Config::parse_config_str(config).is_err());
     }
 
     #[test]
@@ -740,7 +2945,8 @@ mod tests {
             "{}",
             ConfigError {
                 code: ConfigErrorCode::NoSuchFileOrDirectory,
-                message: String::from("No such file or directory ~/.config/pyc/pyc.yml")
+                message: String::from("No such file or directory ~/.config/pyc/pyc.yml"),
+                key: None,
             }
         );
     }
@@ -0,0 +1,489 @@
+//! ## Lev
+//!
+//! `lev` is the Pyc built-in text editor; typed keys are transliterated unless transliteration is suspended,
+//! which is toggled with the same key used at the prompt
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use crate::translator::ioprocessor::IOProcessor;
+use crate::utils::buffer;
+use crate::utils::console::{self, InputEvent};
+use crate::utils::file;
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+//Keybindings
+const KEY_SAVE: u8 = 19; //CTRL+S
+const KEY_QUIT: u8 = 17; //CTRL+Q
+//The editor runs in the alternate screen, so the shell output is restored on exit
+const ALTERNATE_SCREEN_ON: &str = "\x1b[?1049h";
+const ALTERNATE_SCREEN_OFF: &str = "\x1b[?1049l";
+
+/// ## LevEditor
+///
+/// LevEditor is the editor state: the file being edited, its lines and the cursor position
+pub struct LevEditor<'a> {
+    file: Option<PathBuf>,
+    lines: Vec<String>,
+    row: usize,            //Cursor row (line index)
+    col: usize,            //Cursor column (char index in the row)
+    offset: usize,         //First line shown on screen
+    modified: bool,        //Whether the buffer has unsaved changes
+    quit_pending: bool,    //Quit has been requested once with unsaved changes
+    status: String,        //Message shown in the status bar
+    translit: bool,        //Whether typed keys are transliterated
+    translit_toggle_key: Option<u8>, //Control code of the key which toggles transliteration
+    iop: &'a IOProcessor,
+}
+
+impl<'a> LevEditor<'a> {
+
+    /// ### new
+    ///
+    /// Instantiate a new LevEditor with an empty buffer.
+    /// Keys are transliterated if translit is true; transliteration is toggled with translit_toggle_key
+    pub fn new(iop: &'a IOProcessor, translit: bool, translit_toggle_key: Option<u8>) -> LevEditor<'a> {
+        LevEditor {
+            file: None,
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            offset: 0,
+            modified: false,
+            quit_pending: false,
+            status: String::new(),
+            translit,
+            translit_toggle_key,
+            iop,
+        }
+    }
+
+    /// ### main
+    ///
+    /// Edit the file provided in argv (`lev <file>`) until the user quits.
+    /// Returns the exit code of the editor
+    pub fn main(&mut self, argv: &[String]) -> u8 {
+        let file: PathBuf = match argv.get(1) {
+            Some(file) if argv.len() == 2 => PathBuf::from(file),
+            _ => {
+                console::eprintln(String::from("usage: lev <file>"));
+                return 1;
+            }
+        };
+        if let Err(err) = self.open(file.as_path()) {
+            console::eprintln(format!("lev: {}: {}", file.display(), err));
+            return 1;
+        }
+        //The screen is restored on exit, even if the editor panics
+        let _screen: ScreenGuard = ScreenGuard::enter();
+        self.render();
+        //Run until quit; read already waits for the input to be ready
+        loop {
+            if let Some(ev) = console::read() {
+                if !self.handle_input(ev) {
+                    break;
+                }
+                self.render();
+            }
+        }
+        0
+    }
+
+    /// ### open
+    ///
+    /// Load the lines of file into the buffer; if the file doesn't exist, the buffer is empty and the file is created on save
+    pub fn open(&mut self, file: &Path) -> io::Result<()> {
+        if file.is_dir() {
+            return Err(io::Error::other("is a directory"));
+        }
+        self.lines = match file::read_lines(file) {
+            Ok(lines) => lines,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        //The buffer has always at least one line
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.file = Some(file.to_path_buf());
+        self.row = 0;
+        self.col = 0;
+        self.offset = 0;
+        self.modified = false;
+        self.status = format!("\"{}\" {}L", file.display(), self.lines.len());
+        Ok(())
+    }
+
+    /// ### save
+    ///
+    /// Write the buffer to the file
+    pub fn save(&mut self) -> io::Result<()> {
+        let file: &Path = match &self.file {
+            Some(file) => file.as_path(),
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "no file name")),
+        };
+        //An empty buffer is an empty file
+        let lines: Vec<String> = match self.lines.as_slice() {
+            [line] if line.is_empty() => Vec::new(),
+            _ => self.lines.clone(),
+        };
+        file::write_lines(file, lines)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    /// ### handle_input
+    ///
+    /// Handle an input event; returns false once the user has quit
+    pub fn handle_input(&mut self, ev: InputEvent) -> bool {
+        let quit_pending: bool = self.quit_pending;
+        self.quit_pending = false;
+        self.status.clear();
+        match ev {
+            InputEvent::Ctrl(KEY_QUIT) => {
+                //Quit must be confirmed if there are unsaved changes
+                if self.modified && !quit_pending {
+                    self.quit_pending = true;
+                    self.status = String::from("Unsaved changes; press ^Q again to quit");
+                    return true;
+                }
+                return false;
+            }
+            InputEvent::Ctrl(KEY_SAVE) => {
+                self.status = match self.save() {
+                    Ok(_) => format!("\"{}\" {}L written", self.file_name(), self.lines.len()),
+                    Err(err) => format!("Could not save \"{}\": {}", self.file_name(), err),
+                };
+            }
+            InputEvent::Ctrl(key) if Some(key) == self.translit_toggle_key => {
+                self.translit = !self.translit;
+                self.status = String::from(match self.translit {
+                    true => "Transliteration on",
+                    false => "Transliteration off",
+                });
+            }
+            InputEvent::Ctrl(_) => {}
+            InputEvent::Key(key) => {
+                //Transliterate typed keys, unless transliteration is suspended
+                let text: String = match self.translit {
+                    true => self.iop.text_to_cyrillic(&key),
+                    false => key,
+                };
+                self.insert(text.as_str());
+            }
+            InputEvent::Enter | InputEvent::CarriageReturn => self.newline(),
+            InputEvent::Backspace => self.backspace(),
+            InputEvent::ArrowUp => self.move_to(self.row.saturating_sub(1), self.col),
+            InputEvent::ArrowDown => self.move_to(self.row + 1, self.col),
+            InputEvent::ArrowLeft => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    //Go to the end of the previous line
+                    self.move_to(self.row - 1, usize::MAX);
+                }
+            }
+            InputEvent::ArrowRight => {
+                if self.col < self.line_len(self.row) {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    //Go to the beginning of the next line
+                    self.move_to(self.row + 1, 0);
+                }
+            }
+            InputEvent::WordLeft => {
+                let chars: Vec<char> = self.lines[self.row].chars().collect();
+                self.col = buffer::prev_word_boundary(&chars, self.col, None);
+            }
+            InputEvent::WordRight => {
+                let chars: Vec<char> = self.lines[self.row].chars().collect();
+                self.col = buffer::next_word_boundary(&chars, self.col, None);
+            }
+        }
+        true
+    }
+
+    /// ### insert
+    ///
+    /// Insert text at the cursor position
+    fn insert(&mut self, text: &str) {
+        let mut chars: Vec<char> = self.lines[self.row].chars().collect();
+        for ch in text.chars() {
+            chars.insert(self.col, ch);
+            self.col += 1;
+        }
+        self.lines[self.row] = buffer::chars_to_string(&chars);
+        self.modified = true;
+    }
+
+    /// ### newline
+    ///
+    /// Break the current line at the cursor position
+    fn newline(&mut self) {
+        let mut chars: Vec<char> = self.lines[self.row].chars().collect();
+        let tail: Vec<char> = chars.split_off(self.col);
+        self.lines[self.row] = buffer::chars_to_string(&chars);
+        self.lines.insert(self.row + 1, buffer::chars_to_string(&tail));
+        self.row += 1;
+        self.col = 0;
+        self.modified = true;
+    }
+
+    /// ### backspace
+    ///
+    /// Remove the character before the cursor; at the beginning of a line, the line is joined to the previous one
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            let mut chars: Vec<char> = self.lines[self.row].chars().collect();
+            chars.remove(self.col - 1);
+            self.lines[self.row] = buffer::chars_to_string(&chars);
+            self.col -= 1;
+        } else if self.row > 0 {
+            let line: String = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len(self.row);
+            self.lines[self.row].push_str(line.as_str());
+        } else {
+            return;
+        }
+        self.modified = true;
+    }
+
+    /// ### move_to
+    ///
+    /// Move the cursor to the provided position, keeping it inside the buffer
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.lines.len() - 1);
+        self.col = col.min(self.line_len(self.row));
+    }
+
+    /// ### line_len
+    ///
+    /// Returns the length in chars of the provided line
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    /// ### file_name
+    ///
+    /// Returns the name of the file being edited
+    fn file_name(&self) -> String {
+        match &self.file {
+            Some(file) => file.display().to_string(),
+            None => String::from("[No name]"),
+        }
+    }
+
+    /// ### render
+    ///
+    /// Draw the visible lines, the status bar and put the cursor at its position
+    fn render(&mut self) {
+        let (cols, rows): (usize, usize) = console::term_size().unwrap_or((80, 24));
+        //Last row is the status bar
+        let text_rows: usize = rows.saturating_sub(1).max(1);
+        //Scroll to keep the cursor visible
+        if self.row < self.offset {
+            self.offset = self.row;
+        } else if self.row >= self.offset + text_rows {
+            self.offset = self.row + 1 - text_rows;
+        }
+        //Scroll horizontally the row of the cursor only
+        let hscroll: usize = (self.col + 1).saturating_sub(cols);
+        let mut screen: String = console::clear_seq();
+        for (index, line) in self.lines.iter().enumerate().skip(self.offset).take(text_rows) {
+            let skip: usize = match index == self.row {
+                true => hscroll,
+                false => 0,
+            };
+            screen.push_str(line.chars().skip(skip).take(cols).collect::<String>().as_str());
+            screen.push_str("\r\n");
+        }
+        let status: String = match self.status.is_empty() {
+            true => format!(
+                "{}{}{} {}:{}  ^S save  ^Q quit",
+                self.file_name(),
+                match self.modified {
+                    true => " [+]",
+                    false => "",
+                },
+                match self.translit {
+                    true => "",
+                    false => " [lat]",
+                },
+                self.row + 1,
+                self.col + 1
+            ),
+            false => self.status.clone(),
+        };
+        screen.push_str(console::cursor_to_seq(rows, 1).as_str());
+        screen.push_str(format!("\x1b[7m{}\x1b[0m", status.chars().take(cols).collect::<String>()).as_str());
+        screen.push_str(console::cursor_to_seq(self.row - self.offset + 1, self.col - hscroll + 1).as_str());
+        console::print(screen);
+    }
+}
+
+/// ## ScreenGuard
+///
+/// ScreenGuard keeps the editor in the alternate screen until it's dropped
+struct ScreenGuard;
+
+impl ScreenGuard {
+
+    /// ### enter
+    ///
+    /// Switch to the alternate screen
+    fn enter() -> ScreenGuard {
+        console::print(String::from(ALTERNATE_SCREEN_ON));
+        ScreenGuard
+    }
+}
+
+impl Drop for ScreenGuard {
+    fn drop(&mut self) {
+        console::print(String::from(ALTERNATE_SCREEN_OFF));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::translator::lang::Language;
+    use crate::translator::new_translator;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_lev_open_edit_save() {
+        let iop: IOProcessor = get_ioprocessor();
+        let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        assert!(file::write_lines(tmpfile.path(), vec![String::from("first"), String::from("second")]).is_ok());
+        let mut lev: LevEditor = LevEditor::new(&iop, true, Some(20));
+        assert!(lev.open(tmpfile.path()).is_ok());
+        assert_eq!(lev.lines, vec![String::from("first"), String::from("second")]);
+        assert!(!lev.modified);
+        //Type at the end of the first line (keys are transliterated)
+        assert!(lev.handle_input(InputEvent::ArrowRight));
+        assert!(lev.handle_input(InputEvent::WordRight));
+        assert_eq!(lev.col, 5);
+        assert!(lev.handle_input(InputEvent::Key(String::from(" "))));
+        assert!(lev.handle_input(InputEvent::Key(String::from("d"))));
+        assert!(lev.handle_input(InputEvent::Key(String::from("a"))));
+        assert_eq!(lev.lines[0], String::from("first да"));
+        assert!(lev.modified);
+        //Break the second line
+        assert!(lev.handle_input(InputEvent::ArrowDown));
+        assert_eq!((lev.row, lev.col), (1, 6));
+        assert!(lev.handle_input(InputEvent::ArrowLeft));
+        assert!(lev.handle_input(InputEvent::ArrowLeft));
+        assert!(lev.handle_input(InputEvent::Enter));
+        assert_eq!(lev.lines, vec![String::from("first да"), String::from("seco"), String::from("nd")]);
+        assert_eq!((lev.row, lev.col), (2, 0));
+        //Save
+        assert!(lev.handle_input(InputEvent::Ctrl(KEY_SAVE)));
+        assert!(!lev.modified);
+        assert_eq!(
+            file::read_lines(tmpfile.path()).unwrap(),
+            vec![String::from("first да"), String::from("seco"), String::from("nd")]
+        );
+        //Join lines back and save again
+        assert!(lev.handle_input(InputEvent::Backspace));
+        assert_eq!((lev.row, lev.col), (1, 4));
+        assert!(lev.handle_input(InputEvent::Backspace));
+        assert!(lev.save().is_ok());
+        assert_eq!(file::read_lines(tmpfile.path()).unwrap(), vec![String::from("first да"), String::from("secnd")]);
+        //Quit
+        assert!(!lev.handle_input(InputEvent::Ctrl(KEY_QUIT)));
+    }
+
+    #[test]
+    fn test_lev_translit_toggle() {
+        let iop: IOProcessor = get_ioprocessor();
+        let mut lev: LevEditor = LevEditor::new(&iop, true, Some(20));
+        assert!(lev.handle_input(InputEvent::Key(String::from("d"))));
+        //CTRL+T suspends transliteration
+        assert!(lev.handle_input(InputEvent::Ctrl(20)));
+        assert!(!lev.translit);
+        assert!(lev.handle_input(InputEvent::Key(String::from("d"))));
+        //And resumes it
+        assert!(lev.handle_input(InputEvent::Ctrl(20)));
+        assert!(lev.handle_input(InputEvent::Key(String::from("d"))));
+        assert_eq!(lev.lines[0], String::from("дdд"));
+        //Editor started with transliteration suspended and no toggle key
+        let mut lev: LevEditor = LevEditor::new(&iop, false, None);
+        assert!(lev.handle_input(InputEvent::Ctrl(20)));
+        assert!(lev.handle_input(InputEvent::Key(String::from("d"))));
+        assert_eq!(lev.lines[0], String::from("d"));
+    }
+
+    #[test]
+    fn test_lev_open_new_file() {
+        let iop: IOProcessor = get_ioprocessor();
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let file: PathBuf = tmpdir.path().join("new.txt");
+        let mut lev: LevEditor = LevEditor::new(&iop, true, Some(20));
+        assert!(lev.open(file.as_path()).is_ok());
+        assert_eq!(lev.lines, vec![String::new()]);
+        //Nothing to remove
+        assert!(lev.handle_input(InputEvent::Backspace));
+        assert!(!lev.modified);
+        //An empty buffer is saved as an empty file
+        assert!(lev.save().is_ok());
+        assert_eq!(file::read_lines(file.as_path()).unwrap().len(), 0);
+        assert!(lev.handle_input(InputEvent::Key(String::from("x"))));
+        assert!(lev.save().is_ok());
+        assert_eq!(file::read_lines(file.as_path()).unwrap(), vec![String::from("кс")]);
+    }
+
+    #[test]
+    fn test_lev_quit_unsaved() {
+        let iop: IOProcessor = get_ioprocessor();
+        let mut lev: LevEditor = LevEditor::new(&iop, true, Some(20));
+        assert!(lev.handle_input(InputEvent::Key(String::from("a"))));
+        //Quit must be confirmed
+        assert!(lev.handle_input(InputEvent::Ctrl(KEY_QUIT)));
+        assert!(lev.quit_pending);
+        assert!(!lev.handle_input(InputEvent::Ctrl(KEY_QUIT)));
+        //Any other key cancels quit
+        assert!(lev.handle_input(InputEvent::Ctrl(KEY_QUIT)));
+        assert!(lev.handle_input(InputEvent::ArrowLeft));
+        assert!(!lev.quit_pending);
+        //Save fails without a file
+        assert!(lev.save().is_err());
+    }
+
+    #[test]
+    fn test_lev_main_bad_args() {
+        let iop: IOProcessor = get_ioprocessor();
+        let mut lev: LevEditor = LevEditor::new(&iop, true, Some(20));
+        assert_eq!(lev.main(&[String::from("lev")]), 1);
+        assert_eq!(lev.main(&[String::from("lev"), String::from("a"), String::from("b")]), 1);
+        assert_eq!(lev.main(&[String::from("lev"), String::from("/dev/null/foo")]), 1);
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        assert!(lev.open(tmpdir.path()).is_err());
+    }
+
+    fn get_ioprocessor() -> IOProcessor {
+        IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap()
+    }
+}
@@ -21,7 +21,9 @@
 
 #[macro_use] extern crate lazy_static;
 
+pub mod cli;
 pub mod config;
+pub mod lev;
 pub mod runtime;
 pub mod shell;
 pub mod translator;
@@ -36,7 +36,9 @@ use std::env;
 use std::path::PathBuf;
 
 //Internal modules
+mod cli;
 mod config;
+mod lev;
 mod runtime;
 mod shell;
 mod translator;
@@ -58,14 +60,9 @@ fn print_usage(program: &String, opts: Options) {
 /// Convert CLI option language string to Language enum
 
 fn str_to_language(lang: String) -> Language {
-    match lang.as_str() {
-        "ru" | "рус" => Language::Russian,
-        "by" | "бел" => Language::Belarusian,
-        "bg" | "бг" | "блг" => Language::Bulgarian,
-        "rs" | "срб" => Language::Serbian,
-        "ua" | "укр" => Language::Ukrainian,
-        "nil" => Language::Nil,
-        _ => {
+    match Language::from_code(lang.as_str()) {
+        Some(language) => language,
+        None => {
             eprintln!(
                 "{}",
                 Colour::Red.paint(format!(
@@ -82,7 +79,7 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let program: String = args[0].clone();
     //Program CLI options
-    let config_file: PathBuf;
+    
     let mut shell: Option<String> = None;
     let language: Option<Language>;
     //Get home directory
@@ -95,13 +92,7 @@ fn main() {
         None => None,
     };
     //Process options
-    let mut opts = Options::new();
-    opts.optopt("c", "command", "Specify command to run. Shell returns after running the command", "<command>");
-    opts.optopt("C", "config", "Specify YAML configuration file", "<config>");
-    opts.optopt("l", "lang", "Specify shell language", "<ru|рус>");
-    opts.optopt("s", "shell", "Force the shell binary path", "</bin/bash>");
-    opts.optflag("v", "version", "");
-    opts.optflag("h", "help", "Print this menu");
+    let opts: Options = cli::options();
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
         Err(f) => {
@@ -123,6 +114,19 @@ fn main() {
         );
         std::process::exit(255);
     }
+    //Print completion script
+    if let Some(completion_shell) = matches.opt_str("completion") {
+        match cli::parse_completion_shell(completion_shell.as_str()) {
+            Some(completion_shell) => {
+                print!("{}", cli::completion(completion_shell));
+                std::process::exit(0);
+            }
+            None => {
+                eprintln!("{}", Colour::Red.paint(format!("Unsupported shell for completion: '{}'", completion_shell)));
+                std::process::exit(255);
+            }
+        }
+    }
     //Get shell
     if let Some(sh) = matches.opt_str("s") {
         shell = Some(sh);
@@ -137,15 +141,16 @@ fn main() {
         Some(cmd) => Some(cmd.clone()),
         None => None
     };
-    //Set config file to '-C' file or to default file
-    config_file = match matches.opt_str("C") {
-        Some(cfg_override) => PathBuf::from(cfg_override.as_str()),
-        None => {
+    //Set config files to '-C' files (merged in order) or to default file
+    let config_layers: bool = matches.opt_present("C");
+    let config_files: Vec<PathBuf> = match matches.opt_strs("C") {
+        cfg_overrides if !cfg_overrides.is_empty() => cfg_overrides.iter().map(PathBuf::from).collect(),
+        _ => {
             //Default path
             if let Some(dir) = pyc_config_dir.clone() {
                 let mut pyc_config_file: PathBuf = dir;
                 pyc_config_file.push("pyc.yml");
-                pyc_config_file
+                vec![pyc_config_file]
             } else {
                 eprintln!(
                     "{}",
@@ -163,21 +168,50 @@ fn main() {
         0 => None,
         _ => Some(extra_args.get(0).unwrap().clone())
     };
+    //Write default configuration and exit
+    if matches.opt_present("init-config") {
+        let config_file: &PathBuf = config_files.last().unwrap();
+        match config::Config::init_config_file(config_file.as_path(), matches.opt_present("force")) {
+            Ok(()) => {
+                println!("{}", config_file.display());
+                std::process::exit(0);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                eprintln!("{}", Colour::Red.paint(format!("{} already exists; use --force to overwrite it", config_file.display())));
+                std::process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", config_file.display(), err)));
+                std::process::exit(1);
+            }
+        }
+    }
+    //Check configuration and exit
+    if matches.opt_present("check-config") {
+        std::process::exit(cli::check_config(&config_files));
+    }
     //Parse configuration
-    let config: config::Config = match config::Config::parse_config(config_file.clone()) {
+    let mut config: config::Config = match config::Config::parse_config_files(&config_files) {
         Ok(cfg) => cfg,
         Err(err) => match err.code {
+            //Files provided with '-C' must exist, otherwise the configuration would silently lose a layer
+            config::ConfigErrorCode::NoSuchFileOrDirectory | config::ConfigErrorCode::CouldNotReadFile if config_layers => {
+                eprintln!("{}", Colour::Red.paint(err.message));
+                std::process::exit(255);
+            }
             config::ConfigErrorCode::NoSuchFileOrDirectory => {
                 eprintln!(
                     "{}",
                     Colour::Red.paint(format!(
-                        "{}: {}; {}",
-                        String::from("No such file or directory"),
-                        config_file.display(),
+                        "{}; {}",
+                        err.message,
                         String::from("Using default configuration")
                     ))
                 );
-                config::Config::default()
+                let mut config: config::Config = config::Config::default();
+                //Keep the path, so that the configuration can be created with 'config edit'
+                config.config_file = config_files.last().cloned();
+                config
             }
             _ => panic!(
                 "{}",
@@ -189,6 +223,43 @@ fn main() {
             ),
         },
     };
+    //Override configuration values
+    for set in matches.opt_strs("set").iter() {
+        let result: Result<(), config::ConfigError> = match set.find('=') {
+            Some(idx) => config.set_value(&set[..idx], &set[idx + 1..]),
+            None => Err(config::ConfigError {
+                code: config::ConfigErrorCode::YamlSyntaxError,
+                message: format!("'{}' is not in the form key=value", set),
+                key: None,
+            }),
+        };
+        if let Err(err) = result {
+            eprintln!("{}", Colour::Yellow.paint(format!("Ignoring --set {}: {}", set, err.message)));
+        }
+    }
+    //Apply CLI options overriding the configuration (--path, -T, --restricted)
+    cli::apply_overrides(&mut config, &matches);
+    //Start recording
+    if let Some(cast_file) = matches.opt_str("asciicast") {
+        let (width, height): (usize, usize) = utils::console::term_size().unwrap_or((80, 24));
+        match utils::asciicast::Asciicast::create(PathBuf::from(cast_file.clone()).as_path(), width, height) {
+            Ok(recording) => utils::console::start_recording(recording),
+            Err(err) => {
+                eprintln!("{}", Colour::Red.paint(format!("Could not create asciicast '{}': {}", cast_file, err)));
+                std::process::exit(255);
+            }
+        }
+    }
+    //Start tracing; the trace file is truncated, then each shell appends its I/O to it
+    if let Some(trace_file) = matches.opt_str("trace") {
+        match std::fs::File::create(trace_file.as_str()) {
+            Ok(_) => config.trace_file = Some(PathBuf::from(trace_file)),
+            Err(err) => {
+                eprintln!("{}", Colour::Red.paint(format!("Could not create trace file '{}': {}", trace_file, err)));
+                std::process::exit(255);
+            }
+        }
+    }
     //Set language
     let language: Language = match language {
         Some(l) => l,
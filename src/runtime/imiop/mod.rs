@@ -26,10 +26,18 @@
 *
 */
 
-use crate::shell::Shell;
+use super::suggest::Suggester;
+use crate::config::Config;
+use crate::shell::{Shell, ShellState};
+use crate::translator::ioprocessor::IOProcessor;
 use crate::utils::console::InputEvent;
 
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
 // List of Imiop
+pub(crate) mod nulliop;
 pub(crate) mod shiop;
 pub(crate) mod subprociop;
 
@@ -41,6 +49,78 @@ pub(crate) trait Imiop {
     ///
     /// Handle input event received from stdin
     fn handle_input_event(&mut self, ev: InputEvent, shell: &mut Shell);
+
+    /// ### state
+    ///
+    /// Returns the shell state the IMIOP handles the input of
+    fn state(&self) -> ShellState;
+
+    /// ### exit_code
+    ///
+    /// Returns the exit code if the user requested to terminate the session
+    fn exit_code(&self) -> Option<u8> {
+        None
+    }
+
+    /// ### take_reloaded_config
+    ///
+    /// Returns the configuration reloaded by the user, if any. The configuration is returned only once
+    fn take_reloaded_config(&mut self) -> Option<Config> {
+        None
+    }
+
+    /// ### ask_run
+    ///
+    /// Ask the user whether to run the provided command; the answer is the next input event
+    fn ask_run(&mut self, _command: String) {}
+
+    /// ### recall
+    ///
+    /// Put the provided command in the input buffer, so that it can be edited and run again
+    fn recall(&mut self, _command: String) {}
+}
+
+/// ### imiop_for_state
+///
+/// Instantiate the IMIOP which handles the input in the provided shell state
+pub(crate) fn imiop_for_state(
+    state: ShellState,
+    config: &Config,
+    processor: &IOProcessor,
+    suggester: &Rc<RefCell<Suggester>>,
+    history_file: &Option<PathBuf>,
+) -> Box<dyn Imiop> {
+    match state {
+        ShellState::Shell | ShellState::Unknown => Box::new(shiop::ShIop::new(
+            config.clone(),
+            processor.clone(),
+            suggester.clone(),
+            history_file.clone(),
+        )),
+        ShellState::SubprocessRunning => Box::new(subprociop::SubProcIop::new(config.clone(), processor.clone())),
+        ShellState::Terminated => Box::new(nulliop::NullIop::new()),
+    }
 }
 
-// TODO: add factory for imiop
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::translator::lang::Language;
+    use crate::translator::new_translator;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_imiop_for_state() {
+        let config: Config = Config::default();
+        let processor: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let suggester: Rc<RefCell<Suggester>> = Rc::new(RefCell::new(Suggester::new(String::new())));
+        let imiop = |state: ShellState| imiop_for_state(state, &config, &processor, &suggester, &None);
+        assert_eq!(imiop(ShellState::Shell).state(), ShellState::Shell);
+        assert_eq!(imiop(ShellState::SubprocessRunning).state(), ShellState::SubprocessRunning);
+        assert_eq!(imiop(ShellState::Terminated).state(), ShellState::Terminated);
+        //Shell state is not known yet at startup
+        assert_eq!(imiop(ShellState::Unknown).state(), ShellState::Shell);
+    }
+}
@@ -0,0 +1,54 @@
+//! ## nulliop
+//!
+//! `nulliop`, or Null I/O Processor, is the implementation of the IMIOP trait to use
+//! once the shell has terminated: input is discarded
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use super::Imiop;
+use crate::shell::{Shell, ShellState};
+use crate::utils::console::InputEvent;
+
+pub(crate) struct NullIop {}
+
+impl NullIop {
+    /// ### new
+    ///
+    /// Instantiate a new `NullIop`
+    pub fn new() -> NullIop {
+        NullIop {}
+    }
+}
+
+impl Imiop for NullIop {
+    /// ### handle_input_event
+    ///
+    /// Input is discarded, since there's no shell to write it to
+    fn handle_input_event(&mut self, _ev: InputEvent, _shell: &mut Shell) {}
+
+    /// ### state
+    ///
+    /// Returns the shell state the IMIOP handles the input of
+    fn state(&self) -> ShellState {
+        ShellState::Terminated
+    }
+}
@@ -24,20 +24,47 @@
 *
 */
 
+extern crate ansi_term;
+extern crate regex;
+
 use super::Imiop;
-use crate::config::Config;
-use crate::runtime::{console_fmt, print_err, print_out, resolve_command};
-use crate::shell::Shell;
-use crate::translator::ioprocessor::IOProcessor;
+use crate::config::{ClearMode, Config, LayoutAutocorrect};
+use crate::lev::LevEditor;
+use crate::runtime::{append_history, argv_to_latin, console_fmt, expand_home, print_err, print_out, split_args, unquote_arg};
+use crate::runtime::suggest::Suggester;
+use crate::shell::{Shell, ShellState};
+use crate::translator::ioprocessor::{ExpressionParserError, IOProcessor};
 use crate::utils::buffer;
 use crate::utils::console::{self, InputEvent};
 
+use ansi_term::Style;
+use regex::Regex;
+use std::cell::RefCell;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::rc::Rc;
+
+/// Editors used to edit the configuration when `EDITOR` is not set, in order of preference
+const FALLBACK_EDITORS: [&str; 2] = ["vi", "nano"];
+/// Prompt printed while the body of a heredoc is typed
+const HEREDOC_PROMPT: &str = "> ";
+
 pub(crate) struct ShIop {
     input_buffer: Vec<char>,
     input_buffer_cursor: usize,
     rev_search: Option<String>, // Reverse search match
     rev_search_idx: usize,      // Reverse search last match index
     history_index: usize,
+    translit_bypass: bool,      // If true, input is sent to the shell without being transliterated
+    exit_code: Option<u8>,      // Exit code requested through the exit builtin
+    reload_pending: Option<Config>,  // Edited configuration, waiting for the user to confirm the reload
+    run_pending: Option<String>,     // Command fixed by the layout autocorrection, waiting for the user to confirm it
+    reloaded_config: Option<Config>, // Configuration reloaded by the user, not yet taken by the runtime
+    heredoc: Option<Heredoc>,        // Heredoc being typed, not yet sent to the shell
+    history_file: Option<PathBuf>,   // Commands are appended to it as they're entered, if history autosave is enabled
+    suggester: Rc<RefCell<Suggester>>, // Shared with the runtime; used to tell executables from directories with auto cd
     config: Config,
     processor: IOProcessor,
 }
@@ -46,13 +73,21 @@ impl ShIop {
     /// ### new
     ///
     /// Instantiate a new `ShIop`
-    pub fn new(config: Config, processor: IOProcessor) -> ShIop {
+    pub fn new(config: Config, processor: IOProcessor, suggester: Rc<RefCell<Suggester>>, history_file: Option<PathBuf>) -> ShIop {
         ShIop {
             input_buffer: Vec::with_capacity(2048),
             input_buffer_cursor: 0,
             rev_search: None,
             rev_search_idx: 0,
             history_index: 0,
+            translit_bypass: false,
+            exit_code: None,
+            reload_pending: None,
+            run_pending: None,
+            reloaded_config: None,
+            heredoc: None,
+            history_file,
+            suggester,
             config: config,
             processor: processor,
         }
@@ -66,6 +101,21 @@ impl ShIop {
         self.input_buffer_cursor = 0;
     }
 
+    /// ### push_history
+    ///
+    /// Push the command to history; if history autosave is enabled, the command is appended to the history file too
+    fn push_history(&self, shell: &mut Shell, command: String) {
+        //The history file mustn't get lines which are not in the history (e.g. collapsed duplicates)
+        if !shell.history.push(command) || !self.config.history_config.autosave {
+            return;
+        }
+        if let Some(history_file) = self.history_file.as_ref() {
+            if let Err(err) = append_history(history_file.as_path(), &shell.history, self.config.history_config.format) {
+                self.report_err(format!("Could not write history to '{}': {}", history_file.display(), err));
+            }
+        }
+    }
+
     /// ### reset_history_index
     ///
     /// Reset history index to 0
@@ -110,6 +160,48 @@ impl ShIop {
         }
     }
 
+    /// ### move_word_left
+    ///
+    /// Move cursor to the beginning of the previous word
+    fn move_word_left(&mut self) {
+        let boundary: usize = buffer::prev_word_boundary(
+            &self.input_buffer,
+            self.input_buffer_cursor,
+            self.config.input_config.word_chars.as_deref(),
+        );
+        while self.input_buffer_cursor > boundary {
+            self.move_left();
+        }
+    }
+
+    /// ### move_word_right
+    ///
+    /// Move cursor to the end of the next word
+    fn move_word_right(&mut self) {
+        let boundary: usize = buffer::next_word_boundary(
+            &self.input_buffer,
+            self.input_buffer_cursor,
+            self.config.input_config.word_chars.as_deref(),
+        );
+        while self.input_buffer_cursor < boundary {
+            self.move_right();
+        }
+    }
+
+    /// ### delete_word
+    ///
+    /// Delete the word before the cursor
+    fn delete_word(&mut self) {
+        let boundary: usize = buffer::prev_word_boundary(
+            &self.input_buffer,
+            self.input_buffer_cursor,
+            self.config.input_config.word_chars.as_deref(),
+        );
+        while self.input_buffer_cursor > boundary {
+            self.backspace();
+        }
+    }
+
     /// ### perform_history_backward
     ///
     /// Get previous element in history and put it into the buffer
@@ -209,6 +301,77 @@ impl ShIop {
         None
     }
 
+    /// ### search_forward
+    ///
+    /// Perform forward search, looking for a match newer than the current one
+    /// Returns matched command in history
+    fn search_forward(&mut self, shell: &Shell) -> Option<String> {
+        let current_match: String = match &self.rev_search {
+            Some(s) => s.clone(),
+            None => return None,
+        };
+        // Current match is at rev_search_idx - 1; iterate over the newer entries
+        for i in (0..self.rev_search_idx.saturating_sub(1)).rev() {
+            if let Some(check_match) = shell.history.at(i) {
+                if check_match.contains(current_match.as_str()) {
+                    // Update index, as search_reverse does
+                    self.rev_search_idx = i + 1;
+                    return Some(check_match);
+                }
+            }
+        }
+        // Return None if not found
+        None
+    }
+
+    /// ### start_search
+    ///
+    /// Enter search mode, if not searching yet, using the current input buffer as query
+    fn start_search(&mut self) {
+        if self.rev_search.is_none() {
+            // Set reverse search to current input buffer
+            let curr_stdin: String = buffer::chars_to_string(&self.input_buffer);
+            self.rev_search = Some(curr_stdin.clone());
+            // Set index to first element (0)
+            self.rev_search_idx = 0;
+            // Write reverse-i-search prompt
+            console::rewrite(self.rev_search_prompt(curr_stdin.as_str()), curr_stdin.len());
+        }
+    }
+
+    /// ### set_search_match
+    ///
+    /// Set the command matched by search as current input
+    fn set_search_match(&mut self, matched: String) {
+        let prev_length: usize = self.input_buffer.len();
+        self.input_buffer = matched.chars().collect();
+        // Set cursor to new length
+        self.input_buffer_cursor = self.input_buffer.len();
+        // Print prompt
+        console::rewrite(matched, prev_length);
+    }
+
+    /// ### rev_search_prompt
+    ///
+    /// Format the reverse search prompt for the provided query.
+    /// The label (with its glyph) is painted with the configured color, while the query is in bold
+    fn rev_search_prompt(&self, query: &str) -> String {
+        let label: String = console_fmt(
+            self.config.prompt_config.rev_search_label.clone(),
+            self.config.output_config.translate_output,
+            &self.processor,
+        );
+        let label: String = match &self.config.prompt_config.rev_search_glyph {
+            Some(glyph) => format!("{} {}", glyph, label),
+            None => label,
+        };
+        let label: String = match self.config.prompt_config.rev_search_color {
+            Some(color) => color.paint(label).to_string(),
+            None => label,
+        };
+        format!("{}`{}':  ", label, Style::new().bold().paint(query))
+    }
+
     /// ### perform_interactive_enter
     ///
     /// Perform enter in interactive shell mode
@@ -221,30 +384,79 @@ impl ShIop {
         console::println(String::new());
         //Convert input buffer to string
         let stdin_input: String = buffer::chars_to_string(&self.input_buffer);
+        //Line of a heredoc body; empty lines and builtins are part of the body too
+        if self.heredoc.is_some() {
+            self.clear_buffer();
+            self.push_heredoc_line(shell, stdin_input);
+            return;
+        }
         //If input is empty, print prompt (if state is IDLE)
         if stdin_input.trim().len() == 0 {
             console::print(format!("{} ", shell.get_promptline(&self.processor)));
             self.clear_buffer();
+        } else if let Some(exit_code) = parse_exit_builtin(&stdin_input) {
+            //Exit builtin; terminate the session with the provided code, or with the status of the last command
+            self.clear_buffer();
+            self.push_history(shell, String::from(stdin_input.trim()));
+            let exit_code: u8 = exit_code.unwrap_or_else(|| shell.get_exit_status());
+            self.exit_code = Some(exit_code);
+            //Let the shell terminate gracefully too
+            let _ = shell.write(format!("exit {}\n", exit_code));
+        } else if is_config_edit_builtin(&stdin_input) {
+            //Config edit builtin; open the configuration in the editor
+            self.clear_buffer();
+            self.push_history(shell, String::from(stdin_input.trim()));
+            //Restricted mode could be disabled editing the configuration
+            if self.config.shell_config.restricted {
+                self.report_err(String::from("config edit: restricted"));
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                return;
+            }
+            self.edit_config(shell, resolve_editor(env::var("EDITOR").ok()).as_str());
+        } else if is_translate_last_builtin(&stdin_input) {
+            //Translate last builtin; print the output of the last command transliterated
+            self.clear_buffer();
+            self.push_history(shell, String::from(stdin_input.trim()));
+            match self.translate_last(shell) {
+                Some(output) => console::print(output),
+                None => self.report_err(String::from("translate-last: no output to translate")),
+            }
+            console::print(format!("{} ", shell.get_promptline(&self.processor)));
+        } else if let Some(translate_output) = parse_translate_builtin(&stdin_input) {
+            //Translate builtin; enable or disable the output translation
+            self.clear_buffer();
+            self.push_history(shell, String::from(stdin_input.trim()));
+            self.set_translate_output(translate_output);
+            console::print(format!("{} ", shell.get_promptline(&self.processor)));
+        } else if is_prompt_cache_clear_builtin(&stdin_input) {
+            //Prompt cache clear builtin; the prompt modules discover their values again
+            self.clear_buffer();
+            self.push_history(shell, String::from(stdin_input.trim()));
+            shell.clear_prompt_cache();
+            console::print(format!("{} ", shell.get_promptline(&self.processor)));
         } else {
-            //Treat input
+            //Treat input; keep the input as typed to fix the keyboard layout if the command is not found, or to recall it if it fails
+            if self.config.shell_config.layout_autocorrect != LayoutAutocorrect::Off || self.config.input_config.recall_on_failure {
+                shell.set_last_input(String::from(stdin_input.trim()));
+            }
             //If state is Idle, convert expression, otherwise convert text
-            let input: String = {
-                //Resolve alias
-                let mut argv: Vec<String> =
-                    Vec::with_capacity(stdin_input.matches(" ").count() + 1);
-                for arg in stdin_input.split_whitespace() {
-                    argv.push(String::from(arg));
-                }
-                //Process arg 0
-                resolve_command(&mut argv, &self.config);
-                //Rejoin arguments
-                let input: String = argv.join(" ") + "\n";
-                match &self.processor.expression_to_latin(&input) {
-                    Ok(ex) => ex.clone(),
+            let input: String = if self.translit_bypass {
+                //Transliteration is suspended, send input as it is
+                stdin_input.clone() + "\n"
+            } else {
+                match self.input_to_latin(stdin_input.as_str()) {
+                    Ok(Some(ex)) => ex,
+                    Ok(None) => {
+                        //Alias expanded to an empty command, there's nothing to execute
+                        console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                        self.clear_buffer();
+                        return;
+                    }
                     Err(err) => {
                         print_err(
                             String::from(format!("Input error: {:?}", err)),
                             self.config.output_config.translate_output,
+                            self.config.output_config.error_color,
                             &self.processor,
                         );
                         //Clear input buffer
@@ -255,15 +467,258 @@ impl ShIop {
             };
             //Clear input buffer
             self.clear_buffer();
+            //Input may be blank once translated; don't send it to the shell
+            if buffer::is_blank(input.as_str()) {
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                return;
+            }
+            //A heredoc starts; its body is collected before the command is sent to the shell
+            if let Some((delimiter, strip_tabs)) = parse_heredoc_start(input.as_str()) {
+                self.heredoc = Some(Heredoc {
+                    delimiter,
+                    strip_tabs,
+                    command: String::from(input.trim()),
+                    original: String::from(stdin_input.trim()),
+                    block: input,
+                });
+                console::print(String::from(HEREDOC_PROMPT));
+                return;
+            }
             //Process input
-            self.process_input_interactive(shell, input);
+            self.process_input_interactive(shell, input, String::from(stdin_input.trim()));
+        }
+    }
+
+    /// ### push_heredoc_line
+    ///
+    /// Push a line to the heredoc being typed. Once the delimiter is entered, the whole block is sent to the shell
+    fn push_heredoc_line(&mut self, shell: &mut Shell, line: String) {
+        let mut heredoc: Heredoc = match self.heredoc.take() {
+            Some(heredoc) => heredoc,
+            None => return,
+        };
+        //The delimiter is kept as typed (it may be quoted, and then not transliterated)
+        let line: String = match self.config.input_config.translate_heredoc
+            && !self.translit_bypass
+            && !heredoc.is_delimiter(line.as_str())
+        {
+            true => self.processor.text_to_latin(&line),
+            false => line,
+        };
+        heredoc.block.push_str(line.as_str());
+        heredoc.block.push('\n');
+        if heredoc.is_delimiter(line.as_str()) {
+            //Heredoc is complete; only the command line is pushed to history, then the block is sent as a single command
+            self.push_history(shell, match self.config.history_config.store_original {
+                true => heredoc.original,
+                false => heredoc.command,
+            });
+            self.write_input(shell, heredoc.block);
+        } else {
+            console::print(String::from(HEREDOC_PROMPT));
+            self.heredoc = Some(heredoc);
+        }
+    }
+
+    /// ### input_to_latin
+    ///
+    /// Resolve alias and convert the input typed by the user into the latin command line to send to the shell.
+    /// Returns None if the input expands to an empty command
+    fn input_to_latin(&self, input: &str) -> Result<Option<String>, ExpressionParserError> {
+        let argv: Vec<String> = split_args(input);
+        if argv.is_empty() {
+            return Ok(None);
+        }
+        //Resolve alias, rejoin arguments and convert them to latin
+        let command: String = argv_to_latin(&argv, &self.config, &self.processor)?;
+        match command.trim().is_empty() {
+            true => Ok(None),
+            false => Ok(Some(command)),
+        }
+    }
+
+    /// ### translate_last
+    ///
+    /// Returns the output of the last command transliterated; None if the last command had no output
+    fn translate_last(&self, shell: &Shell) -> Option<String> {
+        let output: &str = shell.get_last_output();
+        if output.is_empty() {
+            return None;
+        }
+        let mut output: String = self.processor.text_to_cyrillic(&String::from(output));
+        if !output.ends_with('\n') {
+            output.push('\n');
+        }
+        Some(output)
+    }
+
+    /// ### edit_config
+    ///
+    /// Open the configuration file in editor; once the editor has exited, the user is asked whether to reload the configuration.
+    /// Pyc is blocked while the editor is running, so the editor has full control of the terminal
+    fn edit_config(&mut self, shell: &mut Shell, editor: &str) {
+        let config_file: PathBuf = match self.config.config_file.clone() {
+            Some(path) => path,
+            None => {
+                self.report_err(String::from("config edit: no configuration file is in use"));
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                return;
+            }
+        };
+        match run_editor(editor, config_file.as_path()) {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                self.report_err(format!("config edit: '{}' exited with {}", editor, status));
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                return;
+            }
+            Err(err) => {
+                self.report_err(format!("config edit: could not start '{}': {}", editor, err));
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                return;
+            }
+        }
+        //Reload the configuration layers too
+        let mut config_files: Vec<PathBuf> = self.config.config_layers.clone();
+        config_files.push(config_file);
+        match Config::parse_config_files(&config_files) {
+            Ok(config) => {
+                console::print(String::from("Reload configuration? [y/N] "));
+                self.reload_pending = Some(config);
+            }
+            Err(err) => {
+                self.report_err(format!("config edit: configuration won't be reloaded: {}", err));
+                console::print(format!("{} ", shell.get_promptline(&self.processor)));
+            }
+        }
+    }
+
+    /// ### set_translate_output
+    ///
+    /// Enable or disable the output translation; the runtime takes the updated configuration as a reloaded one
+    fn set_translate_output(&mut self, translate_output: bool) {
+        self.config.output_config.translate_output = translate_output;
+        self.reloaded_config = Some(self.config.clone());
+        print_out(
+            format!("Output translation {}", match translate_output {
+                true => "on",
+                false => "off",
+            }),
+            translate_output,
+            &self.processor,
+        );
+    }
+
+    /// ### start_lev
+    ///
+    /// Run the lev editor with the arguments of the builtin; the prompt loop is suspended until the editor exits.
+    /// The editor starts with transliteration suspended if it's suspended at the prompt.
+    /// Returns the exit code of the editor
+    fn start_lev(&mut self, shell: &Shell, input: &str) -> u8 {
+        let argv: Vec<String> = lev_argv(input, shell.get_wrkdir());
+        LevEditor::new(&self.processor, !self.translit_bypass, self.config.input_config.translit_toggle_key).main(&argv)
+    }
+
+    /// ### answer_reload
+    ///
+    /// Handle the answer to the reload question; any key other than 'y' keeps the current configuration
+    fn answer_reload(&mut self, ev: InputEvent, config: Config, shell: &mut Shell) {
+        let reload: bool = match ev {
+            InputEvent::Key(k) => matches!(k.as_str(), "y" | "Y" | "д" | "Д"),
+            _ => false,
+        };
+        console::println(String::new());
+        if reload {
+            self.config = config.clone();
+            self.reloaded_config = Some(config);
+            //The prompt is printed right away, so it must use the new prompt configuration
+            shell.set_prompt_config(&self.config.prompt_config);
+            print_out(
+                String::from("Configuration reloaded"),
+                self.config.output_config.translate_output,
+                &self.processor,
+            );
+        }
+        console::print(format!("{} ", shell.get_promptline(&self.processor)));
+    }
+
+    /// ### answer_run
+    ///
+    /// Handle the answer to the question whether to run the command fixed by the layout autocorrection;
+    /// any key other than 'y' discards the command
+    fn answer_run(&mut self, ev: InputEvent, command: String, shell: &mut Shell) {
+        let run: bool = match ev {
+            InputEvent::Key(k) => matches!(k.as_str(), "y" | "Y" | "д" | "Д"),
+            _ => false,
+        };
+        console::println(String::new());
+        if run {
+            self.push_history(shell, command.clone());
+            if shell.write(command + "\n").is_ok() {
+                return;
+            }
+        }
+        console::print(format!("{} ", shell.get_promptline(&self.processor)));
+    }
+
+    /// ### report_err
+    ///
+    /// Print error using the current output configuration
+    fn report_err(&self, err: String) {
+        print_err(
+            err,
+            self.config.output_config.translate_output,
+            self.config.output_config.error_color,
+            &self.processor,
+        );
+    }
+
+    /// ### toggle_translit_bypass
+    ///
+    /// Suspend or resume the transliteration of the input.
+    /// The current state is reported to the user, then prompt and input buffer are written again
+    fn toggle_translit_bypass(&mut self, shell: &mut Shell) {
+        self.translit_bypass = !self.translit_bypass;
+        shell.set_translit_bypass(self.translit_bypass);
+        let indicator: &str = match self.translit_bypass {
+            true => "(transliteration off)",
+            false => "(transliteration on)",
+        };
+        console::println(String::new());
+        print_out(
+            String::from(indicator),
+            self.config.output_config.translate_output,
+            &self.processor,
+        );
+        console::print(format!(
+            "{} {}",
+            shell.get_promptline(&self.processor),
+            buffer::chars_to_string(&self.input_buffer)
+        ));
+        //Move cursor at the end of the buffer
+        self.input_buffer_cursor = self.input_buffer.len();
+    }
+
+    /// ### auto_cd
+    ///
+    /// If input (already converted to latin) is the path of a directory, returns the command to change directory to it.
+    /// Inputs made of more than one token and executables in PATH are never treated as directories
+    fn auto_cd(&self, input: &str, wrkdir: &Path) -> Option<String> {
+        let mut tokens = input.split_whitespace();
+        let dir: &str = tokens.next()?;
+        if tokens.next().is_some() || self.suggester.borrow_mut().is_executable(dir) {
+            return None;
+        }
+        match wrkdir.join(expand_home(dir)).is_dir() {
+            true => Some(format!("cd {}\n", dir)),
+            false => None,
         }
     }
 
     /// ### process_input_interactive
     ///
     /// Process input after enter in interactive mode
-    fn process_input_interactive(&mut self, shell: &mut Shell, mut input: String) {
+    fn process_input_interactive(&mut self, shell: &mut Shell, mut input: String, mut original: String) {
         //@! Handle events before anything else
         if input.starts_with("!") {
             //Execute command from history
@@ -276,6 +731,7 @@ impl ShIop {
                     print_err(
                         format!("!{}: event not found", history_index),
                         self.config.output_config.translate_output,
+                        self.config.output_config.error_color,
                         &self.processor,
                     );
                     console::print(format!("{} ", shell.get_promptline(&self.processor)));
@@ -288,12 +744,26 @@ impl ShIop {
                         //Event exists, replace input with command
                         //Reverse index
                         input = format!("{}\n", cmd);
+                        //If history stores the original input, it must be converted again
+                        if self.config.history_config.store_original && !self.translit_bypass {
+                            match self.input_to_latin(cmd.as_str()) {
+                                Ok(Some(ex)) => input = ex,
+                                Ok(None) => {}
+                                Err(err) => {
+                                    self.report_err(format!("Input error: {:?}", err));
+                                    console::print(format!("{} ", shell.get_promptline(&self.processor)));
+                                    return;
+                                }
+                            }
+                        }
+                        original = cmd;
                     }
                     None => {
                         //Event doesn't exist
                         print_err(
                             format!("!{}: event not found", history_index),
                             self.config.output_config.translate_output,
+                            self.config.output_config.error_color,
                             &self.processor,
                         );
                         console::print(format!("{} ", shell.get_promptline(&self.processor)));
@@ -305,19 +775,23 @@ impl ShIop {
                 print_err(
                     format!("!{}: event not found", history_index),
                     self.config.output_config.translate_output,
+                    self.config.output_config.error_color,
                     &self.processor,
                 );
                 console::print(format!("{} ", shell.get_promptline(&self.processor)));
                 return;
             }
         }
-        //Push input to history
-        shell.history.push(input.clone());
+        //Push input to history (as typed by the user, or transliterated)
+        self.push_history(shell, match self.config.history_config.store_original {
+            true => original,
+            false => input.clone(),
+        });
         // @! Built-in commands
         // Check if clear command
         if input.starts_with("clear") {
             //Clear screen, then write prompt
-            console::clear();
+            console::print(clear_screen_seq(self.config.shell_config.clear_mode, console::term_size()));
             console::print(format!("{} ", shell.get_promptline(&self.processor)));
         } else if input.starts_with("history") {
             //Print history
@@ -330,14 +804,40 @@ impl ShIop {
                 );
             }
             console::print(format!("{} ", shell.get_promptline(&self.processor)));
-        } else if input.starts_with("lev") {
-            // TODO: start lev
+        } else if is_lev_builtin(input.as_str()) {
+            //Lev could edit any file, bypassing the restricted denylist
+            if self.config.shell_config.restricted {
+                self.report_err(String::from("lev: restricted"));
+            } else {
+                let _ = self.start_lev(shell, input.as_str());
+            }
+            console::print(format!("{} ", shell.get_promptline(&self.processor)));
+        } else {
+            //Change directory if a directory has been typed; builtins are never shadowed
+            if self.config.shell_config.auto_cd {
+                if let Some(cd) = self.auto_cd(input.as_str(), shell.get_wrkdir()) {
+                    input = cd;
+                }
+            }
+            self.write_input(shell, input);
+        }
+    }
+
+    /// ### write_input
+    ///
+    /// Write input to the shell, unless it is denied in restricted mode
+    fn write_input(&mut self, shell: &mut Shell, input: String) {
+        if self.config.shell_config.restricted && is_denied(input.as_str(), &self.config.shell_config.restricted_denylist) {
+            //Command is not allowed in restricted mode; don't send it to the shell
+            self.report_err(format!("{}: restricted", input.trim()));
+            console::print(format!("{} ", shell.get_promptline(&self.processor)));
         } else {
             //@! Write input as usual
             if let Err(err) = shell.write(input) {
                 print_err(
                     String::from(err.to_string()),
                     self.config.output_config.translate_output,
+                    self.config.output_config.error_color,
                     &self.processor,
                 );
             }
@@ -345,11 +845,211 @@ impl ShIop {
     }
 }
 
+/// ## Heredoc
+///
+/// Heredoc being typed by the user
+struct Heredoc {
+    delimiter: String,
+    strip_tabs: bool, // `<<-`: leading tabs are stripped from the delimiter line
+    command: String,  // Command line, transliterated
+    original: String, // Command line as typed by the user
+    block: String,    // Command line and body typed so far, transliterated
+}
+
+impl Heredoc {
+    /// ### is_delimiter
+    ///
+    /// Returns whether the line terminates the heredoc
+    fn is_delimiter(&self, line: &str) -> bool {
+        let line: &str = match self.strip_tabs {
+            true => line.trim_start_matches('\t'),
+            false => line,
+        };
+        line == self.delimiter
+    }
+}
+
+/// ### parse_heredoc_start
+///
+/// Returns the delimiter of the first heredoc in the command line and whether leading tabs are stripped (`<<-`).
+/// Quoted strings and here-strings (`<<<`) are skipped
+fn parse_heredoc_start(input: &str) -> Option<(String, bool)> {
+    lazy_static! {
+        static ref HEREDOC_RE: Regex = Regex::new(
+            r#"'[^']*'|"(?:[^"\\]|\\.)*"|(?:^|[^<])<<(-?)[ \t]*(?:'([^'\n]+)'|"([^"\n]+)"|\\?([A-Za-z0-9_.-]+))"#
+        ).unwrap();
+    }
+    HEREDOC_RE.captures_iter(input).find_map(|caps| {
+        let delimiter = caps.get(2).or_else(|| caps.get(3)).or_else(|| caps.get(4))?;
+        Some((String::from(delimiter.as_str()), &caps[1] == "-"))
+    })
+}
+
+/// ### parse_exit_builtin
+///
+/// Returns the exit code if the input is the exit builtin (`exit` or `выход`) with an optional numeric code.
+/// If no code is provided, `Some(None)` is returned: as in POSIX shells, the status of the last command is used.
+/// Returns None if the input is not the exit builtin or the code is not a number
+fn parse_exit_builtin(input: &str) -> Option<Option<u8>> {
+    let mut argv = input.split_whitespace();
+    match argv.next() {
+        Some("exit") | Some("выход") => {}
+        _ => return None,
+    }
+    let exit_code: Option<u8> = match argv.next() {
+        Some(code) => match code.parse::<i64>() {
+            Ok(code) => Some((code & 0xff) as u8), //Same as the shell would do
+            Err(_) => return None,
+        },
+        None => None,
+    };
+    //Exit takes at most one argument
+    match argv.next() {
+        Some(_) => None,
+        None => Some(exit_code),
+    }
+}
+
+/// ### clear_screen_seq
+///
+/// Returns the escape sequence to clear the screen with the configured mode.
+/// If the terminal size is unknown, the screen is erased instead of scrolled
+fn clear_screen_seq(mode: ClearMode, term_size: Option<(usize, usize)>) -> String {
+    match (mode, term_size) {
+        (ClearMode::Purge, _) => console::purge_seq(),
+        (ClearMode::Scroll, Some((_, rows))) => console::scroll_up_seq(rows),
+        _ => console::clear_seq(),
+    }
+}
+
+/// ### is_denied
+///
+/// Returns whether the command matches any of the patterns in the denylist.
+/// The command is matched both as it is and once normalized (see `normalize_denied_command`).
+/// This is a best-effort guard: a command run through another interpreter (e.g. `sh -c`) can't be detected,
+/// unless the interpreter itself is in the denylist
+fn is_denied(command: &str, denylist: &[Regex]) -> bool {
+    let normalized: String = normalize_denied_command(command);
+    denylist.iter().any(|re| re.is_match(command) || re.is_match(normalized.as_str()))
+}
+
+/// ### normalize_denied_command
+///
+/// Removes quotes and backslashes from the command and the `command` and `builtin` prefixes,
+/// so that `\cd`, `'cd'` and `command cd` are seen as `cd`
+fn normalize_denied_command(command: &str) -> String {
+    let unquoted: String = command.chars().filter(|c| !matches!(c, '\'' | '"' | '\\')).collect();
+    lazy_static! {
+        static ref PREFIX_RE: Regex = Regex::new(r"(^|[;&|(`])(\s*(command|builtin)\s+)+").unwrap();
+    }
+    PREFIX_RE.replace_all(unquoted.as_str(), "$1").to_string()
+}
+
+/// ### is_config_edit_builtin
+///
+/// Returns whether the input is the `config edit` builtin
+fn is_config_edit_builtin(input: &str) -> bool {
+    let argv: Vec<&str> = input.split_whitespace().collect();
+    argv == ["config", "edit"]
+}
+
+/// ### is_lev_builtin
+///
+/// Returns whether the input is the `lev` builtin
+fn is_lev_builtin(input: &str) -> bool {
+    input.split_whitespace().next() == Some("lev")
+}
+
+/// ### lev_argv
+///
+/// Get the arguments of the `lev` builtin; arguments are split and unquoted as the shell would do,
+/// and files are relative to the working directory of the shell (`~` is expanded only if not quoted)
+fn lev_argv(input: &str, wrkdir: &Path) -> Vec<String> {
+    split_args(input)
+        .iter()
+        .enumerate()
+        .map(|(idx, arg)| match (idx, arg.starts_with('~')) {
+            (0, _) => unquote_arg(arg),
+            (_, true) => wrkdir.join(expand_home(&unquote_arg(arg))).display().to_string(),
+            (_, false) => wrkdir.join(unquote_arg(arg)).display().to_string(),
+        })
+        .collect()
+}
+
+/// ### parse_translate_builtin
+///
+/// Parse the `translate on|off` builtin; returns whether the output has to be translated
+fn parse_translate_builtin(input: &str) -> Option<bool> {
+    let argv: Vec<&str> = input.split_whitespace().collect();
+    match argv.as_slice() {
+        ["translate", "on"] => Some(true),
+        ["translate", "off"] => Some(false),
+        _ => None,
+    }
+}
+
+/// ### is_translate_last_builtin
+///
+/// Returns whether the input is the `translate-last` builtin
+fn is_translate_last_builtin(input: &str) -> bool {
+    input.trim() == "translate-last"
+}
+
+/// ### is_prompt_cache_clear_builtin
+///
+/// Returns whether the input is the `prompt cache clear` builtin
+fn is_prompt_cache_clear_builtin(input: &str) -> bool {
+    let argv: Vec<&str> = input.split_whitespace().collect();
+    argv == ["prompt", "cache", "clear"]
+}
+
+/// ### resolve_editor
+///
+/// Returns the editor to use; if `EDITOR` is not set, the first fallback editor found in PATH is used
+fn resolve_editor(editor: Option<String>) -> String {
+    match editor {
+        Some(editor) if !editor.trim().is_empty() => editor,
+        _ => {
+            let path: String = env::var("PATH").unwrap_or_default();
+            for fallback in FALLBACK_EDITORS.iter() {
+                if env::split_paths(&path).any(|dir| dir.join(fallback).is_file()) {
+                    return String::from(*fallback);
+                }
+            }
+            String::from(FALLBACK_EDITORS[0])
+        }
+    }
+}
+
+/// ### run_editor
+///
+/// Run editor on file and wait for it to exit. Editor may contain arguments (e.g. `code --wait`)
+fn run_editor(editor: &str, file: &Path) -> io::Result<ExitStatus> {
+    let mut argv = editor.split_whitespace();
+    let program: &str = match argv.next() {
+        Some(program) => program,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty editor")),
+    };
+    Command::new(program).args(argv).arg(file).status()
+}
+
 impl Imiop for ShIop {
     /// ### handle_input_event
     ///
     /// Handle input event received from stdin
     fn handle_input_event(&mut self, ev: InputEvent, shell: &mut Shell) {
+        //A new ShIop is created after each command; transliteration state is kept by the shell
+        self.translit_bypass = shell.get_translit_bypass();
+        //If a reload is pending, the event is the answer to the reload question
+        if let Some(config) = self.reload_pending.take() {
+            self.answer_reload(ev, config, shell);
+            return;
+        }
+        //If a fixed command is pending, the event is the answer to the run question
+        if let Some(command) = self.run_pending.take() {
+            self.answer_run(ev, command, shell);
+            return;
+        }
         match ev {
             InputEvent::ArrowDown => {
                 //Get previous element in history
@@ -365,6 +1065,12 @@ impl Imiop for ShIop {
             InputEvent::ArrowRight => {
                 self.move_right();
             }
+            InputEvent::WordLeft => {
+                self.move_word_left();
+            }
+            InputEvent::WordRight => {
+                self.move_word_right();
+            }
             InputEvent::Backspace => {
                 self.backspace();
             }
@@ -372,6 +1078,11 @@ impl Imiop for ShIop {
                 console::carriage_return();
             }
             InputEvent::Ctrl(sig) => {
+                //Suspend / resume transliteration (CTRL + T by default)
+                if Some(sig) == self.config.input_config.translit_toggle_key {
+                    self.toggle_translit_bypass(shell);
+                    return;
+                }
                 //Check running state
                 //if running state is Idle, it will be handled by the console,
                 match sig {
@@ -390,8 +1101,9 @@ impl Imiop for ShIop {
                     }
                     3 => {
                         //CTRL + C
-                        //Abort input and go to newline
+                        //Abort input (and heredoc) and go to newline
                         self.clear_buffer();
+                        self.heredoc = None;
                         //Reset history index
                         self.reset_history_index();
                         // Unset reverse search
@@ -438,52 +1150,46 @@ impl Imiop for ShIop {
                     12 => {
                         // CTRL + L
                         //Clear, but doesn't reset input
-                        console::clear();
+                        console::print(clear_screen_seq(self.config.shell_config.clear_mode, console::term_size()));
                         console::print(format!(
                             "{} {}",
                             shell.get_promptline(&self.processor),
                             buffer::chars_to_string(&self.input_buffer)
                         ));
                     }
+                    23 => {
+                        // CTRL + W
+                        // Delete word before cursor
+                        self.delete_word();
+                    }
                     18 => {
                         // CTRL + R
                         // If reverse search is empty, set reverse search match
-                        if self.rev_search.is_none() {
-                            // Set reverse search to current input buffer
-                            let curr_stdin: String = buffer::chars_to_string(&self.input_buffer);
-                            self.rev_search = Some(curr_stdin.clone());
-                            // Set index to first element (0)
-                            self.rev_search_idx = 0;
-                            // Write reverse-i-search prompt
-                            console::rewrite(
-                                format!(
-                                    "{}`{}':  ",
-                                    console_fmt(
-                                        String::from("(reverse-i-search)"),
-                                        self.config.output_config.translate_output,
-                                        &self.processor
-                                    ),
-                                    curr_stdin
-                                ),
-                                curr_stdin.len(),
-                            );
-                        }
+                        self.start_search();
                         // Find current input in history starting from bottom
                         if let Some(matched) = self.search_reverse(shell) {
-                            // Set matched as current input
-                            let prev_length: usize = self.input_buffer.len();
-                            self.input_buffer.clear();
-                            self.input_buffer = matched.chars().collect();
-                            // Set cursor to new length
-                            self.input_buffer_cursor = self.input_buffer.len();
-                            // Print prompt
-                            console::rewrite(matched, prev_length);
+                            self.set_search_match(matched);
+                        }
+                    }
+                    19 => {
+                        // CTRL + S
+                        // Same as CTRL + R, but moving toward the most recent matches
+                        self.start_search();
+                        if let Some(matched) = self.search_forward(shell) {
+                            self.set_search_match(matched);
                         }
                     }
                     _ => {} //Unhandled
                 }
             }
-            InputEvent::Key(k) => {
+            InputEvent::Key(mut k) => {
+                //Drop zero-width and bidirectional control characters
+                if self.config.input_config.filter_control_chars {
+                    k.retain(|ch| !buffer::is_invisible_control_char(ch));
+                    if k.is_empty() {
+                        return;
+                    }
+                }
                 //Push key
                 //Push k to input buffer
                 for ch in k.chars() {
@@ -506,19 +1212,64 @@ impl Imiop for ShIop {
             }
         }
     }
+
+    /// ### state
+    ///
+    /// Returns the shell state the IMIOP handles the input of
+    fn state(&self) -> ShellState {
+        ShellState::Shell
+    }
+
+    /// ### exit_code
+    ///
+    /// Returns the exit code requested through the exit builtin
+    fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// ### take_reloaded_config
+    ///
+    /// Returns the configuration reloaded through the config edit builtin
+    fn take_reloaded_config(&mut self) -> Option<Config> {
+        self.reloaded_config.take()
+    }
+
+    /// ### ask_run
+    ///
+    /// Ask the user whether to run the command fixed by the layout autocorrection
+    fn ask_run(&mut self, command: String) {
+        console::print(format!("Run '{}'? (wrong keyboard layout) [y/N] ", command));
+        self.run_pending = Some(command);
+    }
+
+    /// ### recall
+    ///
+    /// Put the provided command in the input buffer, so that it can be edited and run again
+    fn recall(&mut self, command: String) {
+        self.clear_buffer();
+        self.input_buffer = command.chars().collect();
+        self.input_buffer_cursor = self.input_buffer.len();
+        console::print(command);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::config::Config;
+    use crate::shell::ShellState;
+    use crate::shell::history::ShellHistory;
+    use crate::config::{Config, HistoryFormat};
+    use crate::runtime::output::OutputBuffer;
+    use crate::runtime::{read_from_shell, shell_path};
     use crate::translator::ioprocessor::IOProcessor;
     use crate::translator::lang::Language;
     use crate::translator::new_translator;
+    use crate::utils::file;
 
+    use std::collections::HashMap;
     use std::thread::sleep;
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_runtimeprops_new() {
@@ -530,30 +1281,621 @@ mod tests {
         assert_eq!(shiop.rev_search, None);
         assert_eq!(shiop.rev_search_idx, 0);
         assert_eq!(shiop.history_index, 0);
+        assert!(!shiop.translit_bypass);
     }
 
     #[test]
-    fn test_runtimeprops_clear_buffer() {
+    fn test_runtimeprops_translit_bypass() {
         let mut shiop = new_shiop();
-        shiop.input_buffer = vec!['a', 'b', 'c'];
-        shiop.input_buffer_cursor = 3;
-        shiop.clear_buffer();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Input is transliterated
+        shiop.input_buffer = "ечо привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("echo privet"));
+        //CTRL T (suspend transliteration)
+        shiop.handle_input_event(InputEvent::Ctrl(20), &mut shell);
+        assert!(shiop.translit_bypass);
+        //Input is forwarded verbatim
+        shiop.input_buffer = "echo привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("echo привет"));
         assert_eq!(shiop.input_buffer.len(), 0);
-        assert_eq!(shiop.input_buffer_cursor, 0);
-        //History index
-        shiop.history_index = 128;
-        shiop.reset_history_index();
-        assert_eq!(shiop.history_index, 0);
+        //CTRL T (resume transliteration)
+        shiop.handle_input_event(InputEvent::Ctrl(20), &mut shell);
+        assert!(!shiop.translit_bypass);
+        //Toggle key is configurable
+        shiop.config.input_config.translit_toggle_key = Some(24);
+        shiop.handle_input_event(InputEvent::Ctrl(20), &mut shell);
+        assert!(!shiop.translit_bypass);
+        shiop.handle_input_event(InputEvent::Ctrl(24), &mut shell);
+        assert!(shiop.translit_bypass);
+        shiop.handle_input_event(InputEvent::Ctrl(24), &mut shell);
+        assert!(!shiop.translit_bypass);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
     }
 
     #[test]
-    fn test_runtimeprops_backspace() {
+    fn test_runtimeprops_empty_alias() {
         let mut shiop = new_shiop();
-        shiop.input_buffer = vec!['a', 'b', 'c'];
-        //If cursor is 0, cursor and input buffer won't change
-        shiop.backspace();
-        assert_eq!(shiop.input_buffer_cursor, 0);
-        assert_eq!(shiop.input_buffer.len(), 3);
+        shiop.config.alias.insert(String::from("ничего"), String::from(" "));
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        shiop.input_buffer = "ничего".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        //Nothing has been written to the shell; prompt has been printed again
+        assert_eq!(shell.history.len(), 0);
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        assert_eq!(shiop.input_buffer.len(), 0);
+        assert_eq!(shiop.input_buffer_cursor, 0);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_history_store_original() {
+        //Transliterated input is stored by default
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        shiop.input_buffer = "ечо привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("echo privet"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Original input
+        let mut shiop = new_shiop();
+        shiop.config.history_config.store_original = true;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        shiop.input_buffer = "ечо привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("ечо привет"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Replaying the event keeps the original input in history
+        let _ = shell.read();
+        shiop.process_input_interactive(&mut shell, String::from("!0\n"), String::from("!0"));
+        assert_eq!(shell.history.len(), 1);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("ечо привет"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_prompt_cache_clear_builtin() {
+        assert!(is_prompt_cache_clear_builtin("prompt cache clear"));
+        assert!(is_prompt_cache_clear_builtin("  prompt  cache clear \n"));
+        assert!(!is_prompt_cache_clear_builtin("prompt cache"));
+        assert!(!is_prompt_cache_clear_builtin("echo prompt cache clear"));
+    }
+
+    #[test]
+    fn test_runtimeprops_translate_last() {
+        assert!(is_translate_last_builtin("translate-last"));
+        assert!(is_translate_last_builtin("  translate-last \n"));
+        assert!(!is_translate_last_builtin("translate-last now"));
+        assert!(!is_translate_last_builtin("echo translate-last"));
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //No output yet
+        assert!(shiop.translate_last(&shell).is_none());
+        //Run command with translation disabled
+        shiop.input_buffer = "echo privet".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        let t_start: Instant = Instant::now();
+        while shell.get_state() != ShellState::Shell && t_start.elapsed() < Duration::from_secs(5) {
+            read_from_shell(&mut shell, &shiop.config, &shiop.processor, &mut output);
+            sleep(Duration::from_millis(50));
+        }
+        read_from_shell(&mut shell, &shiop.config, &shiop.processor, &mut output);
+        assert_eq!(shell.get_last_output(), "privet\n");
+        //Translate last output
+        assert_eq!(shiop.translate_last(&shell).unwrap(), String::from("привет\n"));
+        shiop.input_buffer = "translate-last".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("translate-last"));
+        //Output is still available, since the builtin doesn't run anything
+        assert_eq!(shell.get_last_output(), "privet\n");
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_translate_builtin() {
+        assert_eq!(parse_translate_builtin("translate on"), Some(true));
+        assert_eq!(parse_translate_builtin("  translate   off \n"), Some(false));
+        assert!(parse_translate_builtin("translate").is_none());
+        assert!(parse_translate_builtin("translate maybe").is_none());
+        assert!(parse_translate_builtin("translate-last").is_none());
+        assert!(parse_translate_builtin("echo translate on").is_none());
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = true;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Disable translation
+        shiop.input_buffer = "translate off".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(!shiop.config.output_config.translate_output);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("translate off"));
+        //The runtime takes the updated configuration
+        assert!(!shiop.take_reloaded_config().unwrap().output_config.translate_output);
+        assert!(shiop.take_reloaded_config().is_none());
+        //Enable translation again
+        shiop.input_buffer = "translate on".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shiop.config.output_config.translate_output);
+        assert!(shiop.take_reloaded_config().unwrap().output_config.translate_output);
+        //Builtin is not sent to the shell
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_clear_screen_seq() {
+        assert_eq!(clear_screen_seq(ClearMode::Erase, Some((80, 24))), String::from("\x1b[H\x1b[2J"));
+        assert_eq!(clear_screen_seq(ClearMode::Purge, Some((80, 24))), String::from("\x1b[H\x1b[2J\x1b[3J"));
+        assert_eq!(clear_screen_seq(ClearMode::Scroll, Some((80, 24))), String::from("\x1b[24S\x1b[H"));
+        //Unknown terminal size
+        assert_eq!(clear_screen_seq(ClearMode::Scroll, None), String::from("\x1b[H\x1b[2J"));
+    }
+
+    #[test]
+    fn test_runtimeprops_auto_cd() {
+        let processor: ShIop = new_shiop();
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmpdir.path().join("projects")).unwrap();
+        std::fs::create_dir(tmpdir.path().join("sh")).unwrap();
+        //Absolute and relative directories
+        let dir: String = String::from(tmpdir.path().to_str().unwrap());
+        assert_eq!(processor.auto_cd(format!("{}\n", dir).as_str(), Path::new("/")), Some(format!("cd {}\n", dir)));
+        assert_eq!(processor.auto_cd("projects\n", tmpdir.path()), Some(String::from("cd projects\n")));
+        //Commands are not shadowed
+        assert_eq!(processor.auto_cd("sh\n", tmpdir.path()), None);
+        assert_eq!(processor.auto_cd("ls\n", tmpdir.path()), None);
+        //More than one token
+        assert_eq!(processor.auto_cd("projects foo\n", tmpdir.path()), None);
+        //Not a directory
+        assert_eq!(processor.auto_cd("notadir\n", tmpdir.path()), None);
+    }
+
+    #[test]
+    fn test_runtimeprops_auto_cd_builtins() {
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        shiop.config.shell_config.auto_cd = true;
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmpdir.path().join("history")).unwrap();
+        std::fs::create_dir(tmpdir.path().join("projects")).unwrap();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        shiop.input_buffer = format!("cd {}", tmpdir.path().display()).chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        wait_for_shell(&mut shell, &shiop);
+        //Builtin is not shadowed by a directory with the same name
+        shiop.input_buffer = "history".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        shell.refresh_env();
+        assert_eq!(shell.get_wrkdir(), tmpdir.path());
+        //Directory is changed
+        shiop.input_buffer = "projects".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        wait_for_shell(&mut shell, &shiop);
+        shell.refresh_env();
+        assert_eq!(shell.get_wrkdir(), &tmpdir.path().join("projects"));
+        //Terminate shell
+        assert!(shell.stop().is_ok());
+    }
+
+    #[test]
+    fn test_runtimeprops_is_denied() {
+        let denylist: Vec<Regex> = Config::default().shell_config.restricted_denylist;
+        //Denied
+        assert!(is_denied("cd /tmp\n", &denylist));
+        assert!(is_denied("cd\n", &denylist));
+        assert!(is_denied("ls; cd ..\n", &denylist));
+        assert!(is_denied("export PATH=/tmp\n", &denylist));
+        assert!(is_denied("PATH=/tmp ls\n", &denylist));
+        assert!(is_denied("unset PATH\n", &denylist));
+        assert!(is_denied("/bin/ls\n", &denylist));
+        assert!(is_denied("ls && ./script.sh\n", &denylist));
+        assert!(is_denied("exec bash\n", &denylist));
+        //Quoted, escaped or prefixed commands
+        assert!(is_denied("\\cd /tmp\n", &denylist));
+        assert!(is_denied("'cd' /tmp\n", &denylist));
+        assert!(is_denied("\"c\"d /tmp\n", &denylist));
+        assert!(is_denied("command cd /tmp\n", &denylist));
+        assert!(is_denied("ls; builtin cd /tmp\n", &denylist));
+        //Allowed
+        assert!(!is_denied("ls -l /tmp\n", &denylist));
+        assert!(!is_denied("echo cd\n", &denylist));
+        assert!(!is_denied("cdrecord --help\n", &denylist));
+        assert!(!is_denied("echo $PATH\n", &denylist));
+        assert!(!is_denied("executor\n", &denylist));
+        assert!(!is_denied("command -v ls\n", &denylist));
+        //Empty denylist
+        assert!(!is_denied("cd /tmp\n", &[]));
+    }
+
+    #[test]
+    fn test_runtimeprops_restricted() {
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        shiop.config.shell_config.restricted = true;
+        shiop.config.shell_config.restricted_denylist = vec![Regex::new("^\\s*head(\\s|$)").unwrap()];
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Denied command is not sent to the shell
+        shiop.input_buffer = "head -n 1".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Config edit is denied too
+        shiop.input_buffer = "config edit".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shiop.reload_pending.is_none());
+        //Allowed command passes through
+        shiop.input_buffer = "cat".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_parse_exit_builtin() {
+        assert_eq!(parse_exit_builtin("exit"), Some(None));
+        assert_eq!(parse_exit_builtin("  exit  "), Some(None));
+        assert_eq!(parse_exit_builtin("exit 7"), Some(Some(7)));
+        assert_eq!(parse_exit_builtin("exit 0"), Some(Some(0)));
+        assert_eq!(parse_exit_builtin("exit 256"), Some(Some(0)));
+        assert_eq!(parse_exit_builtin("выход"), Some(None));
+        assert_eq!(parse_exit_builtin("выход 3"), Some(Some(3)));
+        //Not the exit builtin
+        assert_eq!(parse_exit_builtin("exit foo"), None);
+        assert_eq!(parse_exit_builtin("exit 1 2"), None);
+        assert_eq!(parse_exit_builtin("exitcode"), None);
+        assert_eq!(parse_exit_builtin("echo exit"), None);
+    }
+
+    #[test]
+    fn test_runtimeprops_exit_builtin() {
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shiop.exit_code(), None);
+        //Cyrillic exit with code
+        shiop.input_buffer = "выход 7".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shiop.exit_code(), Some(7));
+        assert_eq!(shell.history.at(0).unwrap(), String::from("выход 7"));
+        assert_eq!(shiop.input_buffer.len(), 0);
+        //Shell terminates with the same code
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shell.get_state(), ShellState::Terminated);
+        assert_eq!(shell.stop().unwrap(), 7);
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_exit_builtin_last_status() {
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Run a failing command
+        shiop.input_buffer = "false".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        let t_start: Instant = Instant::now();
+        while shell.get_state() != ShellState::Shell && t_start.elapsed() < Duration::from_secs(5) {
+            read_from_shell(&mut shell, &shiop.config, &shiop.processor, &mut output);
+            sleep(Duration::from_millis(50));
+        }
+        shell.refresh_env();
+        assert_eq!(shell.get_exit_status(), 1);
+        //Bare exit uses the status of the last command
+        shiop.input_buffer = "exit".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shiop.exit_code(), Some(1));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shell.get_state(), ShellState::Terminated);
+        assert_eq!(shell.stop().unwrap(), 1);
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_layout_autocorrect_run() {
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Input is kept only if layout autocorrection is enabled
+        shiop.input_buffer = "ды".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shell.take_last_input().is_none());
+        wait_for_shell(&mut shell, &shiop);
+        shiop.config.shell_config.layout_autocorrect = LayoutAutocorrect::Run;
+        shiop.input_buffer = "ды".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.take_last_input(), Some(String::from("ды")));
+        wait_for_shell(&mut shell, &shiop);
+        assert!(shell.take_command_started());
+        //Decline the fixed command
+        shiop.ask_run(String::from("ls"));
+        assert_eq!(shiop.run_pending, Some(String::from("ls")));
+        shiop.handle_input_event(InputEvent::Key(String::from("n")), &mut shell);
+        assert!(shiop.run_pending.is_none());
+        assert!(!shell.take_command_started());
+        //Confirm the fixed command
+        shiop.ask_run(String::from("ls"));
+        shiop.handle_input_event(InputEvent::Key(String::from("y")), &mut shell);
+        assert!(shiop.run_pending.is_none());
+        assert!(shell.take_command_started());
+        assert_eq!(shell.history.at(0).unwrap(), String::from("ls"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_parse_heredoc_start() {
+        assert_eq!(parse_heredoc_start("cat <<EOF\n"), Some((String::from("EOF"), false)));
+        assert_eq!(parse_heredoc_start("cat << END > /tmp/out\n"), Some((String::from("END"), false)));
+        assert_eq!(parse_heredoc_start("cat <<-EOF\n"), Some((String::from("EOF"), true)));
+        assert_eq!(parse_heredoc_start("cat <<'EOF'\n"), Some((String::from("EOF"), false)));
+        assert_eq!(parse_heredoc_start("cat <<\"ЕОФ\"\n"), Some((String::from("ЕОФ"), false)));
+        assert_eq!(parse_heredoc_start("cat <<\\EOF\n"), Some((String::from("EOF"), false)));
+        //Not heredocs
+        assert!(parse_heredoc_start("cat <<<word\n").is_none());
+        assert!(parse_heredoc_start("echo \"a <<b\"\n").is_none());
+        assert!(parse_heredoc_start("echo 'a <<b'\n").is_none());
+        assert!(parse_heredoc_start("ls -l\n").is_none());
+    }
+
+    #[test]
+    fn test_runtimeprops_heredoc() {
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Start heredoc
+        for line in ["кат <<ЕОФ", "привет", "", "history", "ЕОФ"].iter() {
+            assert!(!shell.take_command_started());
+            shiop.input_buffer = line.chars().collect();
+            shiop.input_buffer_cursor = shiop.input_buffer.len();
+            shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        }
+        //Block has been sent once the delimiter was entered
+        assert!(shiop.heredoc.is_none());
+        assert!(shell.take_command_started());
+        assert_eq!(shell.history.len(), 1);
+        assert_eq!(shell.history.at(0).unwrap(), String::from("cat <<EOF"));
+        wait_for_shell(&mut shell, &shiop);
+        assert_eq!(shell.get_last_output(), "privet\n\nhistory\n");
+        //Body is kept as typed if it mustn't be translated; CTRL+C aborts the heredoc
+        shiop.config.input_config.translate_heredoc = false;
+        shiop.input_buffer = "cat <<EOF".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shiop.heredoc.as_ref().unwrap().block, String::from("cat <<EOF\n"));
+        shiop.input_buffer = "привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shiop.heredoc.as_ref().unwrap().block, String::from("cat <<EOF\nпривет\n"));
+        shiop.handle_input_event(InputEvent::Ctrl(3), &mut shell);
+        assert!(shiop.heredoc.is_none());
+        assert!(!shell.take_command_started());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_history_autosave() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let history_file: PathBuf = tmpdir.path().join("pyc_history");
+        let mut shiop = new_shiop();
+        shiop.history_file = Some(history_file.clone());
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Autosave is disabled
+        shiop.input_buffer = "pwd".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(!history_file.exists());
+        wait_for_shell(&mut shell, &shiop);
+        //Command is in the history file right after it's been entered
+        shiop.config.history_config.autosave = true;
+        shiop.input_buffer = "ечо привет".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(file::read_lines(history_file.as_path()).unwrap(), vec![String::from("echo privet")]);
+        wait_for_shell(&mut shell, &shiop);
+        shiop.input_buffer = "translate-last".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(
+            file::read_lines(history_file.as_path()).unwrap(),
+            vec![String::from("echo privet"), String::from("translate-last")]
+        );
+        //Duplicates collapsed in history are not appended
+        wait_for_shell(&mut shell, &shiop);
+        std::fs::remove_file(history_file.as_path()).unwrap();
+        shell.history.set_ignore_dups(true);
+        for _ in 0..2 {
+            shiop.input_buffer = "pwd".chars().collect();
+            shiop.input_buffer_cursor = shiop.input_buffer.len();
+            shiop.handle_input_event(InputEvent::Enter, &mut shell);
+            wait_for_shell(&mut shell, &shiop);
+        }
+        assert_eq!(file::read_lines(history_file.as_path()).unwrap(), vec![String::from("pwd")]);
+        //JSON history file
+        std::fs::remove_file(history_file.as_path()).unwrap();
+        shiop.config.history_config.format = HistoryFormat::Json;
+        shiop.input_buffer = "ls".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        wait_for_shell(&mut shell, &shiop);
+        let mut history: ShellHistory = ShellHistory::new();
+        history.load(file::read_lines(history_file.as_path()).unwrap());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.at(0).unwrap(), String::from("ls"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_recall_on_failure() {
+        let mut shiop = new_shiop();
+        shiop.config.input_config.recall_on_failure = true;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Run a failing command
+        shiop.input_buffer = "false".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shiop.input_buffer.is_empty());
+        wait_for_shell(&mut shell, &shiop);
+        shell.refresh_env();
+        assert_ne!(shell.get_exit_status(), 0);
+        //The failed command is put back in the input buffer
+        let last_input: String = shell.take_last_input().unwrap();
+        assert_eq!(last_input, String::from("false"));
+        shiop.recall(last_input);
+        assert_eq!(shiop.input_buffer, "false".chars().collect::<Vec<char>>());
+        assert_eq!(shiop.input_buffer_cursor, 5);
+        //The recalled command can be run again
+        assert!(shell.take_command_started());
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shell.take_command_started());
+        assert!(shiop.input_buffer.is_empty());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_filter_control_chars() {
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Zero width space is dropped
+        shiop.handle_input_event(InputEvent::Key(String::from("l")), &mut shell);
+        shiop.handle_input_event(InputEvent::Key(String::from("\u{200B}")), &mut shell);
+        shiop.handle_input_event(InputEvent::Key(String::from("s")), &mut shell);
+        assert_eq!(shiop.input_buffer, vec!['l', 's']);
+        assert_eq!(shiop.input_buffer_cursor, 2);
+        //RTL override is dropped
+        shiop.handle_input_event(InputEvent::Key(String::from("\u{202E}a")), &mut shell);
+        assert_eq!(shiop.input_buffer, vec!['l', 's', 'a']);
+        assert_eq!(shiop.input_buffer_cursor, 3);
+        //Filter disabled
+        shiop.config.input_config.filter_control_chars = false;
+        shiop.handle_input_event(InputEvent::Key(String::from("\u{200B}")), &mut shell);
+        assert_eq!(shiop.input_buffer, vec!['l', 's', 'a', '\u{200B}']);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_blank_input() {
+        let mut shiop = new_shiop();
+        shiop.config.input_config.filter_control_chars = false;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Input made of invisible characters only
+        shiop.handle_input_event(InputEvent::Key(String::from("\u{200B}\u{FEFF}")), &mut shell);
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        //Nothing has been written to the shell
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        assert_eq!(shell.history.len(), 0);
+        assert_eq!(shiop.input_buffer.len(), 0);
+        assert_eq!(shiop.input_buffer_cursor, 0);
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_clear_buffer() {
+        let mut shiop = new_shiop();
+        shiop.input_buffer = vec!['a', 'b', 'c'];
+        shiop.input_buffer_cursor = 3;
+        shiop.clear_buffer();
+        assert_eq!(shiop.input_buffer.len(), 0);
+        assert_eq!(shiop.input_buffer_cursor, 0);
+        //History index
+        shiop.history_index = 128;
+        shiop.reset_history_index();
+        assert_eq!(shiop.history_index, 0);
+    }
+
+    #[test]
+    fn test_runtimeprops_backspace() {
+        let mut shiop = new_shiop();
+        shiop.input_buffer = vec!['a', 'b', 'c'];
+        //If cursor is 0, cursor and input buffer won't change
+        shiop.backspace();
+        assert_eq!(shiop.input_buffer_cursor, 0);
+        assert_eq!(shiop.input_buffer.len(), 3);
         shiop.input_buffer_cursor = 3;
         //Backspace from end of buffer
         shiop.backspace();
@@ -593,11 +1935,49 @@ mod tests {
         assert_eq!(shiop.input_buffer_cursor, 1);
     }
 
+    #[test]
+    fn test_runtimeprops_word_operations() {
+        let mut shiop = new_shiop();
+        shiop.config.input_config.word_chars = Some(String::from("-_"));
+        shiop.input_buffer = "ls /usr/local/bin".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        //Move by words
+        shiop.move_word_left();
+        assert_eq!(shiop.input_buffer_cursor, 14);
+        shiop.move_word_left();
+        assert_eq!(shiop.input_buffer_cursor, 8);
+        shiop.move_word_right();
+        assert_eq!(shiop.input_buffer_cursor, 13);
+        shiop.move_word_right();
+        assert_eq!(shiop.input_buffer_cursor, 17);
+        //Delete word
+        shiop.delete_word();
+        assert_eq!(buffer::chars_to_string(&shiop.input_buffer), String::from("ls /usr/local/"));
+        assert_eq!(shiop.input_buffer_cursor, 14);
+        shiop.delete_word();
+        assert_eq!(buffer::chars_to_string(&shiop.input_buffer), String::from("ls /usr/"));
+        //Option assignment
+        shiop.input_buffer = "--opt=value".chars().collect();
+        shiop.input_buffer_cursor = 0;
+        shiop.move_word_right();
+        assert_eq!(shiop.input_buffer_cursor, 5);
+        shiop.move_word_right();
+        assert_eq!(shiop.input_buffer_cursor, 11);
+        shiop.move_word_left();
+        assert_eq!(shiop.input_buffer_cursor, 6);
+        //Default: whitespaces only
+        shiop.config.input_config.word_chars = None;
+        shiop.input_buffer = "ls /usr/local/bin".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.delete_word();
+        assert_eq!(buffer::chars_to_string(&shiop.input_buffer), String::from("ls "));
+    }
+
     #[test]
     fn test_runtimeprops_handle_input_event() {
         let mut shiop = new_shiop();
         let mut shell: Shell =
-            Shell::start(String::from("sh"), Vec::new(), &shiop.config.prompt_config).unwrap();
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
                                            //Prepare history
         shell.history.push(String::from("pwd"));
@@ -791,7 +2171,7 @@ mod tests {
     fn test_runtimeprops_reverse_search() {
         let mut shiop = new_shiop();
         let mut shell: Shell =
-            Shell::start(String::from("sh"), Vec::new(), &shiop.config.prompt_config).unwrap();
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
                                            //Prepare history
         shell.history.push(String::from("pwd"));
@@ -818,10 +2198,242 @@ mod tests {
         assert_eq!(shiop.search_reverse(&mut shell), None); // No panic?
     }
 
+    #[test]
+    fn test_runtimeprops_forward_search() {
+        let mut shiop = new_shiop();
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Prepare history
+        shell.history.push(String::from("ls -l"));
+        shell.history.push(String::from("pwd"));
+        shell.history.push(String::from("ls"));
+        shell.history.push(String::from("lsd"));
+        // Nothing is newer than the start of the search
+        shiop.input_buffer = vec!['l', 's'];
+        shiop.input_buffer_cursor = 2;
+        shiop.handle_input_event(InputEvent::Ctrl(19), &mut shell);
+        assert_eq!(shiop.rev_search, Some(String::from("ls")));
+        assert_eq!(shiop.rev_search_idx, 0);
+        assert_eq!(shiop.input_buffer, vec!['l', 's']);
+        // Overshoot with reverse search
+        shiop.handle_input_event(InputEvent::Ctrl(18), &mut shell);
+        shiop.handle_input_event(InputEvent::Ctrl(18), &mut shell);
+        shiop.handle_input_event(InputEvent::Ctrl(18), &mut shell);
+        assert_eq!(buffer::chars_to_string(&shiop.input_buffer), String::from("ls -l"));
+        assert_eq!(shiop.rev_search_idx, 4);
+        // Go back toward the most recent matches
+        shiop.handle_input_event(InputEvent::Ctrl(19), &mut shell);
+        assert_eq!(buffer::chars_to_string(&shiop.input_buffer), String::from("ls"));
+        assert_eq!(shiop.rev_search_idx, 2);
+        assert_eq!(shiop.search_forward(&shell), Some(String::from("lsd")));
+        assert_eq!(shiop.search_forward(&shell), None);
+        assert_eq!(shiop.rev_search_idx, 1);
+        // Reverse search goes on from the current match
+        assert_eq!(shiop.search_reverse(&shell), Some(String::from("ls")));
+        //CTRL G ( exit search )
+        shiop.handle_input_event(InputEvent::Ctrl(7), &mut shell);
+        assert_eq!(shiop.input_buffer.len(), 0);
+        assert_eq!(shiop.rev_search, None);
+        assert_eq!(shiop.rev_search_idx, 0);
+    }
+
+    #[test]
+    fn test_runtimeprops_rev_search_prompt() {
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        //Default
+        assert_eq!(
+            shiop.rev_search_prompt("ifc"),
+            String::from("(reverse-i-search)`\x1b[1mifc\x1b[0m':  ")
+        );
+        //Configured label and glyph are used instead of the default
+        shiop.config.prompt_config.rev_search_label = String::from("search");
+        shiop.config.prompt_config.rev_search_glyph = Some(String::from(">"));
+        assert_eq!(
+            shiop.rev_search_prompt("ifc"),
+            String::from("> search`\x1b[1mifc\x1b[0m':  ")
+        );
+        //Colored label
+        shiop.config.prompt_config.rev_search_color = Some(ansi_term::Colour::Cyan);
+        assert_eq!(
+            shiop.rev_search_prompt("ifc"),
+            String::from("\x1b[36m> search\x1b[0m`\x1b[1mifc\x1b[0m':  ")
+        );
+        //Label is translated with output
+        shiop.config.output_config.translate_output = true;
+        shiop.config.prompt_config.rev_search_glyph = None;
+        shiop.config.prompt_config.rev_search_color = None;
+        assert_eq!(
+            shiop.rev_search_prompt("ls"),
+            String::from("сеарч`\x1b[1mls\x1b[0m':  ")
+        );
+    }
+
+    #[test]
+    fn test_runtimeprops_config_edit_builtin() {
+        assert!(is_config_edit_builtin("config edit"));
+        assert!(is_config_edit_builtin("  config   edit "));
+        assert!(!is_config_edit_builtin("config"));
+        assert!(!is_config_edit_builtin("config edit now"));
+        assert!(!is_config_edit_builtin("vim config"));
+    }
+
+    #[test]
+    fn test_runtimeprops_lev_builtin() {
+        assert!(is_lev_builtin("lev"));
+        assert!(is_lev_builtin("  lev file.txt"));
+        assert!(!is_lev_builtin("levenshtein a b"));
+        assert!(!is_lev_builtin("echo lev"));
+    }
+
+    #[test]
+    fn test_runtimeprops_lev_argv() {
+        assert_eq!(lev_argv("lev", Path::new("/home/user")), vec![String::from("lev")]);
+        assert_eq!(
+            lev_argv("lev  notes.txt", Path::new("/home/user")),
+            vec![String::from("lev"), String::from("/home/user/notes.txt")]
+        );
+        assert_eq!(
+            lev_argv("lev /tmp/notes.txt", Path::new("/home/user")),
+            vec![String::from("lev"), String::from("/tmp/notes.txt")]
+        );
+        //Quoted and escaped paths are a single argument
+        assert_eq!(
+            lev_argv("lev \"my notes.txt\"", Path::new("/home/user")),
+            vec![String::from("lev"), String::from("/home/user/my notes.txt")]
+        );
+        assert_eq!(
+            lev_argv("lev my\\ notes.txt", Path::new("/home/user")),
+            vec![String::from("lev"), String::from("/home/user/my notes.txt")]
+        );
+        assert_eq!(
+            lev_argv("lev '~/notes.txt'", Path::new("/home/user")),
+            vec![String::from("lev"), String::from("/home/user/~/notes.txt")]
+        );
+    }
+
+    #[test]
+    fn test_runtimeprops_lev() {
+        let mut shiop = new_shiop();
+        shiop.config.output_config.translate_output = false;
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //The editor receives the file resolved from the shell working directory; directories can't be edited
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        assert_eq!(shiop.start_lev(&shell, format!("lev {}", tmpdir.path().display()).as_str()), 1);
+        //Missing file
+        assert_eq!(shiop.start_lev(&shell, "lev"), 1);
+        //Builtin is not sent to the shell, and the prompt is printed again
+        shiop.input_buffer = "lev".chars().collect();
+        shiop.input_buffer_cursor = shiop.input_buffer.len();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        assert!(shiop.input_buffer.is_empty());
+        //Terminate shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    #[test]
+    fn test_runtimeprops_resolve_editor() {
+        assert_eq!(resolve_editor(Some(String::from("nano"))), String::from("nano"));
+        assert_eq!(resolve_editor(Some(String::from("code --wait"))), String::from("code --wait"));
+        //Fallback
+        assert!(FALLBACK_EDITORS.contains(&resolve_editor(None).as_str()));
+        assert!(FALLBACK_EDITORS.contains(&resolve_editor(Some(String::from(" "))).as_str()));
+    }
+
+    #[test]
+    fn test_runtimeprops_run_editor() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let args_file: PathBuf = tmpdir.path().join("args");
+        let editor: PathBuf = write_fake_editor(tmpdir.path(), &format!("echo \"$@\" > {}", args_file.display()));
+        let config_file: PathBuf = tmpdir.path().join("pyc.yml");
+        let status = run_editor(format!("{} --wait", editor.display()).as_str(), config_file.as_path());
+        assert!(status.unwrap().success());
+        assert_eq!(
+            std::fs::read_to_string(args_file).unwrap(),
+            format!("--wait {}\n", config_file.display())
+        );
+        //Bad editor
+        assert!(run_editor("/tmp/pyc-this-editor-does-not-exist", config_file.as_path()).is_err());
+        assert!(run_editor("", config_file.as_path()).is_err());
+    }
+
+    #[test]
+    fn test_runtimeprops_config_edit() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let config_file: PathBuf = tmpdir.path().join("pyc.yml");
+        std::fs::write(config_file.as_path(), "language: ru\n").unwrap();
+        let edited_file: PathBuf = tmpdir.path().join("edited.yml");
+        let edited: &str = "language: by\nprompt:\n  prompt_line: \"by$\"\n  history_size: 1024\n  translate: false\n  break:\n    enabled: false\n    with: \">\"\n  duration:\n    min_elapsed_time: 5000\n  rc:\n    ok: \"^_^\"\n    error: \"x_x\"\n  git:\n    branch: \"on \"\n    commit_ref_len: 4\n";
+        std::fs::write(edited_file.as_path(), edited).unwrap();
+        let editor: PathBuf = write_fake_editor(tmpdir.path(), &format!("cp {} \"$1\"", edited_file.display()));
+        let editor: String = editor.display().to_string();
+        let mut shiop = new_shiop();
+        shiop.config.config_file = Some(config_file.clone());
+        let mut shell: Shell =
+            Shell::start(String::from("sh"), Vec::new(), &[], &shiop.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Edit and decline reload
+        shiop.edit_config(&mut shell, editor.as_str());
+        assert!(shiop.reload_pending.is_some());
+        assert_eq!(std::fs::read_to_string(config_file.as_path()).unwrap(), edited);
+        shiop.handle_input_event(InputEvent::Key(String::from("n")), &mut shell);
+        assert!(shiop.reload_pending.is_none());
+        assert_eq!(shiop.config.language, String::from("ru"));
+        assert!(shiop.take_reloaded_config().is_none());
+        //Edit and reload
+        shiop.edit_config(&mut shell, editor.as_str());
+        shiop.handle_input_event(InputEvent::Key(String::from("y")), &mut shell);
+        assert_eq!(shiop.config.language, String::from("by"));
+        assert_eq!(shiop.config.config_file, Some(config_file));
+        assert_eq!(shiop.take_reloaded_config().unwrap().language, String::from("by"));
+        assert_eq!(shell.get_promptline(&shiop.processor), String::from("by$"));
+        assert!(shiop.take_reloaded_config().is_none());
+        //No configuration file
+        shiop.config.config_file = None;
+        shiop.input_buffer = "config edit".chars().collect();
+        shiop.handle_input_event(InputEvent::Enter, &mut shell);
+        assert!(shiop.reload_pending.is_none());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+    }
+
+    /// ### wait_for_shell
+    ///
+    /// Read from shell until the running command has terminated
+    fn wait_for_shell(shell: &mut Shell, shiop: &ShIop) {
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        let t_start: Instant = Instant::now();
+        sleep(Duration::from_millis(100));
+        while shell.get_state() != ShellState::Shell && t_start.elapsed() < Duration::from_secs(5) {
+            read_from_shell(shell, &shiop.config, &shiop.processor, &mut output);
+            sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// ### write_fake_editor
+    ///
+    /// Write an executable script in dir which runs the provided shell command
+    fn write_fake_editor(dir: &Path, command: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let editor: PathBuf = dir.join("editor.sh");
+        std::fs::write(editor.as_path(), format!("#!/bin/sh\n{}\n", command)).unwrap();
+        std::fs::set_permissions(editor.as_path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+        editor
+    }
+
     fn new_shiop() -> ShIop {
         ShIop::new(
             Config::default(),
-            IOProcessor::new(Language::Russian, new_translator(Language::Russian)),
+            IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap(),
+            Rc::new(RefCell::new(Suggester::new(shell_path(&[])))),
+            None,
         )
     }
 }
@@ -26,15 +26,13 @@
 
 use super::Imiop;
 use crate::config::Config;
-use crate::runtime::print_err;
-use crate::shell::Shell;
+use crate::runtime::{print_err, shellsignal_to_signal};
+use crate::shell::{Shell, ShellState};
+use crate::shell::unixsignal::UnixSignal;
 use crate::translator::ioprocessor::IOProcessor;
-use crate::utils::buffer;
 use crate::utils::console::{self, InputEvent};
 
 pub(crate) struct SubProcIop {
-    input_buffer: Vec<char>,
-    input_buffer_cursor: usize,
     config: Config,
     processor: IOProcessor,
 }
@@ -45,116 +43,104 @@ impl SubProcIop {
     /// Instantiate a new `SubProcIop`
     pub fn new(config: Config, processor: IOProcessor) -> SubProcIop {
         SubProcIop {
-            input_buffer: Vec::with_capacity(2048),
-            input_buffer_cursor: 0,
             config: config,
             processor: processor,
         }
     }
 
-    /// ### clear_buffer
+    /// ### echo
     ///
-    /// Clear buffer and reset cursor to 0
-    fn clear_buffer(&mut self) {
-        self.input_buffer.clear();
-        self.input_buffer_cursor = 0;
+    /// Returns the text to echo for the typed key
+    fn echo(&self, key: &String) -> String {
+        match self.config.output_config.echo_typed {
+            true => key.clone(),
+            false => self.processor.text_to_latin(key),
+        }
     }
 
-    /// ### backspace
+    /// ### stop
     ///
-    /// Perform backspace on current console and buffers
-    fn backspace(&mut self) {
-        //Remove from buffer and backspace (if possible)
-        if self.input_buffer_cursor > 0 {
-            self.input_buffer_cursor -= 1;
-            if self.input_buffer.len() > self.input_buffer_cursor {
-                self.input_buffer.remove(self.input_buffer_cursor);
-            }
-            console::backspace();
+    /// Stop the running subprocess; the shell reports it and gives the prompt back
+    fn stop(&mut self, shell: &mut Shell) {
+        if let Err(err) = shell.stop_subprocess() {
+            self.print_err(format!("Could not stop process: {}", err));
         }
     }
 
-    /// ### perform_enter
+    /// ### print_err
     ///
-    /// Perform enter in non interactive shell
-    fn perform_enter(&mut self, shell: &mut Shell) {
-        //@! Handle enter...
-        let stdin_input: String = buffer::chars_to_string(&self.input_buffer);
-        //If input is empty, ignore it
-        if stdin_input.trim().len() > 0 {
-            //Treat input
-            //Convert text
-            let input: String = self.processor.text_to_latin(&stdin_input);
-            if let Err(err) = shell.write(input) {
-                print_err(
-                    String::from(err.to_string()),
-                    self.config.output_config.translate_output,
-                    &self.processor,
-                );
-            }
-        }
-        self.clear_buffer();
+    /// Print error using the output configuration
+    fn print_err(&self, err: String) {
+        print_err(
+            err,
+            self.config.output_config.translate_output,
+            self.config.output_config.error_color,
+            &self.processor,
+        );
     }
 }
 
 impl Imiop for SubProcIop {
     /// ### handle_input_event
     ///
-    /// Handle input event received from stdin
+    /// Handle input event received from stdin.
+    /// Input is passed to the subprocess as it is typed; text is converted to latin.
+    /// Lines are edited by the terminal, so input is echoed only while the subprocess reads it one line at a time
     fn handle_input_event(&mut self, ev: InputEvent, shell: &mut Shell) {
+        //Subprocess has terminated meanwhile; input mustn't be written as a new command
+        if shell.get_state() != ShellState::SubprocessRunning {
+            return;
+        }
+        let echo: bool = shell.is_line_mode();
         match ev {
-            InputEvent::ArrowDown => {
-                //Pass key
-                let _ = shell.write(console::input_event_to_string(ev));
-            }
-            InputEvent::ArrowUp => {
-                //Pass key
-                let _ = shell.write(console::input_event_to_string(ev));
-            }
-            InputEvent::ArrowLeft => {
-                //Pass key
-                let _ = shell.write(console::input_event_to_string(ev));
-            }
-            InputEvent::ArrowRight => {
-                //Pass key
-                let _ = shell.write(console::input_event_to_string(ev));
+            InputEvent::Key(k) => {
+                //Pass key converted to latin
+                let _ = shell.write(self.processor.text_to_latin(&k));
+                //Print key
+                if echo {
+                    console::print(self.echo(&k));
+                }
             }
             InputEvent::Backspace => {
-                self.backspace();
-            }
-            InputEvent::CarriageReturn => {
-                let _ = shell.write(console::input_event_to_string(ev));
-            }
-            InputEvent::Ctrl(_) => {
-                //Pass to child
-                //FIXME: doesn't work
                 let _ = shell.write(console::input_event_to_string(ev));
-                //let mut output = String::with_capacity(1);
-                //output.push(sig as char);
-                //let _ = shell.write(output);
-                /*
-                if let Some(sig) = super::shellsignal_to_signal(sig) {
-                    if let Err(_) = shell.raise(sig) {
-                        print_err(String::from("Could not send signal to shell"), self.config.output_config.translate_output, &self.processor);
-                    }
-                }*/
-            }
-            InputEvent::Key(k) => {
-                //Push key
-                //Push k to input buffer
-                for ch in k.chars() {
-                    self.input_buffer.insert(self.input_buffer_cursor, ch);
-                    self.input_buffer_cursor += 1;
+                if echo {
+                    console::backspace();
                 }
-                //Print key
-                console::print(k);
             }
             InputEvent::Enter => {
-                //@! Send input
-                self.perform_enter(shell);
+                if let Err(err) = shell.write(console::input_event_to_string(ev)) {
+                    self.print_err(err.to_string());
+                }
+                if echo {
+                    console::println(String::new());
+                }
+            }
+            InputEvent::Ctrl(sig) => match shellsignal_to_signal(sig) {
+                Some(UnixSignal::Sigstop) => self.stop(shell),
+                //Terminal doesn't generate signals, so they're sent to the subprocess
+                Some(sig) => {
+                    if let Err(err) = shell.raise_subprocess(sig) {
+                        self.print_err(err.to_string());
+                    }
+                }
+                //Pass to child
+                None => {
+                    let _ = shell.write(console::input_event_to_string(ev));
+                }
+            },
+            _ => {
+                //Pass key
+                let _ = shell.write(console::input_event_to_string(ev));
             }
         }
     }
+
+    /// ### state
+    ///
+    /// Returns the shell state the IMIOP handles the input of
+    fn state(&self) -> ShellState {
+        ShellState::SubprocessRunning
+    }
 }
 
 #[cfg(test)]
@@ -166,90 +152,113 @@ mod tests {
     use crate::translator::lang::Language;
     use crate::translator::new_translator;
 
+    use std::collections::HashMap;
     use std::thread::sleep;
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_runtimeprops_new() {
         let processor = new_subprociop();
         assert!(processor.config.get_alias(&String::from("ll")).is_none());
         assert_eq!(processor.processor.language, Language::Russian);
-        assert_eq!(processor.input_buffer.capacity(), 2048);
-        assert_eq!(processor.input_buffer_cursor, 0);
     }
 
     #[test]
-    fn test_runtimeprops_backspace() {
+    fn test_runtimeprops_echo() {
         let mut processor = new_subprociop();
-        processor.input_buffer = vec!['a', 'b', 'c'];
-        //If cursor is 0, cursor and input buffer won't change
-        processor.backspace();
-        assert_eq!(processor.input_buffer_cursor, 0);
-        assert_eq!(processor.input_buffer.len(), 3);
-        processor.input_buffer_cursor = 3;
-        //Backspace from end of buffer
-        processor.backspace();
-        assert_eq!(processor.input_buffer_cursor, 2);
-        assert_eq!(processor.input_buffer, vec!['a', 'b']);
-        //Set cursor to 1 and backspace from the middle
-        processor.input_buffer_cursor = 1;
-        processor.backspace();
-        assert_eq!(processor.input_buffer_cursor, 0);
-        assert_eq!(processor.input_buffer, vec!['b']);
-        //Try to delete with cursor out of range
-        processor.input_buffer = vec!['a', 'b', 'c'];
-        processor.input_buffer_cursor = 4;
-        processor.backspace();
-        assert_eq!(processor.input_buffer_cursor, 3);
-        assert_eq!(processor.input_buffer.len(), 3);
+        //Echo is cyrillic, while latin is sent
+        assert_eq!(processor.echo(&String::from("п")), String::from("п"));
+        //Echo latin
+        processor.config.output_config.echo_typed = false;
+        assert_eq!(processor.echo(&String::from("п")), String::from("p"));
     }
 
     #[test]
-    fn test_runtimeprops_handle_input_event_not_interactive() {
-        //Non interactive shell enter
+    fn test_runtimeprops_handle_input_event() {
         let mut processor = new_subprociop();
         let mut shell: Shell = Shell::start(
             String::from("sh"),
             Vec::new(),
+            &[],
             &processor.config.prompt_config,
         )
         .unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
-        processor.input_buffer = vec!['l', 's'];
-        processor.input_buffer_cursor = 2;
+        //Input is ignored if no subprocess is running
+        processor.handle_input_event(InputEvent::Key(String::from("лс")), &mut shell);
+        processor.handle_input_event(InputEvent::Enter, &mut shell);
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Keys reach the subprocess as they are typed, converted to latin
+        assert!(shell.write(String::from("head -n 1\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
+        processor.handle_input_event(InputEvent::Key(String::from("п")), &mut shell);
+        processor.handle_input_event(InputEvent::Key(String::from("ока")), &mut shell);
         processor.handle_input_event(InputEvent::Enter, &mut shell);
-        assert_eq!(processor.input_buffer.len(), 0);
-        assert_eq!(processor.input_buffer_cursor, 0);
-        //Enter with empty buffer
+        assert_eq!(read_output(&mut shell), String::from("poca\n"));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //The line is edited by the terminal
+        assert!(shell.write(String::from("read x; echo $x\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
+        processor.handle_input_event(InputEvent::Key(String::from("a")), &mut shell);
+        processor.handle_input_event(InputEvent::Key(String::from("b")), &mut shell);
+        processor.handle_input_event(InputEvent::Backspace, &mut shell);
+        processor.handle_input_event(InputEvent::Key(String::from("c")), &mut shell);
         processor.handle_input_event(InputEvent::Enter, &mut shell);
-        assert_eq!(processor.input_buffer.len(), 0);
-        assert_eq!(processor.input_buffer_cursor, 0);
-        //Arrows
-        processor.handle_input_event(InputEvent::ArrowDown, &mut shell);
-        processor.handle_input_event(InputEvent::ArrowLeft, &mut shell);
-        processor.handle_input_event(InputEvent::ArrowRight, &mut shell);
-        processor.handle_input_event(InputEvent::ArrowUp, &mut shell);
-        //Signal
+        assert_eq!(read_output(&mut shell), String::from("ac\n"));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Programs reading each key get it as soon as it is typed
+        assert!(shell.write(String::from("stty -icanon; head -c 2; echo; stty icanon\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
+        assert!(!shell.is_line_mode());
+        processor.handle_input_event(InputEvent::Key(String::from("п")), &mut shell);
+        processor.handle_input_event(InputEvent::Key(String::from("о")), &mut shell);
+        assert_eq!(read_output(&mut shell), String::from("po\n"));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        //Ctrl+Z stops the subprocess and gives the prompt back
+        assert!(shell.write(String::from("sleep 1; echo done\n")).is_ok());
+        sleep(Duration::from_millis(200));
+        processor.handle_input_event(InputEvent::Ctrl(26), &mut shell);
+        assert_eq!(read_output(&mut shell), String::from("done\n"));
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        shell.refresh_env();
+        assert_eq!(shell.get_exit_status(), 0);
+        //Ctrl+C interrupts the subprocess, resumed with 'fg'
+        assert!(shell.write(String::from("fg\n")).is_ok());
+        sleep(Duration::from_millis(200));
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
         processor.handle_input_event(InputEvent::Ctrl(3), &mut shell);
+        let _ = read_output(&mut shell);
+        assert_eq!(shell.get_state(), ShellState::Shell);
+        shell.refresh_env();
+        assert_eq!(shell.get_exit_status(), 130);
         //Stop shell
-        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         let _ = shell.stop();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
-                                           //Send signal once has terminated
+        //Input once the shell has terminated
         processor.handle_input_event(InputEvent::Ctrl(2), &mut shell);
-        //Enter when process has terminated
-        processor.input_buffer = vec!['l', 's'];
-        processor.input_buffer_cursor = 2;
         processor.handle_input_event(InputEvent::Enter, &mut shell);
-        assert_eq!(processor.input_buffer.len(), 0);
-        assert_eq!(processor.input_buffer_cursor, 0);
-        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    fn read_output(shell: &mut Shell) -> String {
+        //Read until the subprocess has terminated
+        let mut output: String = String::new();
+        let t_start: Instant = Instant::now();
+        while shell.get_state() == ShellState::SubprocessRunning && t_start.elapsed() < Duration::from_secs(3) {
+            if let Ok((Some(stdout), _)) = shell.read() {
+                output.push_str(stdout.as_str());
+            }
+            sleep(Duration::from_millis(50));
+        }
+        output
     }
 
     fn new_subprociop() -> SubProcIop {
         SubProcIop::new(
             Config::default(),
-            IOProcessor::new(Language::Russian, new_translator(Language::Russian)),
+            IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap(),
         )
     }
 }
@@ -25,56 +25,141 @@
 
 //Deps
 extern crate ansi_term;
+extern crate dirs;
 extern crate nix;
+extern crate unicode_normalization;
 
 // Runtime modules
 mod props;
 mod imiop;
+mod output;
+mod suggest;
 
 use ansi_term::Colour;
+use std::cell::RefCell;
+use std::fmt;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::thread::sleep;
-use std::time::{Duration};
+use std::time::{Duration, Instant};
+use unicode_normalization::UnicodeNormalization;
 
 //Config
 use crate::config;
+use crate::config::{HistoryFormat, LayoutAutocorrect, OutputBuffering, OutputNormalization};
 //Props
 use props::RuntimeProps;
+use output::{realign_tab_columns, OutputBuffer};
+use suggest::Suggester;
 //Shell
 use crate::shell::{Shell, ShellState};
+use crate::shell::history::ShellHistory;
+use crate::shell::sigwinch;
+use crate::shell::proc::ShellError;
+use crate::shell::proc::trace::Tracer;
 use crate::shell::unixsignal::UnixSignal;
 // Translator
-use crate::translator::ioprocessor::IOProcessor;
+use crate::translator::ioprocessor::{ExpressionParserError, IOProcessor};
 use crate::translator::lang::Language;
-use crate::translator::new_translator;
+use crate::translator::new_gost_translator;
 //Utils
+use crate::utils::ansi::strip_ansi;
 use crate::utils::console;
 use crate::utils::file;
+use crate::utils::layout;
+
+//Time the logout script is given to terminate, before the shell is stopped
+const LOGOUT_FILE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ### RuntimeError
+///
+/// RuntimeError represents an error which caused the runtime to terminate.
+/// Each error has its own exit code (see `exit_code`); 255 is left to bad options and configuration errors
+#[derive(std::fmt::Debug)]
+pub enum RuntimeError {
+    TranslationError(String),                    //250
+    ShellStartFailed(ShellError),                //251
+    ShellWriteFailed(ShellError),                //252
+    ShellStopFailed(ShellError),                 //253
+    Io(PathBuf, io::Error),                      //254: could not read the file at path
+    ExpressionError(PathBuf, ExpressionParserError), //249: could not parse the script at path
+}
+
+impl RuntimeError {
+    /// ### exit_code
+    ///
+    /// Returns the exit code Pyc terminates with when the error occurs
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RuntimeError::ExpressionError(_, _) => 249,
+            RuntimeError::TranslationError(_) => 250,
+            RuntimeError::ShellStartFailed(_) => 251,
+            RuntimeError::ShellWriteFailed(_) => 252,
+            RuntimeError::ShellStopFailed(_) => 253,
+            RuntimeError::Io(_, _) => 254,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::TranslationError(err) => write!(f, "Could not initialize translator for {}", err),
+            RuntimeError::ShellStartFailed(err) => write!(f, "Could not start shell: {}", err),
+            RuntimeError::ShellWriteFailed(err) => write!(f, "Could not write to shell: {}", err),
+            RuntimeError::ShellStopFailed(err) => write!(f, "Could not stop shell: {}", err),
+            RuntimeError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            RuntimeError::ExpressionError(path, err) => write!(f, "{}: {:?}", path.display(), err),
+        }
+    }
+}
 
 //@! Runners
 
 /// ### run_interactive
 ///
-/// Run pyc in interactive mode
-
+/// Run pyc in interactive mode; returns the exit code
 pub fn run_interactive(language: Language, config: config::Config, shell: Option<String>, history_file: Option<PathBuf>) -> u8 {
+    match try_run_interactive(language, config.clone(), shell, history_file) {
+        Ok(rc) => rc,
+        Err(err) => report_error(err, language, &config),
+    }
+}
+
+/// ### run_command
+///
+/// Run command in shell and return the exit code
+pub fn run_command(command: String, language: Language, config: config::Config, shell: Option<String>) -> u8 {
+    match try_run_command(command, language, config.clone(), shell) {
+        Ok(rc) => rc,
+        Err(err) => report_error(err, language, &config),
+    }
+}
+
+/// ### run_file
+///
+/// Run shell reading commands from file and return the exit code
+pub fn run_file(file: String, language: Language, config: config::Config, shell: Option<String>) -> u8 {
+    match try_run_file(file, language, config.clone(), shell) {
+        Ok(rc) => rc,
+        Err(err) => report_error(err, language, &config),
+    }
+}
+
+/// ### try_run_interactive
+///
+/// Run pyc in interactive mode; returns the exit code of the session or the error which terminated it
+pub fn try_run_interactive(language: Language, config: config::Config, shell: Option<String>, history_file: Option<PathBuf>) -> Result<u8, RuntimeError> {
+    let mut processor: IOProcessor = new_processor(language, &config)?;
     //Instantiate Runtime Props
-    let mut props: RuntimeProps = RuntimeProps::new(true, config, language);
-    let processor: IOProcessor = IOProcessor::new(language, new_translator(language));
+    let mut props: RuntimeProps = RuntimeProps::new(true, config, processor.clone(), history_file.clone());
+    let mut output: OutputBuffer = OutputBuffer::new(props.config.output_config.squelch_repeats, props.config.output_config.max_lines_per_sec, props.config.output_config.collapse_progress, props.config.output_config.buffering == OutputBuffering::Line);
+    let suggester: Rc<RefCell<Suggester>> = props.get_suggester();
     //Determine the shell to use
-    let (shell, args): (String, Vec<String>) = resolve_shell(&props.config, shell);
+    let (shell_exec, shell_args): (String, Vec<String>) = resolve_shell(&props.config, shell);
     //Intantiate and start a new shell
-    let mut shell: Shell = match Shell::start(shell, args, &props.config.prompt_config) {
-        Ok(sh) => sh,
-        Err(err) => {
-            print_err(
-                String::from(format!("Could not start shell: {}", err)),
-                props.config.output_config.translate_output,
-                &processor,
-            );
-            return 255;
-        }
-    };
+    let mut shell: Shell = start_shell(shell_exec.clone(), shell_args.clone(), &props.config)?;
     //If history file is set, load history
     if let Some(history_file) = history_file.clone() {
         match file::read_lines(history_file.clone()) {
@@ -82,10 +167,19 @@ pub fn run_interactive(language: Language, config: config::Config, shell: Option
             Err(err) => print_err(
                 String::from(format!("Could not load history from '{}': {}", history_file.display(), err)),
                 props.config.output_config.translate_output,
+                props.config.output_config.error_color,
                 &processor,
             )
         }
     };
+    //If prompt is pinned to the bottom, reserve the last row to the prompt
+    let mut pinned_rows: Option<usize> = get_pinned_rows(&props.config);
+    if let Some(rows) = pinned_rows {
+        console::print(pinned_prompt_enter(rows));
+    }
+    //Forward the terminal size to the shell, now and whenever the terminal is resized
+    let _ = sigwinch::install();
+    resize_shell(&mut shell, pinned_rows);
     //@! Main loop
     while props.get_last_state() != ShellState::Terminated {
         //@! Print prompt if state is Idle and state has changed
@@ -96,85 +190,154 @@ pub fn run_interactive(language: Language, config: config::Config, shell: Option
         if props.get_state_changed() && current_state == ShellState::Shell {
             //Force shellenv to refresh info
             shell.refresh_env();
+            //If the last command was not found, it may have been typed with the wrong keyboard layout
+            let last_input: Option<String> = shell.take_last_input();
+            //Command has terminated; store its result in history
+            if shell.take_command_started() {
+                shell.history.set_last_result(shell.get_exit_status(), shell.get_elapsed_time());
+            }
+            let corrected: Option<String> = match (shell.get_exit_status(), props.config.shell_config.layout_autocorrect, &last_input) {
+                (127, LayoutAutocorrect::Suggest, Some(input)) | (127, LayoutAutocorrect::Run, Some(input)) => correct_layout(input.as_str(), &mut suggester.borrow_mut()),
+                _ => None,
+            };
+            match corrected {
+                Some(command) if props.config.shell_config.layout_autocorrect == LayoutAutocorrect::Run => {
+                    //Ask whether to run the corrected command instead of printing the prompt
+                    props.ask_run(command);
+                    props.report_state_changed_notified();
+                    continue;
+                }
+                Some(command) => print_out(format!("Did you mean '{}'? (wrong keyboard layout)", command), props.config.output_config.translate_output, &processor),
+                None => {
+                    //Suggest the closest command if the last one was not found
+                    if props.config.shell_config.suggest_commands && shell.get_exit_status() == 127 {
+                        if let Some(suggestion) = suggest_command(&shell, &mut suggester.borrow_mut()) {
+                            print_out(suggestion, props.config.output_config.translate_output, &processor);
+                        }
+                    }
+                }
+            }
             //Print prompt
-            console::print(format!("{} ", shell.get_promptline(&processor)));
+            let prompt_line: String = props.get_promptline(&mut shell, &processor);
+            match pinned_rows {
+                Some(rows) => {
+                    console::save_cursor();
+                    console::print(format!("{}{} ", pinned_prompt_line(rows), prompt_line));
+                }
+                None => console::print(format!("{} ", prompt_line)),
+            }
+            if let Some(rprompt_line) = shell.get_rpromptline(&processor) {
+                print_rprompt(&prompt_line, &rprompt_line);
+            }
+            //Put the failed command back in the input buffer
+            if let Some(input) = last_input {
+                if props.config.input_config.recall_on_failure && shell.get_exit_status() != 0 {
+                    props.recall(input);
+                }
+            }
             props.report_state_changed_notified(); //Force state changed to false
         } else if props.get_state_changed() {
             props.report_state_changed_notified(); //Check has been done, nothing to do
         }
+        //Shell has terminated (SIGCHLD); flush its output and leave before blocking on the console
+        if shell.terminated_on_sigchld() {
+            read_from_shell(&mut shell, &props.config, &processor, &mut output);
+            break;
+        }
         //@! Read user input
         if let Some(ev) = console::read() {
             props.handle_input_event(ev, &mut shell);
         };
+        //Apply reloaded configuration; shell exec, args and path apply to new shells only
+        if props.take_config_reloaded() {
+            processor = props.get_processor().clone();
+            output = OutputBuffer::new(props.config.output_config.squelch_repeats, props.config.output_config.max_lines_per_sec, props.config.output_config.collapse_progress, props.config.output_config.buffering == OutputBuffering::Line);
+            shell.set_stop_grace(Duration::from_millis(props.config.shell_config.stop_grace_ms as u64));
+            shell.set_read_timeout(Duration::from_millis(props.config.shell_config.read_timeout_ms as u64));
+            shell.set_read_buffer_size(props.config.shell_config.read_buffer_size);
+            pinned_rows = update_pinned_rows(pinned_rows, &props.config);
+        }
+        //Terminal has been resized; move the pinned prompt and forward the new size to the shell
+        if sigwinch::take_resized() {
+            pinned_rows = update_pinned_rows(pinned_rows, &props.config);
+            resize_shell(&mut shell, pinned_rows);
+        }
+        //Terminate session if exit has been requested
+        if props.get_exit_code().is_some() {
+            break;
+        }
         //Update state after write
         let new_state = shell.get_state(); //Force last state to be changed
         if new_state != props.get_last_state() {
             props.update_state(new_state);
+            //Go back to the scroll region before the output is printed
+            if pinned_rows.is_some() && new_state != ShellState::Shell {
+                console::restore_cursor();
+            }
         }
         //@! Read Shell stdout
-        read_from_shell(&mut shell, &props.config, &processor);
+        read_from_shell(&mut shell, &props.config, &processor, &mut output);
         //Check if shell has terminated
         sleep(Duration::from_nanos(100)); //Sleep for 100ns
     } //@! End of loop
+    //Reset scroll region
+    if let Some(rows) = pinned_rows {
+        console::print(pinned_prompt_exit(rows));
+    }
     //Write history back to file
     if let Some(history_file) = history_file {
-        let lines: Vec<String> = shell.history.dump();
+        let lines: Vec<String> = match props.config.history_config.format {
+            HistoryFormat::Plain => shell.history.dump(),
+            HistoryFormat::Json => shell.history.dump_json(),
+        };
         if let Err(err) = file::write_lines(history_file.clone(), lines) {
             print_err(
                 String::from(format!("Could not write history to '{}': {}", history_file.display(), err)),
                 props.config.output_config.translate_output,
+                props.config.output_config.error_color,
                 &processor,
             );
         }
     };
-    //Return shell exitcode
-    match shell.stop() {
-        Ok(rc) => rc,
-        Err(err) => {
-            print_err(format!("Could not stop shell: {}", err), props.config.output_config.translate_output, &processor);
-            255
+    //Return shell exitcode (or the one requested through exit)
+    let rc: u8 = match shell.stop() {
+        Ok(rc) => match props.get_exit_code() {
+            Some(exit_code) => exit_code,
+            None => rc,
+        },
+        Err(err) => return Err(RuntimeError::ShellStopFailed(err)),
+    };
+    //Run logout script
+    if let Some(logout_file) = props.config.shell_config.logout_file.clone() {
+        if let Err(err) = run_logout_file(expand_home(logout_file.as_str()).as_path(), shell_exec, shell_args, &props.config, &processor, LOGOUT_FILE_TIMEOUT) {
+            print_err(
+                format!("Logout script failed: {}", err),
+                props.config.output_config.translate_output,
+                props.config.output_config.error_color,
+                &processor,
+            );
         }
     }
+    Ok(rc)
 }
 
-/// ### run_command
+/// ### try_run_command
 /// 
-/// Run command in shell and return
-pub fn run_command(mut command: String, language: Language, config: config::Config, shell: Option<String>) -> u8 {
+/// Run command in shell; returns the exit code of the command or the error which terminated the runtime
+pub fn try_run_command(command: String, language: Language, config: config::Config, shell: Option<String>) -> Result<u8, RuntimeError> {
+    let processor: IOProcessor = new_processor(language, &config)?;
     //Instantiate Runtime Props
-    let mut props: RuntimeProps = RuntimeProps::new(false, config, language);
-    let processor: IOProcessor = IOProcessor::new(language, new_translator(language));
+    let mut props: RuntimeProps = RuntimeProps::new(false, config, processor.clone(), None);
+    let mut output: OutputBuffer = OutputBuffer::new(props.config.output_config.squelch_repeats, props.config.output_config.max_lines_per_sec, props.config.output_config.collapse_progress, props.config.output_config.buffering == OutputBuffering::Line);
     //Determine the shell to use
     let (shell, args): (String, Vec<String>) = resolve_shell(&props.config, shell);
     //Intantiate and start a new shell
-    let mut shell: Shell = match Shell::start(shell, args, &props.config.prompt_config) {
-        Ok(sh) => sh,
-        Err(err) => {
-            print_err(
-                String::from(format!("Could not start shell: {}", err)),
-                props.config.output_config.translate_output,
-                &processor,
-            );
-            return 255;
-        }
-    };
+    let mut shell: Shell = start_shell(shell, args, &props.config)?;
     //Prepare command
-    while command.ends_with('\n') {
-        command.pop();
-    }
-    while command.ends_with(';') {
-        command.pop();
-    }
-    //FIXME: handle fish $status
-    command.push_str("; exit $?\n");
+    let command: String = command_with_exit_status(command.as_str());
     //Write command
     if let Err(err) = shell.write(command) {
-        print_err(
-            String::from(format!("Could not start shell: {}", err)),
-            props.config.output_config.translate_output,
-            &processor,
-        );
-        return 255;
+        return Err(RuntimeError::ShellWriteFailed(err));
     }
     let _ = shell.write(String::from("\n"));
     //@! Main loop
@@ -184,57 +347,242 @@ pub fn run_command(mut command: String, language: Language, config: config::Conf
             props.handle_input_event(ev, &mut shell);
         };
         //@! Read Shell stdout
-        read_from_shell(&mut shell, &props.config, &processor);
+        read_from_shell(&mut shell, &props.config, &processor, &mut output);
         //Check if shell has terminated
-        if shell.get_state() == ShellState::Terminated {
+        if !shell.is_alive() {
             break;
         }
         sleep(Duration::from_nanos(100)); //Sleep for 100ns
     } //@! End of main loop
     //Return shell exitcode
     match shell.stop() {
-        Ok(rc) => rc,
-        Err(err) => {
-            print_err(format!("Could not stop shell: {}", err), props.config.output_config.translate_output, &processor);
-            255
-        }
+        Ok(rc) => Ok(rc),
+        Err(err) => Err(RuntimeError::ShellStopFailed(err)),
     }
 }
 
-/// ### run_file
+/// ### try_run_file
 /// 
-/// Run shell reading commands from file
-pub fn run_file(file: String, language: Language, config: config::Config, shell: Option<String>) -> u8 {
+/// Run shell reading commands from file; returns the exit code of the script or the error which terminated the runtime
+pub fn try_run_file(file: String, language: Language, config: config::Config, shell: Option<String>) -> Result<u8, RuntimeError> {
     let file_path: &Path = Path::new(file.as_str());
-    let processor: IOProcessor = IOProcessor::new(language, new_translator(language));
     let lines: Vec<String> = match file::read_lines(file_path) {
         Ok(lines) => lines,
-        Err(_) => {
-            print_err(format!("{}: No such file or directory", file), config.output_config.translate_output, &processor);
-            return 255
-        }
+        Err(err) => return Err(RuntimeError::Io(file_path.to_path_buf(), err)),
     };
     //Join lines in a single command
     let command: String = script_lines_to_string(&lines);
     //Execute command
-    run_command(command, language, config, shell)
+    try_run_command(command, language, config, shell)
+}
+
+/// ### run_logout_file
+///
+/// Run the logout script in a new shell, once the interactive session has ended.
+/// The script is transliterated as any other input; a missing script is ignored.
+/// If the script hasn't terminated after `timeout`, the shell is stopped
+fn run_logout_file(logout_file: &Path, exec: String, args: Vec<String>, config: &config::Config, processor: &IOProcessor, timeout: Duration) -> Result<(), RuntimeError> {
+    let lines: Vec<String> = match file::read_lines(logout_file) {
+        Ok(lines) => lines,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(RuntimeError::Io(logout_file.to_path_buf(), err)),
+    };
+    let command: String = script_lines_to_string(&lines);
+    if command.is_empty() {
+        return Ok(());
+    }
+    let command: String = match processor.expression_to_latin(&command) {
+        Ok(command) => command,
+        Err(err) => return Err(RuntimeError::ExpressionError(logout_file.to_path_buf(), err)),
+    };
+    let mut shell: Shell = start_shell(exec, args, config)?;
+    if let Err(err) = shell.write(command_with_exit_status(command.as_str())) {
+        let _ = shell.stop();
+        return Err(RuntimeError::ShellWriteFailed(err));
+    }
+    let mut output: OutputBuffer = OutputBuffer::new(config.output_config.squelch_repeats, None, config.output_config.collapse_progress, config.output_config.buffering == OutputBuffering::Line);
+    let t_start: Instant = Instant::now();
+    while shell.is_alive() && t_start.elapsed() < timeout {
+        read_from_shell(&mut shell, config, processor, &mut output);
+        sleep(Duration::from_nanos(100)); //Sleep for 100ns
+    }
+    read_from_shell(&mut shell, config, processor, &mut output);
+    //Shell is terminated (or killed, if the script is still running)
+    match shell.stop() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(RuntimeError::ShellStopFailed(err)),
+    }
+}
+
+/// ### expand_home
+///
+/// Replace the leading `~` of path with the user's home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// ### report_error
+///
+/// Print the error which terminated the runtime and returns the associated exit code.
+/// The error is translated, if possible, as any other error
+fn report_error(err: RuntimeError, language: Language, config: &config::Config) -> u8 {
+    match new_processor(language, config) {
+        Ok(processor) => print_err(
+            err.to_string(),
+            config.output_config.translate_output,
+            config.output_config.error_color,
+            &processor,
+        ),
+        Err(_) => console::eprintln(paint_err(err.to_string(), config.output_config.error_color)),
+    }
+    err.exit_code()
+}
+
+/// ### new_processor
+/// 
+/// Instantiate the IOProcessor for language
+fn new_processor(language: Language, config: &config::Config) -> Result<IOProcessor, RuntimeError> {
+    match IOProcessor::new(language, new_gost_translator(language, config.translit_config.gost_system), config.transliteration.clone()) {
+        Ok(mut processor) => {
+            processor.set_max_depth(config.translit_config.max_expression_depth);
+            processor.set_translate_args(config.translit_config.translate_args);
+            Ok(processor)
+        }
+        Err(err) => Err(RuntimeError::TranslationError(format!("'{}': {}", language.to_string(), err))),
+    }
 }
 
 //@! Shell functions
 
 /// ### read_from_shell
 /// 
-/// Read from shell stderr and stdout
-fn read_from_shell(shell: &mut Shell, config: &config::Config, processor: &IOProcessor) {
+/// Read from shell stderr and stdout.
+/// Stdout is printed line by line as lines complete; the partial trailing line is kept in the output buffer
+/// until either more data completes it, or no more data is available
+fn read_from_shell(shell: &mut Shell, config: &config::Config, processor: &IOProcessor, output: &mut OutputBuffer) {
     if let Ok((out, err)) = shell.read() {
-        if out.is_some() {
-            //Convert out to cyrillic
-            print_out(out.unwrap(), config.output_config.translate_output, &processor);
+        match out {
+            Some(out) => {
+                //Convert complete lines to cyrillic
+                let out: String = normalize_output(out, config.output_config.normalize);
+                shell.record_output(out.as_str());
+                let lines: Vec<String> = output.push(out.as_str());
+                shell.count_output_lines(lines.len());
+                //Drop lines exceeding the rate limit before they're translated
+                let lines: Vec<String> = output.throttle(lines);
+                if !lines.is_empty() {
+                    if let Some(dropped) = output.take_dropped() {
+                        print_dropped_lines(dropped, config, processor);
+                    }
+                }
+                let lines: Vec<(String, usize)> = output.squelch(lines);
+                if !lines.is_empty() {
+                    console::print(format_output_lines(lines, config.output_config.translate_output, config.output_config.realign_tabs, processor));
+                }
+            }
+            None => {
+                //No more data available; print line held back by squelch and partial line (e.g. a program waiting for input), unless line buffered
+                if let Some(line) = output.take_repeated() {
+                    console::print(format_output_lines(vec![line], config.output_config.translate_output, config.output_config.realign_tabs, processor));
+                }
+                if let Some(partial) = output.flush_partial() {
+                    console::print(console_fmt(partial, config.output_config.translate_output, processor));
+                }
+            }
         }
-        if err.is_some() {
+        if let Some(err) = err {
             //Convert err to cyrillic
-            print_err(err.unwrap().to_string(), config.output_config.translate_output, &processor);
+            let err: String = normalize_output(err, config.output_config.normalize);
+            print_err(err, config.output_config.translate_output, config.output_config.error_color, processor);
+        }
+    }
+    //Once command has terminated, the last line is complete
+    if shell.get_state() != ShellState::SubprocessRunning {
+        if let Some(line) = output.take_repeated() {
+            console::print(format_output_lines(vec![line], config.output_config.translate_output, config.output_config.realign_tabs, processor));
         }
+        if let Some(partial) = output.flush() {
+            shell.count_output_lines(1);
+            print_out(partial, config.output_config.translate_output, processor);
+        }
+        if let Some(dropped) = output.take_dropped() {
+            print_dropped_lines(dropped, config, processor);
+        }
+    }
+}
+
+/// ### print_dropped_lines
+///
+/// Report the amount of output lines dropped because of the output rate limit
+fn print_dropped_lines(dropped: usize, config: &config::Config, processor: &IOProcessor) {
+    print_err(
+        format!("... {} lines dropped (output exceeded {} lines/s)", dropped, config.output_config.max_lines_per_sec.unwrap_or(0)),
+        config.output_config.translate_output,
+        config.output_config.error_color,
+        processor,
+    );
+}
+
+/// ### format_output_lines
+/// 
+/// Format complete output lines; each line is converted to cyrillic if translate config is true.
+/// Lines which have been repeated get the `(repeated Nx)` suffix.
+/// If realign_tabs is true, tab-separated columns are realigned after the translation
+fn format_output_lines(lines: Vec<(String, usize)>, to_cyrillic: bool, realign_tabs: bool, processor: &IOProcessor) -> String {
+    let (lines, counts): (Vec<String>, Vec<usize>) = lines.into_iter().unzip();
+    let mut lines: Vec<String> = lines.into_iter().map(|line| console_fmt(line, to_cyrillic, processor)).collect();
+    if realign_tabs && to_cyrillic {
+        lines = realign_tab_columns(lines);
+    }
+    let mut out: String = String::new();
+    for (line, count) in lines.into_iter().zip(counts) {
+        match count {
+            1 => out.push_str(line.as_str()),
+            _ => out.push_str(format!("{} (repeated {}x)\n", line.trim_end_matches('\n'), count).as_str()),
+        }
+    }
+    out
+}
+
+/// ### normalize_output
+/// 
+/// Apply the configured unicode normalization to the shell output
+fn normalize_output(out: String, normalization: OutputNormalization) -> String {
+    match normalization {
+        OutputNormalization::None => out,
+        OutputNormalization::Nfc => out.nfc().collect(),
+    }
+}
+
+/// ### suggest_command
+///
+/// Returns the suggestion for the last command in history
+fn suggest_command(shell: &Shell, suggester: &mut Suggester) -> Option<String> {
+    let last_command: String = shell.history.at(0)?;
+    let command: &str = last_command.split_whitespace().next()?;
+    //Commands with a path are not looked up in PATH
+    if command.contains('/') {
+        return None;
+    }
+    suggester.suggest(command).map(|suggestion| format!("Did you mean '{}'?", suggestion))
+}
+
+/// ### correct_layout
+///
+/// Returns the input as it would have been typed with the other keyboard layout,
+/// if the command was typed with the cyrillic layout and the remapped command is an executable in PATH
+fn correct_layout(input: &str, suggester: &mut Suggester) -> Option<String> {
+    let command: &str = input.split_whitespace().next()?;
+    if !layout::is_cyrillic(command) {
+        return None;
+    }
+    let corrected: String = layout::switch_layout(input);
+    match suggester.is_executable(corrected.split_whitespace().next()?) {
+        true => Some(corrected),
+        false => None,
     }
 }
 
@@ -248,6 +596,62 @@ fn resolve_shell(config: &config::Config, shellopt: Option<String>) -> (String,
     }
 }
 
+/// ### start_shell
+/// 
+/// Start a new shell with the environment, the stop grace period and the trace file set in configuration
+fn start_shell(exec: String, args: Vec<String>, config: &config::Config) -> Result<Shell, RuntimeError> {
+    let tracer: Option<Tracer> = match config.trace_file.as_ref() {
+        Some(trace_file) => match Tracer::open(trace_file.as_path()) {
+            Ok(tracer) => Some(tracer),
+            Err(err) => return Err(RuntimeError::Io(trace_file.clone(), err)),
+        },
+        None => None,
+    };
+    let mut shell: Shell = match Shell::start(exec, args, &shell_env(&config.shell_config.path), &config.prompt_config) {
+        Ok(sh) => sh,
+        Err(err) => return Err(RuntimeError::ShellStartFailed(err)),
+    };
+    shell.set_stop_grace(Duration::from_millis(config.shell_config.stop_grace_ms as u64));
+    shell.set_read_timeout(Duration::from_millis(config.shell_config.read_timeout_ms as u64));
+    shell.set_read_buffer_size(config.shell_config.read_buffer_size);
+    shell.set_tracer(tracer);
+    Ok(shell)
+}
+
+/// ### shell_path
+/// 
+/// Returns the PATH of the shell: the configured directories prepended to Pyc's PATH
+fn shell_path(dirs: &[String]) -> String {
+    assemble_path(dirs, std::env::var("PATH").ok())
+}
+
+/// ### shell_env
+/// 
+/// Returns the variables to set in the environment of the shell.
+/// Pyc's own environment is never changed, the variables are set in the shell only
+fn shell_env(dirs: &[String]) -> Vec<(String, String)> {
+    match dirs.is_empty() {
+        true => Vec::new(),
+        false => vec![(String::from("PATH"), shell_path(dirs))],
+    }
+}
+
+/// ### assemble_path
+/// 
+/// Assemble PATH value prepending dirs to the inherited PATH
+fn assemble_path(dirs: &[String], inherited: Option<String>) -> String {
+    let mut path: Vec<String> = dirs.to_vec();
+    if let Some(inherited) = inherited {
+        for dir in inherited.split(':') {
+            //Don't add the same directory twice
+            if !dir.is_empty() && !path.iter().any(|d| d == dir) {
+                path.push(String::from(dir));
+            }
+        }
+    }
+    path.join(":")
+}
+
 /// ### script_lines_to_string
 /// 
 /// Converts script lines to a single command as string
@@ -269,16 +673,183 @@ fn script_lines_to_string(lines: &Vec<String>) -> String {
     command
 }
 
-/// ### resolve_command
+/// ### command_with_exit_status
 ///
-/// resolve command according to configured alias
+/// Append to command the exit of the shell with the command exit status.
+/// `exit` is put on a new line, so commands which run in background or end with a comment are not broken;
+/// incomplete commands (e.g. ending with `&&`) are wrapped in a subshell, so that the shell reports the syntax error
+fn command_with_exit_status(command: &str) -> String {
+    let command: &str = command.trim_end().trim_end_matches(';').trim_end();
+    let incomplete: bool = ["&&", "||", "|", "\\"].iter().any(|op| command.ends_with(op));
+    //FIXME: handle fish $status
+    match incomplete {
+        true => format!("({}\n)\nexit $?\n", command),
+        false => format!("{}\nexit $?\n", command),
+    }
+}
 
-fn resolve_command(argv: &mut Vec<String>, config: &config::Config) {
+/// ### resolve_command
+///
+/// resolve command according to configured alias. The words of the alias value become the arguments of the command.
+/// If the alias value contains placeholders (`$1`, `$2`... for a single argument, `$@` for all of them), they are replaced
+/// by the arguments given to the alias, which are not appended then; otherwise the arguments are appended to the alias value.
+/// Returns whether the command has been replaced by an alias
+fn resolve_command(argv: &mut Vec<String>, config: &config::Config) -> bool {
     //Process arg 0
-    match config.get_alias(&argv[0]) {
-        Some(resolved) => argv[0] = resolved,
-        None => {}
+    let alias: String = match config.get_alias(&argv[0]) {
+        Some(resolved) => resolved,
+        None => return false
     };
+    let args: Vec<String> = argv.split_off(1);
+    argv.clear();
+    let mut substituted: bool = false;
+    for word in alias.split_whitespace() {
+        //'$@' alone is replaced by all the arguments, each one in its own entry
+        if word == "$@" {
+            argv.extend(args.iter().cloned());
+            substituted = true;
+            continue;
+        }
+        let resolved: String = match substitute_placeholders(word, &args) {
+            Some(resolved) => {
+                substituted = true;
+                resolved
+            }
+            None => String::from(word),
+        };
+        if !resolved.is_empty() {
+            argv.push(resolved);
+        }
+    }
+    if !substituted {
+        argv.extend(args);
+    }
+    true
+}
+
+/// ### substitute_placeholders
+///
+/// Replace the placeholders in a word of an alias value with the arguments, scanning it once from left to right,
+/// so that placeholders in the arguments are not replaced. `$10` is the tenth argument, not `$1` followed by 0.
+/// Returns None if the word doesn't contain any placeholder
+fn substitute_placeholders(word: &str, args: &[String]) -> Option<String> {
+    let mut resolved: String = String::with_capacity(word.len());
+    let mut substituted: bool = false;
+    let mut chars = word.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('$', Some('@')) => {
+                chars.next();
+                resolved.push_str(args.join(" ").as_str());
+                substituted = true;
+            }
+            ('$', Some(digit)) if digit.is_ascii_digit() && *digit != '0' => {
+                let mut n: usize = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    n = n.saturating_mul(10).saturating_add(digit as usize);
+                    chars.next();
+                }
+                if let Some(arg) = args.get(n - 1) {
+                    resolved.push_str(arg.as_str());
+                }
+                substituted = true;
+            }
+            (ch, _) => resolved.push(ch),
+        }
+    }
+    match substituted {
+        true => Some(resolved),
+        false => None,
+    }
+}
+
+/// ### split_args
+///
+/// Split a command line into its words. Whitespaces between quotes or escaped by a backslash don't split words;
+/// quotes and backslashes are kept, since the words are sent to the shell
+fn split_args(input: &str) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    let mut arg: String = String::new();
+    let mut quote: Option<char> = None;
+    let mut escaped: bool = false;
+    for ch in input.chars() {
+        if escaped {
+            arg.push(ch);
+            escaped = false;
+            continue;
+        }
+        match (ch, quote) {
+            ('\\', Some('\'')) => arg.push(ch),
+            ('\\', _) => {
+                arg.push(ch);
+                escaped = true;
+            }
+            (ch, Some(q)) if ch == q => {
+                arg.push(ch);
+                quote = None;
+            }
+            ('"', None) | ('\'', None) => {
+                arg.push(ch);
+                quote = Some(ch);
+            }
+            (ch, None) if ch.is_whitespace() => {
+                if !arg.is_empty() {
+                    args.push(arg);
+                    arg = String::new();
+                }
+            }
+            (ch, _) => arg.push(ch),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    args
+}
+
+/// ### unquote_arg
+///
+/// Remove quotes and escaping backslashes from a word returned by `split_args`, as the shell would
+fn unquote_arg(word: &str) -> String {
+    let mut arg: String = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = word.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, quote) {
+            //Inside double quotes, backslash escapes only the characters which are special there
+            ('\\', Some('"')) => match chars.peek() {
+                Some('"') | Some('\\') | Some('$') | Some('`') => arg.push(chars.next().unwrap()),
+                _ => arg.push(ch),
+            },
+            ('\\', None) => {
+                if let Some(next) = chars.next() {
+                    arg.push(next);
+                }
+            }
+            (ch, Some(q)) if ch == q => quote = None,
+            ('"', None) | ('\'', None) => quote = Some(ch),
+            (ch, _) => arg.push(ch),
+        }
+    }
+    arg
+}
+
+/// ### argv_to_latin
+///
+/// Resolve alias and join arguments into the latin command line to send to the shell.
+/// An alias value is already latin and may contain characters the expression parser would interpret (e.g. parentheses),
+/// so only the arguments are converted, before they're put into the alias value
+fn argv_to_latin(argv: &[String], config: &config::Config, processor: &IOProcessor) -> Result<String, ExpressionParserError> {
+    if config.get_alias(&argv[0]).is_none() {
+        return processor.expression_to_latin(&(argv.join(" ") + "\n"));
+    }
+    let mut resolved: Vec<String> = vec![argv[0].clone()];
+    if argv.len() > 1 {
+        let args: String = processor.expression_to_latin(&(argv[1..].join(" ") + "\n"))?;
+        resolved.extend(split_args(args.as_str()));
+    }
+    resolve_command(&mut resolved, config);
+    Ok(format!("{}\n", resolved.join(" ")))
 }
 
 /*
@@ -300,13 +871,23 @@ fn get_shell_from_env() -> Result<String, ()> {
 /// 
 /// print error message; the message is may converted to cyrillic if translate config is true
 
-fn print_err(err: String, to_cyrillic: bool, processor: &IOProcessor) {
+fn print_err(err: String, to_cyrillic: bool, color: Option<Colour>, processor: &IOProcessor) {
     match to_cyrillic {
-        true => eprintln!("{}", Colour::Red.paint(processor.text_to_cyrillic(&err))),
-        false => eprintln!("{}", Colour::Red.paint(err)),
+        true => console::eprintln(paint_err(processor.text_to_cyrillic(&err), color)),
+        false => console::eprintln(paint_err(err, color)),
     };
 }
 
+/// ### paint_err
+///
+/// Paint error message with the configured color; if color is None, the message is returned as it is
+fn paint_err(err: String, color: Option<Colour>) -> String {
+    match color {
+        Some(color) => color.paint(err).to_string(),
+        None => err,
+    }
+}
+
 /// ### print_out
 ///
 /// print normal message; the message is may converted to cyrillic if translate config is true
@@ -318,6 +899,112 @@ fn print_out(out: String, to_cyrillic: bool, processor: &IOProcessor) {
     };
 }
 
+/// ### append_history
+///
+/// Append the newest history entry to the history file, in the format of the file (or in the configured one, if the file is empty)
+fn append_history(history_file: &Path, history: &ShellHistory, format: HistoryFormat) -> io::Result<()> {
+    let first_line: Option<String> = match file::read_first_line(history_file) {
+        Ok(line) => line,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    file::append_lines(history_file, history.dump_newest(first_line.as_deref(), format == HistoryFormat::Json))
+}
+
+/// ### get_pinned_rows
+///
+/// Returns the amount of rows of the terminal if the prompt has to be pinned to the bottom
+fn get_pinned_rows(config: &config::Config) -> Option<usize> {
+    match config.prompt_config.bottom_pinned {
+        true => match console::term_size() {
+            Some((_, rows)) if rows > 1 => Some(rows),
+            _ => None,
+        },
+        false => None,
+    }
+}
+
+/// ### update_pinned_rows
+///
+/// Move the bottom pinned prompt if the amount of rows has changed (or pinning has been toggled); returns the new amount of rows
+fn update_pinned_rows(pinned_rows: Option<usize>, config: &config::Config) -> Option<usize> {
+    let new_pinned_rows: Option<usize> = get_pinned_rows(config);
+    if new_pinned_rows != pinned_rows {
+        if let Some(rows) = pinned_rows {
+            console::print(pinned_prompt_exit(rows));
+        }
+        if let Some(rows) = new_pinned_rows {
+            console::print(pinned_prompt_enter(rows));
+        }
+    }
+    new_pinned_rows
+}
+
+/// ### resize_shell
+///
+/// Set the size of the terminal of the shell to the size of the console; the row of the pinned prompt is not part of it
+fn resize_shell(shell: &mut Shell, pinned_rows: Option<usize>) {
+    if let Some((cols, rows)) = console::term_size() {
+        let rows: usize = match pinned_rows {
+            Some(_) => rows - 1,
+            None => rows,
+        };
+        let _ = shell.resize(rows as u16, cols as u16);
+    }
+}
+
+/// ### pinned_prompt_enter
+///
+/// Returns the sequence to enter the bottom pinned prompt mode.
+/// The last row is reserved to the prompt, while the output scrolls in the rows above
+fn pinned_prompt_enter(rows: usize) -> String {
+    format!("{}{}", console::scroll_region_seq(1, rows - 1), console::cursor_to_seq(rows - 1, 1))
+}
+
+/// ### pinned_prompt_exit
+///
+/// Returns the sequence to exit the bottom pinned prompt mode, restoring the entire screen as scroll region
+fn pinned_prompt_exit(rows: usize) -> String {
+    format!("{}{}\n", console::reset_scroll_region_seq(), console::cursor_to_seq(rows, 1))
+}
+
+/// ### pinned_prompt_line
+///
+/// Returns the sequence to move to the last row and clear it, before writing the prompt line
+fn pinned_prompt_line(rows: usize) -> String {
+    format!("{}{}", console::cursor_to_seq(rows, 1), console::clear_line_seq())
+}
+
+/// ### print_rprompt
+///
+/// Print the right prompt flush right on the row of the prompt line, then move the cursor back after the prompt line
+fn print_rprompt(prompt_line: &str, rprompt_line: &str) {
+    if let Some((columns, _)) = console::term_size() {
+        if let Some(seq) = rprompt_seq(prompt_line, rprompt_line, columns) {
+            console::print(seq);
+        }
+    }
+}
+
+/// ### rprompt_seq
+///
+/// Returns the sequence to write the right prompt flush right and move the cursor back after the prompt line (and its trailing space).
+/// Returns None if the right prompt doesn't fit beside the last row of the prompt line
+fn rprompt_seq(prompt_line: &str, rprompt_line: &str, columns: usize) -> Option<String> {
+    let prompt_width: usize = strip_ansi(prompt_line.rsplit('\n').next().unwrap_or("")).chars().count();
+    let rprompt_width: usize = strip_ansi(rprompt_line).chars().count();
+    //Keep at least a blank column between the prompt (with its trailing space) and the right prompt
+    if rprompt_width == 0 || prompt_width + 2 + rprompt_width > columns {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}",
+        console::cursor_to_col_seq(columns - rprompt_width + 1),
+        rprompt_line,
+        console::cursor_to_col_seq(prompt_width + 2)
+    ))
+}
+
 /// ### console_fmt
 /// 
 /// Format console message
@@ -332,7 +1019,6 @@ fn console_fmt(out: String, to_cyrillic: bool, processor: &IOProcessor) -> Strin
 /// ### shellsignal_to_signal
 /// 
 /// Converts a signal received on prompt to a UnixSignal
-#[allow(dead_code)]
 fn shellsignal_to_signal(sig: u8) -> Option<UnixSignal> {
     match sig {
         3 => Some(UnixSignal::Sigint),
@@ -352,42 +1038,212 @@ mod tests {
     use crate::translator::lang::Language;
 
     use std::collections::HashMap;
-    use std::time::Duration;
+    use std::io::Write;
+    use std::time::{Duration, Instant};
     use std::thread::sleep;
 
+    #[test]
+    fn test_runtime_error_exit_code() {
+        assert_eq!(RuntimeError::ExpressionError(PathBuf::from("script.sh"), ExpressionParserError::MissingToken).exit_code(), 249);
+        assert_eq!(RuntimeError::TranslationError(String::from("'рус': bad regex")).exit_code(), 250);
+        assert_eq!(RuntimeError::ShellStartFailed(ShellError::CouldNotStartProcess).exit_code(), 251);
+        assert_eq!(RuntimeError::ShellWriteFailed(ShellError::ShellTerminated).exit_code(), 252);
+        assert_eq!(RuntimeError::ShellStopFailed(ShellError::CouldNotKill).exit_code(), 253);
+        assert_eq!(RuntimeError::Io(PathBuf::from("script.sh"), io::Error::from(io::ErrorKind::NotFound)).exit_code(), 254);
+    }
+
+    #[test]
+    fn test_runtime_error_display() {
+        assert_eq!(
+            RuntimeError::TranslationError(String::from("'рус': bad regex")).to_string(),
+            String::from("Could not initialize translator for 'рус': bad regex")
+        );
+        assert_eq!(
+            RuntimeError::ShellStartFailed(ShellError::CouldNotStartProcess).to_string(),
+            format!("Could not start shell: {}", ShellError::CouldNotStartProcess)
+        );
+        assert_eq!(
+            RuntimeError::Io(PathBuf::from("script.sh"), io::Error::from(io::ErrorKind::NotFound)).to_string(),
+            format!("script.sh: {}", io::Error::from(io::ErrorKind::NotFound))
+        );
+        assert_eq!(
+            RuntimeError::ExpressionError(PathBuf::from("script.sh"), ExpressionParserError::MissingToken).to_string(),
+            String::from("script.sh: MissingToken")
+        );
+    }
+
+    #[test]
+    fn test_runtime_run_file_not_found() {
+        let config: Config = Config::default();
+        let file: String = String::from("/this/script/does/not/exist.sh");
+        match try_run_file(file.clone(), Language::Russian, config.clone(), None) {
+            Err(RuntimeError::Io(path, err)) => {
+                assert_eq!(path, PathBuf::from("/this/script/does/not/exist.sh"));
+                assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            }
+            _ => panic!("try_run_file should have returned an Io error"),
+        }
+        assert_eq!(run_file(file, Language::Russian, config, None), 254);
+    }
+
+    #[test]
+    fn test_runtime_run_logout_file() {
+        let config: Config = Config::default();
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let logout_file: PathBuf = tmpdir.path().join("pyc_logout");
+        let out_file: PathBuf = tmpdir.path().join("out");
+        //Missing script is ignored
+        assert!(run_logout_file(logout_file.as_path(), String::from("sh"), vec![], &config, &iop, LOGOUT_FILE_TIMEOUT).is_ok());
+        //Script is transliterated and run
+        let script: String = format!("# Logout\nечо пока > {}\n", out_file.display());
+        std::fs::write(logout_file.as_path(), script).unwrap();
+        assert!(run_logout_file(logout_file.as_path(), String::from("sh"), vec![], &config, &iop, LOGOUT_FILE_TIMEOUT).is_ok());
+        assert_eq!(std::fs::read_to_string(out_file.as_path()).unwrap(), String::from("poca\n"));
+        //Script which doesn't terminate is stopped once the timeout expires
+        std::fs::write(logout_file.as_path(), "слееп 60\n").unwrap();
+        let t_start: Instant = Instant::now();
+        assert!(run_logout_file(logout_file.as_path(), String::from("sh"), vec![], &config, &iop, Duration::from_millis(500)).is_ok());
+        assert!(t_start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_runtime_expand_home() {
+        let home: PathBuf = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/.pyc_logout"), home.join(".pyc_logout"));
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("/etc/pyc_logout"), PathBuf::from("/etc/pyc_logout"));
+        assert_eq!(expand_home("~root/.pyc_logout"), PathBuf::from("~root/.pyc_logout"));
+    }
+
     #[test]
     fn test_runtime_read_from_shell() {
         let mut cfg: Config = Config::default();
         cfg.output_config.translate_output = true;
-        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
-        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &cfg.prompt_config).unwrap();
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &[], &cfg.prompt_config).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Write
         let _ = shell.write(String::from("echo 4\n"));
         sleep(Duration::from_millis(100));
         //Read
-        read_from_shell(&mut shell, &cfg, &iop);
+        read_from_shell(&mut shell, &cfg, &iop, &mut output);
         //Don't translate
         cfg.output_config.translate_output = false;
         let _ = shell.write(String::from("echo 5\n"));
         sleep(Duration::from_millis(100));
-        read_from_shell(&mut shell, &cfg, &iop);
+        read_from_shell(&mut shell, &cfg, &iop, &mut output);
         //Try stderr
         cfg.output_config.translate_output = true;
         let _ = shell.write(String::from("poropero\n"));
         sleep(Duration::from_millis(100));
-        read_from_shell(&mut shell, &cfg, &iop);
+        read_from_shell(&mut shell, &cfg, &iop, &mut output);
         //Try stderr not translated
         cfg.output_config.translate_output = false;
         let _ = shell.write(String::from("poropero\n"));
         sleep(Duration::from_millis(100));
-        read_from_shell(&mut shell, &cfg, &iop);
+        read_from_shell(&mut shell, &cfg, &iop, &mut output);
         //Terminate shell
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         assert!(shell.stop().is_ok());
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
     }
 
+    #[test]
+    fn test_runtime_read_from_shell_output_lines() {
+        let mut config: config::Config = config::Config::default();
+        config.prompt_config.prompt_line = String::from("${CMD_OUTPUT_LINES}");
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &[], &config.prompt_config).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.write(String::from("seq 1 5\n")).is_ok());
+        sleep(Duration::from_millis(200));
+        while shell.get_state() == ShellState::SubprocessRunning {
+            read_from_shell(&mut shell, &config, &iop, &mut output);
+            sleep(Duration::from_millis(50));
+        }
+        read_from_shell(&mut shell, &config, &iop, &mut output);
+        assert_eq!(shell.get_promptline(&iop), String::from("5"));
+        //Counter is reset at the next command
+        assert!(shell.write(String::from("echo -n foo\n")).is_ok());
+        assert_eq!(shell.get_promptline(&iop), String::from("0"));
+        sleep(Duration::from_millis(200));
+        while shell.get_state() == ShellState::SubprocessRunning {
+            read_from_shell(&mut shell, &config, &iop, &mut output);
+            sleep(Duration::from_millis(50));
+        }
+        read_from_shell(&mut shell, &config, &iop, &mut output);
+        assert_eq!(shell.get_promptline(&iop), String::from("1"));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+    }
+
+    #[test]
+    fn test_runtime_format_output_lines() {
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        //Line split across two reads is translated only once it's complete
+        assert_eq!(output.push("hel").len(), 0);
+        let lines: Vec<String> = output.push("lo\nwor");
+        assert_eq!(format_output_lines(output.squelch(lines), true, false, &iop), String::from("хелло\n"));
+        let lines: Vec<String> = output.push("ld\n");
+        assert_eq!(format_output_lines(output.squelch(lines), true, false, &iop), String::from("уорлд\n"));
+        //Not translated
+        let lines: Vec<String> = output.push("foo\nbar\n");
+        assert_eq!(format_output_lines(output.squelch(lines), false, false, &iop), String::from("foo\nbar\n"));
+    }
+
+    #[test]
+    fn test_runtime_format_output_lines_progress() {
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, true, false);
+        //Only the settled line is translated
+        assert_eq!(output.push("\rzagruzka 10%\rzagruzka 50%").len(), 0);
+        let lines: Vec<String> = output.push("\rzagruzka 90%\rzagruzka 100%\n");
+        assert_eq!(format_output_lines(output.squelch(lines), true, false, &iop), String::from("\rзагрузка 100%\n"));
+    }
+
+    #[test]
+    fn test_runtime_format_output_lines_squelch() {
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(true, None, false, false);
+        let lines: Vec<String> = output.push("privet\n".repeat(100).as_str());
+        assert_eq!(format_output_lines(output.squelch(lines), true, false, &iop), String::new());
+        let lines: Vec<(String, usize)> = vec![output.take_repeated().unwrap()];
+        assert_eq!(format_output_lines(lines, true, false, &iop), String::from("привет (repeated 100x)\n"));
+    }
+
+    #[test]
+    fn test_runtime_format_output_lines_realign() {
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        let mut output: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        //'exexex' becomes longer than a tab stop once translated
+        let lines: Vec<String> = output.push("exexex\t1\nshchi\t2\n");
+        let out: String = format_output_lines(output.squelch(lines), true, true, &iop);
+        let rows: Vec<&str> = out.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert!(!out.contains('\t'));
+        //Second column starts at the same position
+        let column: Vec<usize> = rows.iter().map(|r| r.chars().count() - 1).collect();
+        assert_eq!(column[0], column[1]);
+        assert_eq!(rows[1], "шчи             2");
+        //Not realigned
+        let lines: Vec<String> = output.push("exexex\t1\n");
+        assert!(format_output_lines(output.squelch(lines), true, false, &iop).contains('\t'));
+    }
+
+    #[test]
+    fn test_runtime_normalize_output() {
+        //NFD 'й' (и + combining breve) and 'ё' (е + combining diaeresis)
+        let nfd: String = String::from("\u{0438}\u{0306}\u{0435}\u{0308}");
+        assert_eq!(normalize_output(nfd.clone(), OutputNormalization::Nfc), String::from("йё"));
+        assert_eq!(normalize_output(nfd.clone(), OutputNormalization::None), nfd);
+        //Already composed text is not changed
+        assert_eq!(normalize_output(String::from("привет"), OutputNormalization::Nfc), String::from("привет"));
+    }
+
     #[test]
     fn test_runtime_resolve_shell() {
         let mut cfg: Config = Config::default();
@@ -398,12 +1254,113 @@ mod tests {
         assert_eq!(resolve_shell(&cfg, Some(String::from("fish"))), (String::from("fish"), vec![]));
     }
 
+    #[test]
+    fn test_runtime_assemble_path() {
+        let dirs: Vec<String> = vec![String::from("/opt/bin"), String::from("/home/omar/bin")];
+        assert_eq!(assemble_path(&dirs, Some(String::from("/usr/bin:/bin"))), String::from("/opt/bin:/home/omar/bin:/usr/bin:/bin"));
+        assert_eq!(assemble_path(&dirs, Some(String::from("/usr/bin:/opt/bin"))), String::from("/opt/bin:/home/omar/bin:/usr/bin"));
+        assert_eq!(assemble_path(&dirs, None), String::from("/opt/bin:/home/omar/bin"));
+    }
+
+    #[test]
+    fn test_runtime_shell_env() {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+        //Place a binary in a temporary directory
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let bin_path: PathBuf = tmpdir.path().join("pyc_path_test");
+        let mut bin: std::fs::File = std::fs::File::create(bin_path.as_path()).unwrap();
+        assert!(write!(bin, "#!/bin/sh\necho test\n").is_ok());
+        drop(bin);
+        assert!(std::fs::set_permissions(bin_path.as_path(), std::fs::Permissions::from_mode(0o755)).is_ok());
+        let dirs: Vec<String> = vec![String::from(tmpdir.path().to_str().unwrap())];
+        assert!(shell_env(&[]).is_empty());
+        let env: Vec<(String, String)> = shell_env(&dirs);
+        assert_eq!(env, vec![(String::from("PATH"), shell_path(&dirs))]);
+        assert!(env[0].1.starts_with(tmpdir.path().to_str().unwrap()));
+        //Shell must find the binary
+        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &env, &config::PromptConfig::default()).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.write(String::from("which pyc_path_test\n"));
+        sleep(Duration::from_millis(500));
+        let (out, _) = shell.read().unwrap();
+        assert_eq!(out.unwrap(), format!("{}\n", bin_path.display()));
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+    }
+
+    #[test]
+    fn test_runtime_suggest_command() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let bin_path: PathBuf = tmpdir.path().join("pyc_suggest_test");
+        assert!(std::fs::File::create(bin_path.as_path()).is_ok());
+        assert!(std::fs::set_permissions(bin_path.as_path(), std::fs::Permissions::from_mode(0o755)).is_ok());
+        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &[], &config::PromptConfig::default()).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let mut suggester: Suggester = Suggester::new(shell_path(&[String::from(tmpdir.path().to_str().unwrap())]));
+        //Near miss
+        shell.history.push(String::from("pyc_sugest_test --help"));
+        assert_eq!(suggest_command(&shell, &mut suggester), Some(String::from("Did you mean 'pyc_suggest_test'?")));
+        //Paths are not looked up
+        shell.history.push(String::from("./pyc_sugest_test"));
+        assert!(suggest_command(&shell, &mut suggester).is_none());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell.stop().is_ok());
+    }
+
+    #[test]
+    fn test_runtime_correct_layout() {
+        let mut suggester: Suggester = Suggester::new(shell_path(&[]));
+        //Typed with the cyrillic layout
+        assert_eq!(correct_layout("ды -дф /еьз", &mut suggester), Some(String::from("ls -la /tmp")));
+        assert_eq!(correct_layout("зцв", &mut suggester), Some(String::from("pwd")));
+        //Typed with the latin layout
+        assert!(correct_layout("lsx -la", &mut suggester).is_none());
+        //Remapped command doesn't exist
+        assert!(correct_layout("ёё", &mut suggester).is_none());
+        assert!(correct_layout("", &mut suggester).is_none());
+    }
+
     #[test]
     fn test_runtime_script_lines_to_command() {
         let lines: Vec<String> = vec![String::from("#!/bin/bash"), String::from(""), String::from("echo 4"), String::from("#this is a comment"), String::from("cat /tmp/output;")];
         assert_eq!(script_lines_to_string(&lines), String::from("echo 4;cat /tmp/output;"));
     }
 
+    #[test]
+    fn test_runtime_command_with_exit_status() {
+        //Plain command
+        assert_eq!(command_with_exit_status("ls -l"), String::from("ls -l\nexit $?\n"));
+        assert_eq!(command_with_exit_status("echo 4;cat /tmp/output;;\n"), String::from("echo 4;cat /tmp/output\nexit $?\n"));
+        assert_eq!(command_with_exit_status("false # comment"), String::from("false # comment\nexit $?\n"));
+        //Background and logical operators
+        assert_eq!(command_with_exit_status("sleep 1 &"), String::from("sleep 1 &\nexit $?\n"));
+        assert_eq!(command_with_exit_status("sleep 1 &;"), String::from("sleep 1 &\nexit $?\n"));
+        assert_eq!(command_with_exit_status("true && echo ok"), String::from("true && echo ok\nexit $?\n"));
+        //Incomplete commands
+        assert_eq!(command_with_exit_status("true &&"), String::from("(true &&\n)\nexit $?\n"));
+        assert_eq!(command_with_exit_status("false ||\n"), String::from("(false ||\n)\nexit $?\n"));
+        assert_eq!(command_with_exit_status("echo a |"), String::from("(echo a |\n)\nexit $?\n"));
+        //Exit status is captured by the shell
+        let exit_status = |command: &str| -> Option<i32> {
+            let mut child = std::process::Command::new("sh")
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .ok()?;
+            child.stdin.take()?.write_all(command_with_exit_status(command).as_bytes()).ok()?;
+            child.wait().ok()?.code()
+        };
+        assert_eq!(exit_status("sleep 1 &"), Some(0));
+        assert_eq!(exit_status("true && echo ok"), Some(0));
+        assert_eq!(exit_status("true && false"), Some(1));
+        assert_eq!(exit_status("exit 3;"), Some(3));
+        assert_eq!(exit_status("false # comment"), Some(1));
+        assert_eq!(exit_status("true &&"), Some(2));
+    }
+
     #[test]
     fn test_runtime_resolve_command() {
         let mut alias_cfg: HashMap<String, String> = HashMap::new();
@@ -412,39 +1369,185 @@ mod tests {
             language: String::from(""),
             shell_config: config::ShellConfig::default(),
             alias: alias_cfg,
+            transliteration: HashMap::new(),
+            input_config: config::InputConfig::default(),
             output_config: config::OutputConfig::default(),
-            prompt_config: config::PromptConfig::default()
+            prompt_config: config::PromptConfig::default(),
+            translit_config: config::TranslitConfig::default(),
+            history_config: config::HistoryConfig::default(),
+            config_file: None,
+            config_layers: Vec::new(),
+            trace_file: None
         };
         //Resolve command
         let mut argv: Vec<String> = vec![String::from("ll"), String::from("/tmp/")];
-        resolve_command(&mut argv, &cfg);
-        assert_eq!(*argv.get(0).unwrap(), String::from("ls -l"));
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, vec![String::from("ls"), String::from("-l"), String::from("/tmp/")]);
 
         //Unresolved command
         let mut argv: Vec<String> = vec![String::from("du"), String::from("-hs")];
-        resolve_command(&mut argv, &cfg);
+        assert!(!resolve_command(&mut argv, &cfg));
         assert_eq!(*argv.get(0).unwrap(), String::from("du"));
     }
 
+    #[test]
+    fn test_runtime_resolve_command_placeholders() {
+        let mut cfg: Config = Config::default();
+        cfg.alias.insert(String::from("gco"), String::from("git checkout $1"));
+        cfg.alias.insert(String::from("swap"), String::from("mv $2 $1"));
+        cfg.alias.insert(String::from("each"), String::from("for f in $@; do echo $f; done"));
+        cfg.alias.insert(String::from("br"), String::from("git log --branches=$1"));
+        let strings = |v: &[&str]| -> Vec<String> { v.iter().map(|s| String::from(*s)).collect() };
+        //Numbered placeholders; extra arguments are not appended
+        let mut argv: Vec<String> = strings(&["gco", "master", "extra"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["git", "checkout", "master"]));
+        let mut argv: Vec<String> = strings(&["swap", "a", "b"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["mv", "b", "a"]));
+        //Missing arguments are empty
+        let mut argv: Vec<String> = strings(&["gco"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["git", "checkout"]));
+        //Placeholder inside a word
+        let mut argv: Vec<String> = strings(&["br", "dev"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["git", "log", "--branches=dev"]));
+        //$@ splat (also when followed by other characters)
+        let mut argv: Vec<String> = strings(&["each", "a", "b"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["for", "f", "in", "a b;", "do", "echo", "$f;", "done"]));
+        cfg.alias.insert(String::from("e"), String::from("echo $@ !"));
+        let mut argv: Vec<String> = strings(&["e", "a", "b"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["echo", "a", "b", "!"]));
+        //Placeholders in the arguments are not replaced
+        cfg.alias.insert(String::from("say"), String::from("echo x$@"));
+        let mut argv: Vec<String> = strings(&["say", "'$1'", "b"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["echo", "x'$1' b"]));
+        let mut argv: Vec<String> = strings(&["swap", "$2", "$1"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["mv", "$1", "$2"]));
+        //$10 is the tenth argument
+        cfg.alias.insert(String::from("ten"), String::from("echo $10"));
+        let mut argv: Vec<String> = strings(&["ten", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["echo", "10"]));
+        let mut argv: Vec<String> = strings(&["ten", "1"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["echo"]));
+        //$0 and a lone $ are not placeholders
+        cfg.alias.insert(String::from("cost"), String::from("echo $0 $ $1"));
+        let mut argv: Vec<String> = strings(&["cost", "a"]);
+        assert!(resolve_command(&mut argv, &cfg));
+        assert_eq!(argv, strings(&["echo", "$0", "$", "a"]));
+    }
+
+    #[test]
+    fn test_runtime_split_args() {
+        let strings = |v: &[&str]| -> Vec<String> { v.iter().map(|s| String::from(*s)).collect() };
+        assert_eq!(split_args("ls  -l /tmp "), strings(&["ls", "-l", "/tmp"]));
+        assert_eq!(split_args("git checkout \"my branch\""), strings(&["git", "checkout", "\"my branch\""]));
+        assert_eq!(split_args("echo 'a \"b' c"), strings(&["echo", "'a \"b'", "c"]));
+        assert_eq!(split_args("echo a\\ b \"c\\\" d\""), strings(&["echo", "a\\ b", "\"c\\\" d\""]));
+        assert_eq!(split_args("echo 'a\\' b"), strings(&["echo", "'a\\'", "b"]));
+        assert_eq!(split_args("echo pre\"fix  x\"post"), strings(&["echo", "pre\"fix  x\"post"]));
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn test_runtime_unquote_arg() {
+        assert_eq!(unquote_arg("notes.txt"), String::from("notes.txt"));
+        assert_eq!(unquote_arg("\"my notes.txt\""), String::from("my notes.txt"));
+        assert_eq!(unquote_arg("my\\ notes.txt"), String::from("my notes.txt"));
+        assert_eq!(unquote_arg("'a \"b\\'"), String::from("a \"b\\"));
+        assert_eq!(unquote_arg("\"c\\\" d\\n\""), String::from("c\" d\\n"));
+        assert_eq!(unquote_arg("pre\"fix  x\"post"), String::from("prefix  xpost"));
+    }
+
+    #[test]
+    fn test_runtime_argv_to_latin() {
+        let mut alias_cfg: HashMap<String, String> = HashMap::new();
+        alias_cfg.insert(String::from("x"), String::from("foo (bar)"));
+        alias_cfg.insert(String::from("sad"), String::from("echo :-("));
+        let mut cfg: Config = Config::default();
+        cfg.alias = alias_cfg;
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+        //Alias value with parentheses is sent intact
+        let argv: Vec<String> = vec![String::from("x")];
+        assert_eq!(argv_to_latin(&argv, &cfg, &iop).unwrap(), String::from("foo (bar)\n"));
+        //Unbalanced parentheses in alias are not a parser error; arguments are still converted
+        let argv: Vec<String> = vec![String::from("sad"), String::from("кат")];
+        assert_eq!(argv_to_latin(&argv, &cfg, &iop).unwrap(), String::from("echo :-( cat\n"));
+        //Not aliased
+        let argv: Vec<String> = vec![String::from("ечо"), String::from("(кат)")];
+        assert_eq!(argv_to_latin(&argv, &cfg, &iop).unwrap(), String::from("echo (cat)\n"));
+        let argv: Vec<String> = vec![String::from("ечо"), String::from(":-(")];
+        assert!(argv_to_latin(&argv, &cfg, &iop).is_err());
+        //Arguments are converted before being put into the alias value
+        cfg.alias.insert(String::from("гцо"), String::from("git checkout $1"));
+        let argv: Vec<String> = vec![String::from("гцо"), String::from("мастер")];
+        assert_eq!(argv_to_latin(&argv, &cfg, &iop).unwrap(), String::from("git checkout master\n"));
+        //Quoted arguments are kept together
+        let argv: Vec<String> = split_args("гцо \"my branch\"");
+        assert_eq!(argv_to_latin(&argv, &cfg, &iop).unwrap(), String::from("git checkout \"my branch\"\n"));
+    }
+
     #[test]
     fn test_runtime_print() {
-        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
         //Out
         print_out(String::from("Hello"), true, &iop);
         print_out(String::from("Hello"), false, &iop);
         //Err
-        print_err(String::from("Hello"), true, &iop);
-        print_err(String::from("Hello"), false, &iop);
+        print_err(String::from("Hello"), true, Some(Colour::Red), &iop);
+        print_err(String::from("Hello"), false, None, &iop);
+    }
+
+    #[test]
+    fn test_runtime_paint_err() {
+        assert_eq!(paint_err(String::from("Hello"), Some(Colour::Red)), String::from("\x1b[31mHello\x1b[0m"));
+        assert_eq!(paint_err(String::from("Hello"), Some(Colour::Yellow)), String::from("\x1b[33mHello\x1b[0m"));
+        assert_eq!(paint_err(String::from("Hello"), None), String::from("Hello"));
     }
 
     #[test]
     fn test_runtime_console_fmt() {
-        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+        let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
         //Out
         assert_eq!(console_fmt(String::from("Hello"), true, &iop), String::from("Хелло"));
         assert_eq!(console_fmt(String::from("Hello"), false, &iop), String::from("Hello"));
     }
 
+    #[test]
+    fn test_runtime_pinned_prompt() {
+        //Entering the mode sets the scroll region
+        let enter: String = pinned_prompt_enter(24);
+        assert!(enter.starts_with("\x1b[1;23r"));
+        assert!(enter.ends_with("\x1b[23;1H"));
+        //Exiting the mode resets it
+        let exit: String = pinned_prompt_exit(24);
+        assert!(exit.starts_with("\x1b[r"));
+        assert!(exit.contains("\x1b[24;1H"));
+        //Prompt line is written on the last row
+        assert_eq!(pinned_prompt_line(24), String::from("\x1b[24;1H\x1b[2K"));
+    }
+
+    #[test]
+    fn test_runtime_rprompt_seq() {
+        //Right prompt flush right, then cursor back after the prompt line
+        assert_eq!(rprompt_seq("user$", "12:00", 20), Some(String::from("\x1b[16G12:00\x1b[7G")));
+        //Colors and broken prompt lines
+        assert_eq!(rprompt_seq("\x1b[31muser\x1b[0m\n❯", "\x1b[32mok\x1b[0m", 10), Some(String::from("\x1b[9G\x1b[32mok\x1b[0m\x1b[3G")));
+        //Fits exactly, keeping a blank column
+        assert!(rprompt_seq("user$", "12:00", 12).is_some());
+        //Terminal too narrow
+        assert!(rprompt_seq("user$", "12:00", 11).is_none());
+        //Empty right prompt
+        assert!(rprompt_seq("user$", "", 80).is_none());
+    }
+
     #[test]
     fn test_runtime_shellsignal() {
         assert_eq!(shellsignal_to_signal(3).unwrap(), UnixSignal::Sigint);
@@ -0,0 +1,437 @@
+//! ## Output
+//!
+//! `output` contains the buffer used by the runtime to stream the shell output line by line
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use crate::utils::ansi::strip_ansi;
+
+use std::time::Instant;
+
+/// Terminal tab width
+const TAB_WIDTH: usize = 8;
+
+/// ## OutputBuffer
+///
+/// OutputBuffer collects the output read from the shell and returns it only once lines are complete.
+/// The trailing partial line is kept across reads, so that each line is translated only once.
+/// If squelch is enabled, consecutive identical lines are collapsed into one.
+/// If a rate limit is set, the lines exceeding it are dropped.
+/// If collapse_progress is enabled, the lines redrawn with carriage return keep only their last update.
+/// If line_buffered is enabled, the partial line is held back until it's complete or the command terminates
+pub(super) struct OutputBuffer {
+    pending: String,
+    squelch_repeats: bool,
+    collapse_progress: bool,
+    line_buffered: bool,
+    repeated: Option<(String, usize)>, // Last line and its consecutive occurrences
+    limiter: Option<RateLimiter>,
+}
+
+/// ## RateLimiter
+///
+/// RateLimiter is a token bucket which limits the amount of lines per second.
+/// The bucket holds up to `rate` tokens and it's refilled at `rate` tokens per second; each line takes a token
+struct RateLimiter {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+    dropped: usize, // Lines dropped since the last time they've been reported
+}
+
+impl OutputBuffer {
+    /// ### new
+    ///
+    /// Instantiates a new empty OutputBuffer
+    pub(super) fn new(squelch_repeats: bool, max_lines_per_sec: Option<usize>, collapse_progress: bool, line_buffered: bool) -> OutputBuffer {
+        OutputBuffer {
+            pending: String::new(),
+            squelch_repeats,
+            collapse_progress,
+            line_buffered,
+            repeated: None,
+            limiter: max_lines_per_sec.map(RateLimiter::new),
+        }
+    }
+
+    /// ### push
+    ///
+    /// Push data read from the shell into the buffer.
+    /// Returns the lines (including the line break) which have been completed by data
+    pub(super) fn push(&mut self, data: &str) -> Vec<String> {
+        self.pending.push_str(data);
+        let lines: Vec<String> = match self.pending.rfind('\n') {
+            None => Vec::new(),
+            Some(last_lf) => {
+                let partial: String = self.pending.split_off(last_lf + 1);
+                let complete: String = std::mem::replace(&mut self.pending, partial);
+                complete.split_inclusive('\n').map(String::from).collect()
+            }
+        };
+        if !self.collapse_progress {
+            return lines;
+        }
+        //Drop the updates of the partial line which have already been overwritten
+        if let Some(idx) = last_carriage_return(self.pending.as_str()) {
+            self.pending.drain(..idx);
+        }
+        lines.into_iter().map(collapse_carriage_returns).collect()
+    }
+
+    /// ### flush
+    ///
+    /// Take the pending partial line out of the buffer
+    pub(super) fn flush(&mut self) -> Option<String> {
+        match self.pending.is_empty() {
+            true => None,
+            false => Some(std::mem::take(&mut self.pending)),
+        }
+    }
+
+    /// ### flush_partial
+    ///
+    /// Take the pending partial line out of the buffer while the command is still running (e.g. a program waiting for input).
+    /// If line buffered, the partial line is held back until it's complete: use `flush` once the command has terminated
+    pub(super) fn flush_partial(&mut self) -> Option<String> {
+        match self.line_buffered {
+            true => None,
+            false => self.flush(),
+        }
+    }
+
+    /// ### squelch
+    ///
+    /// Collapse consecutive identical lines; returns each line with the amount of its consecutive occurrences.
+    /// If squelch is enabled, the last line is held back, since it could still be repeated by the next lines;
+    /// it can be taken out with `take_repeated`
+    pub(super) fn squelch(&mut self, lines: Vec<String>) -> Vec<(String, usize)> {
+        if !self.squelch_repeats {
+            return lines.into_iter().map(|line| (line, 1)).collect();
+        }
+        let mut squelched: Vec<(String, usize)> = Vec::new();
+        for line in lines.into_iter() {
+            match self.repeated.as_mut() {
+                Some((last, count)) if *last == line => *count += 1,
+                _ => {
+                    if let Some(repeated) = self.repeated.replace((line, 1)) {
+                        squelched.push(repeated);
+                    }
+                }
+            }
+        }
+        squelched
+    }
+
+    /// ### take_repeated
+    ///
+    /// Take the line held back by squelch out of the buffer
+    pub(super) fn take_repeated(&mut self) -> Option<(String, usize)> {
+        self.repeated.take()
+    }
+
+    /// ### throttle
+    ///
+    /// Drop the lines exceeding the rate limit, if any.
+    /// Dropped lines are counted and can be taken with `take_dropped`
+    pub(super) fn throttle(&mut self, lines: Vec<String>) -> Vec<String> {
+        match self.limiter.as_mut() {
+            Some(limiter) => limiter.take(lines, Instant::now()),
+            None => lines,
+        }
+    }
+
+    /// ### take_dropped
+    ///
+    /// Take the amount of lines dropped by the rate limiter since the last call
+    pub(super) fn take_dropped(&mut self) -> Option<usize> {
+        match self.limiter.as_mut() {
+            Some(limiter) if limiter.dropped > 0 => Some(std::mem::take(&mut limiter.dropped)),
+            _ => None,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// ### new
+    ///
+    /// Instantiates a new RateLimiter with a full bucket
+    fn new(rate: usize) -> RateLimiter {
+        RateLimiter {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    /// ### take
+    ///
+    /// Refill the bucket with the tokens earned since the last refill, then return the lines
+    /// which fit in the bucket; the others are dropped
+    fn take(&mut self, mut lines: Vec<String>, now: Instant) -> Vec<String> {
+        let elapsed: f64 = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+        let allowed: usize = std::cmp::min(lines.len(), self.tokens as usize);
+        self.tokens -= allowed as f64;
+        self.dropped += lines.len() - allowed;
+        lines.truncate(allowed);
+        lines
+    }
+}
+
+/// ### collapse_carriage_returns
+///
+/// Keep only the last update of a line redrawn with carriage return (e.g. `\r 10%\r 20%\n` => `\r 20%\n`).
+/// The carriage return is kept, so that the update overwrites what has already been printed of the line.
+/// The carriage return of a CRLF line break is not an update
+fn collapse_carriage_returns(line: String) -> String {
+    match last_carriage_return(line.as_str()) {
+        Some(idx) => String::from(&line[idx..]),
+        None => line,
+    }
+}
+
+/// ### last_carriage_return
+///
+/// Returns the index of the last carriage return followed by some text, if any.
+/// Carriage returns at the end of the text (or before the line break) are not taken, since they're followed by nothing
+fn last_carriage_return(text: &str) -> Option<usize> {
+    let body: &str = text.trim_end_matches(['\r', '\n']);
+    match body.rfind('\r') {
+        Some(0) | None => None,
+        Some(idx) => Some(idx),
+    }
+}
+
+/// ### realign_tab_columns
+///
+/// Realign the tab-separated columns of consecutive lines, replacing tabs with spaces.
+/// Each column is padded to the tab stop following its widest cell, so that columns are aligned
+/// even if the cells have changed their length (e.g. after being transliterated)
+pub(super) fn realign_tab_columns(lines: Vec<String>) -> Vec<String> {
+    let mut realigned: Vec<String> = Vec::with_capacity(lines.len());
+    let mut table: Vec<String> = Vec::new();
+    for line in lines.into_iter() {
+        match line.contains('\t') {
+            true => table.push(line),
+            false => {
+                realigned.append(&mut realign_table(std::mem::take(&mut table)));
+                realigned.push(line);
+            }
+        }
+    }
+    realigned.append(&mut realign_table(table));
+    realigned
+}
+
+/// ### realign_table
+///
+/// Realign lines which all contain tab-separated columns
+fn realign_table(rows: Vec<String>) -> Vec<String> {
+    let rows: Vec<Vec<&str>> = rows.iter().map(|row| row.split('\t').collect()).collect();
+    //Calc width of each column (the last cell of each row doesn't affect the alignment); escape sequences aren't visible
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows.iter() {
+        for (idx, cell) in row.iter().take(row.len() - 1).enumerate() {
+            let width: usize = strip_ansi(cell).chars().count();
+            match widths.get_mut(idx) {
+                Some(w) if *w < width => *w = width,
+                Some(_) => {}
+                None => widths.push(width),
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let mut line: String = String::new();
+            for (idx, cell) in row.iter().enumerate() {
+                line.push_str(cell);
+                if idx + 1 < row.len() {
+                    //Pad to the next tab stop after the widest cell
+                    let stop: usize = (widths[idx] / TAB_WIDTH + 1) * TAB_WIDTH;
+                    line.push_str(" ".repeat(stop - strip_ansi(cell).chars().count()).as_str());
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_output_buffer_lines() {
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        assert!(buffer.flush().is_none());
+        //Complete lines are returned immediately
+        assert_eq!(buffer.push("foo\nbar\n"), vec![String::from("foo\n"), String::from("bar\n")]);
+        assert!(buffer.flush().is_none());
+    }
+
+    #[test]
+    fn test_runtime_output_buffer_partial_line() {
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        //Line split across two reads
+        assert_eq!(buffer.push("hel").len(), 0);
+        assert_eq!(buffer.push("lo\nwor"), vec![String::from("hello\n")]);
+        assert_eq!(buffer.push("ld\n"), vec![String::from("world\n")]);
+        //Partial line is returned on flush
+        assert_eq!(buffer.push("prompt: ").len(), 0);
+        assert_eq!(buffer.flush(), Some(String::from("prompt: ")));
+        assert!(buffer.flush().is_none());
+        //Partial line is returned while the command is running, if not line buffered
+        assert_eq!(buffer.push("prompt: ").len(), 0);
+        assert_eq!(buffer.flush_partial(), Some(String::from("prompt: ")));
+    }
+
+    #[test]
+    fn test_runtime_output_buffer_line_buffered() {
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, true);
+        //Output split in the middle of the lines; only whole lines are returned
+        assert_eq!(buffer.push("hel").len(), 0);
+        assert!(buffer.flush_partial().is_none());
+        assert_eq!(buffer.push("lo\nwor"), vec![String::from("hello\n")]);
+        assert!(buffer.flush_partial().is_none());
+        assert_eq!(buffer.push("ld\nfoo\nb"), vec![String::from("world\n"), String::from("foo\n")]);
+        assert!(buffer.flush_partial().is_none());
+        //Partial line is returned once the command has terminated
+        assert_eq!(buffer.flush(), Some(String::from("b")));
+        assert!(buffer.flush().is_none());
+    }
+
+    #[test]
+    fn test_runtime_output_buffer_collapse_progress() {
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, true, false);
+        //Progress updated across reads; only the last update is kept
+        assert!(buffer.push("\rDownloading   0%").is_empty());
+        assert!(buffer.push("\rDownloading  50%\rDownloading  75%").is_empty());
+        assert_eq!(buffer.push("\rDownloading 100%\n"), vec![String::from("\rDownloading 100%\n")]);
+        //Updates in the same read
+        assert_eq!(
+            buffer.push("a\rb\rc\nfoo\n"),
+            vec![String::from("\rc\n"), String::from("foo\n")]
+        );
+        //Partial line
+        assert!(buffer.push("10%\r20%\r30%").is_empty());
+        assert_eq!(buffer.flush(), Some(String::from("\r30%")));
+        //CRLF is not an update
+        assert_eq!(buffer.push("foo\r\n"), vec![String::from("foo\r\n")]);
+        //Carriage return split from the line feed
+        assert!(buffer.push("bar\r").is_empty());
+        assert_eq!(buffer.push("\n"), vec![String::from("bar\r\n")]);
+        //Trailing carriage return is kept, since the update is not known yet
+        assert!(buffer.push("50%\r").is_empty());
+        assert_eq!(buffer.flush(), Some(String::from("50%\r")));
+        //Disabled
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        assert_eq!(buffer.push("a\rb\rc\n"), vec![String::from("a\rb\rc\n")]);
+    }
+
+    #[test]
+    fn test_runtime_output_buffer_squelch() {
+        let mut buffer: OutputBuffer = OutputBuffer::new(true, None, false, false);
+        //100 identical lines are collapsed into one
+        let lines: Vec<String> = buffer.push("spinning...\n".repeat(100).as_str());
+        assert_eq!(lines.len(), 100);
+        assert_eq!(buffer.squelch(lines).len(), 0);
+        //A different line ends the repetition
+        let lines: Vec<String> = buffer.push("done\n");
+        assert_eq!(buffer.squelch(lines), vec![(String::from("spinning...\n"), 100)]);
+        assert_eq!(buffer.take_repeated(), Some((String::from("done\n"), 1)));
+        assert!(buffer.take_repeated().is_none());
+        //Squelch disabled
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        let lines: Vec<String> = buffer.push("foo\nfoo\n");
+        assert_eq!(buffer.squelch(lines), vec![(String::from("foo\n"), 1), (String::from("foo\n"), 1)]);
+        assert!(buffer.take_repeated().is_none());
+    }
+
+    #[test]
+    fn test_runtime_output_buffer_throttle() {
+        //No limit
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, None, false, false);
+        let lines: Vec<String> = buffer.push("y\n".repeat(10000).as_str());
+        assert_eq!(buffer.throttle(lines).len(), 10000);
+        assert!(buffer.take_dropped().is_none());
+        //Limited; burst is limited to the rate
+        let mut buffer: OutputBuffer = OutputBuffer::new(false, Some(100), false, false);
+        let lines: Vec<String> = buffer.push("y\n".repeat(10000).as_str());
+        assert_eq!(buffer.throttle(lines).len(), 100);
+        assert_eq!(buffer.take_dropped(), Some(9900));
+        assert!(buffer.take_dropped().is_none());
+    }
+
+    #[test]
+    fn test_runtime_output_rate_limiter() {
+        let mut limiter: RateLimiter = RateLimiter::new(100);
+        let start: Instant = limiter.last_refill;
+        //Feed 50 lines every 10ms for 2 seconds (5000 lines/s)
+        let mut printed: usize = 0;
+        let mut printed_per_sec: Vec<usize> = vec![0, 0];
+        for tick in 1..=200 {
+            let now: Instant = start + std::time::Duration::from_millis(tick * 10);
+            let allowed: usize = limiter.take(vec![String::from("y\n"); 50], now).len();
+            printed += allowed;
+            printed_per_sec[((tick - 1) / 100) as usize] += allowed;
+        }
+        //Initial burst (100) + 100 lines/s
+        assert!(printed <= 300);
+        assert!(printed >= 290);
+        //After the initial burst the rate stays under the limit
+        assert!(printed_per_sec[1] <= 100);
+        assert_eq!(limiter.dropped, 10000 - printed);
+    }
+
+    #[test]
+    fn test_runtime_output_realign_tab_columns() {
+        let lines: Vec<String> = vec![
+            String::from("name\tsize\tmode\n"),
+            String::from("кодексекс\t1\trw\n"),
+            String::from("total: 2\n"),
+            String::from("a\tb\n"),
+        ];
+        assert_eq!(
+            realign_tab_columns(lines),
+            vec![
+                String::from("name            size    mode\n"),
+                String::from("кодексекс       1       rw\n"),
+                String::from("total: 2\n"),
+                String::from("a       b\n"),
+            ]
+        );
+        //Colors don't affect the alignment
+        let lines: Vec<String> = vec![
+            String::from("\x1b[34mbin\x1b[0m\t4096\n"),
+            String::from("README.md\t512\n"),
+        ];
+        assert_eq!(
+            realign_tab_columns(lines),
+            vec![
+                String::from("\x1b[34mbin\x1b[0m             4096\n"),
+                String::from("README.md       512\n"),
+            ]
+        );
+    }
+}
@@ -24,36 +24,50 @@
 */
 
 use super::imiop::{self, Imiop};
+use super::shell_path;
+use super::suggest::Suggester;
 
 use crate::config::Config;
 use crate::shell::{Shell, ShellState};
 use crate::translator::ioprocessor::IOProcessor;
-use crate::translator::lang::Language;
-use crate::translator::new_translator;
+use crate::translator::new_gost_translator;
 use crate::utils::console::InputEvent;
 
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
 /// ## RuntimeProps
 ///
 /// Runtime Props is a wrapper for all the properties used by the Runtime module
 pub(super) struct RuntimeProps {
     pub config: Config,
-    language: Language,
+    processor: IOProcessor,
+    suggester: Rc<RefCell<Suggester>>,
+    history_file: Option<PathBuf>,
+    config_reloaded: bool,
     last_state: ShellState,
     state_changed: bool,
+    first_prompt_shown: bool,
     imiop: Box<dyn Imiop>,
 }
 
 impl RuntimeProps {
     /// ### new
     ///
-    /// Instantiates a new RuntimeProps
-    pub(super) fn new(interactive: bool, config: Config, language: Language) -> RuntimeProps {
+    /// Instantiates a new RuntimeProps; the processor is cloned for each IMIOP, while the suggester is shared
+    pub(super) fn new(interactive: bool, config: Config, processor: IOProcessor, history_file: Option<PathBuf>) -> RuntimeProps {
+        let suggester: Rc<RefCell<Suggester>> = Rc::new(RefCell::new(Suggester::new(shell_path(&config.shell_config.path))));
         RuntimeProps {
             config: config.clone(),
-            language: language,
+            imiop: RuntimeProps::init_imiop(interactive, &config, &processor, &suggester, &history_file),
+            processor,
+            suggester,
+            history_file,
+            config_reloaded: false,
             last_state: ShellState::Unknown,
             state_changed: true,
-            imiop: RuntimeProps::init_imiop(interactive, &config, language),
+            first_prompt_shown: false,
         }
     }
 
@@ -87,58 +101,110 @@ impl RuntimeProps {
         self.state_changed = false;
     }
 
+    /// ### get_promptline
+    ///
+    /// Get the prompt line to print; the first prompt of the session uses the first prompt line
+    pub(super) fn get_promptline(&mut self, shell: &mut Shell, processor: &IOProcessor) -> String {
+        match self.first_prompt_shown {
+            true => shell.get_promptline(processor),
+            false => {
+                self.first_prompt_shown = true;
+                shell.get_first_promptline(processor)
+            }
+        }
+    }
+
     /// ### handle_input_event
     ///
     /// Handle input event received from stdin
     pub(super) fn handle_input_event(&mut self, ev: InputEvent, shell: &mut Shell) {
         // Check if IMIOP has to be changed
-        self.switch_imiop();
+        self.switch_imiop(shell.get_state());
         // Call handle input event for current IMIOP
         self.imiop.handle_input_event(ev, shell);
+        // Apply reloaded configuration
+        if let Some(config) = self.imiop.take_reloaded_config() {
+            // The reloaded configuration has been validated, but keep the current processor if it can't be built
+            let language = self.processor.language;
+            if let Ok(mut processor) = IOProcessor::new(language, new_gost_translator(language, config.translit_config.gost_system), config.transliteration.clone()) {
+                processor.set_max_depth(config.translit_config.max_expression_depth);
+                processor.set_translate_args(config.translit_config.translate_args);
+                self.processor = processor;
+            }
+            //PATH may have changed
+            *self.suggester.borrow_mut() = Suggester::new(shell_path(&config.shell_config.path));
+            self.config = config;
+            self.config_reloaded = true;
+        }
+    }
+
+    /// ### ask_run
+    ///
+    /// Ask the user whether to run the provided command; the answer is handled by the current IMIOP
+    pub(super) fn ask_run(&mut self, command: String) {
+        self.switch_imiop(self.last_state);
+        self.imiop.ask_run(command);
+    }
+
+    /// ### recall
+    ///
+    /// Put the provided command in the input buffer of the current IMIOP
+    pub(super) fn recall(&mut self, command: String) {
+        self.switch_imiop(self.last_state);
+        self.imiop.recall(command);
+    }
+
+    /// ### take_config_reloaded
+    ///
+    /// Returns whether the configuration has been reloaded since the last call
+    pub(super) fn take_config_reloaded(&mut self) -> bool {
+        let reloaded: bool = self.config_reloaded;
+        self.config_reloaded = false;
+        reloaded
+    }
+
+    /// ### get_processor
+    ///
+    /// Get the IOProcessor built from the current configuration
+    pub(super) fn get_processor(&self) -> &IOProcessor {
+        &self.processor
+    }
+
+    /// ### get_suggester
+    ///
+    /// Get the suggester shared with the IMIOPs
+    pub(super) fn get_suggester(&self) -> Rc<RefCell<Suggester>> {
+        self.suggester.clone()
+    }
+
+    /// ### get_exit_code
+    ///
+    /// Returns the exit code requested by the user, if any
+    pub(super) fn get_exit_code(&self) -> Option<u8> {
+        self.imiop.exit_code()
     }
 
     /// ### init_imiop
     ///
     /// Instantiate the first IMIOP at first launch of props
-
-    fn init_imiop(interactive: bool, config: &Config, language: Language) -> Box<dyn Imiop> {
-        match interactive {
-            true => Box::new(imiop::shiop::ShIop::new(
-                config.clone(),
-                IOProcessor::new(language, new_translator(language)),
-            )),
-            false => Box::new(imiop::subprociop::SubProcIop::new(
-                config.clone(),
-                IOProcessor::new(language, new_translator(language)),
-            )),
-        }
+    fn init_imiop(interactive: bool, config: &Config, processor: &IOProcessor, suggester: &Rc<RefCell<Suggester>>, history_file: &Option<PathBuf>) -> Box<dyn Imiop> {
+        let state: ShellState = match interactive {
+            true => ShellState::Shell,
+            false => ShellState::SubprocessRunning,
+        };
+        imiop::imiop_for_state(state, config, processor, suggester, history_file)
     }
 
     /// ### switch_imiop
     ///
-    /// Change current imiop based on states
-    fn switch_imiop(&mut self) {
-        // Change if last state changed
-        if self.get_state_changed() {
-            // TODO: text editor
-            // Check last_state
-            self.imiop = match self.get_last_state() {
-                ShellState::Shell => Box::new(imiop::shiop::ShIop::new(
-                    self.config.clone(),
-                    IOProcessor::new(self.language, new_translator(self.language)),
-                )),
-                ShellState::SubprocessRunning => Box::new(imiop::subprociop::SubProcIop::new(
-                    self.config.clone(),
-                    IOProcessor::new(self.language, new_translator(self.language)),
-                )),
-                _ => Box::new(imiop::shiop::ShIop::new(
-                    self.config.clone(),
-                    IOProcessor::new(self.language, new_translator(self.language)),
-                )),
-            };
-            // Reset state changed
-            self.report_state_changed_notified();
+    /// Change current imiop if it doesn't handle the provided state; the current one is kept while the state persists
+    fn switch_imiop(&mut self, state: ShellState) {
+        // State is not known yet; keep the current IMIOP
+        if state == ShellState::Unknown || self.imiop.state() == state {
+            return;
         }
+        // TODO: text editor
+        self.imiop = imiop::imiop_for_state(state, &self.config, &self.processor, &self.suggester, &self.history_file);
     }
 }
 
@@ -147,16 +213,17 @@ mod tests {
     use super::*;
 
     use crate::config::Config;
-    use crate::translator::lang::Language;
+    use crate::translator::lang::{GostSystem, Language};
 
+    use std::collections::HashMap;
     use std::thread::sleep;
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_runtimeprops_new() {
         let props: RuntimeProps = new_runtime_props(true);
         assert!(props.config.get_alias(&String::from("ll")).is_none());
-        assert_eq!(props.language, Language::Russian);
+        assert_eq!(props.processor.language, Language::Russian);
         assert_eq!(props.last_state, ShellState::Unknown);
         assert_eq!(props.state_changed, true);
     }
@@ -173,25 +240,42 @@ mod tests {
         assert_eq!(props.get_state_changed(), true);
     }
 
+    #[test]
+    fn test_runtimeprops_get_promptline() {
+        let mut props: RuntimeProps = new_runtime_props(true);
+        props.config.prompt_config.prompt_line = String::from("next$");
+        props.config.prompt_config.first_prompt_line = Some(String::from("first$"));
+        let processor: IOProcessor = props.processor.clone();
+        let mut shell: Shell = Shell::start(String::from("sh"), vec![], &[], &props.config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(!props.first_prompt_shown);
+        assert_eq!(props.get_promptline(&mut shell, &processor), String::from("first$"));
+        assert_eq!(props.get_promptline(&mut shell, &processor), String::from("next$"));
+        assert_eq!(props.get_promptline(&mut shell, &processor), String::from("next$"));
+        let _ = shell.stop();
+    }
+
     #[test]
     fn test_runtimeprops_switch_imiop() {
         let mut props: RuntimeProps = new_runtime_props(true);
-        // State hasn't changed
-        props.state_changed = false;
-        props.last_state = ShellState::Shell;
-        props.switch_imiop();
+        assert_eq!(props.imiop.state(), ShellState::Shell);
+        // State hasn't changed; the current IMIOP is kept
+        props.switch_imiop(ShellState::Shell);
+        assert_eq!(props.imiop.state(), ShellState::Shell);
         // Change state
-        props.state_changed = true;
-        props.last_state = ShellState::SubprocessRunning;
-        props.switch_imiop();
+        props.switch_imiop(ShellState::SubprocessRunning);
+        assert_eq!(props.imiop.state(), ShellState::SubprocessRunning);
         // Change back to Idle
-        props.state_changed = true;
-        props.last_state = ShellState::Shell;
-        props.switch_imiop();
-        // Change to unhandled state
-        props.state_changed = true;
-        props.last_state = ShellState::Unknown;
-        props.switch_imiop();
+        props.switch_imiop(ShellState::Shell);
+        assert_eq!(props.imiop.state(), ShellState::Shell);
+        // Unknown state keeps the current IMIOP
+        props.switch_imiop(ShellState::Unknown);
+        assert_eq!(props.imiop.state(), ShellState::Shell);
+        // Terminated
+        props.switch_imiop(ShellState::Terminated);
+        assert_eq!(props.imiop.state(), ShellState::Terminated);
+        // Non interactive props start with the subprocess IMIOP
+        assert_eq!(new_runtime_props(false).imiop.state(), ShellState::SubprocessRunning);
     }
 
     #[test]
@@ -201,6 +285,7 @@ mod tests {
         let mut shell: Shell = Shell::start(
             String::from("sh"),
             Vec::new(),
+            &[],
             &config.prompt_config,
         )
         .unwrap();
@@ -221,6 +306,7 @@ mod tests {
         let mut shell: Shell = Shell::start(
             String::from("sh"),
             Vec::new(),
+            &[],
             &config.prompt_config,
         )
         .unwrap();
@@ -234,7 +320,57 @@ mod tests {
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
     }
 
+    #[test]
+    fn test_runtimeprops_translit_bypass_after_command() {
+        let mut props: RuntimeProps = new_runtime_props(true);
+        let config: Config = Config::default();
+        let mut shell: Shell = Shell::start(String::from("sh"), Vec::new(), &[], &config.prompt_config).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Suspend transliteration (CTRL+T), then run a command and type while it's running
+        props.handle_input_event(InputEvent::Ctrl(20), &mut shell);
+        type_line(&mut props, &mut shell, "sleep 1");
+        assert_eq!(shell.get_state(), ShellState::SubprocessRunning);
+        props.handle_input_event(InputEvent::Key(String::from("a")), &mut shell);
+        assert_eq!(props.imiop.state(), ShellState::SubprocessRunning);
+        wait_for_shell(&mut shell);
+        //Back to the prompt: transliteration is still suspended, so 'ечо' is not 'echo'
+        type_line(&mut props, &mut shell, "ечо ok");
+        assert_eq!(props.imiop.state(), ShellState::Shell);
+        wait_for_shell(&mut shell);
+        shell.refresh_env();
+        assert_eq!(shell.get_exit_status(), 127);
+        assert!(shell.get_translit_bypass());
+        //Resume transliteration
+        props.handle_input_event(InputEvent::Ctrl(20), &mut shell);
+        assert!(!shell.get_translit_bypass());
+        type_line(&mut props, &mut shell, "ечо ok");
+        wait_for_shell(&mut shell);
+        shell.refresh_env();
+        assert_eq!(shell.get_exit_status(), 0);
+        //Stop shell
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let _ = shell.stop();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+    }
+
+    fn type_line(props: &mut RuntimeProps, shell: &mut Shell, line: &str) {
+        for ch in line.chars() {
+            props.handle_input_event(InputEvent::Key(ch.to_string()), shell);
+        }
+        props.handle_input_event(InputEvent::Enter, shell);
+    }
+
+    fn wait_for_shell(shell: &mut Shell) {
+        let t_start: Instant = Instant::now();
+        sleep(Duration::from_millis(100));
+        while shell.get_state() != ShellState::Shell && t_start.elapsed() < Duration::from_secs(5) {
+            let _ = shell.read();
+            sleep(Duration::from_millis(50));
+        }
+    }
+
     fn new_runtime_props(interactive: bool) -> RuntimeProps {
-        RuntimeProps::new(interactive, Config::default(), Language::Russian)
+        let processor: IOProcessor = IOProcessor::new(Language::Russian, new_gost_translator(Language::Russian, GostSystem::B), HashMap::new()).unwrap();
+        RuntimeProps::new(interactive, Config::default(), processor, None)
     }
 }
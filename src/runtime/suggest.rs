@@ -0,0 +1,161 @@
+//! ## Suggest
+//!
+//! `suggest` provides command suggestions when a command is not found
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use crate::utils::distance::levenshtein;
+
+use std::os::unix::fs::PermissionsExt;
+
+/// Maximum edit distance for a command to be suggested
+const MAX_DISTANCE: usize = 2;
+
+/// ## Suggester
+///
+/// Suggester looks for the executable in PATH closest to a command which has not been found.
+/// Executables are collected from PATH the first time a suggestion is requested
+pub(super) struct Suggester {
+    path: String,
+    executables: Option<Vec<String>>,
+}
+
+impl Suggester {
+    /// ### new
+    ///
+    /// Instantiates a new Suggester, looking for executables in the provided PATH
+    pub(super) fn new(path: String) -> Suggester {
+        Suggester { path, executables: None }
+    }
+
+    /// ### suggest
+    ///
+    /// Returns the executable in PATH closest to command (already transliterated to latin)
+    pub(super) fn suggest(&mut self, command: &str) -> Option<String> {
+        closest_command(command, self.executables())
+    }
+
+    /// ### is_executable
+    ///
+    /// Returns whether command is an executable in PATH
+    pub(super) fn is_executable(&mut self, command: &str) -> bool {
+        self.executables().iter().any(|executable| executable == command)
+    }
+
+    /// ### executables
+    ///
+    /// Returns the executables in PATH, collecting them the first time
+    fn executables(&mut self) -> &Vec<String> {
+        if self.executables.is_none() {
+            self.executables = Some(list_executables(self.path.as_str()));
+        }
+        self.executables.get_or_insert_with(Vec::new)
+    }
+}
+
+/// ### list_executables
+///
+/// List the executables in the directories of PATH
+fn list_executables(path: &str) -> Vec<String> {
+    let mut executables: Vec<String> = Vec::new();
+    for dir in path.split(':').filter(|d| !d.is_empty()) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            //Follow symlinks (e.g. /usr/bin/sh)
+            let is_executable: bool = match std::fs::metadata(entry.path()) {
+                Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+                Err(_) => false,
+            };
+            if is_executable {
+                if let Some(name) = entry.file_name().to_str() {
+                    executables.push(String::from(name));
+                }
+            }
+        }
+    }
+    executables.sort();
+    executables.dedup();
+    executables
+}
+
+/// ### closest_command
+///
+/// Returns the candidate closest to command, if its distance is within `MAX_DISTANCE`
+fn closest_command(command: &str, candidates: &[String]) -> Option<String> {
+    let mut closest: Option<(usize, &String)> = None;
+    for candidate in candidates.iter() {
+        let distance: usize = levenshtein(command, candidate.as_str());
+        if distance == 0 || distance > MAX_DISTANCE {
+            continue;
+        }
+        match closest {
+            Some((min, _)) if min <= distance => {}
+            _ => closest = Some((distance, candidate)),
+        }
+    }
+    closest.map(|(_, candidate)| candidate.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn test_runtime_suggest_closest_command() {
+        let candidates: Vec<String> = vec![String::from("grep"), String::from("less"), String::from("ls"), String::from("python3")];
+        assert_eq!(closest_command("gerp", &candidates), Some(String::from("grep")));
+        assert_eq!(closest_command("lsx", &candidates), Some(String::from("ls")));
+        assert_eq!(closest_command("pyton3", &candidates), Some(String::from("python3")));
+        //Too far
+        assert_eq!(closest_command("foobar", &candidates), None);
+        //Exact match is not a suggestion
+        assert_eq!(closest_command("ls", &[String::from("ls")]), None);
+    }
+
+    #[test]
+    fn test_runtime_suggest_list_executables() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        //Executable
+        let bin: std::path::PathBuf = tmpdir.path().join("pyc_suggest");
+        let mut file: std::fs::File = std::fs::File::create(bin.as_path()).unwrap();
+        assert!(writeln!(file, "#!/bin/sh").is_ok());
+        assert!(std::fs::set_permissions(bin.as_path(), std::fs::Permissions::from_mode(0o755)).is_ok());
+        //Not executable
+        assert!(std::fs::File::create(tmpdir.path().join("pyc_readme")).is_ok());
+        let path: String = format!("/this/does/not/exist:{}", tmpdir.path().display());
+        let executables: Vec<String> = list_executables(path.as_str());
+        assert_eq!(executables, vec![String::from("pyc_suggest")]);
+        //Near miss
+        assert_eq!(closest_command("pyc_suggets", &executables), Some(String::from("pyc_suggest")));
+        //Lookup
+        let mut suggester: Suggester = Suggester { path, executables: Some(executables) };
+        assert!(suggester.is_executable("pyc_suggest"));
+        assert!(!suggester.is_executable("pyc_readme"));
+        assert_eq!(suggester.suggest("pyc_suggets"), Some(String::from("pyc_suggest")));
+    }
+}
@@ -23,10 +23,28 @@
 *
 */
 
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//First line of JSON history files; files without it are plain history
+const JSON_HISTORY_MARKER: &str = "#pyc-history json v1";
 
 pub struct ShellHistory {
-    history: VecDeque<String>
+    history: VecDeque<HistoryEntry>,
+    size: usize,        //Maximum amount of entries
+    ignore_dups: bool   //Collapse consecutive duplicates
+}
+
+/// ### HistoryEntry
+///
+/// HistoryEntry is a command in the history, with its metadata (when known)
+#[derive(Clone, PartialEq, std::fmt::Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub cmd: String,
+    pub ts: Option<u64>,       //Unix timestamp (seconds) of when the command was entered
+    pub exit: Option<u8>,      //Exit code of the command
+    pub duration: Option<u64>  //Duration of the command in milliseconds
 }
 
 impl ShellHistory {
@@ -36,17 +54,34 @@ impl ShellHistory {
     /// Instantiate a new ShellHistory
     pub fn new() -> ShellHistory {
         ShellHistory {
-            history: VecDeque::with_capacity(2048)
+            history: VecDeque::with_capacity(2048),
+            size: 2048,
+            ignore_dups: true
         }
     }
 
+    /// ### set_size
+    /// 
+    /// Set the maximum amount of entries of the history; the oldest entries exceeding it are removed
+    pub fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.history.truncate(size);
+    }
+
+    /// ### set_ignore_dups
+    /// 
+    /// Set whether consecutive duplicates are collapsed into the newest one
+    pub fn set_ignore_dups(&mut self, ignore_dups: bool) {
+        self.ignore_dups = ignore_dups;
+    }
+
     /// ### at
     /// 
     /// Get the command at a certain index of the history
     /// None is returned in case index is out of range
     pub fn at(&self, index: usize) -> Option<String> {
         match self.history.get(index) {
-            Some(s) => Some(s.clone()),
+            Some(entry) => Some(entry.cmd.clone()),
             None => None
         }
     }
@@ -60,15 +95,60 @@ impl ShellHistory {
 
     /// ### dump
     /// 
-    /// Dump history
+    /// Dump history, from the oldest to the newest entry
     pub fn dump(&mut self) -> Vec<String> {
-        let mut history: Vec<String> = Vec::with_capacity(self.history.len());
-        for entry in self.history.iter().rev() {
-            history.push(entry.clone());
+        self.entries_to_dump().into_iter().map(|entry| entry.cmd.clone()).collect()
+    }
+
+    /// ### dump_json
+    /// 
+    /// Dump history as JSON lines, one object per entry, with its metadata.
+    /// The first line is the JSON history marker
+    pub fn dump_json(&self) -> Vec<String> {
+        let mut history: Vec<String> = Vec::with_capacity(self.history.len() + 1);
+        history.push(String::from(JSON_HISTORY_MARKER));
+        for entry in self.entries_to_dump() {
+            history.push(entry.to_json());
         }
         history
     }
 
+    /// ### entries_to_dump
+    /// 
+    /// Returns the most recent entries within the history size, from the oldest to the newest.
+    /// If duplicates are ignored, consecutive duplicates are collapsed
+    fn entries_to_dump(&self) -> Vec<&HistoryEntry> {
+        let mut entries: Vec<&HistoryEntry> = Vec::with_capacity(self.history.len());
+        for entry in self.history.iter() {
+            if entries.len() >= self.size {
+                break;
+            }
+            if self.ignore_dups && entries.last().map(|last| last.cmd == entry.cmd).unwrap_or(false) {
+                continue;
+            }
+            entries.push(entry);
+        }
+        entries.reverse();
+        entries
+    }
+
+    /// ### dump_newest
+    /// 
+    /// Dump the newest entry, to be appended to a history file whose first line is `first_line` (None if the file is empty).
+    /// The entry is dumped in the format of the file; an empty file is written as JSON (starting with the marker) if json is true
+    pub fn dump_newest(&self, first_line: Option<&str>, json: bool) -> Vec<String> {
+        let entry: &HistoryEntry = match self.history.front() {
+            Some(entry) => entry,
+            None => return Vec::new()
+        };
+        match first_line {
+            Some(line) if line.trim_end() == JSON_HISTORY_MARKER => vec![entry.to_json()],
+            Some(_) => vec![entry.cmd.clone()],
+            None if json => vec![String::from(JSON_HISTORY_MARKER), entry.to_json()],
+            None => vec![entry.cmd.clone()]
+        }
+    }
+
     /// ### len
     /// 
     /// Returns history len
@@ -78,46 +158,112 @@ impl ShellHistory {
 
     /// ### load
     /// 
-    /// Load history
-    /// NOTE: the maximum history size will still be the size provided at constructor
+    /// Load history. If the first line is the JSON history marker (as written by `dump_json`), lines are JSON entries;
+    /// otherwise they are plain commands. Invalid JSON entries are skipped
+    /// NOTE: the maximum history size is still the one set with `set_size`
     pub fn load(&mut self, lines: Vec<String>) {
         //Clear current history
         self.clear();
+        let json: bool = lines.first().map(|line| line.trim_end() == JSON_HISTORY_MARKER).unwrap_or(false);
         //Parse file
-        for line in lines.iter() {
-            self.push(line.clone());
+        for line in lines.iter().skip(json as usize) {
+            let entry: HistoryEntry = match json {
+                true => match HistoryEntry::from_json(line.as_str()) {
+                    Some(entry) => entry,
+                    None => continue
+                },
+                false => HistoryEntry::new(line.clone(), None)
+            };
+            let _ = self.push_entry(entry);
         }
     }
 
     /// ### push
     /// 
     /// Push a new entry to the history.
-    /// The entry is stored at the front of the history. The first the newest
-    pub fn push(&mut self, mut line: String) {
+    /// The entry is stored at the front of the history. The first the newest.
+    /// Returns whether a new entry has been added (i.e. the line has not been ignored or collapsed with the newest entry)
+    pub fn push(&mut self, line: String) -> bool {
+        let ts: Option<u64> = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(ts) => Some(ts.as_secs()),
+            Err(_) => None
+        };
+        self.push_entry(HistoryEntry::new(line, ts))
+    }
+
+    /// ### set_last_result
+    /// 
+    /// Set exit code and duration of the newest entry, if they haven't been set yet
+    pub fn set_last_result(&mut self, exit: u8, duration: Duration) {
+        if let Some(entry) = self.history.front_mut() {
+            if entry.exit.is_none() {
+                entry.exit = Some(exit);
+                entry.duration = Some(duration.as_millis() as u64);
+            }
+        }
+    }
+
+    /// ### push_entry
+    /// 
+    /// Push a new entry to the front of the history. Returns whether a new entry has been added
+    fn push_entry(&mut self, mut entry: HistoryEntry) -> bool {
         //@! Remove newline
-        while line.ends_with("\n") {
-            line.pop();
+        while entry.cmd.ends_with("\n") {
+            entry.cmd.pop();
         }
         //Ignore empty lines
-        if line.is_empty() {
-            return;
+        if entry.cmd.is_empty() {
+            return false;
         }
-        //Duplicates not allowed
-        if let Some(last_line) = self.at(0) {
-            if last_line == line {
-                return
+        if self.size == 0 {
+            return false;
+        }
+        //Consecutive duplicates are collapsed if ignored; the newest entry takes the metadata of the new one
+        if let Some(last_entry) = self.history.front_mut() {
+            if self.ignore_dups && last_entry.cmd == entry.cmd {
+                *last_entry = entry;
+                return false
             }
         }
         //Check if history overflows the size
-        let size: usize = (self.history.capacity() + 1) / 2;
-        if self.history.len() + 1 > size {
+        if self.history.len() + 1 > self.size {
             self.history.pop_back();
         }
-        self.history.push_front(line);
+        self.history.push_front(entry);
+        true
     }
 
 }
 
+impl HistoryEntry {
+
+    /// ### new
+    /// 
+    /// Instantiate a new HistoryEntry, whose result is not known yet
+    pub fn new(cmd: String, ts: Option<u64>) -> HistoryEntry {
+        HistoryEntry {
+            cmd,
+            ts,
+            exit: None,
+            duration: None
+        }
+    }
+
+    /// ### to_json
+    /// 
+    /// Serialize entry as a JSON object; unknown metadata is null
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// ### from_json
+    /// 
+    /// Parse entry from a JSON object. Returns None if line is not a valid JSON entry
+    pub fn from_json(line: &str) -> Option<HistoryEntry> {
+        serde_json::from_str(line).ok()
+    }
+}
+
 //@! Test module
 
 #[cfg(test)]
@@ -174,4 +320,135 @@ mod tests {
         assert_eq!(*dump.get(1).unwrap(), String::from("cd /tmp/"));
     }
 
+    #[test]
+    fn test_shell_history_json() {
+        let mut history: ShellHistory = ShellHistory::new();
+        history.push(String::from("ls -l"));
+        history.set_last_result(0, Duration::from_millis(12));
+        history.push(String::from("echo \"привет\""));
+        history.set_last_result(1, Duration::from_millis(1500));
+        //Result is set only once
+        history.set_last_result(2, Duration::from_millis(0));
+        history.push(String::from("cat"));
+        let dump: Vec<String> = history.dump_json();
+        assert_eq!(dump.len(), 4);
+        assert_eq!(*dump.first().unwrap(), String::from(JSON_HISTORY_MARKER));
+        let ts: u64 = history.history.front().unwrap().ts.unwrap();
+        assert!(ts > 0);
+        assert_eq!(*dump.get(1).unwrap(), format!("{{\"cmd\":\"ls -l\",\"ts\":{},\"exit\":0,\"duration\":12}}", history.history.get(2).unwrap().ts.unwrap()));
+        assert!(dump.get(2).unwrap().starts_with("{\"cmd\":\"echo \\\"привет\\\"\",\"ts\":"));
+        assert!(dump.get(3).unwrap().ends_with(",\"exit\":null,\"duration\":null}"));
+        //Round trip
+        let mut loaded: ShellHistory = ShellHistory::new();
+        loaded.load(dump.clone());
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.history, history.history);
+        assert_eq!(loaded.at(1).unwrap(), String::from("echo \"привет\""));
+        assert_eq!(loaded.history.get(1).unwrap().exit, Some(1));
+        assert_eq!(loaded.history.get(1).unwrap().duration, Some(1500));
+        assert_eq!(loaded.dump_json(), dump);
+    }
+
+    #[test]
+    fn test_shell_history_load_plain() {
+        let mut history: ShellHistory = ShellHistory::new();
+        //Legacy plain history; lines looking like JSON are plain commands too
+        let json_cmd: String = String::from("{\"cmd\": \"pwd\", \"ts\": 1600000000, \"exit\": 0, \"duration\": 3}");
+        history.load(vec![
+            String::from("ls"),
+            String::from("{ echo foo; }"),
+            json_cmd.clone(),
+        ]);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.at(2).unwrap(), String::from("ls"));
+        assert_eq!(history.history.get(2).unwrap(), &HistoryEntry::new(String::from("ls"), None));
+        assert_eq!(history.at(1).unwrap(), String::from("{ echo foo; }"));
+        assert_eq!(history.at(0).unwrap(), json_cmd);
+        //Plain dump
+        assert_eq!(history.dump(), vec![String::from("ls"), String::from("{ echo foo; }"), json_cmd]);
+        //Missing metadata is null
+        assert_eq!(history.dump_json().get(1).unwrap(), &String::from("{\"cmd\":\"ls\",\"ts\":null,\"exit\":null,\"duration\":null}"));
+    }
+
+    #[test]
+    fn test_shell_history_load_json() {
+        let mut history: ShellHistory = ShellHistory::new();
+        history.load(vec![
+            String::from(JSON_HISTORY_MARKER),
+            String::from("{\"cmd\": \"pwd\", \"ts\": 1600000000, \"exit\": 0, \"duration\": 3}"),
+            String::from("{\"cmd\": \"ls\"}"),
+            //Invalid entries are skipped
+            String::from("{\"cmd\": \"cat\", \"exit\": 256}"),
+            String::from("ls -l"),
+        ]);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.at(1).unwrap(), String::from("pwd"));
+        assert_eq!(history.history.get(1).unwrap().ts, Some(1600000000));
+        assert_eq!(history.history.get(1).unwrap().duration, Some(3));
+        assert_eq!(history.history.front().unwrap(), &HistoryEntry::new(String::from("ls"), None));
+    }
+
+    #[test]
+    fn test_shell_history_dump_newest() {
+        let mut history: ShellHistory = ShellHistory::new();
+        assert!(history.dump_newest(None, false).is_empty());
+        history.push(String::from("ls"));
+        history.push(String::from("pwd"));
+        //Empty file
+        assert_eq!(history.dump_newest(None, false), vec![String::from("pwd")]);
+        let lines: Vec<String> = history.dump_newest(None, true);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], String::from(JSON_HISTORY_MARKER));
+        assert_eq!(HistoryEntry::from_json(lines[1].as_str()).unwrap().cmd, String::from("pwd"));
+        //The format of the file is kept
+        assert_eq!(history.dump_newest(Some("ls"), true), vec![String::from("pwd")]);
+        let lines: Vec<String> = history.dump_newest(Some(JSON_HISTORY_MARKER), false);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(HistoryEntry::from_json(lines[0].as_str()).unwrap().cmd, String::from("pwd"));
+    }
+
+    #[test]
+    fn test_shell_history_push_duplicate() {
+        let mut history: ShellHistory = ShellHistory::new();
+        assert!(history.push(String::from("make")));
+        history.set_last_result(2, Duration::from_millis(300));
+        //Duplicate replaces the result of the previous run
+        assert!(!history.push(String::from("make")));
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.history.front().unwrap().exit, None);
+        history.set_last_result(0, Duration::from_millis(100));
+        assert_eq!(history.history.front().unwrap().exit, Some(0));
+        assert_eq!(history.history.front().unwrap().duration, Some(100));
+    }
+
+    #[test]
+    fn test_shell_history_size_and_dups() {
+        let mut history: ShellHistory = ShellHistory::new();
+        history.set_size(3);
+        //Consecutive duplicates are kept if not ignored
+        history.set_ignore_dups(false);
+        for cmd in ["ls", "ls", "pwd", "pwd"].iter() {
+            history.push(String::from(*cmd));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.dump(), vec![String::from("ls"), String::from("pwd"), String::from("pwd")]);
+        //Duplicates are collapsed in dump once ignored
+        history.set_ignore_dups(true);
+        assert_eq!(history.dump(), vec![String::from("ls"), String::from("pwd")]);
+        assert_eq!(history.dump_json().len(), 3);
+        //Only the most recent entries are kept
+        for cmd in ["cd /tmp", "cd /tmp", "make", "make", "ls"].iter() {
+            history.push(String::from(*cmd));
+        }
+        assert_eq!(history.dump(), vec![String::from("cd /tmp"), String::from("make"), String::from("ls")]);
+        //Reducing size trims the oldest entries
+        history.set_size(2);
+        assert_eq!(history.dump(), vec![String::from("make"), String::from("ls")]);
+        //Nothing is stored with size 0
+        history.set_size(0);
+        history.push(String::from("pwd"));
+        assert_eq!(history.len(), 0);
+        assert!(history.dump().is_empty());
+    }
+
 }
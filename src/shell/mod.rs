@@ -26,6 +26,7 @@
 pub mod history;
 pub mod proc;
 pub mod prompt;
+pub mod sigwinch;
 pub mod unixsignal;
 
 extern crate nix;
@@ -33,13 +34,18 @@ extern crate whoami;
 
 use history::ShellHistory;
 use proc::{ShellError, ShellProc, ShellProcState};
+use proc::trace::Tracer;
 use prompt::ShellPrompt;
 
 use crate::config::PromptConfig;
 use crate::translator::ioprocessor::IOProcessor;
 
 use std::path::PathBuf;
-use std::time::{Duration};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Maximum size of the output of the last command retained by the shell; older output is discarded
+const LAST_OUTPUT_MAX_SIZE: usize = 1048576;
 
 /// ### ShellState
 /// 
@@ -61,7 +67,11 @@ pub struct Shell {
     process: ShellProc,
     prompt: ShellPrompt,
     props: ShellProps,
-    state: ShellState
+    state: ShellState,
+    last_input: Option<String>, //Input of the last command, as typed by the user
+    command_started: bool, //Whether a command has been started since the last check
+    last_output: String, //Raw output of the current (or last) command
+    stop_grace: Duration //Time given to the shell to exit after SIGTERM, before it is killed
 }
 
 /// ### ShellProps
@@ -72,14 +82,19 @@ pub(crate) struct ShellProps {
     pub hostname: String,
     pub elapsed_time: Duration,
     pub exit_status: u8,
-    pub wrkdir: PathBuf
+    pub output_lines: usize,
+    pub wrkdir: PathBuf,
+    pub pid: i32,
+    pub jobs: usize,
+    pub translit_bypass: bool
 }
 
 impl Shell {
     /// ### start
     ///  
-    /// Start a new shell instance and instantiates a new Shell struct
-    pub fn start(exec: String, args: Vec<String>, prompt_config: &PromptConfig) -> Result<Shell, ShellError> {
+    /// Start a new shell instance and instantiates a new Shell struct.
+    /// The variables in `env` (e.g. PATH) are set in the environment of the shell only; Pyc's own environment is left untouched
+    pub fn start(exec: String, args: Vec<String>, env: &[(String, String)], prompt_config: &PromptConfig) -> Result<Shell, ShellError> {
         //Start shell
         let mut argv: Vec<String> = Vec::with_capacity(1 + args.len());
         let shell_prompt: ShellPrompt = ShellPrompt::new(prompt_config);
@@ -87,29 +102,80 @@ impl Shell {
         for arg in args.iter() {
             argv.push(arg.clone());
         }
-        let shell_process: ShellProc = match ShellProc::start(argv) {
-            Ok(p) => p,
-            Err(err) => return Err(err),
-        };
+        let shell_process: ShellProc = ShellProc::start(argv, env)?;
+        let mut history: ShellHistory = ShellHistory::new();
+        history.set_size(prompt_config.history_size);
+        history.set_ignore_dups(prompt_config.history_ignore_dups);
         //Get process username
         let user: String = whoami::username();
         //Get hostname
         let hostname: String = Shell::get_hostname();
         let wrkdir: PathBuf = shell_process.wrkdir.clone();
+        let pid: i32 = shell_process.pid;
         Ok(Shell {
             process: shell_process,
             prompt: shell_prompt,
-            props: ShellProps::new(hostname, user, wrkdir),
-            history: ShellHistory::new(),
-            state: ShellState::Shell
+            props: ShellProps::new(hostname, user, wrkdir, pid),
+            history,
+            state: ShellState::Shell,
+            last_input: None,
+            command_started: false,
+            last_output: String::new(),
+            stop_grace: Duration::from_millis(0)
         })
     }
 
+    /// ### set_stop_grace
+    /// 
+    /// Set the time given to the shell to exit after SIGTERM when stopped, before it is killed.
+    /// If zero, the shell is killed immediately
+    pub fn set_stop_grace(&mut self, grace: Duration) {
+        self.stop_grace = grace;
+    }
+
+    /// ### set_read_timeout
+    /// 
+    /// Mirrors ShellProc set_read_timeout
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.process.set_read_timeout(timeout);
+    }
+
+    /// ### set_read_buffer_size
+    /// 
+    /// Mirrors ShellProc set_read_buffer_size
+    pub fn set_read_buffer_size(&mut self, size: usize) {
+        self.process.set_read_buffer_size(size);
+    }
+
+    /// ### set_tracer
+    /// 
+    /// Mirrors ShellProc set_tracer
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.process.set_tracer(tracer);
+    }
+
+    /// ### set_prompt_config
+    /// 
+    /// Replace the prompt configuration (e.g. after the configuration has been reloaded)
+    pub fn set_prompt_config(&mut self, prompt_config: &PromptConfig) {
+        self.prompt = ShellPrompt::new(prompt_config);
+        self.history.set_size(prompt_config.history_size);
+        self.history.set_ignore_dups(prompt_config.history_ignore_dups);
+    }
+
     /// ### stop
     /// 
-    /// Stop shell execution
+    /// Stop shell execution.
+    /// The shell is asked to terminate with SIGTERM (so it can run its traps), then it's killed once the grace period has elapsed
     pub fn stop(&mut self) -> Result<u8, ShellError> {
-        while self.get_state() != ShellState::Terminated {
+        if self.stop_grace.as_millis() > 0 && self.is_alive() {
+            let _ = self.process.terminate();
+            let t_start: Instant = Instant::now();
+            while self.is_alive() && t_start.elapsed() < self.stop_grace {
+                sleep(Duration::from_millis(10));
+            }
+        }
+        while self.is_alive() {
             let _ = self.process.kill();
         }
         self.history.clear();
@@ -125,11 +191,96 @@ impl Shell {
 
     /// ### write
     ///
-    /// Mirrors ShellProc write
+    /// Mirrors ShellProc write.
+    /// If the shell is idle, a new command is started, so the output lines counter is reset
     pub fn write(&mut self, input: String) -> Result<(), ShellError> {
+        if self.process.state == ShellProcState::Idle {
+            self.props.output_lines = 0;
+            self.last_output.clear();
+            self.command_started = true;
+        }
         self.process.write(input)
     }
 
+    /// ### set_last_input
+    ///
+    /// Set the input of the last command, as typed by the user (before transliteration)
+    pub fn set_last_input(&mut self, input: String) {
+        self.last_input = Some(input);
+    }
+
+    /// ### take_command_started
+    ///
+    /// Returns whether a command has been started since the last call
+    pub fn take_command_started(&mut self) -> bool {
+        let started: bool = self.command_started;
+        self.command_started = false;
+        started
+    }
+
+    /// ### take_last_input
+    ///
+    /// Returns the input of the last command, as typed by the user. The input is returned only once
+    pub fn take_last_input(&mut self) -> Option<String> {
+        self.last_input.take()
+    }
+
+    /// ### count_output_lines
+    ///
+    /// Add lines to the amount of lines printed by the current command
+    pub fn count_output_lines(&mut self, lines: usize) {
+        self.props.output_lines += lines;
+    }
+
+    /// ### record_output
+    ///
+    /// Retain the raw output of the current command. Only the last `LAST_OUTPUT_MAX_SIZE` bytes are kept
+    pub fn record_output(&mut self, output: &str) {
+        self.last_output.push_str(output);
+        if self.last_output.len() > LAST_OUTPUT_MAX_SIZE {
+            let mut start: usize = self.last_output.len() - LAST_OUTPUT_MAX_SIZE;
+            while !self.last_output.is_char_boundary(start) {
+                start += 1;
+            }
+            self.last_output.drain(..start);
+        }
+    }
+
+    /// ### get_last_output
+    ///
+    /// Returns the raw output of the current (or last) command
+    pub fn get_last_output(&self) -> &str {
+        self.last_output.as_str()
+    }
+
+    /// ### pid
+    ///
+    /// Returns the pid of the shell process. The pid is kept after the shell has terminated
+    pub fn pid(&self) -> i32 {
+        self.process.pid
+    }
+
+    /// ### job_count
+    ///
+    /// Returns the amount of running children of the shell (e.g. background jobs)
+    pub fn job_count(&self) -> usize {
+        self.process.child_count()
+    }
+
+    /// ### set_translit_bypass
+    ///
+    /// Set whether the input transliteration is suspended; it is reported in the prompt through the TRANSLIT key
+    pub fn set_translit_bypass(&mut self, bypass: bool) {
+        self.props.translit_bypass = bypass;
+    }
+
+    /// ### get_translit_bypass
+    ///
+    /// Returns whether the input transliteration is suspended
+    pub fn get_translit_bypass(&self) -> bool {
+        self.props.translit_bypass
+    }
+
     /// ### raise
     ///
     /// Send a signal to shell process
@@ -138,6 +289,34 @@ impl Shell {
         self.process.raise(sig.to_nix_signal())
     }
 
+    /// ### raise_subprocess
+    ///
+    /// Send a signal to the subprocess running in the shell
+    pub fn raise_subprocess(&mut self, sig: unixsignal::UnixSignal) -> Result<(), ShellError> {
+        self.process.raise_subprocess(sig.to_nix_signal())
+    }
+
+    /// ### is_line_mode
+    /// 
+    /// Returns whether the running subprocess reads its input one line at a time (rather than key by key)
+    pub fn is_line_mode(&self) -> bool {
+        self.process.is_line_mode()
+    }
+
+    /// ### stop_subprocess
+    /// 
+    /// Stop the running subprocess, as CTRL+Z does; the shell reports the stop and gives the prompt back
+    pub fn stop_subprocess(&mut self) -> Result<(), ShellError> {
+        self.process.stop_subprocess()
+    }
+
+    /// ### resize
+    ///
+    /// Set the size of the terminal of the shell; the shell and its children are notified with SIGWINCH
+    pub fn resize(&mut self, rows: u16, cols: u16) -> Result<(), ShellError> {
+        self.process.resize(rows, cols)
+    }
+
     /// ### get_state
     ///
     /// Returns the current Shell state
@@ -155,15 +334,62 @@ impl Shell {
         }
     }
 
+    /// ### terminated_on_sigchld
+    ///
+    /// Returns whether the shell process has terminated. The process is checked only if a SIGCHLD has been received since the last call
+    pub fn terminated_on_sigchld(&mut self) -> bool {
+        self.process.terminated_on_sigchld()
+    }
+
+    /// ### is_alive
+    ///
+    /// Returns whether the shell process is still running.
+    /// Unlike `get_state`, the shell state is not computed, so this is cheaper when only liveness matters
+    pub fn is_alive(&mut self) -> bool {
+        match self.process.state {
+            ShellProcState::Terminated => false,
+            _ => self.process.update_state() != ShellProcState::Terminated,
+        }
+    }
+
+    /// ### get_elapsed_time
+    ///
+    /// Returns the execution time of the last command executed in the shell
+    pub fn get_elapsed_time(&self) -> Duration {
+        self.props.elapsed_time
+    }
+
+    /// ### get_wrkdir
+    ///
+    /// Returns the working directory of the shell
+    pub fn get_wrkdir(&self) -> &PathBuf {
+        &self.props.wrkdir
+    }
+
+    /// ### get_exit_status
+    ///
+    /// Returns the exit status of the last command executed in the shell
+    pub fn get_exit_status(&self) -> u8 {
+        self.props.exit_status
+    }
+
     /// ### refresh_env
     /// 
     /// Refresh Shell Environment information
     pub fn refresh_env(&mut self) {
-        self.props.username = whoami::username();
-        self.props.hostname = Shell::get_hostname();
+        //Prefer the user reported by the shell (it changes after su, sudo -s, ...); hostname is read once at startup
+        if let Some(username) = &self.process.username {
+            self.props.username = username.clone();
+        }
         self.props.wrkdir = self.process.wrkdir.clone();
         self.props.exit_status = self.process.exit_status;
         self.props.elapsed_time = self.process.exec_time;
+        self.props.pid = self.pid();
+        //Looking for the children of the shell is expensive; they're counted only if shown
+        self.props.jobs = match self.prompt.shows_jobs() {
+            true => self.job_count(),
+            false => 0,
+        };
     }
 
     /// ### pprompt
@@ -173,6 +399,27 @@ impl Shell {
         self.prompt.get_line(&self.props, processor)
     }
 
+    /// ### get_first_promptline
+    /// 
+    /// Get the first prompt line of the session
+    pub fn get_first_promptline(&mut self, processor: &IOProcessor) -> String {
+        self.prompt.get_first_line(&self.props, processor)
+    }
+
+    /// ### get_rpromptline
+    /// 
+    /// Get the right prompt line, if configured
+    pub fn get_rpromptline(&mut self, processor: &IOProcessor) -> Option<String> {
+        self.prompt.get_rline(&self.props, processor)
+    }
+
+    /// ### clear_prompt_cache
+    /// 
+    /// Clear the prompt cache, forcing the prompt modules to discover their values again (e.g. the git repository)
+    pub fn clear_prompt_cache(&mut self) {
+        self.prompt.clear_cache();
+    }
+
     /// ### get_hostname
     /// 
     /// Get hostname without domain
@@ -190,13 +437,17 @@ impl ShellProps {
     /// ### new
     /// 
     /// Instantiates a new ShellProps object
-    pub(self) fn new(hostname: String, username: String, wrkdir: PathBuf) -> ShellProps {
+    pub(self) fn new(hostname: String, username: String, wrkdir: PathBuf, pid: i32) -> ShellProps {
         ShellProps {
-            hostname: hostname,
-            username: username,
-            wrkdir: wrkdir,
+            hostname,
+            username,
+            wrkdir,
+            pid,
+            jobs: 0,
             elapsed_time: Duration::from_secs(0),
-            exit_status: 0
+            exit_status: 0,
+            output_lines: 0,
+            translit_bypass: false
         }
     }
 }
@@ -212,13 +463,14 @@ mod tests {
 
     #[test]
     fn test_shell_props_new() {
-        let shell_props: ShellProps = ShellProps::new(String::from("computer"), String::from("root"), PathBuf::from("/tmp/"));
+        let shell_props: ShellProps = ShellProps::new(String::from("computer"), String::from("root"), PathBuf::from("/tmp/"), 1024);
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         assert_eq!(shell_props.username, String::from("root"));
         assert_eq!(shell_props.hostname, String::from("computer"));
         assert_eq!(shell_props.wrkdir, PathBuf::from("/tmp/"));
         assert_eq!(shell_props.elapsed_time.as_millis(), 0);
         assert_eq!(shell_props.exit_status, 0);
+        assert_eq!(shell_props.pid, 1024);
     }
 
     #[test]
@@ -226,7 +478,7 @@ mod tests {
         //Use universal accepted shell
         let shell: String = String::from("sh");
         //Instantiate and start a shell
-        let mut shell_env: Shell = Shell::start(shell, vec![], &PromptConfig::default()).ok().unwrap();
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Verify PID
         assert_ne!(shell_env.process.pid, 0);
@@ -245,21 +497,90 @@ mod tests {
         assert!(format!("{}", shell_env.props.wrkdir.display()).len() > 0);
         //Refresh environment
         shell_env.refresh_env();
+        //Identity reported by the shell is preferred
+        let hostname: String = shell_env.props.hostname.clone();
+        shell_env.process.username = Some(String::from("omar"));
+        shell_env.refresh_env();
+        assert_eq!(shell_env.props.username, String::from("omar"));
+        assert_eq!(shell_env.props.hostname, hostname);
+        //User read at startup is kept if the shell doesn't report it
+        shell_env.process.username = None;
+        shell_env.refresh_env();
+        assert_eq!(shell_env.props.username, String::from("omar"));
         //Terminate shell
         assert_eq!(shell_env.stop().unwrap(), 9);
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         assert_eq!(shell_env.get_state(), ShellState::Terminated);
     }
 
+    #[test]
+    fn test_shell_pid() {
+        let mut shell_env: Shell = Shell::start(String::from("sh"), vec![], &[], &PromptConfig::default()).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let pid: i32 = shell_env.pid();
+        assert!(pid > 0);
+        assert_eq!(shell_env.props.pid, pid);
+        //Pid is still reported after termination
+        assert!(shell_env.stop().is_ok());
+        assert!(!shell_env.is_alive());
+        assert_eq!(shell_env.pid(), pid);
+    }
+
+    #[test]
+    fn test_shell_stop_grace() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let trapped: PathBuf = tmpdir.path().join("trapped");
+        //Shell with a SIGTERM trap
+        let mut shell_env: Shell = Shell::start(String::from("sh"), vec![], &[], &PromptConfig::default()).ok().unwrap();
+        shell_env.set_stop_grace(Duration::from_secs(5));
+        assert!(shell_env.write(format!("trap 'echo trapped > {}; exit 0' TERM\n", trapped.display())).is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let t_start: Instant = Instant::now();
+        //The trap runs, and the shell exits by itself
+        assert_eq!(shell_env.stop().unwrap(), 0);
+        assert!(t_start.elapsed() < Duration::from_secs(5));
+        assert_eq!(std::fs::read_to_string(trapped.as_path()).unwrap(), String::from("trapped\n"));
+        //Shell which ignores SIGTERM is killed once the grace period has elapsed
+        let mut shell_env: Shell = Shell::start(String::from("sh"), vec![], &[], &PromptConfig::default()).ok().unwrap();
+        shell_env.set_stop_grace(Duration::from_millis(500));
+        assert!(shell_env.write(String::from("trap '' TERM\n")).is_ok());
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        let t_start: Instant = Instant::now();
+        assert_eq!(shell_env.stop().unwrap(), 9);
+        assert!(t_start.elapsed() >= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_shell_terminated_sigchld() {
+        let mut shell_env: Shell = Shell::start(String::from("sh"), vec![], &[], &PromptConfig::default()).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_env.is_alive());
+        //SIGCHLD from another child must not report the shell as terminated
+        assert!(std::process::Command::new("true").status().unwrap().success());
+        assert!(!shell_env.terminated_on_sigchld());
+        assert!(shell_env.is_alive());
+        assert!(shell_env.write(String::from("exit 0\n")).is_ok());
+        //Termination must be noticed as soon as SIGCHLD is received
+        let t_start: Instant = Instant::now();
+        while t_start.elapsed() < Duration::from_secs(2) {
+            if shell_env.terminated_on_sigchld() {
+                break;
+            }
+            sleep(Duration::from_millis(1));
+        }
+        assert!(t_start.elapsed() < Duration::from_secs(2));
+        assert_eq!(shell_env.get_state(), ShellState::Terminated);
+    }
+
     #[test]
     fn test_shell_start_failed() {
         //Use fictional shell
         let shell: String = String::from("pipponbash");
-        //Instantiate and start a shell
-        let mut shell_env: Shell = Shell::start(shell, vec![], &PromptConfig::default()).unwrap();
-        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
-        //Shell should have terminated
-        assert_eq!(shell_env.get_state(), ShellState::Terminated);
+        //Shell should fail to start, reporting the cause
+        assert_eq!(
+            Shell::start(shell, vec![], &[], &PromptConfig::default()).err().unwrap(),
+            ShellError::CouldNotExec(nix::errno::Errno::ENOENT)
+        );
     }
 
     #[test]
@@ -267,20 +588,24 @@ mod tests {
         //Use universal accepted shell
         let shell: String = String::from("sh");
         //Instantiate and start a shell
-        let mut shell_env: Shell = Shell::start(shell, vec![], &PromptConfig::default()).ok().unwrap();
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Verify PID
         assert_ne!(shell_env.process.pid, 0);
         //Verify shell status
         assert_eq!(shell_env.get_state(), ShellState::Shell);
         //Try to start a blocking process (e.g. cat)
+        assert!(!shell_env.take_command_started());
         let command: String = String::from("head -n 2\n");
         assert!(shell_env.write(command).is_ok());
         sleep(Duration::from_millis(500));
         //Check if status is SubprocessRunning
         assert_eq!(shell_env.get_state(), ShellState::SubprocessRunning);
+        assert!(shell_env.take_command_started());
         let stdin: String = String::from("foobar\n");
         assert!(shell_env.write(stdin.clone()).is_ok());
+        //Input to the running subprocess doesn't start a command
+        assert!(!shell_env.take_command_started());
         //Wait 100ms
         sleep(Duration::from_millis(500));
         //Try to read stdout
@@ -318,16 +643,37 @@ mod tests {
         assert_eq!(shell_env.stop().unwrap(), 9);
     }
 
+    #[test]
+    fn test_shell_last_output() {
+        let mut shell_env: Shell = Shell::start(String::from("sh"), vec![], &[], &PromptConfig::default()).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shell_env.get_last_output(), "");
+        shell_env.record_output("foo\n");
+        shell_env.record_output("bar\n");
+        assert_eq!(shell_env.get_last_output(), "foo\nbar\n");
+        //Only the tail is kept
+        shell_env.record_output("я".repeat(LAST_OUTPUT_MAX_SIZE).as_str());
+        assert!(shell_env.get_last_output().len() <= LAST_OUTPUT_MAX_SIZE);
+        assert!(shell_env.get_last_output().len() >= LAST_OUTPUT_MAX_SIZE - 1);
+        assert!(shell_env.get_last_output().starts_with("я"));
+        //Output is cleared once a new command starts
+        assert!(shell_env.write(String::from("true\n")).is_ok());
+        assert_eq!(shell_env.get_last_output(), "");
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_env.stop().is_ok());
+    }
+
     #[test]
     fn test_shell_terminate_gracefully() {
         //Use universal accepted shell
         let shell: String = String::from("sh");
         //Instantiate and start a shell
-        let mut shell_env: Shell = Shell::start(shell, vec![], &PromptConfig::default()).ok().unwrap();
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Verify PID
         assert_ne!(shell_env.process.pid, 0);
         //Verify shell status
+        assert!(shell_env.is_alive());
         assert_eq!(shell_env.get_state(), ShellState::Shell);
         //Terminate the shell gracefully
         sleep(Duration::from_millis(500));
@@ -336,6 +682,7 @@ mod tests {
         //Wait shell to terminate
         sleep(Duration::from_millis(1000));
         //Verify shell has terminated
+        assert!(!shell_env.is_alive());
         assert_eq!(shell_env.get_state(), ShellState::Terminated);
         //Verify exitcode to be 0
         assert_eq!(shell_env.stop().unwrap(), 5);
@@ -346,15 +693,94 @@ mod tests {
         //Use universal accepted shell
         let shell: String = String::from("sh");
         //Instantiate and start a shell
-        let mut shell_env: Shell = Shell::start(shell, vec![], &PromptConfig::default()).ok().unwrap();
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //SIGINT is trapped by the shell
         assert!(shell_env.raise(unixsignal::UnixSignal::Sigint).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell_env.get_state(), ShellState::Shell);
+        assert!(shell_env.raise(unixsignal::UnixSignal::Sigterm).is_ok());
         //Wait shell to terminate
         sleep(Duration::from_millis(500));
         //Verify shell has terminated
         assert_eq!(shell_env.get_state(), ShellState::Terminated);
-        //Verify exitcode to be 0
-        assert_eq!(shell_env.stop().unwrap(), 2);
+        //Verify exitcode to be 15
+        assert_eq!(shell_env.stop().unwrap(), 15);
+    }
+
+    #[test]
+    fn test_shell_stop_subprocess() {
+        //Use universal accepted shell
+        let shell: String = String::from("sh");
+        //Instantiate and start a shell
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Nothing to stop
+        assert!(shell_env.stop_subprocess().is_err());
+        //Stop a long-running child; the rest of the command runs and the prompt comes back
+        assert!(shell_env.write(String::from("sleep 1; echo done\n")).is_ok());
+        sleep(Duration::from_millis(200));
+        assert!(shell_env.stop_subprocess().is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, stderr) = shell_env.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("done\n"));
+        assert!(stderr.unwrap().contains("Stopped"));
+        assert_eq!(shell_env.get_state(), ShellState::Shell);
+        //Resume it
+        assert!(shell_env.write(String::from("fg\n")).is_ok());
+        sleep(Duration::from_millis(1500));
+        let _ = shell_env.read();
+        assert_eq!(shell_env.get_state(), ShellState::Shell);
+        shell_env.refresh_env();
+        assert_eq!(shell_env.get_exit_status(), 0);
+        assert!(shell_env.process.kill().is_ok());
+    }
+
+    #[test]
+    fn test_shell_resize() {
+        //Use universal accepted shell
+        let shell: String = String::from("sh");
+        //Instantiate and start a shell
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &PromptConfig::default()).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_env.resize(24, 80).is_ok());
+        //The new size is reported by the terminal
+        assert!(shell_env.write(String::from("stty size\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_env.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("24 80\n"));
+        assert_eq!(shell_env.get_state(), ShellState::Shell);
+        assert!(shell_env.process.kill().is_ok());
+    }
+
+    #[test]
+    fn test_shell_job_count() {
+        //Use universal accepted shell
+        let shell: String = String::from("sh");
+        //Instantiate and start a shell
+        let mut prompt_config: PromptConfig = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${JOBS}$");
+        let mut shell_env: Shell = Shell::start(shell, vec![], &[], &prompt_config).ok().unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shell_env.job_count(), 0);
+        //Start a background job
+        assert!(shell_env.write(String::from("sleep 5 &\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_env.read();
+        assert_eq!(shell_env.get_state(), ShellState::Shell);
+        shell_env.refresh_env();
+        assert_eq!(shell_env.props.jobs, 1);
+        //Jobs are not counted if they're not in the prompt
+        shell_env.set_prompt_config(&PromptConfig::default());
+        shell_env.refresh_env();
+        assert_eq!(shell_env.props.jobs, 0);
+        assert_eq!(shell_env.job_count(), 1);
+        assert!(shell_env.write(String::from("kill $!; wait\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_env.read();
+        shell_env.refresh_env();
+        assert_eq!(shell_env.props.jobs, 0);
+        assert!(shell_env.process.kill().is_ok());
     }
 
     #[test]
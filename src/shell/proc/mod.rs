@@ -27,6 +27,8 @@ extern crate nix;
 
 mod pipe;
 pub mod process;
+pub mod sigchld;
+pub mod trace;
 
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
@@ -51,11 +53,13 @@ pub enum ShellProcState {
 #[derive(Copy, Clone, PartialEq, std::fmt::Debug)]
 pub enum ShellError {
     CouldNotStartProcess,
+    CouldNotExec(nix::errno::Errno),
     InvalidData,
     IoTimeout,
     ShellRunning,
     ShellTerminated,
     CouldNotKill,
+    NoJobControl,
     PipeError(nix::errno::Errno)
 }
 
@@ -68,16 +72,23 @@ pub struct ShellProc {
     pub exit_status: u8,                    //Exit status of the subprocess (child of shell)
     pub pid: i32,                           //Shell pid
     pub wrkdir: PathBuf,                    //Working directory
+    pub username: Option<String>,           //Effective user of the shell (reported after each command)
     pub exec_time: Duration,                //Execution time of the last command
     //Private
     rc: u8,                                 //Return code of the shell process
     uuid: String,                           //UUID used for handshake with the shell
     start_time: Instant,                    //Instant when the last command was started
     stdout_cache: Option<String>,           //Used to prevent buffer fragmentation
+    stdout_remainder: Vec<u8>,              //Trailing bytes of an UTF-8 sequence split across stdout reads
+    stderr_remainder: Vec<u8>,              //Trailing bytes of an UTF-8 sequence split across stderr reads
+    tracer: Option<trace::Tracer>,          //Traces the I/O exchanged with the shell
+    sigchld_seen: usize,                    //SIGCHLD received when the shell state was last checked
     echo_command: String,                   //Echo command
+    read_timeout: Duration,                 //Time waited for output on each read
+    read_buffer_size: usize,                //Initial size of the read buffer
     //Pipes
-    stdin_pipe: Pipe,
-    stdout_pipe: Pipe,
+    pty_master: Pipe,                       //Master side of the pseudo terminal; used for stdin and stdout
+    pty_slave: Pipe,                        //Slave side of the pseudo terminal; kept open, so that reads never fail with EIO
     stderr_pipe: Pipe
 }
 
@@ -85,11 +96,17 @@ impl std::fmt::Display for ShellError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let code_str: String = match self {
             ShellError::CouldNotStartProcess => String::from("Could not start process"),
+            ShellError::CouldNotExec(errno) => match errno {
+                nix::errno::Errno::ENOENT => String::from("Could not start process: command not found"),
+                nix::errno::Errno::EACCES => String::from("Could not start process: permission denied"),
+                _ => format!("Could not start process: {}", errno),
+            },
             ShellError::InvalidData => String::from("Invalid data from process"),
             ShellError::IoTimeout => String::from("I/O timeout"),
             ShellError::ShellTerminated => String::from("Shell has terminated"),
             ShellError::ShellRunning => String::from("Tried to clean shell up while still running"),
             ShellError::CouldNotKill => String::from("Could not send signal to shell process"),
+            ShellError::NoJobControl => String::from("The shell has no job control"),
             ShellError::PipeError(errno) => format!("Pipe error: {}", errno),
         };
         write!(f, "{}", code_str)
@@ -106,11 +123,15 @@ mod tests {
     #[test]
     fn test_proc_fmt_shell_error() {
         assert_eq!(format!("{}", ShellError::CouldNotStartProcess), String::from("Could not start process"));
+        assert_eq!(format!("{}", ShellError::CouldNotExec(nix::errno::Errno::ENOENT)), String::from("Could not start process: command not found"));
+        assert_eq!(format!("{}", ShellError::CouldNotExec(nix::errno::Errno::EACCES)), String::from("Could not start process: permission denied"));
+        assert_eq!(format!("{}", ShellError::CouldNotExec(nix::errno::Errno::ENOEXEC)), format!("Could not start process: {}", nix::errno::Errno::ENOEXEC));
         assert_eq!(format!("{}", ShellError::InvalidData), String::from("Invalid data from process"));
         assert_eq!(format!("{}", ShellError::IoTimeout), String::from("I/O timeout"));
         assert_eq!(format!("{}", ShellError::ShellTerminated), String::from("Shell has terminated"));
         assert_eq!(format!("{}", ShellError::ShellRunning), String::from("Tried to clean shell up while still running"));
         assert_eq!(format!("{}", ShellError::CouldNotKill), String::from("Could not send signal to shell process"));
+        assert_eq!(format!("{}", ShellError::NoJobControl), String::from("The shell has no job control"));
         assert_eq!(format!("{}", ShellError::PipeError(nix::errno::Errno::EACCES)), format!("Pipe error: {}", nix::errno::Errno::EACCES));
     }
 
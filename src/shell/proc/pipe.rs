@@ -32,8 +32,14 @@ use std::os::unix::io::RawFd;
 use std::time::{Instant, Duration};
 
 //UNIX
+use nix::sys::termios::{LocalFlags, OutputFlags};
 use nix::unistd;
 
+//Time waited for more data once something has been read; a writer blocked on a full buffer writes again right after it's read
+const DRAIN_POLL_TIMEOUT: i32 = 5;
+//Maximum time spent draining after the timeout, so that a process which never stops writing doesn't block the read
+const DRAIN_MAX_TIME: Duration = Duration::from_millis(100);
+
 #[derive(Clone, std::fmt::Debug)]
 pub(crate) struct Pipe {
     pub path: PathBuf, //Pipe path
@@ -70,9 +76,49 @@ impl Pipe {
         }
     }
 
+    /// ### open_pty
+    /// 
+    /// Open a new pseudo terminal. Returns the master and the slave side as pipes.
+    /// The slave keeps the canonical mode, so that lines read by programs are edited by the terminal (e.g. backspace),
+    /// but input is not echoed (it's echoed by Pyc), nor turned into signals (they're raised by Pyc), and output is not processed;
+    /// programs which need a different mode (e.g. editors) set it up by themselves
+    pub fn open_pty() -> Result<(Pipe, Pipe), ShellError> {
+        let pty: nix::pty::OpenptyResult = match nix::pty::openpty(None, None) {
+            Ok(pty) => pty,
+            Err(nix::Error::Sys(errno)) => return Err(ShellError::PipeError(errno)),
+            Err(_) => return Err(ShellError::PipeError(nix::errno::Errno::UnknownErrno))
+        };
+        let master: Pipe = Pipe {
+            path: PathBuf::new(),
+            fd: pty.master
+        };
+        let slave: Pipe = Pipe {
+            path: PathBuf::new(),
+            fd: pty.slave
+        };
+        //Set slave mode
+        let result: nix::Result<()> = nix::sys::termios::tcgetattr(slave.fd).and_then(|mut termios| {
+            termios.local_flags.remove(
+                LocalFlags::ECHO | LocalFlags::ECHOE | LocalFlags::ECHOK | LocalFlags::ECHONL | LocalFlags::ECHOCTL | LocalFlags::ISIG
+            );
+            //Output is read as it is written ("\n" mustn't become "\r\n")
+            termios.output_flags.remove(OutputFlags::OPOST);
+            nix::sys::termios::tcsetattr(slave.fd, nix::sys::termios::SetArg::TCSANOW, &termios)
+        });
+        if let Err(err) = result {
+            let _ = master.close();
+            let _ = slave.close();
+            match err {
+                nix::Error::Sys(errno) => return Err(ShellError::PipeError(errno)),
+                _ => return Err(ShellError::PipeError(nix::errno::Errno::UnknownErrno))
+            }
+        }
+        Ok((master, slave))
+    }
+
     /// ### close
     /// 
-    /// Close and delete pipe
+    /// Close and delete pipe (pseudo terminals have no path and are just closed)
     pub fn close(&self) -> Result<(), ShellError> {
         if let Err(err) = unistd::close(self.fd) {
             match err {
@@ -81,45 +127,55 @@ impl Pipe {
             }
         };
         //Unlink pipe
-        let _ = unistd::unlink(self.path.as_path());
+        if ! self.path.as_os_str().is_empty() {
+            let _ = unistd::unlink(self.path.as_path());
+        }
         Ok(())
     }
 
     /// ### read
     /// 
     /// Read from pipe
-    /// If read_all parameter is False, then the function returns after reading 8192 or less
-    /// otherwise, if set to True, reads until there's something available to be read
-    pub fn read(&self, timeout: u64, read_all: bool) -> Result<Option<String>, ShellError> {
+    /// If read_all parameter is False, then the function returns once the data immediately available has been read,
+    /// otherwise, if set to True, reads until there's something available to be read.
+    /// Reads start with a buffer of `buffer_size` bytes, which is doubled each time a read fills it.
+    /// Once something has been read, the burst is drained even if the timeout has elapsed (e.g. timeout is 0), for at most 100ms more.
+    /// Data is returned as raw bytes
+    pub fn read(&self, timeout: u64, read_all: bool, buffer_size: usize) -> Result<Option<Vec<u8>>, ShellError> {
         //Create poll fd wrapper
         let mut poll_fds: [nix::poll::PollFd; 1] = [nix::poll::PollFd::new(self.fd, nix::poll::PollFlags::POLLIN | nix::poll::PollFlags::POLLRDBAND | nix::poll::PollFlags::POLLHUP)];
         //Prepare out buffer
-        let mut data_out: String = String::new();
+        let mut data_out: Vec<u8> = Vec::new();
         let mut data_size: usize = 0;
+        let mut buffer: Vec<u8> = vec![0; std::cmp::max(buffer_size, 1)];
+        //Once something has been read, only the data of the burst is drained
+        let mut draining: bool = false;
         //Prepare times
+        let poll_timeout: i32 = std::cmp::min(timeout, 50) as i32;
         let timeout: Duration = Duration::from_millis(timeout);
         let time: Instant = Instant::now();
-        while time.elapsed() < timeout {
+        //Pipe is polled at least once, even if timeout is 0
+        let mut polled: bool = false;
+        while ! polled || time.elapsed() < timeout || (draining && time.elapsed() < timeout + DRAIN_MAX_TIME) {
+            polled = true;
             //Poll pipe
-            match nix::poll::poll(&mut poll_fds, 50) {
+            match nix::poll::poll(&mut poll_fds, if draining { DRAIN_POLL_TIMEOUT } else { poll_timeout }) {
                 Ok(ret) => {
                     if ret > 0 && poll_fds[0].revents().is_some() { //Fifo is available to be read
                         let event: nix::poll::PollFlags = poll_fds[0].revents().unwrap();
                         if event.intersects(nix::poll::PollFlags::POLLIN) || event.intersects(nix::poll::PollFlags::POLLRDBAND) {
                             //Read from FIFO
-                            let mut buffer: [u8; 8192] = [0; 8192];
-                            match unistd::read(self.fd, &mut buffer) {
+                            match unistd::read(self.fd, buffer.as_mut_slice()) {
                                 Ok(bytes_read) => {
                                     data_size += bytes_read;
                                     //Push bytes converted to string to data out
-                                    data_out.push_str(match std::str::from_utf8(&buffer[0..bytes_read]) {
-                                        Ok(s) => s,
-                                        Err(_) => {
-                                            return Err(ShellError::InvalidData)
-                                        }
-                                    });
+                                    data_out.extend_from_slice(&buffer[0..bytes_read]);
+                                    //Grow buffer if filled; there's probably more to read
+                                    if bytes_read == buffer.len() {
+                                        buffer.resize(buffer.len() * 2, 0);
+                                    }
                                     if ! read_all {
-                                        break;
+                                        draining = true;
                                     }
                                 },
                                 Err(err) => {
@@ -133,6 +189,7 @@ impl Pipe {
                                                         break; //All data has been read
                                                     }
                                                 },
+                                                nix::errno::Errno::EINTR => continue, //Interrupted by a signal; retry
                                                 _ => return Err(ShellError::PipeError(errno)) //Error
                                             }
                                         },
@@ -170,6 +227,7 @@ impl Pipe {
                                         break; //All data has been read
                                     }
                                 },
+                                nix::errno::Errno::EINTR => continue, //Interrupted by a signal; poll again
                                 _ => return Err(ShellError::PipeError(errno)) //Error
                             }
                         },
@@ -275,8 +333,8 @@ mod tests {
         let pipe_thread: Pipe = pipe.clone();
         //Start thread
         let join_hnd: thread::JoinHandle<()> = thread::spawn(move || {
-            let input: String = pipe_thread.read(1000, true).unwrap().unwrap();
-            assert_eq!(input, String::from("HELLO\n"));
+            let input: Vec<u8> = pipe_thread.read(1000, true, 8192).unwrap().unwrap();
+            assert_eq!(input, b"HELLO\n".to_vec());
             thread::sleep(Duration::from_millis(100)); //Sleep for 100 msecond
             //Write
             assert!(pipe_thread.write(String::from("HI THERE\n"), 1000).is_ok());
@@ -285,16 +343,30 @@ mod tests {
         assert!(pipe.write(String::from("HELLO\n"), 1000).is_ok(), "Write timeout");
         //Read pipe
         thread::sleep(Duration::from_millis(100)); //Sleep for 100 msecond
-        let read: Result<Option<String>, ShellError> = pipe.read(1000, true);
+        let read: Result<Option<Vec<u8>>, ShellError> = pipe.read(1000, true, 8192);
         assert!(read.is_ok(), format!("Read should be Ok, but is {:?}", read));
-        let read: Option<String> = read.unwrap();
-        assert_eq!(read.unwrap(), String::from("HI THERE\n"));
+        let read: Option<Vec<u8>> = read.unwrap();
+        assert_eq!(read.unwrap(), b"HI THERE\n".to_vec());
         //Join thread
         assert!(join_hnd.join().is_ok());
         //Close Pipe
         assert!(pipe.close().is_ok());
     }
 
+    #[test]
+    fn test_pipe_open_pty() {
+        let (master, slave): (Pipe, Pipe) = Pipe::open_pty().unwrap();
+        //Lines are edited by the terminal and not echoed
+        assert!(master.write(String::from("ab\x7fc\n"), 500).is_ok());
+        assert_eq!(slave.read(500, false, 8192).unwrap().unwrap(), b"ac\n".to_vec());
+        assert!(master.read(100, false, 8192).unwrap().is_none());
+        //Output is not processed
+        assert!(slave.write(String::from("a\n"), 500).is_ok());
+        assert_eq!(master.read(500, false, 8192).unwrap().unwrap(), b"a\n".to_vec());
+        assert!(master.close().is_ok());
+        assert!(slave.close().is_ok());
+    }
+
     #[test]
     fn test_pipe_read_all() {
         let tmpdir: tempfile::TempDir = create_tmp_dir();
@@ -317,19 +389,48 @@ mod tests {
             assert!(pipe_thread.write(data, 1000).is_ok());
         });
         //Read all (10240 bytes should be read)
-        assert_eq!(pipe.read(500, true).unwrap().unwrap().len(), 10240);
+        assert_eq!(pipe.read(500, true, 8192).unwrap().unwrap().len(), 10240);
         //Read all set to false
         thread::sleep(Duration::from_millis(500)); //Sleep for 500 msecond
-        //Now only 8192 bytes should have been read
-        assert_eq!(pipe.read(500, false).unwrap().unwrap().len(), 8192);
-        //Now finish to read
-        assert_eq!(pipe.read(500, false).unwrap().unwrap().len(), 2048);
+        //The whole burst is read, even if it's bigger than the buffer
+        assert_eq!(pipe.read(500, false, 1024).unwrap().unwrap().len(), 10240);
+        //Nothing is left to read
+        assert!(pipe.read(100, false, 1024).unwrap().is_none());
         //Join thread
         assert!(join_hnd.join().is_ok());
         //Close Pipe
         assert!(pipe.close().is_ok());
     }
 
+    #[test]
+    fn test_pipe_read_interrupted() {
+        extern "C" fn handle_sigusr1(_: nix::libc::c_int) {}
+        let tmpdir: tempfile::TempDir = create_tmp_dir();
+        let pipe_path: PathBuf = tmpdir.path().join("stdout.fifo");
+        let pipe: Pipe = Pipe::open(&pipe_path).unwrap();
+        let pipe_thread: Pipe = pipe.clone();
+        //Install a harmless signal handler (without SA_RESTART)
+        let action = nix::sys::signal::SigAction::new(
+            nix::sys::signal::SigHandler::Handler(handle_sigusr1),
+            nix::sys::signal::SaFlags::empty(),
+            nix::sys::signal::SigSet::empty(),
+        );
+        assert!(unsafe { nix::sys::signal::sigaction(nix::sys::signal::Signal::SIGUSR1, &action) }.is_ok());
+        let reader: nix::libc::pthread_t = nix::sys::pthread::pthread_self();
+        //Interrupt the reader while it's polling, then write
+        let join_hnd: thread::JoinHandle<()> = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            assert_eq!(unsafe { nix::libc::pthread_kill(reader, nix::libc::SIGUSR1) }, 0);
+            thread::sleep(Duration::from_millis(100));
+            assert!(pipe_thread.write(String::from("HELLO\n"), 1000).is_ok());
+        });
+        //Read must recover from EINTR
+        let read: Result<Option<Vec<u8>>, ShellError> = pipe.read(1000, false, 8192);
+        assert_eq!(read, Ok(Some(b"HELLO\n".to_vec())));
+        assert!(join_hnd.join().is_ok());
+        assert!(pipe.close().is_ok());
+    }
+
     #[test]
     fn test_pipe_open_close_error() {
         //Open error
@@ -353,7 +454,7 @@ mod tests {
         assert!(pipe.is_ok(), format!("Pipe ({}) should be OK, but is {:?}", pipe_path.display(), pipe));
         let pipe: Pipe = pipe.unwrap();
         //assert!(pipe.write(String::from("HELLO\n"), 1000).is_err(), "Write should time out");
-        assert!(pipe.read(1000, true).unwrap().is_none(), "Read should be None");
+        assert!(pipe.read(1000, true, 8192).unwrap().is_none(), "Read should be None");
         assert!(pipe.close().is_ok());
     }
 
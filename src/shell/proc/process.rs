@@ -29,48 +29,92 @@ extern crate uuid;
 
 use super::{ShellError, ShellProc, ShellProcState};
 use super::pipe::Pipe;
+use super::sigchld;
+use super::trace::{Direction, Tracer};
 
-use std::ffi::{CStr, CString};
+use std::ffi::{CString, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
 use std::os::unix::io::RawFd;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+//Shells which support '+i' and '-m' to be started non interactive with job control
+const POSIX_SHELLS: [&str; 9] = ["sh", "bash", "dash", "zsh", "ksh", "mksh", "ash", "yash", "posh"];
+
 impl ShellProc {
 
     /// ### start
     /// 
-    /// Start a process
-    pub fn start(argv: Vec<String>) -> Result<ShellProc, ShellError> {
+    /// Start a process. The process inherits the environment, with the variables in `env` set.
+    /// Stdin and stdout of the process are attached to a pseudo terminal
+    pub fn start(mut argv: Vec<String>, env: &[(String, String)]) -> Result<ShellProc, ShellError> {
         if argv.len() == 0 {
             return Err(ShellError::CouldNotStartProcess)
         }
+        //POSIX shells become interactive on a terminal (printing prompts), unless the arguments set it;
+        //they're started with job control instead, so that they give the prompt back once a subprocess is stopped
+        let job_control_flags: bool = ShellProc::needs_job_control_flags(&argv);
+        let mut env: Vec<(String, String)> = env.to_vec();
+        if job_control_flags {
+            for (i, flag) in ShellProc::job_control_flags(argv[0].as_str()).iter().enumerate() {
+                argv.insert(i + 1, String::from(*flag));
+            }
+            //Interactive shells print their prompts on stderr
+            env.retain(|(key, _)| key != "PS1" && key != "PS2");
+            env.push((String::from("PS1"), String::new()));
+            env.push((String::from("PS2"), String::new()));
+        }
+        let init_command: Option<String> = ShellProc::init_command(argv[0].as_str(), job_control_flags);
         //Generate UUID - NOTE: UUID is used to notice process that shell subprocess has terminated
         let uuid: String = Uuid::new_v4().to_hyphenated().to_string();
-        //Create pipes
+        //Create pseudo terminal for stdin and stdout; stderr is kept on a pipe, so that it can still be told apart
+        let (pty_master, pty_slave): (Pipe, Pipe) = Pipe::open_pty()?;
         let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
-        let stdin_pipe: Pipe = match Pipe::open(&tmpdir.path().join("stdin.fifo")) {
-            Ok(p) => p,
-            Err(err) => return Err(err)
-        };
         let stderr_pipe: Pipe = match Pipe::open(&tmpdir.path().join("stderr.fifo")) {
             Ok(p) => p,
-            Err(err) => return Err(err)
+            Err(err) => {
+                ShellProc::close_pipes(&[&pty_master, &pty_slave]);
+                return Err(err)
+            }
         };
-        let stdout_pipe: Pipe = match Pipe::open(&tmpdir.path().join("stdout.fifo")) {
-            Ok(p) => p,
-            Err(err) => return Err(err)
+        //Notice shell termination as soon as it happens (if this fails, termination is still detected by polling)
+        let _ = sigchld::install();
+        let sigchld_seen: usize = sigchld::received();
+        //Exec pipe: closed on exec, used by the child to report exec errors
+        let (exec_rd, exec_wr): (RawFd, RawFd) = match nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC) {
+            Ok(fds) => fds,
+            Err(_) => {
+                ShellProc::close_pipes(&[&pty_master, &pty_slave, &stderr_pipe]);
+                return Err(ShellError::CouldNotStartProcess)
+            }
         };
         //Fork process
         match unsafe {nix::unistd::fork()} {
             Ok(nix::unistd::ForkResult::Parent { child, .. }) => {
+                //Wait for exec result
+                let _ = nix::unistd::close(exec_wr);
+                let exec_result: Result<(), nix::errno::Errno> = ShellProc::read_exec_result(exec_rd);
+                let _ = nix::unistd::close(exec_rd);
+                if let Err(errno) = exec_result {
+                    //Reap child
+                    let _ = nix::sys::wait::waitpid(child, None);
+                    ShellProc::close_pipes(&[&pty_master, &pty_slave, &stderr_pipe]);
+                    return Err(ShellError::CouldNotExec(errno))
+                }
                 //Prepare echo command
                 //FIXME: handle fish $status
-                let echo_command: String = format!("echo \"\x02$?;`pwd`;{}\x03\"\n", uuid);
+                //NOTE: user is read from $USER, so that no process is spawned for it after each command
+                let echo_command: String = format!("echo \"\x02{};$?;$USER;`pwd`;{}\x03\"\n", uuid, uuid);
                 let wrkdir: PathBuf = match std::env::current_dir() {
                     Err(_) => PathBuf::from("/"),
                     Ok(path) => PathBuf::from(path.as_path())
                 };
+                //Set the shell up before the first command (a failure only affects job control)
+                if let Some(init_command) = init_command {
+                    let _ = pty_master.write(init_command, 50);
+                }
                 //Return Shell Proc
                 Ok(ShellProc {
                     state: ShellProcState::Idle,
@@ -78,25 +122,45 @@ impl ShellProc {
                     exit_status: 0,
                     exec_time: Duration::from_millis(0),
                     wrkdir: wrkdir,
+                    username: None,
                     pid: child.as_raw(),
                     rc: 255,
                     stdout_cache: None,
+                    stdout_remainder: Vec::new(),
+                    stderr_remainder: Vec::new(),
+                    tracer: None,
+                    sigchld_seen,
                     start_time: Instant::now(),
                     echo_command: echo_command,
-                    stdin_pipe: stdin_pipe,
-                    stderr_pipe: stderr_pipe,
-                    stdout_pipe: stdout_pipe
+                    read_timeout: Duration::from_millis(50),
+                    read_buffer_size: 8192,
+                    pty_master,
+                    pty_slave,
+                    stderr_pipe
                 })
             },
             Ok(nix::unistd::ForkResult::Child) => {
-                std::process::exit(ShellProc::run(argv, stdin_pipe.fd, stderr_pipe.fd, stdout_pipe.fd));
+                let _ = nix::unistd::close(exec_rd);
+                std::process::exit(ShellProc::run(argv, &env, pty_master.fd, pty_slave.fd, stderr_pipe.fd, exec_wr));
             },
             Err(_) => {
+                let _ = nix::unistd::close(exec_rd);
+                let _ = nix::unistd::close(exec_wr);
+                ShellProc::close_pipes(&[&pty_master, &pty_slave, &stderr_pipe]);
                 return Err(ShellError::CouldNotStartProcess)
             }
         }
     }
 
+    /// ### close_pipes
+    /// 
+    /// Close and remove the pipes of a shell which couldn't be started
+    fn close_pipes(pipes: &[&Pipe]) {
+        for pipe in pipes.iter() {
+            let _ = pipe.close();
+        }
+    }
+
     /// ### cleanup
     /// 
     /// cleanup shell once exited. Returns the shell exit code
@@ -105,8 +169,8 @@ impl ShellProc {
             return Err(ShellError::ShellRunning)
         }
         //Close pipes
-        let _ = self.stdin_pipe.close();
-        let _ = self.stdout_pipe.close();
+        let _ = self.pty_master.close();
+        let _ = self.pty_slave.close();
         let _ = self.stderr_pipe.close();
         Ok(self.rc)
     }
@@ -121,12 +185,146 @@ impl ShellProc {
         }
     }
 
+    /// ### resize
+    /// 
+    /// Set the window size of the pseudo terminal. The kernel sends SIGWINCH to the foreground process group of the terminal
+    pub fn resize(&self, rows: u16, cols: u16) -> Result<(), ShellError> {
+        let winsize: nix::libc::winsize = nix::libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0
+        };
+        match unsafe { nix::libc::ioctl(self.pty_master.fd, nix::libc::TIOCSWINSZ, &winsize) } {
+            0 => Ok(()),
+            _ => Err(ShellError::PipeError(nix::errno::Errno::last()))
+        }
+    }
+
+    /// ### is_line_mode
+    /// 
+    /// Returns whether the terminal is in canonical mode, where input is read one line at a time, as edited by the terminal.
+    /// Programs which handle each key by themselves (e.g. editors) switch it off
+    pub fn is_line_mode(&self) -> bool {
+        match nix::sys::termios::tcgetattr(self.pty_slave.fd) {
+            Ok(termios) => termios.local_flags.contains(nix::sys::termios::LocalFlags::ICANON),
+            Err(_) => true
+        }
+    }
+
+    /// ### child_count
+    /// 
+    /// Returns the amount of running children of the shell (e.g. background jobs), looking for their parent pid in procfs.
+    /// Returns 0 if procfs is not available
+    pub fn child_count(&self) -> usize {
+        self.children().len()
+    }
+
+    /// ### raise_subprocess
+    /// 
+    /// Send signal to the running subprocess.
+    /// If the shell has job control, the subprocess is the foreground process group of the terminal;
+    /// otherwise subprocesses are in the process group of the shell, so the signal is sent to all the descendants of the shell
+    pub fn raise_subprocess(&self, signal: nix::sys::signal::Signal) -> Result<(), ShellError> {
+        if let Ok(pgrp) = nix::unistd::tcgetpgrp(self.pty_master.fd) {
+            if pgrp.as_raw() != self.pid {
+                return nix::sys::signal::killpg(pgrp, signal).map_err(|_| ShellError::CouldNotKill)
+            }
+        }
+        let descendants: Vec<i32> = self.descendants();
+        if descendants.is_empty() {
+            return Err(ShellError::CouldNotKill)
+        }
+        for pid in descendants.iter() {
+            //Processes may have terminated meanwhile
+            let _ = nix::sys::signal::kill(nix::unistd::Pid::from_raw(*pid), signal);
+        }
+        Ok(())
+    }
+
+    /// ### stop_subprocess
+    /// 
+    /// Stop the running subprocess sending SIGTSTP to the foreground process group of the terminal, as CTRL+Z does.
+    /// The shell reports the stop and gives the prompt back ('fg' resumes the subprocess);
+    /// a shell without job control would keep waiting for the stopped subprocess instead, so it's not stopped at all
+    pub fn stop_subprocess(&mut self) -> Result<(), ShellError> {
+        if self.update_state() != ShellProcState::SubprocessRunning {
+            return Err(ShellError::CouldNotKill)
+        }
+        match nix::unistd::tcgetpgrp(self.pty_master.fd) {
+            Ok(pgrp) if pgrp.as_raw() != self.pid => {
+                nix::sys::signal::killpg(pgrp, nix::sys::signal::Signal::SIGTSTP).map_err(|_| ShellError::CouldNotKill)
+            },
+            _ => Err(ShellError::NoJobControl)
+        }
+    }
+
+    /// ### children
+    /// 
+    /// Returns the pids of the children of the shell
+    fn children(&self) -> Vec<i32> {
+        ShellProc::processes().into_iter().filter(|(_, ppid)| *ppid == self.pid).map(|(pid, _)| pid).collect()
+    }
+
+    /// ### descendants
+    /// 
+    /// Returns the pids of the children of the shell and of their descendants (e.g. make and the compilers it runs)
+    fn descendants(&self) -> Vec<i32> {
+        let processes: Vec<(i32, i32)> = ShellProc::processes();
+        let mut descendants: Vec<i32> = Vec::new();
+        let mut parents: Vec<i32> = vec![self.pid];
+        while let Some(parent) = parents.pop() {
+            for (pid, _) in processes.iter().filter(|(_, ppid)| *ppid == parent) {
+                descendants.push(*pid);
+                parents.push(*pid);
+            }
+        }
+        descendants
+    }
+
+    /// ### processes
+    /// 
+    /// Returns pid and parent pid of the running processes, read from procfs. Empty if procfs is not available
+    fn processes() -> Vec<(i32, i32)> {
+        let entries = match std::fs::read_dir("/proc") {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_string_lossy().parse::<i32>().ok().map(|pid| (pid, entry.path())))
+            .filter_map(|(pid, path)| std::fs::read_to_string(path.join("stat")).ok().map(|stat| (pid, stat)))
+            .filter_map(|(pid, stat)| ShellProc::parent_pid(stat.as_str()).map(|ppid| (pid, ppid)))
+            .collect()
+    }
+
+    /// ### parent_pid
+    /// 
+    /// Get the parent pid from the content of /proc/<pid>/stat.
+    /// The process name may contain spaces and parentheses, so fields are split after the last ')'
+    fn parent_pid(stat: &str) -> Option<i32> {
+        let fields: &str = &stat[stat.rfind(')')? + 1..];
+        //Fields after the name are state and parent pid
+        fields.split_whitespace().nth(1)?.parse::<i32>().ok()
+    }
+
     /// ### kill
     /// 
     /// Kill shell sending SIGKILL
     pub fn kill(&self) -> Result<(), ShellError> {
         self.raise(nix::sys::signal::Signal::SIGKILL)
     }
+
+    /// ### terminate
+    /// 
+    /// Ask the shell to terminate sending SIGTERM.
+    /// Shells run their traps only once the pending read has returned, so an empty line is written to stdin too,
+    /// otherwise a shell waiting for input wouldn't handle the signal until the next command
+    pub fn terminate(&self) -> Result<(), ShellError> {
+        self.raise(nix::sys::signal::Signal::SIGTERM)?;
+        let _ = self.pty_master.write(String::from("\n"), 50);
+        Ok(())
+    }
     
     /// ### read
     /// 
@@ -136,15 +334,15 @@ impl ShellProc {
         if self.update_state() == ShellProcState::Terminated {
             return Err(ShellError::ShellTerminated)
         }*/
-        let stdout: Option<String> = match self.stdout_pipe.read(50, false) {
-            Ok(stdout) => self.parse_stdout(stdout),
+        let stdout: Option<String> = match self.pty_master.read(self.read_timeout.as_millis() as u64, false, self.read_buffer_size) {
+            Ok(stdout) => {
+                let stdout: Option<String> = self.trace_read(Direction::Stdout, stdout)?;
+                self.parse_stdout(stdout)
+            },
             Err(err) => return Err(err)
         };
-        let stderr: Option<String> = match self.stderr_pipe.read(50, false) {
-            Ok(stderr) => match stderr {
-                None => None,
-                Some(stderr) => Some(stderr)
-            },
+        let stderr: Option<String> = match self.stderr_pipe.read(self.read_timeout.as_millis() as u64, false, self.read_buffer_size) {
+            Ok(stderr) => self.trace_read(Direction::Stderr, stderr)?,
             Err(err) => return Err(err)
         };
         Ok((stdout, stderr))
@@ -159,31 +357,116 @@ impl ShellProc {
         }
         //Add echo command to data if shell state is Idle
         if self.state == ShellProcState::Idle {
-            //Replace data newline with ';'
-            while data.ends_with('\n') {
-                data.pop();
-            }
-            //Append semicolon to data
-            if ! data.ends_with(';') {
-                data.push(';');
-            }
+            data = ShellProc::terminate_command(data);
             //Append echo command to data
             data.push_str(self.echo_command.as_str());
             //Set state to running
             self.set_state_running();
         }
-        self.stdin_pipe.write(data, 5000)
+        if let Some(tracer) = self.tracer.as_mut() {
+            let _ = tracer.trace(Direction::Stdin, data.as_bytes());
+        }
+        self.pty_master.write(data, 5000)
+    }
+
+    /// ### terminate_command
+    /// 
+    /// Terminate the command, so that the echo command can be appended to it:
+    /// - trailing newlines are replaced by `;`
+    /// - blocks of more lines (e.g. heredocs) are terminated by a newline, since the last line (e.g. the delimiter) must be kept as it is
+    /// - commands run in background (`cmd &`) are already terminated (`&;` is a syntax error), while `&&` is terminated as any other command
+    fn terminate_command(mut data: String) -> String {
+        while data.ends_with('\n') {
+            data.pop();
+        }
+        if data.contains('\n') {
+            data.push('\n');
+        } else if ! data.ends_with(';') && (! data.ends_with('&') || data.ends_with("&&")) {
+            data.push(';');
+        }
+        data
+    }
+
+    /// ### set_read_timeout
+    /// 
+    /// Set the time output is waited for by `read` on stdout and stderr; if zero, pipes are just polled
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.read_timeout = timeout;
+    }
+
+    /// ### set_read_buffer_size
+    /// 
+    /// Set the initial size of the buffer used by `read`; the buffer grows if a read fills it
+    pub fn set_read_buffer_size(&mut self, size: usize) {
+        self.read_buffer_size = size;
+    }
+
+    /// ### set_tracer
+    /// 
+    /// Set the tracer the I/O exchanged with the shell is traced to; None stops tracing
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// ### trace_read
+    /// 
+    /// Trace the raw bytes read from the shell, if tracing is enabled, then convert them into a string.
+    /// An UTF-8 sequence split across two reads is kept and prepended to the next read
+    fn trace_read(&mut self, direction: Direction, data: Option<Vec<u8>>) -> Result<Option<String>, ShellError> {
+        match data {
+            Some(data) => {
+                if let Some(tracer) = self.tracer.as_mut() {
+                    let _ = tracer.trace(direction, data.as_slice());
+                }
+                let remainder: &mut Vec<u8> = match direction {
+                    Direction::Stderr => &mut self.stderr_remainder,
+                    _ => &mut self.stdout_remainder,
+                };
+                decode_utf8(remainder, data)
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// ### read_exec_result
+    /// 
+    /// Read the exec result reported by the child on the exec pipe.
+    /// The pipe is closed without data if exec succeeded, otherwise the child writes the errno
+    fn read_exec_result(exec_rd: RawFd) -> Result<(), nix::errno::Errno> {
+        let mut buf: [u8; 4] = [0; 4];
+        let mut bytes_read: usize = 0;
+        while bytes_read < buf.len() {
+            match nix::unistd::read(exec_rd, &mut buf[bytes_read..]) {
+                Ok(0) => break,
+                Ok(bytes) => bytes_read += bytes,
+                Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => continue,
+                Err(_) => break
+            }
+        }
+        match bytes_read {
+            4 => Err(nix::errno::Errno::from_i32(i32::from_ne_bytes(buf))),
+            _ => Ok(())
+        }
     }
 
     /// ### run
     /// 
-    /// Run method for thread
-    fn run(argv: Vec<String>, stdin: RawFd, stderr: RawFd, stdout: RawFd) -> i32 {
+    /// Run method for thread.
+    /// The child starts a new session, whose controlling terminal is the slave side of the pseudo terminal
+    fn run(argv: Vec<String>, env: &[(String, String)], pty_master: RawFd, pty_slave: RawFd, stderr: RawFd, exec_wr: RawFd) -> i32 {
+        //Make the pseudo terminal the controlling terminal of the child
+        let _ = nix::unistd::close(pty_master);
+        if nix::unistd::setsid().is_err() {
+            return 255
+        }
+        if unsafe { nix::libc::ioctl(pty_slave, nix::libc::TIOCSCTTY, 0) } == -1 {
+            return 255
+        }
         //Set child process stdout/stdin/stderr
-        if let Err(_) = nix::unistd::dup2(stdin, 0) {
+        if nix::unistd::dup2(pty_slave, 0).is_err() {
             return 255
         }
-        if let Err(_) = nix::unistd::dup2(stdout, 1) {
+        if nix::unistd::dup2(pty_slave, 1).is_err() {
             return 255
         }
         if let Err(_) = nix::unistd::dup2(stderr, 2) {
@@ -194,15 +477,140 @@ impl ShellProc {
         for arg in argv.iter() {
             c_argv.push(CString::new(arg.as_str()).unwrap());
         }
-        let mut c_argv_refs: Vec<&CStr> = Vec::with_capacity(c_argv.len());
-        for arg in c_argv.iter() {
-            c_argv_refs.push(arg);
-        }
+        //Prepare environment
+        let c_env: Vec<CString> = ShellProc::child_env(env);
+        //Look for the executable in the PATH of the child
+        let path: Option<OsString> = match env.iter().find(|(key, _)| key == "PATH") {
+            Some((_, path)) => Some(OsString::from(path)),
+            None => std::env::var_os("PATH"),
+        };
+        let exec: CString = match ShellProc::find_executable(argv[0].as_str(), path) {
+            Some(exec) => CString::new(exec.as_os_str().as_bytes()).unwrap(),
+            None => {
+                //Report command not found to parent
+                let errno: i32 = nix::errno::Errno::ENOENT as i32;
+                let _ = nix::unistd::write(exec_wr, &errno.to_ne_bytes());
+                return 255
+            }
+        };
         //Exec process
-        if let Err(_) = nix::unistd::execvp(c_argv_refs.get(0).unwrap(), c_argv_refs.as_slice()) {
+        if nix::unistd::execve(&exec, c_argv.as_slice(), c_env.as_slice()).is_err() {
+            //Report errno to parent
+            let errno: i32 = nix::errno::errno();
+            let _ = nix::unistd::write(exec_wr, &errno.to_ne_bytes());
             return 255
         }
-        return 0
+        0
+    }
+
+    /// ### needs_job_control_flags
+    /// 
+    /// Returns whether the shell must be started with the job control flags: only POSIX shells support them (e.g. fish doesn't)
+    /// and they mustn't override an interactive mode set in the arguments
+    fn needs_job_control_flags(argv: &[String]) -> bool {
+        if ! ShellProc::is_posix_shell(argv[0].as_str()) {
+            return false
+        }
+        //Options are before the first operand ('-c' takes the command string, which is not an option)
+        ! argv[1..].iter()
+            .take_while(|arg| arg.starts_with('-') || arg.starts_with('+'))
+            .filter(|arg| arg.as_str() != "-" && arg.as_str() != "--" && ! arg.starts_with("--"))
+            .any(|arg| arg[1..].contains('i'))
+    }
+
+    /// ### job_control_flags
+    /// 
+    /// Returns the flags which start the shell non interactive, but with job control ('-m').
+    /// Bash takes the terminal for job control from stderr, which is a pipe, unless it is interactive;
+    /// so it is started interactive, without rc file nor line editing (prompts are cleared through the environment)
+    fn job_control_flags(shell: &str) -> &'static [&'static str] {
+        match ShellProc::shell_name(shell) {
+            Some("bash") => &["--norc", "--noediting", "-i"],
+            _ => &["+i", "-m"]
+        }
+    }
+
+    /// ### init_command
+    /// 
+    /// Returns the command written to the shell once started.
+    /// With job control, the shell interrupts itself when the foreground subprocess is killed by SIGINT,
+    /// as if CTRL+C had been typed on its terminal: the empty trap keeps it alive (traps are reset in subprocesses).
+    /// An interactive bash doesn't export its prompts, nor keeps history, nor expands '!'
+    fn init_command(shell: &str, job_control_flags: bool) -> Option<String> {
+        if ! ShellProc::is_posix_shell(shell) {
+            return None
+        }
+        match (ShellProc::shell_name(shell), job_control_flags) {
+            (Some("bash"), true) => Some(String::from("export -n PS1 PS2; unset HISTFILE MAILCHECK; set +o history +H; trap : INT\n")),
+            _ => Some(String::from("trap : INT\n"))
+        }
+    }
+
+    /// ### is_posix_shell
+    /// 
+    /// Returns whether the shell is a POSIX shell
+    fn is_posix_shell(shell: &str) -> bool {
+        match ShellProc::shell_name(shell) {
+            Some(name) => POSIX_SHELLS.contains(&name),
+            None => false
+        }
+    }
+
+    /// ### shell_name
+    /// 
+    /// Returns the file name of the shell executable
+    fn shell_name(shell: &str) -> Option<&str> {
+        Path::new(shell).file_name().and_then(|name| name.to_str())
+    }
+
+    /// ### child_env
+    /// 
+    /// Returns the environment of the child process ("key=value"): the inherited environment, with the variables in `env` set
+    fn child_env(env: &[(String, String)]) -> Vec<CString> {
+        let mut c_env: Vec<CString> = Vec::new();
+        for (key, value) in std::env::vars_os() {
+            if env.iter().any(|(k, _)| key.as_bytes() == k.as_bytes()) {
+                continue;
+            }
+            let var: Vec<u8> = [key.as_bytes(), b"=", value.as_bytes()].concat();
+            if let Ok(var) = CString::new(var) {
+                c_env.push(var);
+            }
+        }
+        for (key, value) in env.iter() {
+            if let Ok(var) = CString::new(format!("{}={}", key, value)) {
+                c_env.push(var);
+            }
+        }
+        c_env
+    }
+
+    /// ### find_executable
+    /// 
+    /// Look for the executable in the directories of path, as execvp does.
+    /// If file contains a slash, it is returned as it is. Returns None if the executable doesn't exist
+    fn find_executable(file: &str, path: Option<OsString>) -> Option<PathBuf> {
+        if file.contains('/') {
+            return Some(PathBuf::from(file));
+        }
+        let path: OsString = path?;
+        let mut found: Option<PathBuf> = None;
+        for dir in std::env::split_paths(&path) {
+            let candidate: PathBuf = match dir.as_os_str().is_empty() {
+                true => PathBuf::from(file),
+                false => dir.join(file),
+            };
+            if let Ok(metadata) = std::fs::metadata(candidate.as_path()) {
+                if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 {
+                    return Some(candidate);
+                }
+                //Not executable: exec will report permission denied, unless an executable is found later
+                if found.is_none() && metadata.is_file() {
+                    found = Some(candidate);
+                }
+            }
+        }
+        found
     }
 
     /// ### update_state
@@ -227,6 +635,16 @@ impl ShellProc {
         self.state
     }
 
+    /// ### terminated_on_sigchld
+    /// 
+    /// Returns whether the shell has terminated. The shell state is checked only if a SIGCHLD has been received since the last call
+    pub fn terminated_on_sigchld(&mut self) -> bool {
+        if !sigchld::received_since(&mut self.sigchld_seen) {
+            return false;
+        }
+        self.update_state() == ShellProcState::Terminated
+    }
+
     /// ### parse_stdout
     /// 
     /// Parse stdout received from shell process
@@ -235,9 +653,14 @@ impl ShellProc {
             None => None,
             Some(stdout) => {
                 //Treat stdout
-                let termination_string: String = format!("{}\x03\n", self.uuid);
-                //Check if ends with this (\x02${?};${PWD};${UUID}\x03\n)
+                //Sentinel is \x02${UUID};${?};${USER};${PWD};${UUID}\x03\n
+                let start_marker: String = format!("\x02{};", self.uuid);
+                let end_marker: String = format!(";{}\x03\n", self.uuid);
                 //Create check string (cache + stdout)
+                let cache_len: usize = match &self.stdout_cache {
+                    Some(cache) => cache.len(),
+                    None => 0
+                };
                 let check_string: String = match &self.stdout_cache {
                     None => stdout.clone(),
                     Some(cache) => {
@@ -247,38 +670,40 @@ impl ShellProc {
                         s
                     }
                 };
-                //Check if string ends with termination string
-                if check_string.ends_with(termination_string.as_str()) {
-                    //It's the end of shell execution, split string in output and METADATA
-                    //Let's find the index of \x02
-                    let mut stx_index: usize = check_string.len();
-                    for c in check_string.chars().rev() {
-                        if c == '\x02' {
-                            break;
+                //Sentinel is valid only at the end of the output and only if enclosed by both markers
+                let metadata_end: usize = check_string.len().saturating_sub(end_marker.len());
+                let sentinel_start: Option<usize> = match check_string.ends_with(end_marker.as_str()) {
+                    true => match check_string[..metadata_end].rfind(start_marker.as_str()) {
+                        Some(idx) if idx + start_marker.len() <= metadata_end => Some(idx),
+                        _ => None
+                    },
+                    false => None
+                };
+                match sentinel_start {
+                    Some(sentinel_start) => {
+                        //It's the end of shell execution, split string in output and METADATA
+                        let metadata: String = String::from(&check_string[sentinel_start + start_marker.len()..metadata_end]);
+                        //Get stdout (what comes before the sentinel in the current read)
+                        let stdout: String = match sentinel_start > cache_len {
+                            true => String::from(&stdout[..sentinel_start - cache_len]),
+                            false => String::new()
+                        };
+                        //get metadata
+                        self.set_state_idle(metadata);
+                        //Clear cache
+                        self.stdout_cache = None;
+                        match stdout.len() {
+                            0 => None,
+                            _ => Some(stdout)
                         }
-                        stx_index -= 1; //Decrement STX index
+                    },
+                    None => {
+                        //Not a termination
+                        //Push stdout to cache
+                        self.stdout_cache = Some(stdout.clone());
+                        //Return stdout
+                        Some(stdout)
                     }
-                    let metadata: String = String::from(&check_string[stx_index..check_string.len() - 2]);
-                    //Get stdout
-                    let stx_index_stdout: usize = stx_index - match &self.stdout_cache {
-                        Some(s) => s.len(),
-                        None => 0
-                    };
-                    let stdout: String = String::from(&stdout[..stx_index_stdout - 1]);
-                    //get metadata
-                    self.set_state_idle(metadata);
-                    //Clear cache
-                    self.stdout_cache = None;
-                    match stdout.len() {
-                        0 => None,
-                        _ => Some(stdout)
-                    }
-                } else {
-                    //Not a termination
-                    //Push stdout to cache
-                    self.stdout_cache = Some(stdout.clone());
-                    //Return stdout
-                    Some(stdout)
                 }
             }
         }
@@ -288,10 +713,12 @@ impl ShellProc {
     /// 
     /// Parse metadata string and set state back to idle
     fn set_state_idle(&mut self, metadata: String) {
-        for (index, token) in metadata.split(";").enumerate() {
+        //Metadata: ${?};${USER};${PWD} (working directory may contain ';')
+        for (index, token) in metadata.splitn(3, ';').enumerate() {
             match index {
                 0 => self.exit_status = token.parse::<u8>().unwrap_or(255),
-                1 => self.wrkdir = PathBuf::from(token),
+                1 => self.username = ShellProc::metadata_value(token),
+                2 => self.wrkdir = PathBuf::from(token),
                 _ => continue
             }
         }
@@ -299,6 +726,16 @@ impl ShellProc {
         self.state = ShellProcState::Idle;
     }
 
+    /// ### metadata_value
+    /// 
+    /// Returns metadata value; None if empty (e.g. command not available in the shell)
+    fn metadata_value(token: &str) -> Option<String> {
+        match token.trim().len() {
+            0 => None,
+            _ => Some(String::from(token.trim()))
+        }
+    }
+
     /// ### set_state_running
     /// 
     /// Set state to running
@@ -317,6 +754,37 @@ impl Drop for ShellProc {
     }
 }
 
+/// ### decode_utf8
+/// 
+/// Convert data, preceeded by the remainder of the previous read, into a string.
+/// If data ends with an incomplete UTF-8 sequence, it is stored into remainder; other invalid sequences are an error
+fn decode_utf8(remainder: &mut Vec<u8>, data: Vec<u8>) -> Result<Option<String>, ShellError> {
+    let data: Vec<u8> = match remainder.is_empty() {
+        true => data,
+        false => {
+            let mut buf: Vec<u8> = std::mem::take(remainder);
+            buf.extend(data);
+            buf
+        }
+    };
+    match String::from_utf8(data) {
+        Ok(s) => Ok(Some(s)),
+        Err(err) => {
+            //error_len is None if the sequence is just incomplete
+            if err.utf8_error().error_len().is_some() {
+                return Err(ShellError::InvalidData);
+            }
+            let valid_up_to: usize = err.utf8_error().valid_up_to();
+            let mut data: Vec<u8> = err.into_bytes();
+            *remainder = data.split_off(valid_up_to);
+            match data.is_empty() {
+                true => Ok(None),
+                false => Ok(Some(String::from_utf8(data).unwrap())),
+            }
+        }
+    }
+}
+
 //@! Test module
 
 #[cfg(test)]
@@ -330,7 +798,7 @@ mod tests {
 
     #[test]
     fn test_process_start_stop() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")]).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
         println!("A new shell started with PID {}", shell_proc.pid);
         //Check shell parameters
         assert_eq!(shell_proc.state, ShellProcState::Idle);
@@ -341,7 +809,8 @@ mod tests {
         assert_eq!(shell_proc.rc, 255);
         assert_ne!(shell_proc.uuid.len(), 0);
         assert!(shell_proc.stdout_cache.is_none());
-        assert_eq!(shell_proc.echo_command, format!("echo \"\x02$?;`pwd`;{}\x03\"\n", shell_proc.uuid));
+        assert_eq!(shell_proc.echo_command, format!("echo \"\x02{};$?;$USER;`pwd`;{}\x03\"\n", shell_proc.uuid, shell_proc.uuid));
+        assert!(shell_proc.username.is_none());
         //Verify shell is still running
         sleep(Duration::from_millis(500));
         assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
@@ -358,55 +827,378 @@ mod tests {
 
     #[test]
     fn test_process_start_error() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("piroporopero")]).unwrap();
-        println!("A new shell started with PID {}", shell_proc.pid);
-        //Shell should have died
-        sleep(Duration::from_millis(1000));
-        assert_eq!(shell_proc.update_state(), ShellProcState::Terminated);
-        assert_eq!(shell_proc.rc, 255);
+        //Missing binary
+        let err: ShellError = ShellProc::start(vec![String::from("piroporopero")], &[]).err().unwrap();
+        assert_eq!(err, ShellError::CouldNotExec(nix::errno::Errno::ENOENT));
+        //Not executable file
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let script: PathBuf = tmpdir.path().join("shell.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        let err_noexec: ShellError = ShellProc::start(vec![String::from(script.to_str().unwrap())], &[]).err().unwrap();
+        assert_eq!(err_noexec, ShellError::CouldNotExec(nix::errno::Errno::EACCES));
+        //Messages are distinct
+        assert_ne!(err.to_string(), err_noexec.to_string());
+        //Empty argv
+        assert_eq!(ShellProc::start(vec![], &[]).err().unwrap(), ShellError::CouldNotStartProcess);
+    }
+
+    #[test]
+    fn test_process_close_pipes() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let stdin_path: PathBuf = tmpdir.path().join("stdin.fifo");
+        let stdout_path: PathBuf = tmpdir.path().join("stdout.fifo");
+        let stdin_pipe: Pipe = Pipe::open(&stdin_path).unwrap();
+        let stdout_pipe: Pipe = Pipe::open(&stdout_path).unwrap();
+        ShellProc::close_pipes(&[&stdin_pipe, &stdout_pipe]);
+        //Fifos are removed
+        assert!(!stdin_path.exists());
+        assert!(!stdout_path.exists());
+    }
+
+    #[test]
+    fn test_process_needs_job_control_flags() {
+        let argv = |args: &[&str]| -> Vec<String> { args.iter().map(|arg| String::from(*arg)).collect() };
+        assert!(ShellProc::needs_job_control_flags(&argv(&["sh"])));
+        assert!(ShellProc::needs_job_control_flags(&argv(&["/bin/bash", "-l"])));
+        assert!(ShellProc::needs_job_control_flags(&argv(&["zsh", "--login"])));
+        //Interactive mode set by the user
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["bash", "-i"])));
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["bash", "-li"])));
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["sh", "+i"])));
+        //Operands are not options
+        assert!(ShellProc::needs_job_control_flags(&argv(&["sh", "init.sh", "-i"])));
+        //Not POSIX shells
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["fish"])));
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["/usr/bin/nu"])));
+        assert!(!ShellProc::needs_job_control_flags(&argv(&["elvish"])));
+    }
+
+    #[test]
+    fn test_process_job_control_flags() {
+        assert_eq!(ShellProc::job_control_flags("sh"), &["+i", "-m"]);
+        assert_eq!(ShellProc::job_control_flags("/bin/dash"), &["+i", "-m"]);
+        assert_eq!(ShellProc::job_control_flags("/usr/bin/bash"), &["--norc", "--noediting", "-i"]);
+        //Init command
+        assert_eq!(ShellProc::init_command("sh", true).unwrap(), String::from("trap : INT\n"));
+        assert_eq!(ShellProc::init_command("bash", false).unwrap(), String::from("trap : INT\n"));
+        assert!(ShellProc::init_command("bash", true).unwrap().starts_with("export -n PS1 PS2;"));
+        assert!(ShellProc::init_command("fish", false).is_none());
+    }
+
+    #[test]
+    fn test_process_child_env() {
+        let env: Vec<CString> = ShellProc::child_env(&[(String::from("PATH"), String::from("/opt/bin:/bin")), (String::from("PYC_TEST"), String::from("a=b"))]);
+        assert!(env.contains(&CString::new("PATH=/opt/bin:/bin").unwrap()));
+        assert!(env.contains(&CString::new("PYC_TEST=a=b").unwrap()));
+        //Overridden variables appear once
+        assert_eq!(env.iter().filter(|var| var.as_bytes().starts_with(b"PATH=")).count(), 1);
+        //Inherited variables are kept
+        if let Some(home) = std::env::var_os("HOME") {
+            assert!(env.contains(&CString::new([b"HOME=", home.as_bytes()].concat()).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_process_find_executable() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let bin: PathBuf = tmpdir.path().join("pyc_exec");
+        std::fs::write(&bin, "#!/bin/sh\n").unwrap();
+        //Not executable yet
+        let path: OsString = OsString::from(format!("/this/does/not/exist:{}", tmpdir.path().display()));
+        assert_eq!(ShellProc::find_executable("pyc_exec", Some(path.clone())), Some(bin.clone()));
+        assert!(std::fs::set_permissions(bin.as_path(), std::fs::Permissions::from_mode(0o755)).is_ok());
+        assert_eq!(ShellProc::find_executable("pyc_exec", Some(path.clone())), Some(bin.clone()));
+        //Not found
+        assert!(ShellProc::find_executable("piroporopero", Some(path)).is_none());
+        assert!(ShellProc::find_executable("pyc_exec", None).is_none());
+        //Paths are not looked up
+        assert_eq!(ShellProc::find_executable("./pyc_exec", None), Some(PathBuf::from("./pyc_exec")));
+    }
+
+    #[test]
+    fn test_process_start_env() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[(String::from("PYC_TEST_ENV"), String::from("pyc"))]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.write(String::from("echo $PYC_TEST_ENV\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("pyc\n"));
+        //Pyc environment is untouched
+        assert!(std::env::var("PYC_TEST_ENV").is_err());
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_write_block() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Write a heredoc; the delimiter line mustn't be changed
+        assert!(shell_proc.write(String::from("cat <<EOF\nprivet\nEOF\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("privet\n"));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 0);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_tty() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Stdin must be a terminal
+        assert!(shell_proc.write(String::from("tty")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert!(stdout.unwrap().starts_with("/dev/"));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 0);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_child_count() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert_eq!(shell_proc.child_count(), 0);
+        //Start a background job
+        assert!(shell_proc.write(String::from("sleep 5 &")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.child_count(), 1);
+        //Kill the job
+        assert!(shell_proc.write(String::from("kill $!; wait")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.child_count(), 0);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_stop_subprocess() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.stop_subprocess().is_err());
+        //Grandchildren are stopped too; the shell reports the stop and gives the prompt back
+        assert!(shell_proc.write(String::from("sh -c 'sleep 30; true'")).is_ok());
+        sleep(Duration::from_millis(500));
+        let descendants: Vec<i32> = shell_proc.descendants();
+        assert_eq!(descendants.len(), 2);
+        assert!(shell_proc.stop_subprocess().is_ok());
+        sleep(Duration::from_millis(500));
+        let (_, stderr) = shell_proc.read().unwrap();
+        assert!(stderr.unwrap().contains("Stopped"));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 148);
+        assert!(descendants.iter().all(|pid| proc_state(*pid) == Some('T')));
+        //Resume it with 'fg'
+        assert!(shell_proc.write(String::from("fg")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::SubprocessRunning);
+        assert!(descendants.iter().all(|pid| proc_state(*pid) != Some('T')));
+        //Interrupt it; the shell survives
+        assert!(shell_proc.raise_subprocess(nix::sys::signal::Signal::SIGINT).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 130);
+        assert_eq!(shell_proc.descendants().len(), 0);
+        assert!(shell_proc.kill().is_ok());
+        //A shell without job control can't give the prompt back
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh"), String::from("+i")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.write(String::from("sleep 30")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell_proc.stop_subprocess().err().unwrap(), ShellError::NoJobControl);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_stop_subprocess_bash() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("bash")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Neither prompts nor history expansion
+        assert!(shell_proc.write(String::from("echo \"a!b\"")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell_proc.read().unwrap(), (Some(String::from("a!b\n")), None));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        //Stop and resume
+        assert!(shell_proc.write(String::from("sleep 30")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert!(shell_proc.stop_subprocess().is_ok());
+        sleep(Duration::from_millis(500));
+        let (_, stderr) = shell_proc.read().unwrap();
+        assert!(stderr.unwrap().contains("Stopped"));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 148);
+        assert!(shell_proc.write(String::from("fg")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::SubprocessRunning);
+        assert!(shell_proc.raise_subprocess(nix::sys::signal::Signal::SIGINT).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 130);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    fn proc_state(pid: i32) -> Option<char> {
+        let stat: String = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        stat[stat.rfind(')')? + 1..].split_whitespace().next()?.chars().next()
+    }
+
+    #[test]
+    fn test_process_is_line_mode() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.is_line_mode());
+        //A program reading each key
+        assert!(shell_proc.write(String::from("stty -icanon; sleep 30")).is_ok());
+        sleep(Duration::from_millis(500));
+        assert!(!shell_proc.is_line_mode());
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_resize() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.resize(30, 100).is_ok());
+        //Programs started by the shell get the new size: bash sets $COLUMNS and $LINES from the size of the terminal on its stderr,
+        //which is redirected to the terminal since stderr is a pipe
+        assert!(shell_proc.write(String::from("bash -c 'shopt -s checkwinsize; /bin/true; echo $COLUMNS $LINES' 2>&1")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("100 30\n"));
+        //Resize again
+        assert!(shell_proc.resize(40, 120).is_ok());
+        assert!(shell_proc.write(String::from("bash -c 'shopt -s checkwinsize; /bin/true; echo $COLUMNS $LINES' 2>&1")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("120 40\n"));
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_terminate_command() {
+        assert_eq!(ShellProc::terminate_command(String::from("ls -l\n")), String::from("ls -l;"));
+        assert_eq!(ShellProc::terminate_command(String::from("cd /tmp;")), String::from("cd /tmp;"));
+        //Background jobs are already terminated
+        assert_eq!(ShellProc::terminate_command(String::from("sleep 5 &\n")), String::from("sleep 5 &"));
+        assert_eq!(ShellProc::terminate_command(String::from("make &&")), String::from("make &&;"));
+        //Blocks keep their last line
+        assert_eq!(ShellProc::terminate_command(String::from("cat <<EOF\nprivet\nEOF\n")), String::from("cat <<EOF\nprivet\nEOF\n"));
+        //A background job doesn't keep the shell running
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        assert!(shell_proc.write(String::from("sleep 5 &\n")).is_ok());
+        sleep(Duration::from_millis(500));
+        let _ = shell_proc.read();
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert_eq!(shell_proc.exit_status, 0);
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_parent_pid() {
+        assert_eq!(ShellProc::parent_pid("1234 (sh) S 1000 1234 1234 34816"), Some(1000));
+        assert_eq!(ShellProc::parent_pid("1234 (my (weird) proc) R 42 1234"), Some(42));
+        assert_eq!(ShellProc::parent_pid("1234 (sh"), None);
+        assert_eq!(ShellProc::parent_pid(""), None);
+    }
+
+    #[test]
+    fn test_process_read_timeout() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Output is waited for on both stdout and stderr
+        shell_proc.set_read_timeout(Duration::from_millis(300));
+        let t_start: Instant = Instant::now();
+        assert_eq!(shell_proc.read().unwrap(), (None, None));
+        assert!(t_start.elapsed() >= Duration::from_millis(600));
+        //0 just polls
+        shell_proc.set_read_timeout(Duration::from_millis(0));
+        let t_start: Instant = Instant::now();
+        assert_eq!(shell_proc.read().unwrap(), (None, None));
+        assert!(t_start.elapsed() < Duration::from_millis(50));
+        //Available output is still read
+        assert!(shell_proc.write(String::from("echo privet")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("privet\n"));
+        assert!(shell_proc.kill().is_ok());
+    }
+
+    #[test]
+    fn test_process_read_burst() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        //Output bigger than the buffer is returned at once
+        shell_proc.set_read_buffer_size(1024);
+        assert!(shell_proc.write(String::from("head -c 16384 /dev/zero | tr '\\0' 'a'")).is_ok());
+        sleep(Duration::from_millis(500));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), "a".repeat(16384));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        assert!(shell_proc.kill().is_ok());
     }
 
     #[test]
     fn test_process_raise() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")]).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
         println!("A new shell started with PID {}", shell_proc.pid);
         //Verify shell is still running
         sleep(Duration::from_millis(500));
         assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
-        //Send SIGINT
+        //SIGINT is trapped, since the shell interrupts itself when the subprocess is interrupted
         assert!(shell_proc.raise(nix::sys::signal::Signal::SIGINT).is_ok());
         sleep(Duration::from_millis(500));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
+        //Send SIGTERM
+        assert!(shell_proc.raise(nix::sys::signal::Signal::SIGTERM).is_ok());
+        sleep(Duration::from_millis(500));
         assert_eq!(shell_proc.update_state(), ShellProcState::Terminated);
-        assert_eq!(shell_proc.rc, 2);
+        assert_eq!(shell_proc.rc, 15);
     }
 
     #[test]
     fn test_process_parse_metadata() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")]).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
         println!("A new shell started with PID {}", shell_proc.pid);
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Parse metadata
-        let metadata: String = String::from("128;/home;ee9ec814-a751-4329-850f-6d54d12c8a5c");
+        let metadata: String = String::from("128;root;/home");
         shell_proc.state = ShellProcState::SubprocessRunning;
         shell_proc.set_state_idle(metadata);
         //Verify metadata have been parsed successfully
         assert_eq!(shell_proc.exit_status, 128);
+        assert_eq!(shell_proc.username.as_ref().unwrap(), "root");
         assert_eq!(shell_proc.wrkdir, PathBuf::from("/home"));
         assert_eq!(shell_proc.state, ShellProcState::Idle);
+        //Working directory with ';' and missing user
+        let metadata: String = String::from("0;;/tmp/a;b");
+        shell_proc.state = ShellProcState::SubprocessRunning;
+        shell_proc.set_state_idle(metadata);
+        assert_eq!(shell_proc.exit_status, 0);
+        assert!(shell_proc.username.is_none());
+        assert_eq!(shell_proc.wrkdir, PathBuf::from("/tmp/a;b"));
         //Kill
         assert!(shell_proc.kill().is_ok());
     }
 
     #[test]
     fn test_process_parse_stdout() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")]).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
         println!("A new shell started with PID {}", shell_proc.pid);
         sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
         //Parse stdout when empty
         assert!(shell_proc.parse_stdout(None).is_none());
         //Parse stdout with metadata only (and parse theme)
         shell_proc.state = ShellProcState::SubprocessRunning;
-        assert!(shell_proc.parse_stdout(Some(format!("\x02128;/home;{}\x03\n", shell_proc.uuid))).is_none());
+        assert!(shell_proc.parse_stdout(Some(format!("\x02{};128;root;/home;{}\x03\n", shell_proc.uuid, shell_proc.uuid))).is_none());
         assert_eq!(shell_proc.exit_status, 128);
         assert_eq!(shell_proc.wrkdir, PathBuf::from("/home"));
         assert_eq!(shell_proc.state, ShellProcState::Idle);
@@ -417,7 +1209,7 @@ mod tests {
         assert_eq!(*shell_proc.stdout_cache.as_ref().unwrap(), String::from("HELLO\n"));
         //Parse stdout with everything
         shell_proc.state = ShellProcState::SubprocessRunning;
-        assert_eq!(shell_proc.parse_stdout(Some(format!("HELLO\n\x022;/tmp;{}\x03\n", shell_proc.uuid))).unwrap(), String::from("HELLO\n"));
+        assert_eq!(shell_proc.parse_stdout(Some(format!("HELLO\n\x02{};2;root;/tmp;{}\x03\n", shell_proc.uuid, shell_proc.uuid))).unwrap(), String::from("HELLO\n"));
         assert_eq!(shell_proc.exit_status, 2);
         assert_eq!(shell_proc.wrkdir, PathBuf::from("/tmp"));
         assert_eq!(shell_proc.state, ShellProcState::Idle);
@@ -426,9 +1218,39 @@ mod tests {
         assert!(shell_proc.kill().is_ok());
     }
 
+    #[test]
+    fn test_process_parse_stdout_lookalike() {
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        println!("A new shell started with PID {}", shell_proc.pid);
+        sleep(Duration::from_millis(500)); //DON'T REMOVE THIS SLEEP
+        shell_proc.state = ShellProcState::SubprocessRunning;
+        //Sentinel of another session
+        let lookalike: String = String::from("\x02ee9ec814-a751-4329-850f-6d54d12c8a5c;0;root;/;ee9ec814-a751-4329-850f-6d54d12c8a5c\x03\n");
+        assert_eq!(shell_proc.parse_stdout(Some(lookalike.clone())).unwrap(), lookalike);
+        assert_eq!(shell_proc.state, ShellProcState::SubprocessRunning);
+        //Session end marker without start marker
+        shell_proc.stdout_cache = None;
+        let lookalike: String = format!("\x020;/;{}\x03\n", shell_proc.uuid);
+        assert_eq!(shell_proc.parse_stdout(Some(lookalike.clone())).unwrap(), lookalike);
+        assert_eq!(shell_proc.state, ShellProcState::SubprocessRunning);
+        //Session sentinel not at the end of the output
+        shell_proc.stdout_cache = None;
+        let lookalike: String = format!("\x02{};0;root;/;{}\x03\nHELLO\n", shell_proc.uuid, shell_proc.uuid);
+        assert_eq!(shell_proc.parse_stdout(Some(lookalike.clone())).unwrap(), lookalike);
+        assert_eq!(shell_proc.state, ShellProcState::SubprocessRunning);
+        //Real sentinel after output with non-ascii characters
+        shell_proc.stdout_cache = None;
+        let output: String = format!("привет\n\x02{};0;root;/home/привет;{}\x03\n", shell_proc.uuid, shell_proc.uuid);
+        assert_eq!(shell_proc.parse_stdout(Some(output)).unwrap(), String::from("привет\n"));
+        assert_eq!(shell_proc.state, ShellProcState::Idle);
+        assert_eq!(shell_proc.wrkdir, PathBuf::from("/home/привет"));
+        //Kill
+        assert!(shell_proc.kill().is_ok());
+    }
+
     #[test]
     fn test_process_command() {
-        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")]).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
         println!("A new shell started with PID {}", shell_proc.pid);
         //Send a cd command
         assert!(shell_proc.write(String::from("cd /tmp\n")).is_ok());
@@ -445,6 +1267,8 @@ mod tests {
         assert_eq!(shell_proc.update_state(), ShellProcState::Idle);
         //Verify wrkdir is now /tmp/
         assert_eq!(shell_proc.wrkdir, PathBuf::from("/tmp"));
+        //Verify identity has been reported by the shell
+        assert_eq!(shell_proc.username, std::env::var("USER").ok());
         //Verify exit status
         assert_eq!(shell_proc.exit_status, 0);
         //Verify execution time
@@ -460,4 +1284,72 @@ mod tests {
         assert!(shell_proc.cleanup().is_ok());
     }
 
+    #[test]
+    fn test_process_decode_utf8() {
+        let mut remainder: Vec<u8> = Vec::new();
+        //'п' is 0xD0 0xBF; split it across two reads
+        assert_eq!(decode_utf8(&mut remainder, vec![b'a', 0xD0]).unwrap(), Some(String::from("a")));
+        assert_eq!(remainder, vec![0xD0]);
+        assert_eq!(decode_utf8(&mut remainder, vec![0xBF, b'b']).unwrap(), Some(String::from("пb")));
+        assert!(remainder.is_empty());
+        //Only an incomplete sequence
+        assert_eq!(decode_utf8(&mut remainder, vec![0xD0]).unwrap(), None);
+        assert_eq!(decode_utf8(&mut remainder, vec![0xBF]).unwrap(), Some(String::from("п")));
+        //Invalid sequence
+        assert!(decode_utf8(&mut remainder, vec![b'a', 0xFF, b'b']).is_err());
+    }
+
+    #[test]
+    fn test_process_read_split_utf8() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let file: PathBuf = tmpdir.path().join("cyrillic.txt");
+        //A leading ASCII character makes the 2-bytes characters straddle the read buffer boundary
+        let text: String = format!("a{}\n", "привет мир ".repeat(4096));
+        std::fs::write(file.as_path(), text.as_bytes()).unwrap();
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        sleep(Duration::from_millis(500));
+        assert!(shell_proc.write(format!("cat {}\n", file.display())).is_ok());
+        //Read until the command terminates
+        let mut output: String = String::new();
+        let t_start: Instant = Instant::now();
+        while shell_proc.update_state() != ShellProcState::Idle && t_start.elapsed() < Duration::from_secs(10) {
+            let (stdout, _) = shell_proc.read().unwrap();
+            if let Some(stdout) = stdout {
+                output.push_str(stdout.as_str());
+            }
+        }
+        if let (Some(stdout), _) = shell_proc.read().unwrap() {
+            output.push_str(stdout.as_str());
+        }
+        assert_eq!(output.as_bytes(), text.as_bytes());
+        assert!(shell_proc.kill().is_ok());
+        sleep(Duration::from_millis(500));
+        assert!(shell_proc.cleanup().is_ok());
+    }
+
+    #[test]
+    fn test_process_trace() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let trace_file: PathBuf = tmpdir.path().join("pyc.trace");
+        let mut shell_proc: ShellProc = ShellProc::start(vec![String::from("sh")], &[]).unwrap();
+        shell_proc.set_tracer(Some(Tracer::open(trace_file.as_path()).unwrap()));
+        println!("A new shell started with PID {}", shell_proc.pid);
+        //Write a command and read its output
+        assert!(shell_proc.write(String::from("echo pyc-trace-test\n")).is_ok());
+        sleep(Duration::from_millis(100));
+        let (stdout, _) = shell_proc.read().unwrap();
+        assert_eq!(stdout.unwrap(), String::from("pyc-trace-test\n"));
+        shell_proc.set_tracer(None);
+        //Both the write and the read must be in the trace file
+        let content: String = std::fs::read_to_string(trace_file.as_path()).unwrap();
+        println!("{}", content);
+        assert!(content.lines().any(|l| l.contains("] >> \"echo pyc-trace-test;echo ")));
+        assert!(content.lines().any(|l| l.contains("] << \"pyc-trace-test\\n")));
+        //Stop process
+        assert!(shell_proc.kill().is_ok());
+        sleep(Duration::from_millis(500));
+        assert_eq!(shell_proc.update_state(), ShellProcState::Terminated);
+        assert!(shell_proc.cleanup().is_ok());
+    }
+
 }
@@ -0,0 +1,103 @@
+//! ## SigChld
+//!
+//! `SigChld` provides the SIGCHLD handler used to notice as soon as possible that the shell has changed state
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+extern crate nix;
+
+use nix::libc::c_int;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static SIGCHLD_RECEIVED: AtomicUsize = AtomicUsize::new(0);
+static HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// ### handle_sigchld
+///
+/// SIGCHLD handler; it only counts the signals received, since nothing else is async-signal-safe here
+extern "C" fn handle_sigchld(_: c_int) {
+    SIGCHLD_RECEIVED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// ### install
+///
+/// Install the SIGCHLD handler. The handler is installed only once per process
+pub fn install() -> nix::Result<()> {
+    if HANDLER_INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let action: SigAction = SigAction::new(SigHandler::Handler(handle_sigchld), SaFlags::SA_RESTART, SigSet::empty());
+    match unsafe { signal::sigaction(Signal::SIGCHLD, &action) } {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            HANDLER_INSTALLED.store(false, Ordering::SeqCst);
+            Err(err)
+        }
+    }
+}
+
+/// ### received
+///
+/// Returns the amount of SIGCHLD received since the handler has been installed
+pub fn received() -> usize {
+    SIGCHLD_RECEIVED.load(Ordering::SeqCst)
+}
+
+/// ### received_since
+///
+/// Returns whether a SIGCHLD has been received since `seen` was updated; `seen` is updated.
+/// Since the signal is sent for any child, the caller must check whether its own child has changed state
+pub fn received_since(seen: &mut usize) -> bool {
+    let received: usize = received();
+    let changed: bool = received != *seen;
+    *seen = received;
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_sigchld_received_since() {
+        assert!(install().is_ok());
+        //Installing twice is fine
+        assert!(install().is_ok());
+        let mut seen: usize = received();
+        let mut child = Command::new("true").spawn().unwrap();
+        assert!(child.wait().unwrap().success());
+        //Counter is incremented by the handler
+        let deadline: Instant = Instant::now() + Duration::from_secs(1);
+        while received() == seen && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(received_since(&mut seen));
+        //Each caller keeps its own state
+        assert!(!received_since(&mut seen));
+    }
+}
@@ -0,0 +1,109 @@
+//! ## Trace
+//!
+//! `Trace` logs the raw I/O exchanged with the shell process, for debugging purposes
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+extern crate chrono;
+
+use chrono::Local;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// ### Direction
+///
+/// Direction describes whether data has been written to or read from the shell
+#[derive(Copy, Clone, PartialEq, std::fmt::Debug)]
+pub enum Direction {
+    Stdin,
+    Stdout,
+    Stderr,
+}
+
+/// ## Tracer
+///
+/// Tracer writes each chunk of bytes exchanged with the shell to a file, one line per chunk,
+/// with the local timestamp and the direction marker (`>>` stdin, `<<` stdout, `<!` stderr).
+/// Bytes are escaped, so control characters (e.g. the handshake markers) and invalid UTF-8 are kept visible
+#[derive(std::fmt::Debug)]
+pub struct Tracer {
+    file: File,
+}
+
+impl Tracer {
+    /// ### open
+    ///
+    /// Open the trace file at path; traces are appended to the file, so that more shells can be traced to the same file
+    pub fn open(path: &Path) -> io::Result<Tracer> {
+        Ok(Tracer {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+        })
+    }
+
+    /// ### trace
+    ///
+    /// Write data to trace file with its direction
+    pub fn trace(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let marker: &str = match direction {
+            Direction::Stdin => ">>",
+            Direction::Stdout => "<<",
+            Direction::Stderr => "<!",
+        };
+        let escaped: String = data.iter().flat_map(|b| std::ascii::escape_default(*b)).map(char::from).collect();
+        writeln!(
+            self.file,
+            "[{}] {} \"{}\"",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.6f"),
+            marker,
+            escaped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_proc_trace_tracer() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let path: std::path::PathBuf = tmpdir.path().join("pyc.trace");
+        let mut tracer: Tracer = Tracer::open(path.as_path()).unwrap();
+        assert!(tracer.trace(Direction::Stdin, "echo щ;\n".as_bytes()).is_ok());
+        assert!(tracer.trace(Direction::Stdout, b"hi\n\x02\xff").is_ok());
+        assert!(tracer.trace(Direction::Stderr, b"error").is_ok());
+        //Traces are appended
+        let mut tracer: Tracer = Tracer::open(path.as_path()).unwrap();
+        assert!(tracer.trace(Direction::Stdin, b"exit\n").is_ok());
+        let content: String = std::fs::read_to_string(path.as_path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("["));
+        assert!(lines[0].ends_with("] >> \"echo \\xd1\\x89;\\n\""));
+        assert!(lines[1].ends_with("] << \"hi\\n\\x02\\xff\""));
+        assert!(lines[2].ends_with("] <! \"error\""));
+        assert!(lines[3].ends_with("] >> \"exit\\n\""));
+    }
+}
@@ -26,9 +26,13 @@
 extern crate git2;
 
 use git2::Repository;
+use std::path::PathBuf;
 
 pub struct PromptCache {
-    git_cache: Option<Repository>,
+    wrkdir_cache: Option<PathBuf>,
+    git_cache: Option<Option<Repository>>, //Inner option is None if the working directory is not in a repository
+    git_dirty_cache: Option<bool>,
+    git_ahead_behind_cache: Option<Option<(usize, usize)>>,
 }
 
 impl PromptCache {
@@ -36,20 +40,49 @@ impl PromptCache {
     ///
     /// Instantiate a new Prompt cache object
     pub fn new() -> PromptCache {
-        PromptCache { git_cache: None }
+        PromptCache {
+            wrkdir_cache: None,
+            git_cache: None,
+            git_dirty_cache: None,
+            git_ahead_behind_cache: None,
+        }
     }
 
     /// ### invalidate
     ///
     /// Invalidate cache
     pub fn invalidate(&mut self) {
-        self.git_cache = None
+        self.wrkdir_cache = None;
+        self.git_cache = None;
+        self.invalidate_status();
+    }
+
+    /// ### invalidate_status
+    ///
+    /// Invalidate only the cached values which may change between two prompts in the same directory (e.g. the working tree status)
+    pub fn invalidate_status(&mut self) {
+        self.git_dirty_cache = None;
+        self.git_ahead_behind_cache = None;
+    }
+
+    /// ### cache_wrkdir
+    ///
+    /// Cache the working directory the cached values refer to
+    pub fn cache_wrkdir(&mut self, wrkdir: PathBuf) {
+        self.wrkdir_cache = Some(wrkdir);
+    }
+
+    /// ### get_cached_wrkdir
+    ///
+    /// Get the working directory the cached values refer to
+    pub fn get_cached_wrkdir(&self) -> Option<&PathBuf> {
+        self.wrkdir_cache.as_ref()
     }
 
     /// ### cache_git
     ///
-    /// Cache git repository
-    pub fn cache_git(&mut self, git_repo: Repository) {
+    /// Cache the result of the git repository lookup; None if the working directory is not in a repository
+    pub fn cache_git(&mut self, git_repo: Option<Repository>) {
         self.git_cache = Some(git_repo);
     }
 
@@ -58,10 +91,45 @@ impl PromptCache {
     /// Get git repository
     pub fn get_cached_git(&self) -> Option<&Repository> {
         match self.git_cache.as_ref() {
-            Some(g) => Some(&g),
-            None => None,
+            Some(Some(g)) => Some(g),
+            _ => None,
         }
     }
+
+    /// ### is_git_cached
+    ///
+    /// Returns whether the git repository has been looked up, even if none has been found
+    pub fn is_git_cached(&self) -> bool {
+        self.git_cache.is_some()
+    }
+
+    /// ### cache_git_dirty
+    ///
+    /// Cache the working tree status of the cached git repository
+    pub fn cache_git_dirty(&mut self, dirty: bool) {
+        self.git_dirty_cache = Some(dirty);
+    }
+
+    /// ### get_cached_git_dirty
+    ///
+    /// Get the cached working tree status
+    pub fn get_cached_git_dirty(&self) -> Option<bool> {
+        self.git_dirty_cache
+    }
+
+    /// ### cache_git_ahead_behind
+    ///
+    /// Cache the amount of commits the branch of the cached git repository is ahead and behind its upstream
+    pub fn cache_git_ahead_behind(&mut self, ahead_behind: Option<(usize, usize)>) {
+        self.git_ahead_behind_cache = Some(ahead_behind);
+    }
+
+    /// ### get_cached_git_ahead_behind
+    ///
+    /// Get the cached ahead/behind counts; the outer option is None if they haven't been cached
+    pub fn get_cached_git_ahead_behind(&self) -> Option<Option<(usize, usize)>> {
+        self.git_ahead_behind_cache
+    }
 }
 
 #[cfg(test)]
@@ -76,12 +144,35 @@ mod tests {
         let git_repo: Repository = Repository::init(tmpdir.path()).unwrap();
         let mut cache: PromptCache = PromptCache::new();
         //Cache repository
-        cache.cache_git(git_repo);
+        cache.cache_wrkdir(PathBuf::from(tmpdir.path()));
+        assert!(!cache.is_git_cached());
+        cache.cache_git(Some(git_repo));
         //Verify git cache is Some
         assert!(cache.get_cached_git().is_some());
+        assert!(cache.is_git_cached());
+        assert!(cache.get_cached_git_dirty().is_none());
+        cache.cache_git_dirty(true);
+        assert_eq!(cache.get_cached_git_dirty(), Some(true));
+        assert!(cache.get_cached_git_ahead_behind().is_none());
+        cache.cache_git_ahead_behind(None);
+        assert_eq!(cache.get_cached_git_ahead_behind(), Some(None));
+        //Invalidate status only
+        cache.invalidate_status();
+        assert!(cache.get_cached_git_dirty().is_none());
+        assert!(cache.get_cached_git_ahead_behind().is_none());
+        assert!(cache.get_cached_git().is_some());
+        assert_eq!(cache.get_cached_wrkdir(), Some(&PathBuf::from(tmpdir.path())));
         //Invalidate cache
         cache.invalidate();
         //Verify git is None
         assert!(cache.get_cached_git().is_none());
+        assert!(!cache.is_git_cached());
+        //Not in a repository
+        cache.cache_git(None);
+        assert!(cache.get_cached_git().is_none());
+        assert!(cache.is_git_cached());
+        assert!(cache.get_cached_wrkdir().is_none());
+        assert!(cache.get_cached_git_dirty().is_none());
+        assert!(cache.get_cached_git_ahead_behind().is_none());
     }
 }
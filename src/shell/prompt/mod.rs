@@ -23,18 +23,25 @@
 *
 */
 
+extern crate chrono;
 extern crate regex;
 
 mod cache;
 mod modules;
 
 use super::ShellProps;
-use crate::config::PromptConfig;
+use crate::config::{PromptConfig, PROMPT_MODULE_DURATION, PROMPT_MODULE_GIT, PROMPT_MODULE_RC};
 use crate::translator::ioprocessor::IOProcessor;
 use cache::PromptCache;
 use modules::*;
+use modules::git::{GitRepositoryFinder, RepositoryFinder};
 
+use chrono::format::{Item, StrftimeItems};
+use chrono::Local;
+use git2::Repository;
 use regex::Regex;
+use std::env;
+use std::path::{Component, Path, PathBuf};
 use std::time::Duration;
 
 const PROMPT_KEY_REGEX: &str = r"\$\{(.*?)\}";
@@ -44,17 +51,38 @@ const PROMPT_HOSTNAME: &str = "${HOSTNAME}";
 const PROMPT_WRKDIR: &str = "${WRKDIR}";
 const PROMPT_CMDTIME: &str = "${CMD_TIME}";
 const PROMPT_RC: &str = "${RC}";
+const PROMPT_RC_CODE: &str = "${RC_CODE}";
+const PROMPT_CMD_OUTPUT_LINES: &str = "${CMD_OUTPUT_LINES}";
+const PROMPT_SHELL_PID: &str = "${SHELL_PID}";
+const PROMPT_JOBS: &str = "${JOBS}";
+const PROMPT_TRANSLIT: &str = "${TRANSLIT}";
+const PROMPT_TIME_PREFIX: &str = "${TIME:";
+const PROMPT_CLOCK: &str = "${CLOCK}";
+const PROMPT_ENV_PREFIX: &str = "${ENV:";
+//Placeholder for the resolved values which must not be translated
+const PROMPT_UNTRANSLATABLE_PLACEHOLDER: char = '\u{E000}';
 
 /// ## ShellPrompt
 ///
 /// ShellPrompt is the struct which contains the current shell prompt configuration
 pub struct ShellPrompt {
     prompt_line: String,
+    first_prompt_line: Option<String>,
+    rprompt_line: Option<String>,
     translate: bool,
+    lang_flag_emoji: bool,
+    colors: bool, //Color keys are resolved to empty strings if the terminal doesn't support colors
     break_opt: Option<BreakOptions>,
     duration_opt: Option<DurationOptions>,
     rc_opt: Option<RcOptions>,
     git_opt: Option<GitOptions>,
+    translit_off: String,
+    clock_format: String,
+    wrkdir_home_tilde: bool,
+    wrkdir_max_components: usize,
+    jobs: bool, //Whether the jobs key is in any of the prompt lines; jobs are counted only in this case
+    repo_finder: Box<dyn RepositoryFinder>, //Looks for the repository of the working directory when it's not cached
+    untranslatable: Vec<String>, //Resolved values replaced by placeholders until the prompt line is translated
     cache: PromptCache,
 }
 
@@ -70,6 +98,11 @@ struct BreakOptions {
 /// DurationOptions is the struct which contains the current duration configuration
 struct DurationOptions {
     pub minimum: Duration,
+    pub label: String,
+    pub separator: String,
+    pub unit_ms: Option<String>,
+    pub unit_s: String,
+    pub unit_m: Option<String>,
 }
 
 /// ## RcOptions
@@ -87,7 +120,10 @@ struct GitOptions {
     pub branch: String,
     pub commit_ref_len: usize,
     pub commit_ref_prepend: Option<String>,
-    pub commit_ref_append: Option<String>
+    pub commit_ref_append: Option<String>,
+    pub dirty: String,
+    pub ahead: String,
+    pub behind: String,
 }
 
 impl ShellPrompt {
@@ -95,35 +131,63 @@ impl ShellPrompt {
     ///
     /// Instantiate a new ShellPrompt with the provided parameters
     pub(super) fn new(prompt_opt: &PromptConfig) -> ShellPrompt {
+        //Modules are enabled if their keys are in any of the prompt lines
+        let prompt_lines: String = format!(
+            "{}{}{}",
+            prompt_opt.prompt_line,
+            prompt_opt.first_prompt_line.as_deref().unwrap_or(""),
+            prompt_opt.rprompt_line.as_deref().unwrap_or("")
+        );
         let break_opt: Option<BreakOptions> = match prompt_opt.break_enabled {
             true => Some(BreakOptions::new(&prompt_opt.break_str)),
             false => None,
         };
         let duration_opt: Option<DurationOptions> =
-            match DurationOptions::should_enable(&prompt_opt.prompt_line) {
-                true => Some(DurationOptions::new(prompt_opt.min_duration)),
+            match DurationOptions::should_enable(&prompt_lines) && !prompt_opt.is_module_disabled(PROMPT_MODULE_DURATION) {
+                true => Some(DurationOptions::new(
+                    prompt_opt.min_duration,
+                    &prompt_opt.duration_label,
+                    &prompt_opt.duration_separator,
+                    &prompt_opt.duration_unit_ms,
+                    &prompt_opt.duration_unit_s,
+                    &prompt_opt.duration_unit_m
+                )),
                 false => None,
             };
-        let rc_opt: Option<RcOptions> = match RcOptions::should_enable(&prompt_opt.prompt_line) {
+        let rc_opt: Option<RcOptions> = match RcOptions::should_enable(&prompt_lines) && !prompt_opt.is_module_disabled(PROMPT_MODULE_RC) {
             true => Some(RcOptions::new(&prompt_opt.rc_ok, &prompt_opt.rc_err)),
             false => None,
         };
-        let git_opt: Option<GitOptions> = match GitOptions::should_enable(&prompt_opt.prompt_line) {
+        let git_opt: Option<GitOptions> = match GitOptions::should_enable(&prompt_lines) && !prompt_opt.is_module_disabled(PROMPT_MODULE_GIT) {
             true => Some(GitOptions::new(
                 &prompt_opt.git_branch,
                 prompt_opt.git_commit_ref,
                 &prompt_opt.git_commit_prepend,
-                &prompt_opt.git_commit_append
+                &prompt_opt.git_commit_append,
+                &prompt_opt.git_dirty,
+                &prompt_opt.git_ahead,
+                &prompt_opt.git_behind
             )),
             false => None,
         };
         ShellPrompt {
             prompt_line: prompt_opt.prompt_line.clone(),
+            first_prompt_line: prompt_opt.first_prompt_line.clone(),
+            rprompt_line: prompt_opt.rprompt_line.clone(),
             translate: prompt_opt.translate,
+            lang_flag_emoji: prompt_opt.lang_flag_emoji,
+            colors: colors::colors_supported(env::var("TERM").ok().as_deref(), env::var("COLORTERM").ok().as_deref()),
             break_opt: break_opt,
             duration_opt: duration_opt,
             rc_opt: rc_opt,
             git_opt: git_opt,
+            translit_off: prompt_opt.translit_off.clone(),
+            clock_format: prompt_opt.clock_format.clone(),
+            wrkdir_home_tilde: prompt_opt.wrkdir_home_tilde,
+            wrkdir_max_components: prompt_opt.wrkdir_max_components,
+            jobs: prompt_lines.contains(PROMPT_JOBS),
+            repo_finder: Box::new(GitRepositoryFinder),
+            untranslatable: Vec::new(),
             cache: PromptCache::new(),
         }
     }
@@ -132,42 +196,131 @@ impl ShellPrompt {
     ///
     /// get prompt line with resolved values
     pub(super) fn get_line(&mut self, shell_props: &ShellProps, processor: &IOProcessor) -> String {
-        let mut prompt_line: String = self.process_prompt(shell_props, processor);
-        //Translate prompt if necessary
+        let prompt_line: String = self.process_template(self.prompt_line.clone(), shell_props, processor);
+        self.translate_line(prompt_line, processor)
+    }
+
+    /// ### get_first_line
+    ///
+    /// get the first prompt line of the session with resolved values.
+    /// If the first prompt line is not configured, the prompt line is used
+    pub(super) fn get_first_line(&mut self, shell_props: &ShellProps, processor: &IOProcessor) -> String {
+        let prompt_line: String = match self.first_prompt_line.clone() {
+            Some(first_prompt_line) => self.process_template(first_prompt_line, shell_props, processor),
+            None => self.process_template(self.prompt_line.clone(), shell_props, processor),
+        };
+        self.translate_line(prompt_line, processor)
+    }
+
+    /// ### get_rline
+    ///
+    /// get the right prompt line with resolved values, if configured.
+    /// The right prompt is never broken
+    pub(super) fn get_rline(&mut self, shell_props: &ShellProps, processor: &IOProcessor) -> Option<String> {
+        let rprompt_line: String = self.rprompt_line.clone()?;
+        let rprompt_line: String = self.resolve_template(rprompt_line, shell_props, processor);
+        self.cache.invalidate_status();
+        Some(self.translate_line(rprompt_line, processor))
+    }
+
+    /// ### shows_jobs
+    ///
+    /// Returns whether the jobs of the shell are shown in the prompt
+    pub(super) fn shows_jobs(&self) -> bool {
+        self.jobs
+    }
+
+    /// ### clear_cache
+    ///
+    /// Invalidate the prompt cache; the git repository is discovered again on the next prompt
+    pub(super) fn clear_cache(&mut self) {
+        self.cache.invalidate();
+    }
+
+    /// ### translate_line
+    ///
+    /// Translate the processed prompt line, if necessary, then restore the values which must not be translated
+    fn translate_line(&mut self, mut prompt_line: String, processor: &IOProcessor) -> String {
+        //Translate prompt if necessary; each line is translated independently
         if self.translate {
-            prompt_line = processor.text_to_cyrillic(&prompt_line);
+            prompt_line = prompt_line
+                .split('\n')
+                .map(|line| processor.text_to_cyrillic(&line.to_string()))
+                .collect::<Vec<String>>()
+                .join("\n");
         }
         //Write prompt
+        self.restore_untranslatable(prompt_line)
+    }
+
+    /// ### restore_untranslatable
+    ///
+    /// Replace the placeholders in the prompt line with the values which must not be translated, in order
+    fn restore_untranslatable(&mut self, mut prompt_line: String) -> String {
+        for value in self.untranslatable.drain(..) {
+            prompt_line = prompt_line.replacen(PROMPT_UNTRANSLATABLE_PLACEHOLDER, value.as_str(), 1);
+        }
         prompt_line
     }
 
     /// ### process_prompt
     ///
-    /// Process prompt keys and resolve prompt line
+    /// Process prompt keys and resolve prompt line, without translating it
     /// Returns the processed prompt line
     /// This function is optimized to try to cache the previous values
+    #[cfg(test)]
     fn process_prompt(&mut self, shell_props: &ShellProps, processor: &IOProcessor) -> String {
-        let mut prompt_line: String = self.prompt_line.clone();
+        let prompt_line: String = self.process_template(self.prompt_line.clone(), shell_props, processor);
+        self.restore_untranslatable(prompt_line)
+    }
+
+    /// ### process_template
+    ///
+    /// Process the keys of the provided prompt line template
+    fn process_template(&mut self, prompt_line: String, shell_props: &ShellProps, processor: &IOProcessor) -> String {
+        let mut prompt_line: String = self.resolve_template(prompt_line, shell_props, processor);
+        //If break, break line
+        if let Some(brkopt) = &self.break_opt {
+            prompt_line += "\n";
+            prompt_line += brkopt.break_with.trim();
+        }
+        //Invalidate the values which may change between prompts; the repository is kept while the working directory doesn't change
+        self.cache.invalidate_status();
+        //Return prompt line
+        prompt_line
+    }
+
+    /// ### resolve_template
+    ///
+    /// Resolve the keys of the provided template; each line of the template is resolved and trimmed independently
+    fn resolve_template(&mut self, prompt_line: String, shell_props: &ShellProps, processor: &IOProcessor) -> String {
+        prompt_line
+            .split('\n')
+            .map(|line| self.resolve_template_line(line.to_string(), shell_props, processor))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// ### resolve_template_line
+    ///
+    /// Resolve the keys of a single line of the template and trim the result
+    fn resolve_template_line(&mut self, mut prompt_line: String, shell_props: &ShellProps, processor: &IOProcessor) -> String {
         //Iterate over keys through regex ```\${(.*?)}```
         lazy_static! {
             static ref RE: Regex = Regex::new(PROMPT_KEY_REGEX).unwrap();
         }
         for regex_match in RE.captures_iter(prompt_line.clone().as_str()) {
             let mtch: String = String::from(&regex_match[0]);
-            let replace_with: String = self.resolve_key(shell_props, processor, &mtch);
+            let mut replace_with: String = self.resolve_key(shell_props, processor, &mtch);
+            //Keep values which must not be translated (e.g. commit hash) out of the translation
+            if self.translate && !replace_with.is_empty() && ShellPrompt::is_untranslatable(mtch.as_str()) {
+                self.untranslatable.push(replace_with);
+                replace_with = PROMPT_UNTRANSLATABLE_PLACEHOLDER.to_string();
+            }
             prompt_line = prompt_line.replace(mtch.as_str(), replace_with.as_str());
         }
         //Trim prompt line
-        prompt_line = String::from(prompt_line.trim());
-        //If break, break line
-        if let Some(brkopt) = &self.break_opt {
-            prompt_line += "\n";
-            prompt_line += brkopt.break_with.trim();
-        }
-        //Invalidate cache
-        self.cache.invalidate();
-        //Return prompt line
-        prompt_line
+        String::from(prompt_line.trim())
     }
 
     /// ### resolve_key
@@ -184,9 +337,7 @@ impl ShellPrompt {
                 match &self.duration_opt {
                     Some(opt) => {
                         if shell_props.elapsed_time.as_millis() >= opt.minimum.as_millis() {
-                            let millis: u128 = shell_props.elapsed_time.as_millis();
-                            let secs: f64 = (millis as f64 / 1000 as f64) as f64;
-                            String::from(format!("took {:.1}s", secs))
+                            format!("{} {}", opt.label, opt.format(shell_props.elapsed_time))
                         } else {
                             String::from("")
                         }
@@ -198,13 +349,9 @@ impl ShellPrompt {
                 if self.git_opt.is_none() {
                     return String::from("");
                 }
-                //If repository is not cached, find repository
-                if self.cache.get_cached_git().is_none() {
-                    let repo_opt = git::find_repository(&shell_props.wrkdir);
-                    match repo_opt {
-                        Some(repo) => self.cache.cache_git(repo),
-                        None => return String::from(""),
-                    };
+                //If repository is not cached for the working directory, find repository
+                if !self.cache_repository(&shell_props.wrkdir) {
+                    return String::from("");
                 }
                 //Get branch (unwrap without fear; can't be None here)
                 let branch: String = match git::get_branch(self.cache.get_cached_git().unwrap()) {
@@ -222,13 +369,9 @@ impl ShellPrompt {
                 if self.git_opt.is_none() {
                     return String::from("");
                 }
-                //If repository is not cached, find repository
-                if self.cache.get_cached_git().is_none() {
-                    let repo_opt = git::find_repository(&shell_props.wrkdir);
-                    match repo_opt {
-                        Some(repo) => self.cache.cache_git(repo),
-                        None => return String::from(""),
-                    };
+                //If repository is not cached for the working directory, find repository
+                if !self.cache_repository(&shell_props.wrkdir) {
+                    return String::from("");
                 }
                 //Get commit (unwrap without fear; can't be None here)
                 match git::get_commit(
@@ -250,9 +393,69 @@ impl ShellPrompt {
                     None => String::from(""),
                 }
             }
+            modules::git::PROMPT_GIT_DIRTY => {
+                if self.git_opt.is_none() {
+                    return String::from("");
+                }
+                //If repository is not cached for the working directory, find repository
+                if !self.cache_repository(&shell_props.wrkdir) {
+                    return String::from("");
+                }
+                //Get status (unwrap without fear; can't be None here)
+                let dirty: bool = match self.cache.get_cached_git_dirty() {
+                    Some(dirty) => dirty,
+                    None => {
+                        let dirty: bool = git::is_dirty(self.cache.get_cached_git().unwrap());
+                        self.cache.cache_git_dirty(dirty);
+                        dirty
+                    }
+                };
+                match dirty {
+                    true => self.git_opt.as_ref().unwrap().dirty.clone(),
+                    false => String::from(""),
+                }
+            }
+            modules::git::PROMPT_GIT_AHEAD | modules::git::PROMPT_GIT_BEHIND => {
+                if self.git_opt.is_none() {
+                    return String::from("");
+                }
+                //If repository is not cached for the working directory, find repository
+                if !self.cache_repository(&shell_props.wrkdir) {
+                    return String::from("");
+                }
+                //Get ahead/behind counts (unwrap without fear; can't be None here)
+                let ahead_behind: Option<(usize, usize)> = match self.cache.get_cached_git_ahead_behind() {
+                    Some(ahead_behind) => ahead_behind,
+                    None => {
+                        let ahead_behind = git::ahead_behind(self.cache.get_cached_git().unwrap());
+                        self.cache.cache_git_ahead_behind(ahead_behind);
+                        ahead_behind
+                    }
+                };
+                let git_opt: &GitOptions = self.git_opt.as_ref().unwrap();
+                let (label, count): (&String, usize) = match (key.as_str(), ahead_behind) {
+                    (_, None) => return String::from(""),
+                    (modules::git::PROMPT_GIT_AHEAD, Some((ahead, _))) => (&git_opt.ahead, ahead),
+                    (_, Some((_, behind))) => (&git_opt.behind, behind),
+                };
+                match count {
+                    0 => String::from(""),
+                    _ => format!("{}{}", label, count),
+                }
+            }
+            PROMPT_CLOCK => ShellPrompt::format_time(self.clock_format.as_str()).unwrap_or_else(|| key.clone()),
+            PROMPT_CMD_OUTPUT_LINES => shell_props.output_lines.to_string(),
             PROMPT_HOSTNAME => shell_props.hostname.clone(),
-            modules::colors::PROMPT_KBLINK | modules::colors::PROMPT_KBLK | modules::colors::PROMPT_KBLU | modules::colors::PROMPT_KBOLD | modules::colors::PROMPT_KCYN | modules::colors::PROMPT_KGRN | modules::colors::PROMPT_KGRY | modules::colors::PROMPT_KMAG | modules::colors::PROMPT_KRED | modules::colors::PROMPT_KRST | modules::colors::PROMPT_KSELECT | modules::colors::PROMPT_KWHT | modules::colors::PROMPT_KYEL => colors::PromptColor::from_key(key.as_str()).to_string(),
-            modules::language::PROMPT_LANG => language::language_to_str(processor.language),
+            PROMPT_JOBS => match shell_props.jobs {
+                0 => String::new(),
+                jobs => jobs.to_string(),
+            },
+            modules::colors::PROMPT_KBLINK | modules::colors::PROMPT_KBLK | modules::colors::PROMPT_KBLU | modules::colors::PROMPT_KBOLD | modules::colors::PROMPT_KCYN | modules::colors::PROMPT_KGRN | modules::colors::PROMPT_KGRY | modules::colors::PROMPT_KMAG | modules::colors::PROMPT_KRED | modules::colors::PROMPT_KRST | modules::colors::PROMPT_KSELECT | modules::colors::PROMPT_KWHT | modules::colors::PROMPT_KYEL => match self.colors {
+                true => colors::PromptColor::from_key(key.as_str()).to_string(),
+                false => String::new(),
+            },
+            modules::language::PROMPT_LANG => language::language_to_str(processor.language, self.colors),
+            modules::language::PROMPT_LANG_FLAG => language::language_to_flag(processor.language, self.lang_flag_emoji),
             PROMPT_RC => match &self.rc_opt {
                 Some(opt) => match shell_props.exit_status {
                     0 => opt.ok.clone(),
@@ -260,11 +463,94 @@ impl ShellPrompt {
                 },
                 None => String::from(""),
             },
+            PROMPT_RC_CODE => shell_props.exit_status.to_string(),
+            PROMPT_SHELL_PID => shell_props.pid.to_string(),
+            PROMPT_TRANSLIT => match shell_props.translit_bypass {
+                true => self.translit_off.clone(),
+                false => String::new(),
+            },
             PROMPT_USER => shell_props.username.clone(),
-            PROMPT_WRKDIR => shell_props.wrkdir.as_path().display().to_string(),
+            PROMPT_WRKDIR => self.format_wrkdir(shell_props.wrkdir.as_path(), dirs::home_dir()),
+            _ if key.starts_with(PROMPT_TIME_PREFIX) => ShellPrompt::resolve_time(key),
+            _ if key.starts_with(PROMPT_ENV_PREFIX) => ShellPrompt::resolve_env(key),
             _ => key.clone(), //Keep unresolved keys
         }
     }
+
+    /// ### cache_repository
+    ///
+    /// Make sure the git repository of the provided working directory is cached; the cache is invalidated if the working
+    /// directory has changed since the last prompt. Directories which are not in a repository are cached too, so they're
+    /// looked up only once. Returns whether the working directory is inside a repository
+    fn cache_repository(&mut self, wrkdir: &PathBuf) -> bool {
+        if self.cache.get_cached_wrkdir() != Some(wrkdir) {
+            self.cache.invalidate();
+            self.cache.cache_wrkdir(wrkdir.clone());
+        }
+        if !self.cache.is_git_cached() {
+            let repo: Option<Repository> = self.repo_finder.find_repository(wrkdir);
+            self.cache.cache_git(repo);
+        }
+        self.cache.get_cached_git().is_some()
+    }
+
+    /// ### is_untranslatable
+    ///
+    /// Returns whether the value of the provided key must be kept unchanged when the prompt is translated
+    fn is_untranslatable(key: &str) -> bool {
+        matches!(key, modules::git::PROMPT_GIT_COMMIT | PROMPT_CLOCK) || key.starts_with(PROMPT_TIME_PREFIX) || key.starts_with(PROMPT_ENV_PREFIX)
+    }
+
+    /// ### resolve_time
+    ///
+    /// Resolve a ${TIME:<fmt>} key formatting the current local time with the provided strftime format.
+    /// If the format is not valid, the key is returned unchanged
+    fn resolve_time(key: &str) -> String {
+        let fmt: &str = &key[PROMPT_TIME_PREFIX.len()..key.len() - 1];
+        ShellPrompt::format_time(fmt).unwrap_or_else(|| String::from(key))
+    }
+
+    /// ### format_time
+    ///
+    /// Format the current local time with the provided strftime format.
+    /// Returns None if the format is not valid
+    fn format_time(fmt: &str) -> Option<String> {
+        //Check whether format is valid
+        if StrftimeItems::new(fmt).any(|item| item == Item::Error) {
+            return None;
+        }
+        Some(Local::now().format(fmt).to_string())
+    }
+
+    /// ### format_wrkdir
+    ///
+    /// Format the working directory for ${WRKDIR}: the home directory is replaced with '~' if wrkdir_home_tilde is set
+    /// and only the last wrkdir_max_components components are kept, if the path is longer than that
+    fn format_wrkdir(&self, wrkdir: &Path, home: Option<PathBuf>) -> String {
+        let mut path: PathBuf = wrkdir.to_path_buf();
+        if self.wrkdir_home_tilde {
+            if let Some(rel) = home.as_ref().and_then(|home| wrkdir.strip_prefix(home).ok()) {
+                path = match rel.as_os_str().is_empty() {
+                    true => PathBuf::from("~"),
+                    false => PathBuf::from("~").join(rel),
+                };
+            }
+        }
+        let components: Vec<Component> = path.components().filter(|c| matches!(c, Component::Normal(_))).collect();
+        if self.wrkdir_max_components > 0 && components.len() > self.wrkdir_max_components {
+            path = components[components.len() - self.wrkdir_max_components..].iter().collect();
+        }
+        path.display().to_string()
+    }
+
+    /// ### resolve_env
+    ///
+    /// Resolve a ${ENV:<name>} key with the value of the environment variable `name`.
+    /// If the variable is not set, the key is resolved to an empty string
+    fn resolve_env(key: &str) -> String {
+        let name: &str = &key[PROMPT_ENV_PREFIX.len()..key.len() - 1];
+        env::var(name).unwrap_or_default()
+    }
 }
 
 impl BreakOptions {
@@ -289,9 +575,39 @@ impl DurationOptions {
     /// ### new
     ///
     /// Instantiate a new DurationOptions with the provided parameters
-    pub fn new(min_duration: usize) -> DurationOptions {
+    pub fn new(min_duration: usize, label: &str, separator: &str, unit_ms: &Option<String>, unit_s: &str, unit_m: &Option<String>) -> DurationOptions {
         DurationOptions {
             minimum: Duration::from_millis(min_duration as u64),
+            label: label.to_string(),
+            separator: separator.to_string(),
+            unit_ms: unit_ms.clone(),
+            unit_s: unit_s.to_string(),
+            unit_m: unit_m.clone(),
+        }
+    }
+
+    /// ### format
+    ///
+    /// Format the duration with the configured decimal separator and unit labels.
+    /// Seconds have one decimal digit; durations shorter than a second are in milliseconds and
+    /// durations longer than a minute are split in minutes only if the unit label is configured
+    pub fn format(&self, elapsed: Duration) -> String {
+        let millis: u128 = elapsed.as_millis();
+        if let Some(unit_ms) = &self.unit_ms {
+            if millis < 1000 {
+                return format!("{}{}", millis, unit_ms);
+            }
+        }
+        //Round to tenths of second before splitting minutes, so that seconds never reach 60
+        let tenths: u128 = (millis + 50) / 100;
+        let (minutes, tenths): (u128, u128) = match self.unit_m {
+            Some(_) => (tenths / 600, tenths % 600),
+            None => (0, tenths),
+        };
+        let secs: String = format!("{}{}{}{}", tenths / 10, self.separator, tenths % 10, self.unit_s);
+        match (&self.unit_m, minutes) {
+            (Some(unit_m), minutes) if minutes > 0 => format!("{}{}{}", minutes, unit_m, secs),
+            _ => secs,
         }
     }
 }
@@ -320,18 +636,22 @@ impl GitOptions {
     ///
     /// helper which says if git module should be enabled
     pub fn should_enable(prompt_line: &String) -> bool {
-        prompt_line.contains(modules::git::PROMPT_GIT_BRANCH) || prompt_line.contains(modules::git::PROMPT_GIT_COMMIT)
+        prompt_line.contains(modules::git::PROMPT_GIT_BRANCH) || prompt_line.contains(modules::git::PROMPT_GIT_COMMIT) || prompt_line.contains(modules::git::PROMPT_GIT_DIRTY)
+            || prompt_line.contains(modules::git::PROMPT_GIT_AHEAD) || prompt_line.contains(modules::git::PROMPT_GIT_BEHIND)
     }
 
     /// ### new
     ///
     /// Instantiate a new GitOptions with the provided parameters
-    pub fn new(branch: &String, commit: usize, commit_prepend: &Option<String>, commit_append: &Option<String>) -> GitOptions {
+    pub fn new(branch: &str, commit: usize, commit_prepend: &Option<String>, commit_append: &Option<String>, dirty: &str, ahead: &str, behind: &str) -> GitOptions {
         GitOptions {
-            branch: branch.clone(),
+            branch: branch.to_string(),
             commit_ref_len: commit,
             commit_ref_prepend: commit_prepend.clone(),
-            commit_ref_append: commit_append.clone()
+            commit_ref_append: commit_append.clone(),
+            dirty: dirty.to_string(),
+            ahead: ahead.to_string(),
+            behind: behind.to_string(),
         }
     }
 }
@@ -344,9 +664,12 @@ mod tests {
     use crate::translator::ioprocessor::IOProcessor;
     use crate::translator::new_translator;
     use crate::translator::lang::Language;
+    use crate::utils::ansi::strip_ansi;
     use colors::PromptColor;
 
-    use git2::Repository;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
     use std::path::PathBuf;
     use std::time::Duration;
 
@@ -354,6 +677,7 @@ mod tests {
     fn test_prompt_simple() {
         let prompt_config_default = PromptConfig::default();
         let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        prompt.colors = true;
         let iop: IOProcessor = get_ioprocessor();
         let shellenv: ShellProps = get_shellenv();
         //Print first in latin
@@ -375,12 +699,198 @@ mod tests {
         println!("\n");
     }
 
+    #[test]
+    fn test_prompt_cmd_output_lines() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${CMD_OUTPUT_LINES}$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("0$"));
+        shellenv.output_lines = 128;
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("128$"));
+    }
+
+    #[test]
+    fn test_prompt_first_line() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${USER}$");
+        //First prompt line not set
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        assert_eq!(prompt.get_first_line(&shellenv, &iop), String::from("user$"));
+        //First prompt line set
+        prompt_config_default.first_prompt_line = Some(String::from("${USER} on ${HOSTNAME} ${RC}$"));
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert!(prompt.rc_opt.is_some());
+        assert_eq!(prompt.get_first_line(&shellenv, &iop), String::from("user on default ✔$"));
+        assert_eq!(prompt.get_line(&shellenv, &iop), String::from("user$"));
+    }
+
+    #[test]
+    fn test_prompt_rline() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${USER}$");
+        prompt_config_default.break_enabled = true;
+        //Right prompt not set
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        assert!(prompt.get_rline(&shellenv, &iop).is_none());
+        //Right prompt set; modules are enabled by its keys too
+        prompt_config_default.rprompt_line = Some(String::from(" ${RC} ${HOSTNAME} "));
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert!(prompt.rc_opt.is_some());
+        assert_eq!(prompt.get_rline(&shellenv, &iop), Some(String::from("✔ default")));
+        assert_eq!(prompt.get_line(&shellenv, &iop), String::from("user$\n❯"));
+    }
+
+    #[test]
+    fn test_prompt_multiline() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("  ${USER} on ${HOSTNAME}  \n ${RC} $ ");
+        prompt_config_default.break_enabled = true;
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert!(prompt.rc_opt.is_some());
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        //Each line is resolved and trimmed; then the break is appended
+        assert_eq!(prompt.get_line(&shellenv, &iop), String::from("user on default\n✔ $\n❯"));
+        //Each line is translated
+        prompt.translate = true;
+        assert_eq!(prompt.get_line(&shellenv, &iop), String::from("усер он дефаулт\n✔ $\n❯"));
+    }
+
+    #[test]
+    fn test_prompt_jobs() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("[${JOBS}]$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert!(prompt.shows_jobs());
+        assert!(!ShellPrompt::new(&PromptConfig::default()).shows_jobs());
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        //Empty if there are no jobs
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("[]$"));
+        shellenv.jobs = 2;
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("[2]$"));
+    }
+
+    #[test]
+    fn test_prompt_rc_code() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${RC_CODE}$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        //RC options are not required by RC_CODE
+        assert!(prompt.rc_opt.is_none());
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("0$"));
+        shellenv.exit_status = 42;
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("42$"));
+    }
+
+    #[test]
+    fn test_prompt_shell_pid() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${SHELL_PID}$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("1024$"));
+    }
+
+    #[test]
+    fn test_prompt_translit() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${USER} ${TRANSLIT}$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user $"));
+        shellenv.translit_bypass = true;
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user (translit off)$"));
+    }
+
+    #[test]
+    fn test_prompt_time() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${TIME:%H:%M}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        //Hours and minutes
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        println!("{}", prompt_line);
+        assert_eq!(prompt_line.len(), 5);
+        for (i, ch) in prompt_line.chars().enumerate() {
+            match i {
+                2 => assert_eq!(ch, ':'),
+                _ => assert!(ch.is_ascii_digit()),
+            }
+        }
+        //Date
+        prompt.prompt_line = String::from("[${TIME:%Y-%m-%d}]$");
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        println!("{}", prompt_line);
+        assert_eq!(prompt_line.len(), 13);
+        for (i, ch) in prompt_line.chars().enumerate() {
+            match i {
+                0 => assert_eq!(ch, '['),
+                5 | 8 => assert_eq!(ch, '-'),
+                11 => assert_eq!(ch, ']'),
+                12 => assert_eq!(ch, '$'),
+                _ => assert!(ch.is_ascii_digit()),
+            }
+        }
+        //Invalid format is kept unchanged
+        prompt.prompt_line = String::from("${TIME:%Q}$");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("${TIME:%Q}$"));
+    }
+
+    #[test]
+    fn test_prompt_clock() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("[${CLOCK}]$");
+        //Clock doesn't depend on the duration and rc modules
+        prompt_config_default.disabled_modules = vec![String::from("duration"), String::from("rc")];
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert!(prompt.duration_opt.is_none());
+        assert!(prompt.rc_opt.is_none());
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        //Default format
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line.len(), 11);
+        for (i, ch) in prompt_line.chars().enumerate() {
+            match i {
+                0 => assert_eq!(ch, '['),
+                3 | 6 => assert_eq!(ch, ':'),
+                9 => assert_eq!(ch, ']'),
+                10 => assert_eq!(ch, '$'),
+                _ => assert!(ch.is_ascii_digit()),
+            }
+        }
+        //Configured format
+        prompt_config_default.clock_format = String::from("%H%M");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line.len(), 7);
+        assert!(prompt_line[1..5].chars().all(|ch| ch.is_ascii_digit()));
+        //Invalid format is kept unchanged
+        prompt_config_default.clock_format = String::from("%Q");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("[${CLOCK}]$"));
+    }
+
     #[test]
     fn test_prompt_colors() {
         let mut prompt_config_default = PromptConfig::default();
         //Update prompt line
         prompt_config_default.prompt_line = String::from("${KRED}RED${KYEL}YEL${KBLU}BLU${KGRN}GRN${KWHT}WHT${KGRY}GRY${KBLK}BLK${KMAG}MAG${KCYN}CYN${KBOLD}BOLD${KBLINK}BLINK${KSELECT}SELECTED${KRST}");
         let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        prompt.colors = true;
         let iop: IOProcessor = get_ioprocessor();
         let shellenv: ShellProps = get_shellenv();
         //Print first in latin
@@ -410,6 +920,9 @@ mod tests {
         //Terminate shell at the end of a test
         //terminate_shell(&mut shellenv);
         println!("\n");
+        //Colors are not supported by the terminal (e.g. TERM=dumb)
+        prompt.colors = false;
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("REDYELBLUGRNWHTGRYBLKMAGCYNBOLDBLINKSELECTED"));
     }
 
     #[test]
@@ -419,6 +932,7 @@ mod tests {
         prompt_config_default.prompt_line = String::from("${LANG} ~ ${KYEL}${USER}${KRST} on ${KGRN}${HOSTNAME}${KRST} in ${KCYN}${WRKDIR}${KRST} ${KYEL}${CMD_TIME}${KRST}");
         prompt_config_default.break_enabled = true;
         let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        prompt.colors = true;
         let iop: IOProcessor = get_ioprocessor();
         let mut shellenv: ShellProps = get_shellenv();
         shellenv.elapsed_time = Duration::from_millis(5100);
@@ -432,7 +946,7 @@ mod tests {
         let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
         let expected_prompt_line = String::from(format!(
             "{} ~ {}{}{} on {}{}{} in {}{}{} {}took 5.1s{}\n❯",
-            language::language_to_str(Language::Russian),
+            language::language_to_str(Language::Russian, true),
             PromptColor::Yellow.to_string(),
             shellenv.username.clone(),
             PromptColor::Reset.to_string(),
@@ -451,6 +965,90 @@ mod tests {
         println!("\n");
     }
 
+    #[test]
+    fn test_prompt_time_format() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${CMD_TIME}");
+        prompt_config_default.min_duration = 0;
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        //Default: seconds only
+        shellenv.elapsed_time = Duration::from_millis(5100);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 5.1s"));
+        shellenv.elapsed_time = Duration::from_millis(340);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 0.3s"));
+        shellenv.elapsed_time = Duration::from_millis(75240);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 75.2s"));
+        shellenv.elapsed_time = Duration::from_millis(59960);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 60.0s"));
+        //Milliseconds and minutes
+        prompt_config_default.duration_unit_ms = Some(String::from("ms"));
+        prompt_config_default.duration_unit_m = Some(String::from("m"));
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        shellenv.elapsed_time = Duration::from_millis(340);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 340ms"));
+        shellenv.elapsed_time = Duration::from_millis(75240);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 1m15.2s"));
+        //Seconds are rounded before splitting minutes
+        shellenv.elapsed_time = Duration::from_millis(59940);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 59.9s"));
+        shellenv.elapsed_time = Duration::from_millis(59960);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 1m0.0s"));
+        shellenv.elapsed_time = Duration::from_millis(119960);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("took 2m0.0s"));
+        //Comma separator, localized units and label
+        prompt_config_default.duration_label = String::from("за");
+        prompt_config_default.duration_separator = String::from(",");
+        prompt_config_default.duration_unit_ms = Some(String::from("мс"));
+        prompt_config_default.duration_unit_s = String::from("с");
+        prompt_config_default.duration_unit_m = Some(String::from("м"));
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        shellenv.elapsed_time = Duration::from_millis(5100);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("за 5,1с"));
+        shellenv.elapsed_time = Duration::from_millis(340);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("за 340мс"));
+        shellenv.elapsed_time = Duration::from_millis(125000);
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("за 2м5,0с"));
+    }
+
+    #[test]
+    fn test_prompt_lang_flag() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${LANG_FLAG} ${USER}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line, format!("🇷🇺 {}", shellenv.username));
+        // Serbian
+        let iop: IOProcessor = IOProcessor::new(Language::Serbian, new_translator(Language::Serbian), HashMap::new()).unwrap();
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line, format!("🇷🇸 {}", shellenv.username));
+        // Emoji disabled
+        prompt_config_default.lang_flag_emoji = false;
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line, format!("RS {}", shellenv.username));
+    }
+
+    #[test]
+    fn test_prompt_lang() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("${LANG}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let shellenv: ShellProps = get_shellenv();
+        for lang in Language::all() {
+            let iop: IOProcessor = IOProcessor::new(lang, new_translator(lang), HashMap::new()).unwrap();
+            //Plain name without colors
+            prompt.colors = false;
+            assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(lang.native_name()));
+            //Painted name with colors
+            prompt.colors = true;
+            assert_eq!(strip_ansi(prompt.process_prompt(&shellenv, &iop).as_str()), String::from(lang.native_name()));
+        }
+    }
+
     #[test]
     fn test_prompt_git() {
         //Get current git info
@@ -516,6 +1114,175 @@ mod tests {
         println!("\n");
     }
 
+    #[test]
+    fn test_prompt_git_clear_cache() {
+        let repo: Repository = git::find_repository(&PathBuf::from("./")).unwrap();
+        let branch: String = git::get_branch(&repo).unwrap();
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${GIT_BRANCH}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from("./");
+        //Cache a stale repository (without commits)
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        prompt.cache.cache_wrkdir(shellenv.wrkdir.clone());
+        prompt.cache.cache_git(Some(Repository::init(tmpdir.path()).unwrap()));
+        //The stale repository is used
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(""));
+        //Cache the stale repository again, then clear the cache
+        prompt.cache.cache_git(Some(Repository::init(tmpdir.path()).unwrap()));
+        prompt.clear_cache();
+        assert!(prompt.cache.get_cached_git().is_none());
+        //The repository is discovered again
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("on {}", branch));
+    }
+
+    #[test]
+    fn test_prompt_git_cache_wrkdir() {
+        let repo: Repository = git::find_repository(&PathBuf::from("./")).unwrap();
+        let branch: String = git::get_branch(&repo).unwrap();
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${GIT_BRANCH}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from("./");
+        //The repository is discovered and kept after the prompt
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("on {}", branch));
+        assert!(prompt.cache.get_cached_git().is_some());
+        assert_eq!(prompt.cache.get_cached_wrkdir(), Some(&PathBuf::from("./")));
+        //Replace it with a stale repository (without commits): it's reused while the working directory doesn't change
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        prompt.cache.cache_git(Some(Repository::init(tmpdir.path()).unwrap()));
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(""));
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(""));
+        //Working directory changes; the repository is discovered again
+        shellenv.wrkdir = std::env::current_dir().unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("on {}", branch));
+        assert_eq!(prompt.cache.get_cached_wrkdir(), Some(&shellenv.wrkdir));
+        //Not in a repository
+        shellenv.wrkdir = PathBuf::from("/");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(""));
+        assert!(prompt.cache.get_cached_git().is_none());
+    }
+
+    #[test]
+    fn test_prompt_git_cache_lookups() {
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${GIT_BRANCH}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        let lookups: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+        prompt.repo_finder = Box::new(CountingRepositoryFinder { lookups: lookups.clone() });
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        //Not in a repository: the directory is looked up only once
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        shellenv.wrkdir = PathBuf::from(tmpdir.path());
+        for _ in 0..3 {
+            assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from(""));
+        }
+        assert_eq!(lookups.get(), 1);
+        //In a repository
+        shellenv.wrkdir = PathBuf::from("./");
+        let branch: String = git::get_branch(&git::find_repository(&shellenv.wrkdir).unwrap()).unwrap();
+        for _ in 0..3 {
+            assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("on {}", branch));
+        }
+        assert_eq!(lookups.get(), 2);
+        //Clearing the cache looks the directory up again
+        prompt.clear_cache();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("on {}", branch));
+        assert_eq!(lookups.get(), 3);
+    }
+
+    #[test]
+    fn test_prompt_git_dirty() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        Repository::init(tmpdir.path()).unwrap();
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${USER}${GIT_DIRTY}$");
+        prompt_config.git_dirty = String::from(" *");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        assert!(prompt.git_opt.is_some());
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from(tmpdir.path());
+        //Clean repository
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user$"));
+        //Add a file
+        std::fs::write(tmpdir.path().join("README.md"), "# Test repository\n").unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user *$"));
+        //Not in a repository
+        shellenv.wrkdir = PathBuf::from("/");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user$"));
+    }
+
+    #[test]
+    fn test_prompt_git_ahead_behind() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let repo: Repository = Repository::init(tmpdir.path()).unwrap();
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${USER} ${GIT_AHEAD}${GIT_BEHIND}$");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        assert!(prompt.git_opt.is_some());
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from(tmpdir.path());
+        //No upstream
+        let signature = git2::Signature::now("pyc", "pyc@localhost").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let first = repo.commit(Some("HEAD"), &signature, &signature, "First commit", &tree, &[]).unwrap();
+        let first = repo.find_commit(first).unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user $"));
+        //Set upstream
+        repo.branch("upstream", &first, false).unwrap();
+        let branch_name: String = git::get_branch(&repo).unwrap();
+        repo.find_branch(branch_name.as_str(), git2::BranchType::Local).unwrap().set_upstream(Some("upstream")).unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user $"));
+        //Ahead and behind
+        repo.commit(Some("HEAD"), &signature, &signature, "Second commit", &tree, &[&first]).unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user ↑1$"));
+        repo.commit(Some("refs/heads/upstream"), &signature, &signature, "Upstream commit", &tree, &[&first]).unwrap();
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), String::from("user ↑1↓1$"));
+    }
+
+    #[test]
+    fn test_prompt_git_commit_not_translated() {
+        let repo: Repository = git::find_repository(&PathBuf::from("./")).unwrap();
+        let commit: String = git::get_commit(&repo, 8).unwrap();
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${USER} ${GIT_COMMIT} ${ENV:PYC_TEST_PROMPT_UNTRANSLATED}$");
+        prompt_config.translate = true;
+        env::set_var("PYC_TEST_PROMPT_UNTRANSLATED", "venv");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from("./");
+        //User is translated, commit hash and env value are kept
+        assert_eq!(prompt.get_line(&shellenv, &iop), format!("усер {} venv$", commit));
+        assert!(prompt.untranslatable.is_empty());
+        //Resolved values are kept by process_prompt too
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("user {} venv$", commit));
+        env::remove_var("PYC_TEST_PROMPT_UNTRANSLATED");
+    }
+
+    #[test]
+    fn test_prompt_git_disabled() {
+        let mut prompt_config = PromptConfig::default();
+        prompt_config.prompt_line = String::from("${USER} ${GIT_BRANCH}${GIT_COMMIT}${RC}$");
+        prompt_config.disabled_modules = vec![String::from("git")];
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config);
+        assert!(prompt.git_opt.is_none());
+        assert!(prompt.rc_opt.is_some());
+        let iop: IOProcessor = get_ioprocessor();
+        let mut shellenv: ShellProps = get_shellenv();
+        shellenv.wrkdir = PathBuf::from("./");
+        //Git segment must be empty, even if we're in a repository
+        let prompt_line: String = prompt.process_prompt(&shellenv, &iop);
+        assert_eq!(prompt_line, format!("{} ✔$", shellenv.username));
+    }
+
     #[test]
     fn test_prompt_git_not_in_repo() {
         let mut prompt_config_default = PromptConfig::default();
@@ -605,6 +1372,47 @@ mod tests {
         println!("\n");
     }
 
+    #[test]
+    fn test_prompt_wrkdir() {
+        let mut prompt_config_default = PromptConfig::default();
+        let home: Option<PathBuf> = Some(PathBuf::from("/home/user"));
+        //Default: full path
+        let prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/user/projects/pyc"), home.clone()), String::from("/home/user/projects/pyc"));
+        //Home tilde
+        prompt_config_default.wrkdir_home_tilde = true;
+        let prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/user/projects/pyc"), home.clone()), String::from("~/projects/pyc"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/user"), home.clone()), String::from("~"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/username"), home.clone()), String::from("/home/username"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/tmp"), None), String::from("/tmp"));
+        //Truncate
+        prompt_config_default.wrkdir_max_components = 2;
+        let prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/user/projects/pyc/src"), home.clone()), String::from("pyc/src"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/home/user/projects"), home.clone()), String::from("~/projects"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/usr/bin"), home.clone()), String::from("/usr/bin"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/usr/local/bin"), home.clone()), String::from("local/bin"));
+        assert_eq!(prompt.format_wrkdir(Path::new("/"), home), String::from("/"));
+    }
+
+    #[test]
+    fn test_prompt_env() {
+        let mut prompt_config_default = PromptConfig::default();
+        prompt_config_default.prompt_line = String::from("(${ENV:PYC_TEST_PROMPT_ENV}) ${ENV:PYC_TEST_PROMPT_ENV_UNSET}${USER}");
+        let mut prompt: ShellPrompt = ShellPrompt::new(&prompt_config_default);
+        let iop: IOProcessor = get_ioprocessor();
+        let shellenv: ShellProps = get_shellenv();
+        env::set_var("PYC_TEST_PROMPT_ENV", "venv");
+        env::remove_var("PYC_TEST_PROMPT_ENV_UNSET");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("(venv) {}", shellenv.username));
+        //Value is read again at each prompt
+        env::set_var("PYC_TEST_PROMPT_ENV", "other");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("(other) {}", shellenv.username));
+        env::remove_var("PYC_TEST_PROMPT_ENV");
+        assert_eq!(prompt.process_prompt(&shellenv, &iop), format!("() {}", shellenv.username));
+    }
+
     #[test]
     fn test_prompt_unresolved() {
         let mut prompt_config_default = PromptConfig::default();
@@ -636,8 +1444,22 @@ mod tests {
         println!("\n");
     }
 
+    /// ### CountingRepositoryFinder
+    ///
+    /// Repository finder which counts the lookups
+    struct CountingRepositoryFinder {
+        lookups: Rc<Cell<usize>>,
+    }
+
+    impl RepositoryFinder for CountingRepositoryFinder {
+        fn find_repository(&self, wrkdir: &Path) -> Option<Repository> {
+            self.lookups.set(self.lookups.get() + 1);
+            git::find_repository(&wrkdir.to_path_buf())
+        }
+    }
+
     fn get_ioprocessor() -> IOProcessor {
-        IOProcessor::new(Language::Russian, new_translator(Language::Russian))
+        IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap()
     }
 
     fn get_shellenv() -> ShellProps {
@@ -646,7 +1468,11 @@ mod tests {
             username: String::from("user"),
             elapsed_time: Duration::from_secs(0),
             exit_status: 0,
-            wrkdir: PathBuf::from("/home/user/")
+            output_lines: 0,
+            wrkdir: PathBuf::from("/home/user/"),
+            pid: 1024,
+            jobs: 0,
+            translit_bypass: false
         }
     }
 }
@@ -90,6 +90,20 @@ impl ToString for PromptColor {
     }
 }
 
+/// ### colors_supported
+///
+/// Returns whether the terminal supports colors, given the values of `TERM` and `COLORTERM`.
+/// Colors are not supported by dumb terminals or if TERM is not set, unless COLORTERM is set
+pub fn colors_supported(term: Option<&str>, colorterm: Option<&str>) -> bool {
+    if colorterm.map(|c| !c.is_empty()).unwrap_or(false) {
+        return true;
+    }
+    match term {
+        Some(term) => !term.is_empty() && term != "dumb",
+        None => false,
+    }
+}
+
 impl PromptColor {
     pub fn from_key(key: &str) -> PromptColor {
         match key {
@@ -134,6 +148,16 @@ mod tests {
         assert_eq!(PromptColor::from_key("UnknownColor"), PromptColor::Reset);
     }
 
+    #[test]
+    fn test_prompt_colors_supported() {
+        assert!(colors_supported(Some("xterm-256color"), None));
+        assert!(colors_supported(Some("linux"), Some("")));
+        assert!(colors_supported(Some("dumb"), Some("truecolor")));
+        assert!(!colors_supported(Some("dumb"), None));
+        assert!(!colors_supported(Some(""), None));
+        assert!(!colors_supported(None, None));
+    }
+
     #[test]
     fn test_prompt_color_print() {
         assert_eq!(PromptColor::Red.to_string(), KRED);
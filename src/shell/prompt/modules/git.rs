@@ -25,12 +25,33 @@
 
 extern crate git2;
 
-use git2::Repository;
+use git2::{BranchType, Repository, StatusOptions};
 use std::path::{Path, PathBuf};
 
 //Keys
 pub(crate) const PROMPT_GIT_BRANCH: &str = "${GIT_BRANCH}";
 pub(crate) const PROMPT_GIT_COMMIT: &str = "${GIT_COMMIT}";
+pub(crate) const PROMPT_GIT_DIRTY: &str = "${GIT_DIRTY}";
+pub(crate) const PROMPT_GIT_AHEAD: &str = "${GIT_AHEAD}";
+pub(crate) const PROMPT_GIT_BEHIND: &str = "${GIT_BEHIND}";
+
+/// ### RepositoryFinder
+///
+/// RepositoryFinder looks for the git repository a directory belongs to
+pub trait RepositoryFinder {
+    fn find_repository(&self, wrkdir: &Path) -> Option<Repository>;
+}
+
+/// ### GitRepositoryFinder
+///
+/// GitRepositoryFinder looks for the repository with git discovery (see `find_repository`)
+pub struct GitRepositoryFinder;
+
+impl RepositoryFinder for GitRepositoryFinder {
+    fn find_repository(&self, wrkdir: &Path) -> Option<Repository> {
+        find_repository(&wrkdir.to_path_buf())
+    }
+}
 
 /// ### find_repository
 ///
@@ -72,6 +93,33 @@ pub fn get_commit(repository: &Repository, hashlen: usize) -> Option<String> {
     Some(bytes_to_hexstr(commit_oid.as_bytes(), hashlen))
 }
 
+/// ### is_dirty
+///
+/// Returns whether the working tree of the provided repository has uncommitted changes (untracked files included)
+pub fn is_dirty(repository: &Repository) -> bool {
+    let mut opts: StatusOptions = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    match repository.statuses(Some(&mut opts)) {
+        Ok(statuses) => !statuses.is_empty(),
+        Err(_) => false,
+    }
+}
+
+/// ### ahead_behind
+///
+/// Get the amount of commits the current branch is ahead and behind its upstream.
+/// Returns None if the current branch has no upstream
+pub fn ahead_behind(repository: &Repository) -> Option<(usize, usize)> {
+    let git_head = repository.head().ok()?;
+    if !git_head.is_branch() {
+        return None;
+    }
+    let local_oid = git_head.target()?;
+    let branch = repository.find_branch(git_head.shorthand()?, BranchType::Local).ok()?;
+    let upstream_oid = branch.upstream().ok()?.get().target()?;
+    repository.graph_ahead_behind(local_oid, upstream_oid).ok()
+}
+
 /// ### bytes_to_hexstr
 ///
 /// Convert bytes to hex string representation
@@ -134,6 +182,59 @@ mod tests {
         assert_eq!(commit.unwrap().len(), 8);
     }
 
+    #[test]
+    fn test_prompt_git_module_dirty() {
+        //Create temp directory
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let repo: Repository = Repository::init(tmpdir.path()).unwrap();
+        //Empty repository is clean
+        assert!(!is_dirty(&repo));
+        //Commit a file
+        let readme: PathBuf = tmpdir.path().join("README.md");
+        std::fs::write(&readme, "# Test repository\n").unwrap();
+        //Untracked file makes the tree dirty
+        assert!(is_dirty(&repo));
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("pyc", "pyc@localhost").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "First commit", &tree, &[]).unwrap();
+        assert!(!is_dirty(&repo));
+        //Modify file
+        std::fs::write(&readme, "# Test repository\n\nThis is a test repository\n").unwrap();
+        assert!(is_dirty(&repo));
+    }
+
+    #[test]
+    fn test_prompt_git_module_ahead_behind() {
+        //Create temp directory
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let repo: Repository = Repository::init(tmpdir.path()).unwrap();
+        //No commits
+        assert!(ahead_behind(&repo).is_none());
+        let signature = git2::Signature::now("pyc", "pyc@localhost").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let first = repo.commit(Some("HEAD"), &signature, &signature, "First commit", &tree, &[]).unwrap();
+        let first = repo.find_commit(first).unwrap();
+        //No upstream
+        assert!(ahead_behind(&repo).is_none());
+        //Track a local branch as upstream
+        repo.branch("upstream", &first, false).unwrap();
+        let branch_name: String = get_branch(&repo).unwrap();
+        let mut branch = repo.find_branch(branch_name.as_str(), BranchType::Local).unwrap();
+        branch.set_upstream(Some("upstream")).unwrap();
+        assert_eq!(ahead_behind(&repo), Some((0, 0)));
+        //Local commits
+        let second = repo.commit(Some("HEAD"), &signature, &signature, "Second commit", &tree, &[&first]).unwrap();
+        let second = repo.find_commit(second).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Third commit", &tree, &[&second]).unwrap();
+        assert_eq!(ahead_behind(&repo), Some((2, 0)));
+        //Upstream commits
+        repo.commit(Some("refs/heads/upstream"), &signature, &signature, "Upstream commit", &tree, &[&first]).unwrap();
+        assert_eq!(ahead_behind(&repo), Some((2, 1)));
+    }
+
     #[test]
     fn test_prompt_git_repo_not_found() {
         assert!(find_repository(&PathBuf::from("/")).is_none());
@@ -28,72 +28,50 @@ use crate::translator::lang::Language;
 use super::colors::PromptColor;
 
 pub(crate) const PROMPT_LANG: &str = "${LANG}";
+pub(crate) const PROMPT_LANG_FLAG: &str = "${LANG_FLAG}";
 
-pub fn language_to_str(language: Language) -> String {
-    let mut lang_str: String = language.to_string();
-    if lang_str.len() < 3 {
-        lang_str.push_str("   ");
+/// ### language_to_flag
+///
+/// Returns the flag of the country associated to the language.
+/// If emoji is false, or the language has no country, the text code is returned instead
+pub fn language_to_flag(language: Language, emoji: bool) -> String {
+    match language.country_code() {
+        Some(code) => match emoji {
+            //Regional indicator symbols
+            true => code
+                .chars()
+                .filter_map(|c| std::char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+                .collect(),
+            false => String::from(code),
+        },
+        None => language.to_string(),
     }
-    match language {
-        Language::Belarusian => String::from(format!(
-            "{}{}{}{}{}{}{}",
-            PromptColor::Red.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            PromptColor::Green.to_string(),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            PromptColor::White.to_string(),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        )),
-        Language::Bulgarian => String::from(format!(
-            "{}{}{}{}{}{}{}",
-            PromptColor::White.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            PromptColor::Green.to_string(),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            PromptColor::Red.to_string(),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        )),
-        Language::Russian => String::from(format!(
-            "{}{}{}{}{}{}{}",
-            PromptColor::White.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            PromptColor::Blue.to_string(),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            PromptColor::Red.to_string(),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        )),
-        Language::Serbian => String::from(format!(
-            "{}{}{}{}{}{}{}",
-            PromptColor::Red.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            PromptColor::Blue.to_string(),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            PromptColor::White.to_string(),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        )),
-        Language::Ukrainian => String::from(format!(
-            "{}{}{}{}{}{}{}",
-            PromptColor::Cyan.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            PromptColor::Yellow.to_string(),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            PromptColor::Cyan.to_string(),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        )),
-        Language::Nil => String::from(format!(
-            "{}{}{}{}{}",
-            PromptColor::Blink.to_string(),
-            lang_str.chars().nth(0).unwrap_or(' '),
-            lang_str.chars().nth(1).unwrap_or(' '),
-            lang_str.chars().nth(2).unwrap_or(' '),
-            PromptColor::Reset.to_string()
-        ))
+}
+
+/// ### language_to_str
+///
+/// Returns the name of the language (see `Language::native_name`), painted with the colors of its flag.
+/// If colors is false, the plain name is returned
+pub fn language_to_str(language: Language, colors: bool) -> String {
+    let name: &str = language.native_name();
+    if !colors {
+        return String::from(name);
+    }
+    let flag_colors: [PromptColor; 3] = match language {
+        Language::Belarusian => [PromptColor::Red, PromptColor::Green, PromptColor::White],
+        Language::Bulgarian => [PromptColor::White, PromptColor::Green, PromptColor::Red],
+        Language::Macedonian => [PromptColor::Red, PromptColor::Yellow, PromptColor::Red],
+        Language::Russian => [PromptColor::White, PromptColor::Blue, PromptColor::Red],
+        Language::Serbian => [PromptColor::Red, PromptColor::Blue, PromptColor::White],
+        Language::Ukrainian => [PromptColor::Cyan, PromptColor::Yellow, PromptColor::Cyan],
+        Language::Nil => return format!("{}{}{}", PromptColor::Blink.to_string(), name, PromptColor::Reset.to_string()),
+    };
+    let mut lang_str: String = String::new();
+    for (color, ch) in flag_colors.iter().zip(name.chars()) {
+        lang_str.push_str(color.to_string().as_str());
+        lang_str.push(ch);
     }
+    lang_str + PromptColor::Reset.to_string().as_str()
 }
 
 #[cfg(test)]
@@ -105,27 +83,43 @@ mod tests {
     fn test_prompt_lang_flag() {
         // Belarusian
         let expected_str = String::from("\x1b[31mб\x1b[32mе\x1b[37mл\x1b[0m");
-        println!("{}", language_to_str(Language::Belarusian));
-        assert_eq!(language_to_str(Language::Belarusian), expected_str);
+        println!("{}", language_to_str(Language::Belarusian, true));
+        assert_eq!(language_to_str(Language::Belarusian, true), expected_str);
         // Bulgarian
         let expected_str = String::from("\x1b[37mб\x1b[32mл\x1b[31mг\x1b[0m");
-        println!("{}", language_to_str(Language::Bulgarian));
-        assert_eq!(language_to_str(Language::Bulgarian), expected_str);
+        println!("{}", language_to_str(Language::Bulgarian, true));
+        assert_eq!(language_to_str(Language::Bulgarian, true), expected_str);
+        // Macedonian
+        let expected_str = String::from("\x1b[31mм\x1b[33mк\x1b[31mд\x1b[0m");
+        println!("{}", language_to_str(Language::Macedonian, true));
+        assert_eq!(language_to_str(Language::Macedonian, true), expected_str);
         // Russian
         let expected_str = String::from("\x1b[37mр\x1b[34mу\x1b[31mс\x1b[0m");
-        println!("{}", language_to_str(Language::Russian));
-        assert_eq!(language_to_str(Language::Russian), expected_str);
+        println!("{}", language_to_str(Language::Russian, true));
+        assert_eq!(language_to_str(Language::Russian, true), expected_str);
         // Serbian
         let expected_str = String::from("\x1b[31mс\x1b[34mр\x1b[37mб\x1b[0m");
-        println!("{}", language_to_str(Language::Serbian));
-        assert_eq!(language_to_str(Language::Serbian), expected_str);
+        println!("{}", language_to_str(Language::Serbian, true));
+        assert_eq!(language_to_str(Language::Serbian, true), expected_str);
         // Ukrainian
         let expected_str = String::from("\x1b[36mу\x1b[33mк\x1b[36mр\x1b[0m");
-        println!("{}", language_to_str(Language::Ukrainian));
-        assert_eq!(language_to_str(Language::Ukrainian), expected_str);
+        println!("{}", language_to_str(Language::Ukrainian, true));
+        assert_eq!(language_to_str(Language::Ukrainian, true), expected_str);
         // Nil
         let expected_str = String::from("\x1b[5mnil\x1b[0m");
-        println!("{}", language_to_str(Language::Nil));
-        assert_eq!(language_to_str(Language::Nil), expected_str);
+        println!("{}", language_to_str(Language::Nil, true));
+        assert_eq!(language_to_str(Language::Nil, true), expected_str);
+    }
+
+    #[test]
+    fn test_prompt_lang_flag_emoji() {
+        assert_eq!(language_to_flag(Language::Russian, true), String::from("🇷🇺"));
+        assert_eq!(language_to_flag(Language::Serbian, true), String::from("🇷🇸"));
+        assert_eq!(language_to_flag(Language::Ukrainian, true), String::from("🇺🇦"));
+        assert_eq!(language_to_flag(Language::Nil, true), String::from("nil"));
+        // Text fallback
+        assert_eq!(language_to_flag(Language::Russian, false), String::from("RU"));
+        assert_eq!(language_to_flag(Language::Belarusian, false), String::from("BY"));
+        assert_eq!(language_to_flag(Language::Nil, false), String::from("nil"));
     }
 }
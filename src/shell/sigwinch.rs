@@ -0,0 +1,84 @@
+//! ## SigWinch
+//!
+//! `SigWinch` provides the SIGWINCH handler used to notice that the terminal has been resized
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+extern crate nix;
+
+use super::unixsignal::UnixSignal;
+
+use nix::libc::c_int;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+static HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// ### handle_sigwinch
+///
+/// SIGWINCH handler; it only sets the resized flag
+extern "C" fn handle_sigwinch(_: c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+/// ### install
+///
+/// Install the SIGWINCH handler. The handler is installed only once per process
+pub fn install() -> nix::Result<()> {
+    if HANDLER_INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let action: SigAction = SigAction::new(SigHandler::Handler(handle_sigwinch), SaFlags::SA_RESTART, SigSet::empty());
+    match unsafe { signal::sigaction(UnixSignal::Sigwinch.to_nix_signal(), &action) } {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            HANDLER_INSTALLED.store(false, Ordering::SeqCst);
+            Err(err)
+        }
+    }
+}
+
+/// ### take_resized
+///
+/// Returns whether the terminal has been resized since the last call
+pub fn take_resized() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_sigwinch_take_resized() {
+        assert!(install().is_ok());
+        //Installing twice is fine
+        assert!(install().is_ok());
+        let _ = take_resized();
+        assert!(signal::raise(UnixSignal::Sigwinch.to_nix_signal()).is_ok());
+        assert!(take_resized());
+        //Flag is reset once taken
+        assert!(!take_resized());
+    }
+}
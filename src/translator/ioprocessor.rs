@@ -26,17 +26,26 @@
 extern crate regex;
 
 use regex::Regex;
+use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
 
 use super::Language;
 use super::Translator;
 
 const COLORS_ESCAPE_REGEX: &str = "\x1b\\[[0-9]{1,2}m";
+pub const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 64; //Maximum nesting of '(...)' blocks in an expression
+const OSC_ESCAPE_REGEX: &str = "\x1b\\][^\x07\x1b]*(\x07|\x1b\\\\)"; //Operating system command (e.g. title), terminated by BEL or ST
 
+#[derive(Clone)]
 pub struct IOProcessor {
-  translator: Box<dyn Translator>,
+  translator: Rc<dyn Translator>,
   pub language: Language,
   escape_colors_regex: Regex, //Escape regex as struct member to increase speed up to 500%
+  escape_osc_regex: Regex,
+  max_depth: usize, //Maximum nesting of expression blocks
+  overrides: HashMap<char, String>, //User-defined transliteration of single characters, consulted before the translator
+  translate_args: bool, //Translate the arguments of commands too, not only the command token
 }
 
 /// ### ExpressionParserError
@@ -46,6 +55,7 @@ pub struct IOProcessor {
 #[derive(Copy, Clone, PartialEq, fmt::Debug)]
 pub enum ExpressionParserError {
   MissingToken,
+  TooDeep,
 }
 
 /// ### ExpressionParserStates
@@ -58,6 +68,8 @@ struct ExpressionParserStates {
   escape_block: bool, //Check if we're inside an escaped block (hey, keep out for expressions though)
   backslash: bool,    //Check if backslash is active
   in_expression: bool, //Check is we're inside an expression
+  in_command: bool,   //Check if the command token has started
+  in_args: bool,      //Check if we're past the command token (the arguments are kept as they are, if args are not translated)
   previous_state: Option<Box<ExpressionParserStates>>, //Reference to previous state
 }
 
@@ -74,22 +86,43 @@ enum ExpressionConversion {
 impl IOProcessor {
   /// ### new
   ///
-  /// Instantiates a new IOProcessor with the provided translator
-  pub fn new(language: Language, translator: Box<dyn Translator>) -> IOProcessor {
-    let this_lang_regex: String =
-      String::from(translator.to_cyrillic(&String::from(COLORS_ESCAPE_REGEX)));
-    let re: Regex = Regex::new(this_lang_regex.as_str()).unwrap();
-    IOProcessor {
-      translator: translator,
+  /// Instantiates a new IOProcessor with the provided translator.
+  /// Overrides map single characters to the sequence they must be transliterated into, taking precedence over the translator:
+  /// cyrillic (non-ASCII) keys are used when converting to latin, ASCII keys when converting to cyrillic.
+  /// Returns error if the colors escape regex, once translated, is not a valid regex
+  pub fn new(language: Language, translator: Box<dyn Translator>, overrides: HashMap<char, String>) -> Result<IOProcessor, regex::Error> {
+    let this_lang_regex: String = apply_overrides(&String::from(COLORS_ESCAPE_REGEX), &overrides, false, |text| translator.to_cyrillic(text));
+    let re: Regex = Regex::new(this_lang_regex.as_str())?;
+    Ok(IOProcessor {
+      translator: Rc::from(translator),
       language: language,
       escape_colors_regex: re,
-    }
+      escape_osc_regex: Regex::new(OSC_ESCAPE_REGEX)?,
+      max_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
+      overrides,
+      translate_args: true,
+    })
+  }
+
+  /// ### set_max_depth
+  ///
+  /// Set the maximum nesting of expression blocks; deeper expressions are rejected with `TooDeep`
+  pub fn set_max_depth(&mut self, max_depth: usize) {
+    self.max_depth = max_depth;
+  }
+
+  /// ### set_translate_args
+  ///
+  /// Set whether the arguments of the commands are translated; if not, only the command token is translated in expressions
+  pub fn set_translate_args(&mut self, translate_args: bool) {
+    self.translate_args = translate_args;
   }
 
   /// ### expression_to_latin
   ///
   /// Converts a cyrillic expression into a latin string ready to be performed as a shell process
-  /// An expression must care of backslashes, escapes and inner expressions '(...)'
+  /// An expression must care of backslashes, escapes and inner expressions '(...)'.
+  /// The translator command table is used, so the output is always ASCII (e.g. with ISO 9 selected)
   pub fn expression_to_latin(&self, expression: &String) -> Result<String, ExpressionParserError> {
     self.translate_expression(&expression, ExpressionConversion::ToLatin)
   }
@@ -103,14 +136,32 @@ impl IOProcessor {
   ///
   /// Converts a cyrillic text into latin using the provided translator
   pub fn text_to_latin(&self, text: &String) -> String {
-    self.translator.to_latin(text)
+    self.translate_to_latin(text)
   }
 
   /// ### text_to_cyrillic
   ///
-  /// Converts a latin text into cyrillic using the provided translator
+  /// Converts a latin text into cyrillic using the provided translator.
+  /// OSC sequences (e.g. terminal title) are kept verbatim
   pub fn text_to_cyrillic(&self, text: &String) -> String {
-    self.escape_cyrillic(self.translator.to_cyrillic(text))
+    //Fast path: nothing to translate (e.g. digits, punctuation or cyrillic text)
+    if !text.chars().any(|c| is_translatable(c) || self.overrides.contains_key(&c)) {
+      return text.clone();
+    }
+    //Fast path: no OSC sequence in text
+    if !text.contains("\x1b]") {
+      return self.escape_cyrillic(self.translate_to_cyrillic(text));
+    }
+    let mut res: String = String::with_capacity(text.len());
+    let mut last: usize = 0;
+    for osc in self.escape_osc_regex.find_iter(text.as_str()) {
+      //Translate text before the OSC sequence, then push the sequence as is
+      res.push_str(self.escape_cyrillic(self.translate_to_cyrillic(&String::from(&text[last..osc.start()]))).as_str());
+      res.push_str(osc.as_str());
+      last = osc.end();
+    }
+    res.push_str(self.escape_cyrillic(self.translate_to_cyrillic(&String::from(&text[last..]))).as_str());
+    res
   }
 
   /// ### translate_expression
@@ -119,18 +170,24 @@ impl IOProcessor {
   fn translate_expression(&self, expression: &String, conversion: ExpressionConversion) -> Result<String, ExpressionParserError> {
     //Instantiate a new Parser State
     let mut states: ExpressionParserStates = ExpressionParserStates::new(None);
+    let mut depth: usize = 0;
     //Iterate over input
     for c in expression.chars() {
       //If character is '(' an expression block starts (if backlsash is disabled)
       if c == '(' && !states.backslash {
+        //Too many nested expressions
+        depth += 1;
+        if depth > self.max_depth {
+          return Err(ExpressionParserError::TooDeep);
+        }
         //Set escape to false
         states.escape_block = false;
         //Convert current expression to latin and push it to text
         states.text.push_str(
           match conversion {
-            ExpressionConversion::ToLatin => self.translator.to_latin(&states.expression_token),
+            ExpressionConversion::ToLatin => self.token_to_latin(&states.expression_token, &mut states.in_args, &mut states.in_command),
             ExpressionConversion::ToCyrillic => {
-              self.translator.to_cyrillic(&states.expression_token)
+              self.translate_to_cyrillic(&states.expression_token)
             }
           }.as_str(),
         );
@@ -152,10 +209,10 @@ impl IOProcessor {
         states.text.push_str(
           match conversion {
             ExpressionConversion::ToLatin => {
-              self.translator.to_latin(&states.expression_token.clone())
+              self.token_to_latin(&states.expression_token, &mut states.in_args, &mut states.in_command)
             }
             ExpressionConversion::ToCyrillic => {
-              self.translator.to_cyrillic(&states.expression_token.clone())
+              self.translate_to_cyrillic(&states.expression_token.clone())
             }
           }.as_str(),
         );
@@ -171,6 +228,7 @@ impl IOProcessor {
           Some(_) => states.restore_previous_state(),
           None => return Err(ExpressionParserError::MissingToken),
         };
+        depth -= 1;
         //Push converted expression to previous state's text
         states.text.push_str(expression_output.as_str());
         continue;
@@ -180,6 +238,10 @@ impl IOProcessor {
       if c == '"' && !states.backslash {
         if states.escape_block {
           //Escape block ends, push current token to text WITHOUT CONVERTING IT
+          //A quoted command token is part of the command
+          if !states.in_args {
+            states.in_command = true;
+          }
           //Push quote to expression token
           states.expression_token.push(c);
           //Push expression token to text without converting it
@@ -191,9 +253,9 @@ impl IOProcessor {
           //Convert and then Push current expression token to text
           states.text.push_str(
             match conversion {
-              ExpressionConversion::ToLatin => self.translator.to_latin(&states.expression_token),
+              ExpressionConversion::ToLatin => self.token_to_latin(&states.expression_token, &mut states.in_args, &mut states.in_command),
               ExpressionConversion::ToCyrillic => {
-                self.translator.to_cyrillic(&states.expression_token)
+                self.translate_to_cyrillic(&states.expression_token)
               }
             }.as_str(),
           );
@@ -221,8 +283,8 @@ impl IOProcessor {
       //Push last expression token to text
     states.text.push_str(
       match conversion {
-        ExpressionConversion::ToLatin => self.translator.to_latin(&states.expression_token),
-        ExpressionConversion::ToCyrillic => self.translator.to_cyrillic(&states.expression_token),
+        ExpressionConversion::ToLatin => self.token_to_latin(&states.expression_token, &mut states.in_args, &mut states.in_command),
+        ExpressionConversion::ToCyrillic => self.translate_to_cyrillic(&states.expression_token),
       }.as_str(),
     );
     //If there are still active states, return error 'missing token'
@@ -233,6 +295,67 @@ impl IOProcessor {
     Ok(states.text)
   }
 
+  /// ### token_to_latin
+  ///
+  /// Converts an expression token to latin. If arguments are not translated, only the command token is;
+  /// the text after it is kept as it is, until a command separator ('|', ';', '&') is found
+  fn token_to_latin(&self, token: &str, in_args: &mut bool, in_command: &mut bool) -> String {
+    if self.translate_args {
+      return self.translate_command_to_latin(&String::from(token));
+    }
+    let mut res: String = String::with_capacity(token.len());
+    let mut piece: String = String::new();
+    for c in token.chars() {
+      if *in_args {
+        piece.push(c);
+        //A new command starts after a separator
+        if c == '|' || c == ';' || c == '&' {
+          res.push_str(piece.as_str());
+          piece.clear();
+          *in_args = false;
+          *in_command = false;
+        }
+      } else if c.is_whitespace() && *in_command {
+        //Command token ends
+        res.push_str(self.translate_command_to_latin(&piece).as_str());
+        piece.clear();
+        piece.push(c);
+        *in_args = true;
+      } else {
+        if !c.is_whitespace() {
+          *in_command = true;
+        }
+        piece.push(c);
+      }
+    }
+    match *in_args {
+      true => res.push_str(piece.as_str()),
+      false => res.push_str(self.translate_command_to_latin(&piece).as_str()),
+    }
+    res
+  }
+
+  /// ### translate_to_latin
+  ///
+  /// Converts text to latin using the overrides and then the translator
+  fn translate_to_latin(&self, text: &String) -> String {
+    apply_overrides(text, &self.overrides, true, |text| self.translator.to_latin(text))
+  }
+
+  /// ### translate_command_to_latin
+  ///
+  /// Converts a command to latin using the overrides and then the translator command table
+  fn translate_command_to_latin(&self, text: &String) -> String {
+    apply_overrides(text, &self.overrides, true, |text| self.translator.command_to_latin(text))
+  }
+
+  /// ### translate_to_cyrillic
+  ///
+  /// Converts text to cyrillic using the overrides and then the translator
+  fn translate_to_cyrillic(&self, text: &String) -> String {
+    apply_overrides(text, &self.overrides, false, |text| self.translator.to_cyrillic(text))
+  }
+
   /// ### escape_cyrillic
   ///
   /// Apply different escapes to escape cyrillic texts
@@ -255,6 +378,51 @@ impl IOProcessor {
   }
 }
 
+/// ### apply_overrides
+///
+/// Replaces the characters which have an override (cyrillic keys if `to_latin`, ASCII keys otherwise)
+/// and translates the text between them with the provided function
+fn apply_overrides<F>(text: &String, overrides: &HashMap<char, String>, to_latin: bool, translate: F) -> String
+where
+  F: Fn(&String) -> String,
+{
+  if overrides.is_empty() {
+    return translate(text);
+  }
+  let mut res: String = String::with_capacity(text.len());
+  let mut token: String = String::new();
+  for c in text.chars() {
+    match overrides.get(&c) {
+      Some(replacement) if c.is_ascii() != to_latin => {
+        //Translate the text before the overridden character
+        if !token.is_empty() {
+          res.push_str(translate(&token).as_str());
+          token.clear();
+        }
+        res.push_str(replacement.as_str());
+      }
+      _ => token.push(c),
+    }
+  }
+  if !token.is_empty() {
+    res.push_str(translate(&token).as_str());
+  }
+  res
+}
+
+/// ### is_translatable
+///
+/// Returns whether the character could be changed by the translation to cyrillic.
+/// Only ASCII letters and non-cyrillic letters (e.g. GOST diacritics) are translated; escape sequences are rewritten by the processor
+fn is_translatable(c: char) -> bool {
+  match c {
+    '\x1b' => true,
+    c if c.is_ascii() => c.is_ascii_alphabetic(),
+    '\u{0400}'..='\u{04FF}' => false,
+    _ => true,
+  }
+}
+
 impl ExpressionParserStates {
   fn new(previous_state: Option<ExpressionParserStates>) -> ExpressionParserStates {
     ExpressionParserStates {
@@ -263,6 +431,8 @@ impl ExpressionParserStates {
       escape_block: false,
       backslash: false,
       in_expression: false,
+      in_command: false,
+      in_args: false,
       previous_state: match previous_state {
         None => None,
         Some(prev_state) => Some(Box::new(prev_state)),
@@ -277,6 +447,8 @@ impl ExpressionParserStates {
       escape_block: strref.escape_block,
       backslash: strref.backslash,
       in_expression: strref.in_expression,
+      in_command: strref.in_command,
+      in_args: strref.in_args,
       previous_state: match &strref.previous_state {
         //Recursive clone
         None => None,
@@ -299,12 +471,23 @@ impl ExpressionParserStates {
 mod tests {
 
   use super::*;
-  use crate::translator::{new_translator, Language};
+  use crate::translator::{new_gost_translator, new_translator, GostSystem, Language};
+
+  use std::time::{Duration, Instant};
+
+  #[test]
+  fn new_all_languages() {
+    for language in Language::all().into_iter() {
+      let iop: Result<IOProcessor, regex::Error> = IOProcessor::new(language, new_translator(language), HashMap::new());
+      assert!(iop.is_ok(), "IOProcessor for {:?} returned error", language);
+      assert_eq!(iop.unwrap().language, language);
+    }
+  }
 
   #[test]
   fn to_cyrillic_simple() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     let input: String = String::from("Привет Мир!");
     assert_eq!(iop.text_to_latin(&input), String::from("Privet Mir!"));
@@ -313,7 +496,7 @@ mod tests {
   #[test]
   fn to_cyrillic_expressions() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Simple command
     let input: String = String::from("экхо фообар");
@@ -368,7 +551,7 @@ mod tests {
   #[should_panic]
   fn to_cyrillic_missing_token_parenthesis() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("экхо ₽(хостнамэ");
@@ -379,7 +562,7 @@ mod tests {
   #[should_panic]
   fn to_cyrillic_missing_token_quotes() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("экхо \"привет");
@@ -390,7 +573,7 @@ mod tests {
   #[should_panic]
   fn to_cyrillic_missing_token_backslash() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("экхо \"привет\\");
@@ -400,7 +583,7 @@ mod tests {
   #[test]
   fn to_latin_simple() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     let input: String = String::from("Hello World!");
     assert_eq!(iop.text_to_cyrillic(&input), String::from("Хелло Уорлд!"));
@@ -409,7 +592,7 @@ mod tests {
   #[test]
   fn to_latin_expressions() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Simple command
     let input: String = String::from("echo foobar");
@@ -459,7 +642,7 @@ mod tests {
   #[should_panic]
   fn to_latin_missing_token_parenthesis() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("echo $(hostname");
@@ -470,7 +653,7 @@ mod tests {
   #[should_panic]
   fn to_latin_missing_token_quotes() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("echo \"hello");
@@ -481,19 +664,134 @@ mod tests {
   #[should_panic]
   fn to_latin_missing_token_backslash() {
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     //Bad expression
     let input: String = String::from("echo \"hello\\");
     assert!(iop.expression_to_latin(&input).is_ok());
   }
 
+  #[test]
+  fn to_latin_too_deep() {
+    //Instantiate IOProcessor
+    let mut iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+    //Nesting within the limit
+    let depth: usize = DEFAULT_MAX_EXPRESSION_DEPTH;
+    let input: String = format!("эчо {}привет{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(iop.expression_to_latin(&input).is_ok());
+    //Nesting beyond the limit
+    let depth: usize = 100000;
+    let input: String = format!("эчо {}привет{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(iop.expression_to_latin(&input).err().unwrap(), ExpressionParserError::TooDeep);
+    //Custom limit
+    iop.set_max_depth(2);
+    assert!(iop.expression_to_latin(&String::from("эчо ((привет))")).is_ok());
+    assert_eq!(iop.expression_to_latin(&String::from("эчо (((привет)))")).err().unwrap(), ExpressionParserError::TooDeep);
+  }
+
+  #[test]
+  fn test_translate_args() {
+    let mut iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+    //Args are translated by default
+    assert_eq!(iop.expression_to_latin(&String::from("кд Документы")).unwrap(), String::from("cd Documenty"));
+    iop.set_translate_args(false);
+    //Only the command token is translated
+    assert_eq!(iop.expression_to_latin(&String::from("кд \"Документы\"")).unwrap(), String::from("cd \"Документы\""));
+    assert_eq!(iop.expression_to_latin(&String::from("кд Документы")).unwrap(), String::from("cd Документы"));
+    assert_eq!(iop.expression_to_latin(&String::from("  лс -л Загрузки")).unwrap(), String::from("  ls -л Загрузки"));
+    //Inner expressions and chained commands are commands too
+    assert_eq!(iop.expression_to_latin(&String::from("эчо $(лс) Привет")).unwrap(), String::from("echo $(ls) Привет"));
+    assert_eq!(iop.expression_to_latin(&String::from("кат Файл | грэп Слово; лс Папка")).unwrap(), String::from("cat Файл | grep Слово; ls Папка"));
+    //Quoted command
+    assert_eq!(iop.expression_to_latin(&String::from("\"эхо\" Привет")).unwrap(), String::from("\"эхо\" Привет"));
+    //Errors are still reported
+    assert_eq!(iop.expression_to_latin(&String::from("кд \"Документы")).err().unwrap(), ExpressionParserError::MissingToken);
+  }
+
+  #[test]
+  fn test_overrides() {
+    let mut overrides: HashMap<char, String> = HashMap::new();
+    overrides.insert('ж', String::from("zh"));
+    overrides.insert('j', String::from("дж"));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), overrides).unwrap();
+    //Cyrillic keys are used converting to latin
+    assert_eq!(iop.text_to_latin(&String::from("жук")), String::from("zhuk"));
+    assert_eq!(iop.expression_to_latin(&String::from("эчо жара \"жара\"")).unwrap(), String::from("echo zhara \"жара\""));
+    assert_eq!(iop.text_to_latin(&String::from("ls -j")), String::from("ls -j"));
+    //Latin keys are used converting to cyrillic
+    assert_eq!(iop.text_to_cyrillic(&String::from("jar")), String::from("джар"));
+    assert_eq!(iop.text_to_cyrillic(&String::from("\x1b[31mjar\x1b[0m")), String::from("\x1b[31mджар\x1b[0m"));
+    //Without overrides the translator is used
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+    assert_eq!(iop.text_to_latin(&String::from("жук")), String::from("juk"));
+  }
+
   #[test]
   fn test_escapes() {
     let latin_text: String = String::from("\x1b[31mRED\x1b[0m");
     //Instantiate IOProcessor
-    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian));
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
     assert_eq!(iop.language, Language::Russian);
     assert_eq!(iop.text_to_cyrillic(&latin_text), String::from("\x1b[31mРЕД\x1b[0m"));
   }
+
+  #[test]
+  fn test_escapes_osc() {
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+    //Title terminated by BEL
+    let latin_text: String = String::from("\x1b]0;user@host: ~/src\x07RED");
+    assert_eq!(iop.text_to_cyrillic(&latin_text), String::from("\x1b]0;user@host: ~/src\x07РЕД"));
+    //Title terminated by ST, with colors around
+    let latin_text: String = String::from("\x1b[31mRED\x1b]2;vim main.rs\x1b\\\x1b[0mRED");
+    assert_eq!(iop.text_to_cyrillic(&latin_text), String::from("\x1b[31mРЕД\x1b]2;vim main.rs\x1b\\\x1b[0mРЕД"));
+    //Multiple sequences
+    let latin_text: String = String::from("\x1b]0;a\x07b\x1b]0;c\x07");
+    assert_eq!(iop.text_to_cyrillic(&latin_text), String::from("\x1b]0;a\x07б\x1b]0;c\x07"));
+  }
+
+  #[test]
+  fn test_to_cyrillic_untranslatable() {
+    let chunks: Vec<String> = vec![
+      String::from("1234567890\n"),
+      String::from("  -> [42] {*} 3.14%, #!?\t|\n"),
+      String::from("Привет мир! Ёлка, щи и чай\n"),
+      String::from(""),
+    ];
+    for language in Language::all().into_iter() {
+      for gost_system in [GostSystem::A, GostSystem::B].iter() {
+        let iop: IOProcessor = IOProcessor::new(language, new_gost_translator(language, *gost_system), HashMap::new()).unwrap();
+        for chunk in chunks.iter() {
+          assert!(!chunk.chars().any(is_translatable));
+          //Skipped chunks are the same as the translated ones
+          let translated: String = iop.escape_cyrillic(iop.translator.to_cyrillic(chunk));
+          assert_eq!(iop.text_to_cyrillic(chunk), translated, "{:?} {:?}", language, chunk);
+          assert_eq!(iop.text_to_cyrillic(chunk), *chunk);
+        }
+      }
+    }
+    //Translatable chunks
+    assert!("ls -l".chars().any(is_translatable));
+    assert!("\x1b[0м".chars().any(is_translatable));
+    assert!("šč".chars().any(is_translatable));
+  }
+
+  #[test]
+  #[ignore]
+  fn bench_to_cyrillic_untranslatable() {
+    //Run with `cargo test bench_to_cyrillic_untranslatable -- --ignored --nocapture`
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_translator(Language::Russian), HashMap::new()).unwrap();
+    let chunk: String = String::from("  1234  0.0  1.2  12:34:56  [=====>    ] 42%\n  Загрузка: 100%\n").repeat(64);
+    let rounds: u32 = 1000;
+    let t_start: Instant = Instant::now();
+    for _ in 0..rounds {
+      let _ = iop.text_to_cyrillic(&chunk);
+    }
+    let fast_path: Duration = t_start.elapsed();
+    let t_start: Instant = Instant::now();
+    for _ in 0..rounds {
+      let _ = iop.escape_cyrillic(iop.translator.to_cyrillic(&chunk));
+    }
+    let translated: Duration = t_start.elapsed();
+    println!("fast path: {:?}/chunk; translation: {:?}/chunk", fast_path / rounds, translated / rounds);
+  }
 }
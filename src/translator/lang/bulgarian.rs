@@ -245,8 +245,20 @@ impl Translator for Bulgarian {
         'ш' => "sh",
         'Щ' => "SHT",
         'щ' => "sht",
-        'Ъ' => "'",
-        'ъ' => "'",
+        'Ъ' | 'ъ' => {
+          //Between two consonants 'ъ' is a vowel, otherwise it is kept as quote
+          let prev: Option<char> = match i {
+            0 => None,
+            _ => input.chars().nth(i - 1),
+          };
+          match (prev, input.chars().nth(i + 1)) {
+            (Some(prev), Some(next)) if is_consonant(prev) && is_consonant(next) => match c {
+              'Ъ' => "A",
+              _ => "a",
+            },
+            _ => "'",
+          }
+        }
         'Ы' => "Y",
         'ы' => "y",
         'Ь' => "`",
@@ -382,20 +394,32 @@ impl Translator for Bulgarian {
         'r' => "р",
         'S' => match input.chars().nth(i + 1) {
           Some(ch) => match ch {
-            'h' | 'H' => {
-              skip_cycles += 1;
-              "Ш"
-            }
+            'h' | 'H' => match input.chars().nth(i + 2) {
+              Some('t') | Some('T') => {
+                skip_cycles += 2;
+                "Щ"
+              }
+              _ => {
+                skip_cycles += 1;
+                "Ш"
+              }
+            },
             _ => "С",
           },
           None => "С",
         },
         's' => match input.chars().nth(i + 1) {
           Some(ch) => match ch {
-            'h' | 'H' => {
-              skip_cycles += 1;
-              "ш"
-            }
+            'h' | 'H' => match input.chars().nth(i + 2) {
+              Some('t') | Some('T') => {
+                skip_cycles += 2;
+                "щ"
+              }
+              _ => {
+                skip_cycles += 1;
+                "ш"
+              }
+            },
             _ => "с",
           },
           None => "с",
@@ -460,6 +484,13 @@ impl Translator for Bulgarian {
   }
 }
 
+/// ### is_consonant
+///
+/// Returns whether the provided character is a bulgarian cyrillic consonant
+fn is_consonant(c: char) -> bool {
+  "бвгджзйклмнпрстфхцчшщ".contains(c.to_lowercase().next().unwrap_or(c))
+}
+
 //@! Tests
 
 #[cfg(test)]
@@ -589,6 +620,20 @@ mod tests {
     let output = translator.to_latin(&input);
     println!("\"{}\" => \"{}\"", input, output);
     assert_eq!(output, "'`'`");
+    //Ъ as vowel
+    let input: String = String::from("път мъж");
+    let output = translator.to_latin(&input);
+    println!("\"{}\" => \"{}\"", input, output);
+    assert_eq!(output, "pat maj");
+    let input: String = String::from("ПЪТ");
+    let output = translator.to_latin(&input);
+    println!("\"{}\" => \"{}\"", input, output);
+    assert_eq!(output, "PAT");
+    //Щ
+    let input: String = String::from("щом ЩОМ");
+    let output = translator.to_latin(&input);
+    println!("\"{}\" => \"{}\"", input, output);
+    assert_eq!(output, "shtom SHTOM");
     //Symbols
     let input: String = String::from("№ €");
     let output = translator.to_latin(&input);
@@ -754,5 +799,10 @@ mod tests {
     let output = translator.to_cyrillic(&input);
     println!("\"{}\" => \"{}\"", input, output);
     assert_eq!(output, "Ы");
+    //Special cases: sht
+    let input: String = String::from("shtom SHTOM shell");
+    let output = translator.to_cyrillic(&input);
+    println!("\"{}\" => \"{}\"", input, output);
+    assert_eq!(output, "щом ЩОМ шелл");
   }
 }
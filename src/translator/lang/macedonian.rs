@@ -0,0 +1,451 @@
+//! ### Macedonian
+//!
+//! `macedonian` language implementation of Translator trait
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use super::super::Translator;
+use super::Macedonian;
+
+impl Translator for Macedonian {
+    /// ### Macedonian translator
+    ///
+    /// Converts a string which contains macedonian cyrillic characters into a latin string.
+    /// Characters between '"' (quotes) are escaped, expressions inside escaped blocks are translitarated anyway
+    /// Transliteration according to the macedonian official romanization, with some differences
+    fn to_latin(&self, input: &String) -> String {
+        let mut output = String::new();
+        let mut skip_counter: usize = 0;
+        for (i, c) in input.chars().enumerate() {
+            if skip_counter > 0 {
+                //Skip cycles
+                skip_counter -= 1; //Decrement skip counter
+                continue;
+            }
+            //Push transliterated character
+            let unchanged_str: String;
+            output.push_str(match c {
+                'А' => "A",
+                'а' => "a",
+                'Б' => "B",
+                'б' => "b",
+                'В' => {
+                    //If following character is 'В', then is always W
+                    match input.chars().nth(i + 1) {
+                        Some('в') | Some('В') => {
+                            skip_counter += 1; //Skip character
+                            "W"
+                        }
+                        _ => "V",
+                    }
+                }
+                'в' => {
+                    //If following character is 'В', then is always W
+                    match input.chars().nth(i + 1) {
+                        Some('в') | Some('В') => {
+                            skip_counter += 1; //Skip character
+                            "w"
+                        }
+                        _ => "v",
+                    }
+                }
+                'Г' => "G",
+                'г' => "g",
+                'Д' => "D",
+                'д' => "d",
+                'Ѓ' => "GJ",
+                'ѓ' => "gj",
+                'Е' => "E",
+                'е' => "e",
+                'Ж' => "ZH",
+                'ж' => "zh",
+                'З' => "Z",
+                'з' => "z",
+                'Ѕ' => "DZ",
+                'ѕ' => "dz",
+                'И' => {
+                    //If following character is 'И', then is always Y
+                    match input.chars().nth(i + 1) {
+                        Some('и') | Some('И') => {
+                            skip_counter += 1; //Skip character
+                            "Y"
+                        }
+                        _ => "I",
+                    }
+                }
+                'и' => {
+                    //If following character is 'И', then is always Y
+                    match input.chars().nth(i + 1) {
+                        Some('и') | Some('И') => {
+                            skip_counter += 1; //Skip character
+                            "y"
+                        }
+                        _ => "i",
+                    }
+                }
+                'Ј' => "J",
+                'ј' => "j",
+                'К' => {
+                    match input.chars().nth(i + 1) {
+                        //If following character is 'С', then is always X
+                        Some('с') | Some('С') => {
+                            skip_counter += 1; //Skip character
+                            "X"
+                        }
+                        Some('и') | Some('И') => {
+                            // If following characters are 'ИУ', then is always Q
+                            match input.chars().nth(i + 2) {
+                                Some('у') | Some('У') => {
+                                    skip_counter += 2; // Skip 2
+                                    "Q"
+                                }
+                                _ => "K",
+                            }
+                        }
+                        _ => "K",
+                    }
+                }
+                'к' => {
+                    match input.chars().nth(i + 1) {
+                        //If following character is 'С', then is always X
+                        Some('с') | Some('С') => {
+                            skip_counter += 1; //Skip character
+                            "x"
+                        }
+                        Some('и') | Some('И') => {
+                            // If following characters are 'ИУ', then is always Q
+                            match input.chars().nth(i + 2) {
+                                Some('у') | Some('У') => {
+                                    skip_counter += 2; // Skip 2
+                                    "q"
+                                }
+                                _ => "k",
+                            }
+                        }
+                        _ => "k",
+                    }
+                }
+                'Л' => "L",
+                'л' => "l",
+                'Љ' => "LJ",
+                'љ' => "lj",
+                'М' => "M",
+                'м' => "m",
+                'Н' => "N",
+                'н' => "n",
+                'Њ' => "NJ",
+                'њ' => "nj",
+                'О' => "O",
+                'о' => "o",
+                'П' => "P",
+                'п' => "p",
+                'Р' => "R",
+                'р' => "r",
+                'С' => "S",
+                'с' => "s",
+                'Т' => "T",
+                'т' => "t",
+                'Ќ' => "KJ",
+                'ќ' => "kj",
+                'У' => "U",
+                'у' => "u",
+                'Ф' => "F",
+                'ф' => "f",
+                'Х' => "H",
+                'х' => "h",
+                'Ц' => "C",
+                'ц' => "c",
+                'Ч' => "CH",
+                'ч' => "ch",
+                'Џ' => "DZH",
+                'џ' => "dzh",
+                'Ш' => "SH",
+                'ш' => "sh",
+                _ => {
+                    unchanged_str = c.to_string();
+                    unchanged_str.as_str()
+                }
+            });
+        }
+        output
+    }
+
+    /// Converts a string which contains latin characters into a macedonian cyrillic string.
+    /// Characters between quotes are escapes
+    fn to_cyrillic(&self, input: &String) -> String {
+        let mut output: String = String::new();
+        let mut skip_cycles: usize = 0;
+        for (i, c) in input.chars().enumerate() {
+            if skip_cycles > 0 {
+                skip_cycles -= 1;
+                continue;
+            }
+            let unchanged_str: String;
+            output.push_str(match c {
+                'A' => "А",
+                'a' => "а",
+                'B' => "Б",
+                'b' => "б",
+                'C' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "Ч"
+                    }
+                    _ => "Ц",
+                },
+                'c' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "ч"
+                    }
+                    _ => "ц",
+                },
+                'D' => match input.chars().nth(i + 1) {
+                    // If 'ZH' follows => Џ; if 'Z' follows => Ѕ
+                    Some('Z') | Some('z') => match input.chars().nth(i + 2) {
+                        Some('H') | Some('h') => {
+                            skip_cycles += 2;
+                            "Џ"
+                        }
+                        _ => {
+                            skip_cycles += 1;
+                            "Ѕ"
+                        }
+                    },
+                    _ => "Д",
+                },
+                'd' => match input.chars().nth(i + 1) {
+                    // If 'ZH' follows => Џ; if 'Z' follows => Ѕ
+                    Some('Z') | Some('z') => match input.chars().nth(i + 2) {
+                        Some('H') | Some('h') => {
+                            skip_cycles += 2;
+                            "џ"
+                        }
+                        _ => {
+                            skip_cycles += 1;
+                            "ѕ"
+                        }
+                    },
+                    _ => "д",
+                },
+                'E' => "Е",
+                'e' => "е",
+                'F' => "Ф",
+                'f' => "ф",
+                'G' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Ѓ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "Ѓ"
+                    }
+                    _ => "Г",
+                },
+                'g' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Ѓ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "ѓ"
+                    }
+                    _ => "г",
+                },
+                'H' => "Х",
+                'h' => "х",
+                'I' => "И",
+                'i' => "и",
+                'J' => "Ј",
+                'j' => "ј",
+                'K' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Ќ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "Ќ"
+                    }
+                    _ => "К",
+                },
+                'k' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Ќ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "ќ"
+                    }
+                    _ => "к",
+                },
+                'L' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Љ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "Љ"
+                    }
+                    _ => "Л",
+                },
+                'l' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Љ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "љ"
+                    }
+                    _ => "л",
+                },
+                'M' => "М",
+                'm' => "м",
+                'N' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Њ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "Њ"
+                    }
+                    _ => "Н",
+                },
+                'n' => match input.chars().nth(i + 1) {
+                    // If 'J' follows => Њ
+                    Some('J') | Some('j') => {
+                        skip_cycles += 1;
+                        "њ"
+                    }
+                    _ => "н",
+                },
+                'O' => "О",
+                'o' => "о",
+                'P' => "П",
+                'p' => "п",
+                'Q' => "КИУ",
+                'q' => "киу",
+                'R' => "Р",
+                'r' => "р",
+                'S' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "Ш"
+                    }
+                    _ => "С",
+                },
+                's' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "ш"
+                    }
+                    _ => "с",
+                },
+                'T' => "Т",
+                't' => "т",
+                'U' => "У",
+                'u' => "у",
+                'V' => "В",
+                'v' => "в",
+                'W' => "ВВ",
+                'w' => "вв",
+                'X' => "КС",
+                'x' => "кс",
+                'Y' => "ИИ",
+                'y' => "ии",
+                'Z' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "Ж"
+                    }
+                    _ => "З",
+                },
+                'z' => match input.chars().nth(i + 1) {
+                    Some('h') | Some('H') => {
+                        skip_cycles += 1;
+                        "ж"
+                    }
+                    _ => "з",
+                },
+                _ => {
+                    unchanged_str = c.to_string();
+                    unchanged_str.as_str()
+                }
+            });
+        }
+        output
+    }
+}
+
+//@! Tests
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::translator::{new_translator, Language};
+
+    #[test]
+    fn test_translator_lang_macedonian_to_latin() {
+        // Macedonian translator
+        let translator: Box<dyn Translator> = new_translator(Language::Macedonian);
+        // All characters
+        assert_eq!(translator.to_latin(&String::from("АБВВВГДЃЕЖЗЅИИИЈККСКИУЛЉМНЊОПРСТЌУФХЦЧЏШ")), String::from("ABWVGDGJEZHZDZYIJKXQLLJMNNJOPRSTKJUFHCCHDZHSH"));
+        assert_eq!(translator.to_latin(&String::from("абвввгдѓежзѕииијккскиулљмнњопрстќуфхцчџш")), String::from("abwvgdgjezhzdzyijkxqlljmnnjoprstkjufhcchdzhsh"));
+        // Simple commands (lower)
+        assert_eq!(translator.to_latin(&String::from("лс поотис/")), String::from("ls pootis/"));
+        assert_eq!(translator.to_latin(&String::from("ексец зш")), String::from("exec zsh"));
+        assert_eq!(translator.to_latin(&String::from("цд тестс/")), String::from("cd tests/"));
+        // Simple commands (upper)
+        assert_eq!(translator.to_latin(&String::from("ЛС ПООТИС/")), String::from("LS POOTIS/"));
+        assert_eq!(translator.to_latin(&String::from("ЕКСЕЦ ЗШ")), String::from("EXEC ZSH"));
+        assert_eq!(translator.to_latin(&String::from("ЦД ТЕСТС/")), String::from("CD TESTS/"));
+        // Macedonian letters
+        assert_eq!(translator.to_latin(&String::from("ѓакон ќеиф ѕвон џез")), String::from("gjakon kjeif dzvon dzhez"));
+        assert_eq!(translator.to_latin(&String::from("ЃАКОН ЌЕИФ ЅВОН ЏЕЗ")), String::from("GJAKON KJEIF DZVON DZHEZ"));
+        // With next char special at the end of word
+        assert_eq!(translator.to_latin(&String::from("лс в")), String::from("ls v"));
+        assert_eq!(translator.to_latin(&String::from("лс ки")), String::from("ls ki"));
+        assert_eq!(translator.to_latin(&String::from("ЛС В")), String::from("LS V"));
+        assert_eq!(translator.to_latin(&String::from("ЛС КИ")), String::from("LS KI"));
+    }
+
+    #[test]
+    fn test_translator_lang_macedonian_to_cyrillic() {
+        // Macedonian translator
+        let translator: Box<dyn Translator> = new_translator(Language::Macedonian);
+        // All characters
+        assert_eq!(translator.to_cyrillic(&String::from("ABCCHDDZDZHEFGGJHIJKKJLLJMNNJOPQRSSHTUWVXYZZH")), String::from("АБЦЧДЅЏЕФГЃХИЈКЌЛЉМНЊОПКИУРСШТУВВВКСИИЗЖ"));
+        assert_eq!(translator.to_cyrillic(&String::from("abcchddzdzhefggjhijkkjlljmnnjopqrsshtuwvxyzzh")), String::from("абцчдѕџефгѓхијкќлљмнњопкиурсштувввксиизж"));
+        // Macedonian letters
+        assert_eq!(translator.to_cyrillic(&String::from("gjakon kjeif dzvon dzhez")), "ѓакон ќеиф ѕвон џез");
+        assert_eq!(translator.to_cyrillic(&String::from("GJAKON KJEIF DZVON DZHEZ")), "ЃАКОН ЌЕИФ ЅВОН ЏЕЗ");
+        // Test particular case (sh)
+        assert_eq!(translator.to_cyrillic(&String::from("shell sis")), "шелл сис");
+        assert_eq!(translator.to_cyrillic(&String::from("SHELL SIS")), "ШЕЛЛ СИС");
+        // Test particular case (ch)
+        assert_eq!(translator.to_cyrillic(&String::from("channel cd")), "чаннел цд");
+        assert_eq!(translator.to_cyrillic(&String::from("CHANNEL CD")), "ЧАННЕЛ ЦД");
+        // Test particular case (last character is a digraph start)
+        assert_eq!(translator.to_cyrillic(&String::from("gag kak d")), "гаг как д");
+        assert_eq!(translator.to_cyrillic(&String::from("GAG KAK D")), "ГАГ КАК Д");
+    }
+
+    #[test]
+    fn test_translator_lang_macedonian_round_trip() {
+        let translator: Box<dyn Translator> = new_translator(Language::Macedonian);
+        // Full alphabet, both directions
+        let latin: String = String::from("abcchddzdzhefggjhijkkjlljmnnjopqrsshtuwvxyzzh");
+        assert_eq!(translator.to_latin(&translator.to_cyrillic(&latin)), latin);
+        let latin: String = latin.to_uppercase();
+        assert_eq!(translator.to_latin(&translator.to_cyrillic(&latin)), latin);
+        let cyrillic: String = String::from("абвгдѓежзѕијклљмнњопрстќуфхцчџш");
+        assert_eq!(translator.to_cyrillic(&translator.to_latin(&cyrillic)), cyrillic);
+        let cyrillic: String = cyrillic.to_uppercase();
+        assert_eq!(translator.to_cyrillic(&translator.to_latin(&cyrillic)), cyrillic);
+    }
+}
@@ -31,12 +31,27 @@
 pub enum Language {
   Belarusian,
   Bulgarian,
+  Macedonian,
   Russian,
   Serbian,
   Ukrainian,
   Nil
 }
 
+/// Latin codes of the supported languages, as accepted by `Language::from_code`
+pub const LANGUAGE_CODES: [&str; 7] = ["ru", "by", "bg", "mk", "rs", "ua", "nil"];
+
+/// ### GostSystem
+///
+/// GOST 7.79-2000 transliteration system:
+/// System A uses diacritics (one latin letter for each cyrillic letter),
+/// System B uses ASCII digraphs
+#[derive(Copy, Clone, PartialEq, std::fmt::Debug)]
+pub enum GostSystem {
+  A,
+  B
+}
+
 /// ## Languages
 ///
 /// Languages are empty structs which must implement the Translator trait
@@ -44,27 +59,88 @@ pub enum Language {
 //NOTE: languages are listed here
 pub(crate) struct Belarusian {}
 pub(crate) struct Bulgarian {}
-pub(crate) struct Russian {}
+pub(crate) struct Macedonian {}
+pub(crate) struct Russian {
+  pub(crate) gost_system: GostSystem
+}
 pub(crate) struct Serbian {}
 pub(crate) struct Ukrainian {}
 pub(crate) struct Nil {}
 mod belarusian;
 mod bulgarian;
+mod macedonian;
 mod russian;
 mod serbian;
 mod ukrainian;
 mod nil;
 
+impl Language {
+  /// ### all
+  ///
+  /// Returns all the supported languages
+  #[cfg(test)]
+  pub fn all() -> Vec<Language> {
+    vec![
+      Language::Belarusian,
+      Language::Bulgarian,
+      Language::Macedonian,
+      Language::Russian,
+      Language::Serbian,
+      Language::Ukrainian,
+      Language::Nil
+    ]
+  }
+
+  /// ### from_code
+  ///
+  /// Get the language from its code (e.g. 'ru' or 'рус'); returns None if the language is not supported
+  pub fn from_code(code: &str) -> Option<Language> {
+    match code {
+      "ru" | "рус" => Some(Language::Russian),
+      "by" | "бел" => Some(Language::Belarusian),
+      "bg" | "бг" | "блг" => Some(Language::Bulgarian),
+      "mk" | "мкд" => Some(Language::Macedonian),
+      "rs" | "срб" => Some(Language::Serbian),
+      "ua" | "укр" => Some(Language::Ukrainian),
+      "nil" => Some(Language::Nil),
+      _ => None,
+    }
+  }
+
+  /// ### country_code
+  ///
+  /// Returns the ISO 3166-1 alpha-2 code of the country associated to the language (if any)
+  pub fn country_code(&self) -> Option<&'static str> {
+    match self {
+      Language::Belarusian => Some("BY"),
+      Language::Bulgarian => Some("BG"),
+      Language::Macedonian => Some("MK"),
+      Language::Russian => Some("RU"),
+      Language::Serbian => Some("RS"),
+      Language::Ukrainian => Some("UA"),
+      Language::Nil => None
+    }
+  }
+
+  /// ### native_name
+  ///
+  /// Returns the canonical name of the language, abbreviated in the language itself (e.g. 'рус')
+  pub fn native_name(&self) -> &'static str {
+    match self {
+      Language::Belarusian => "бел",
+      Language::Bulgarian => "блг",
+      Language::Macedonian => "мкд",
+      Language::Russian => "рус",
+      Language::Serbian => "срб",
+      Language::Ukrainian => "укр",
+      Language::Nil => "nil"
+    }
+  }
+}
+
 impl ToString for Language {
     fn to_string(&self) -> String {
-        match self {
-        Language::Belarusian => String::from("бел"),
-        Language::Bulgarian => String::from("блг"),
-        Language::Russian => String::from("рус"),
-        Language::Serbian => String::from("срб"),
-        Language::Ukrainian => String::from("укр"),
-        Language::Nil => String::from("nil")
-        }
+        String::from(self.native_name())
     }
 }
 
@@ -77,10 +153,48 @@ mod tests {
   fn test_translator_language_to_string() {
     assert_eq!(Language::Belarusian.to_string(), String::from("бел"));
     assert_eq!(Language::Bulgarian.to_string(), String::from("блг"));
+    assert_eq!(Language::Macedonian.to_string(), String::from("мкд"));
     assert_eq!(Language::Russian.to_string(), String::from("рус"));
     assert_eq!(Language::Serbian.to_string(), String::from("срб"));
     assert_eq!(Language::Ukrainian.to_string(), String::from("укр"));
     assert_eq!(Language::Nil.to_string(), String::from("nil"));
   }
 
+  #[test]
+  fn test_translator_language_all() {
+    let languages: Vec<Language> = Language::all();
+    assert_eq!(languages.len(), 7);
+    assert!(languages.contains(&Language::Russian));
+    assert!(languages.contains(&Language::Nil));
+  }
+
+  #[test]
+  fn test_translator_language_from_code() {
+    assert_eq!(Language::from_code("ru"), Some(Language::Russian));
+    assert_eq!(Language::from_code("рус"), Some(Language::Russian));
+    assert_eq!(Language::from_code("by"), Some(Language::Belarusian));
+    assert_eq!(Language::from_code("бг"), Some(Language::Bulgarian));
+    assert_eq!(Language::from_code("mk"), Some(Language::Macedonian));
+    assert_eq!(Language::from_code("мкд"), Some(Language::Macedonian));
+    assert_eq!(Language::from_code("rs"), Some(Language::Serbian));
+    assert_eq!(Language::from_code("укр"), Some(Language::Ukrainian));
+    assert_eq!(Language::from_code("nil"), Some(Language::Nil));
+    assert_eq!(Language::from_code("xx"), None);
+    //All the codes are supported
+    for code in LANGUAGE_CODES.iter() {
+      assert!(Language::from_code(code).is_some());
+    }
+  }
+
+  #[test]
+  fn test_translator_language_country_code() {
+    assert_eq!(Language::Belarusian.country_code(), Some("BY"));
+    assert_eq!(Language::Bulgarian.country_code(), Some("BG"));
+    assert_eq!(Language::Macedonian.country_code(), Some("MK"));
+    assert_eq!(Language::Russian.country_code(), Some("RU"));
+    assert_eq!(Language::Serbian.country_code(), Some("RS"));
+    assert_eq!(Language::Ukrainian.country_code(), Some("UA"));
+    assert_eq!(Language::Nil.country_code(), None);
+  }
+
 }
@@ -23,7 +23,7 @@
 *
 */
 
-use super::Russian;
+use super::{GostSystem, Russian};
 use super::super::Translator;
 
 impl Translator for Russian {
@@ -31,8 +31,11 @@ impl Translator for Russian {
 
   /// Converts a string which contains russian cyrillic characters into a latin string.
   /// Characters between '"' (quotes) are escaped, expressions inside escaped blocks are translitarated anyway
-  /// Transliteration according to GOST 7.79-2000
+  /// Transliteration according to GOST 7.79-2000 (System A or System B)
   fn to_latin(&self, input: &String) -> String {
+    if self.gost_system == GostSystem::A {
+      return to_latin_system_a(input);
+    }
     let mut output = String::new();
     let mut skip_counter: usize = 0;
     for (i, c) in input.chars().enumerate() {
@@ -265,9 +268,35 @@ impl Translator for Russian {
     output
   }
 
+  /// Commands are always converted with System B, since System A (ISO 9) output is not typeable in a shell
+  fn command_to_latin(&self, input: &String) -> String {
+    match self.gost_system {
+      GostSystem::A => Russian { gost_system: GostSystem::B }.to_latin(input),
+      GostSystem::B => self.to_latin(input),
+    }
+  }
+
+  /// Pairs of System B; System A is not used for commands, so it has no pairs
+  #[cfg(test)]
+  fn transliteration_pairs(&self) -> Vec<(char, &'static str)> {
+    match self.gost_system {
+      GostSystem::A => Vec::new(),
+      GostSystem::B => vec![
+        ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('д', "d"), ('е', "e"), ('ё', "yo"), ('ж', "j"),
+        ('з', "z"), ('и', "i"), ('й', "j"), ('к', "k"), ('л', "l"), ('м', "m"), ('н', "n"), ('о', "o"),
+        ('п', "p"), ('р', "r"), ('с', "s"), ('т', "t"), ('у', "u"), ('ф', "f"), ('х', "h"), ('ц', "z"),
+        ('ч', "ch"), ('ш', "sh"), ('щ', "shh"), ('ъ', "'"), ('ы', "y"), ('ь', "`"), ('э', "e"), ('ю', "yu"),
+        ('я', "ya"),
+      ],
+    }
+  }
+
   /// Converts a string which contains latin characters into a russian cyrillic string.
   /// Characters between quotes are escapes
   fn to_cyrillic(&self, input: &String) -> String {
+    if self.gost_system == GostSystem::A {
+      return to_cyrillic_system_a(input);
+    }
     let mut output: String = String::new();
     let mut skip_cycles: usize = 0;
     for (i, c) in input.chars().enumerate() {
@@ -457,13 +486,177 @@ impl Translator for Russian {
   }
 }
 
+/// ### to_latin_system_a
+///
+/// Converts russian cyrillic into latin using diacritics (GOST 7.79-2000 System A).
+/// Each cyrillic letter is converted into exactly one latin letter.
+/// As for System B, quoted blocks and escaped characters are handled by the IOProcessor
+fn to_latin_system_a(input: &str) -> String {
+  let mut output = String::new();
+  for c in input.chars() {
+    output.push(match c {
+      'А' => 'A',
+      'а' => 'a',
+      'Б' => 'B',
+      'б' => 'b',
+      'В' => 'V',
+      'в' => 'v',
+      'Г' => 'G',
+      'г' => 'g',
+      'Д' => 'D',
+      'д' => 'd',
+      'Е' => 'E',
+      'е' => 'e',
+      'Ё' => 'Ë',
+      'ё' => 'ë',
+      'Ж' => 'Ž',
+      'ж' => 'ž',
+      'З' => 'Z',
+      'з' => 'z',
+      'И' => 'I',
+      'и' => 'i',
+      'Й' => 'J',
+      'й' => 'j',
+      'К' => 'K',
+      'к' => 'k',
+      'Л' => 'L',
+      'л' => 'l',
+      'М' => 'M',
+      'м' => 'm',
+      'Н' => 'N',
+      'н' => 'n',
+      'О' => 'O',
+      'о' => 'o',
+      'П' => 'P',
+      'п' => 'p',
+      'Р' => 'R',
+      'р' => 'r',
+      'С' => 'S',
+      'с' => 's',
+      'Т' => 'T',
+      'т' => 't',
+      'У' => 'U',
+      'у' => 'u',
+      'Ф' => 'F',
+      'ф' => 'f',
+      'Х' => 'H',
+      'х' => 'h',
+      'Ц' => 'C',
+      'ц' => 'c',
+      'Ч' => 'Č',
+      'ч' => 'č',
+      'Ш' => 'Š',
+      'ш' => 'š',
+      'Щ' => 'Ŝ',
+      'щ' => 'ŝ',
+      'Ъ' | 'ъ' => 'ʺ',
+      'Ы' => 'Y',
+      'ы' => 'y',
+      'Ь' | 'ь' => 'ʹ',
+      'Э' => 'È',
+      'э' => 'è',
+      'Ю' => 'Û',
+      'ю' => 'û',
+      'Я' => 'Â',
+      'я' => 'â',
+      '№' => '#',
+      '₽' => '$',
+      _ => c,
+    });
+  }
+  output
+}
+
+/// ### to_cyrillic_system_a
+///
+/// Converts latin into russian cyrillic, reading diacritics (GOST 7.79-2000 System A).
+/// Latin letters which are not part of System A (q, w, x) are kept unchanged, so that the conversion is reversible
+fn to_cyrillic_system_a(input: &str) -> String {
+  let mut output = String::new();
+  for c in input.chars() {
+    output.push_str(match c {
+      'A' => "А",
+      'a' => "а",
+      'B' => "Б",
+      'b' => "б",
+      'V' => "В",
+      'v' => "в",
+      'G' => "Г",
+      'g' => "г",
+      'D' => "Д",
+      'd' => "д",
+      'E' => "Е",
+      'e' => "е",
+      'Ë' => "Ё",
+      'ë' => "ё",
+      'Ž' => "Ж",
+      'ž' => "ж",
+      'Z' => "З",
+      'z' => "з",
+      'I' => "И",
+      'i' => "и",
+      'J' => "Й",
+      'j' => "й",
+      'K' => "К",
+      'k' => "к",
+      'L' => "Л",
+      'l' => "л",
+      'M' => "М",
+      'm' => "м",
+      'N' => "Н",
+      'n' => "н",
+      'O' => "О",
+      'o' => "о",
+      'P' => "П",
+      'p' => "п",
+      'R' => "Р",
+      'r' => "р",
+      'S' => "С",
+      's' => "с",
+      'T' => "Т",
+      't' => "т",
+      'U' => "У",
+      'u' => "у",
+      'F' => "Ф",
+      'f' => "ф",
+      'H' => "Х",
+      'h' => "х",
+      'C' => "Ц",
+      'c' => "ц",
+      'Č' => "Ч",
+      'č' => "ч",
+      'Š' => "Ш",
+      'š' => "ш",
+      'Ŝ' => "Щ",
+      'ŝ' => "щ",
+      'ʺ' => "ъ",
+      'Y' => "Ы",
+      'y' => "ы",
+      'ʹ' => "ь",
+      'È' => "Э",
+      'è' => "э",
+      'Û' => "Ю",
+      'û' => "ю",
+      'Â' => "Я",
+      'â' => "я",
+      _ => {
+        output.push(c);
+        continue;
+      }
+    });
+  }
+  output
+}
+
 //@! Tests
 
 #[cfg(test)]
 mod tests {
 
   use super::*;
-  use crate::translator::{new_translator, Language};
+  use crate::translator::ioprocessor::IOProcessor;
+  use crate::translator::{new_gost_translator, new_translator, unstable_pairs, Language};
+  use std::collections::HashMap;
 
   #[test]
   fn test_translator_lang_russian_to_latin() {
@@ -752,4 +945,53 @@ mod tests {
     println!("\"{}\" => \"{}\"", input, output);
     assert_eq!(output, "Ы");
   }
+
+  #[test]
+  fn test_translator_lang_russian_pairs() {
+    let translator: Box<dyn Translator> = new_translator(Language::Russian);
+    //All the latin words of System B are round-trip stable
+    assert!(unstable_pairs(translator.as_ref()).is_empty());
+    //System A has no pairs
+    let translator: Box<dyn Translator> = new_gost_translator(Language::Russian, GostSystem::A);
+    assert!(translator.transliteration_pairs().is_empty());
+  }
+
+  #[test]
+  fn test_translator_lang_russian_gost_systems() {
+    let system_a: Box<dyn Translator> = new_gost_translator(Language::Russian, GostSystem::A);
+    let system_b: Box<dyn Translator> = new_gost_translator(Language::Russian, GostSystem::B);
+    //Same word under both systems
+    let input: String = String::from("Жёлтый щит");
+    assert_eq!(system_a.to_latin(&input), "Žëltyj ŝit");
+    assert_eq!(system_b.to_latin(&input), "Jyoltyj shhit");
+    let input: String = String::from("съешь эту чашу, ящерица");
+    assert_eq!(system_a.to_latin(&input), "sʺešʹ ètu čašu, âŝerica");
+    //ISO 9 (System A) and the command-oriented table (System B) differ for ж, ч, ш
+    let input: String = String::from("жчш");
+    assert_eq!(system_a.to_latin(&input), "žčš");
+    assert_eq!(system_b.to_latin(&input), "jchsh");
+    //Commands are always converted into ASCII
+    assert_eq!(system_a.command_to_latin(&input), "jchsh");
+    assert_eq!(system_b.command_to_latin(&input), "jchsh");
+    //System A is reversible
+    let input: String = String::from("Žëltyj ŝit");
+    assert_eq!(system_a.to_cyrillic(&input), "Жёлтый щит");
+    let input: String = String::from("sʺešʹ ètu čašu, âŝerica");
+    assert_eq!(system_a.to_cyrillic(&input), "съешь эту чашу, ящерица");
+    //Letters which are not in System A are kept, so that the conversion is reversible
+    let input: String = String::from("wget -qx QWX");
+    assert_eq!(system_a.to_cyrillic(&input), "wгет -qx QWX");
+    assert_eq!(system_a.to_latin(&system_a.to_cyrillic(&input)), input);
+    let input: String = String::from("Quick brown fox jumps over the lazy dog");
+    assert_eq!(system_a.to_latin(&system_a.to_cyrillic(&input)), input);
+    //Expressions are commands, so System B is used for them; text is displayed with System A
+    let iop: IOProcessor = IOProcessor::new(Language::Russian, new_gost_translator(Language::Russian, GostSystem::A), HashMap::new()).unwrap();
+    let input: String = String::from("эхо \"щи\" \\ж (чат)");
+    assert_eq!(iop.expression_to_latin(&input).unwrap(), "eho \"щи\" \\j (chat)");
+    assert_eq!(iop.text_to_latin(&String::from("чат")), "čat");
+    //System B is the default
+    let default: Box<dyn Translator> = new_translator(Language::Russian);
+    let input: String = String::from("Жёлтый щит");
+    assert_eq!(default.to_latin(&input), system_b.to_latin(&input));
+  }
 }
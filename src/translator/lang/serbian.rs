@@ -228,6 +228,16 @@ impl Translator for Serbian {
         output
     }
 
+    #[cfg(test)]
+    fn transliteration_pairs(&self) -> Vec<(char, &'static str)> {
+        vec![
+            ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('д', "d"), ('ђ', "dj"), ('е', "e"), ('ж', "j"),
+            ('з', "z"), ('и', "i"), ('ј', "j"), ('к', "k"), ('л', "l"), ('љ', "lj"), ('м', "m"), ('н', "n"),
+            ('њ', "nj"), ('о', "o"), ('п', "p"), ('р', "r"), ('с', "s"), ('т', "t"), ('ћ', "c"), ('у', "u"),
+            ('ф', "f"), ('х', "h"), ('ц', "ts"), ('ч', "ch"), ('џ', "dz"), ('ш', "sh"),
+        ]
+    }
+
     /// Converts a string which contains latin characters into a serbian cyrillic string.
     /// Characters between quotes are escapes
     fn to_cyrillic(&self, input: &String) -> String {
@@ -442,7 +452,7 @@ impl Translator for Serbian {
 mod tests {
 
     use super::*;
-    use crate::translator::{new_translator, Language};
+    use crate::translator::{new_translator, unstable_pairs, Language};
 
     #[test]
     fn test_translator_lang_serbian_to_latin() {
@@ -471,6 +481,13 @@ mod tests {
         assert_eq!(translator.to_latin(&String::from("ЛС КИ")), String::from("LS KI"));
     }
 
+    #[test]
+    fn test_translator_lang_serbian_pairs() {
+        let translator: Box<dyn Translator> = new_translator(Language::Serbian);
+        // 'c' is converted into 'к', which is then converted into 'k'
+        assert_eq!(unstable_pairs(translator.as_ref()), vec!["c"]);
+    }
+
     #[test]
     fn test_translator_lang_serbian_to_cyrillic() {
         // Serbian translator
@@ -751,4 +751,23 @@ mod tests {
     println!("\"{}\" => \"{}\"", input, output);
     assert_eq!(output, "Й");
   }
+
+  #[test]
+  fn test_translator_lang_ukrainian_round_trip() {
+    let translator: Box<dyn Translator> = new_translator(Language::Ukrainian);
+    //Common command words must survive a cyrillic => latin => cyrillic round trip
+    for input in &["лс", "кд", "ехо"] {
+      let latin = translator.to_latin(&input.to_string());
+      let output = translator.to_cyrillic(&latin);
+      println!("\"{}\" => \"{}\" => \"{}\"", input, latin, output);
+      assert_eq!(output, *input);
+    }
+    //And a latin => cyrillic => latin one
+    for input in &["ls", "cd", "echo"] {
+      let cyrillic = translator.to_cyrillic(&input.to_string());
+      let output = translator.to_latin(&cyrillic);
+      println!("\"{}\" => \"{}\" => \"{}\"", input, cyrillic, output);
+      assert_eq!(output, *input);
+    }
+  }
 }
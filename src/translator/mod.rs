@@ -26,7 +26,7 @@
 pub mod ioprocessor;
 pub mod lang;
 
-use lang::Language;
+use lang::{GostSystem, Language};
 
 /// ### Translator
 ///
@@ -38,22 +38,67 @@ pub trait Translator {
   /// Characters between '"' (quotes) are escaped, expressions inside escaped blocks are translitarated anyway
   fn to_latin(&self, input: &String) -> String;
 
+  /// ### command_to_latin
+  ///
+  /// Converts cyrillic into latin for command execution.
+  /// Translators with a display-only table (e.g. GOST System A, with diacritics) must return ASCII output here
+  fn command_to_latin(&self, input: &String) -> String {
+    self.to_latin(input)
+  }
+
   /// ### to_cyrillic
   ///
   /// Converts a string which contains latin characters into a russian cyrillic string.
   /// Characters between quotes are escapes
   fn to_cyrillic(&self, input: &String) -> String;
+
+  /// ### transliteration_pairs
+  ///
+  /// Returns the pairs between each lowercase cyrillic letter and its latin transliteration.
+  /// Used to audit the round-trip stability of the translator; languages without a table return no pairs
+  #[cfg(test)]
+  fn transliteration_pairs(&self) -> Vec<(char, &'static str)> {
+    Vec::new()
+  }
 }
 
 /// ### new_translator
 ///
 /// instantiates a new Translator with the provided language,
 /// associating the correct conversion functions
+#[cfg(test)]
 pub fn new_translator(language: Language) -> Box<dyn Translator> {
+  new_gost_translator(language, GostSystem::B)
+}
+
+/// ### unstable_pairs
+///
+/// Audit the transliteration pairs of translator: returns the latin words of the pairs
+/// which are not idempotent through `to_cyrillic` and then `to_latin`.
+/// Panics if a pair doesn't match the output of `to_latin`, since the table is out of date
+#[cfg(test)]
+pub(crate) fn unstable_pairs(translator: &dyn Translator) -> Vec<&'static str> {
+  let mut unstable: Vec<&'static str> = Vec::new();
+  for (cyrillic, latin) in translator.transliteration_pairs().into_iter() {
+    assert_eq!(translator.to_latin(&cyrillic.to_string()), latin, "'{}' pair is out of date", cyrillic);
+    let word: String = String::from(latin);
+    if translator.to_latin(&translator.to_cyrillic(&word)) != word && !unstable.contains(&latin) {
+      unstable.push(latin);
+    }
+  }
+  unstable
+}
+
+/// ### new_gost_translator
+///
+/// instantiates a new Translator with the provided language,
+/// using the provided GOST 7.79-2000 system for the languages which support it
+pub fn new_gost_translator(language: Language, gost_system: GostSystem) -> Box<dyn Translator> {
   match language {
     Language::Belarusian => Box::new(lang::Belarusian {}),
     Language::Bulgarian => Box::new(lang::Bulgarian {}),
-    Language::Russian => Box::new(lang::Russian {}),
+    Language::Macedonian => Box::new(lang::Macedonian {}),
+    Language::Russian => Box::new(lang::Russian { gost_system }),
     Language::Serbian => Box::new(lang::Serbian {}),
     Language::Ukrainian => Box::new(lang::Ukrainian {}),
     Language::Nil => Box::new(lang::Nil {})
@@ -70,10 +115,109 @@ mod tests {
     //Just don't panic
     let _ = new_translator(Language::Belarusian);
     let _ = new_translator(Language::Bulgarian);
+    let _ = new_translator(Language::Macedonian);
     let _ = new_translator(Language::Russian);
     let _ = new_translator(Language::Serbian);
     let _ = new_translator(Language::Ukrainian);
     let _ = new_translator(Language::Nil);
+    let _ = new_gost_translator(Language::Russian, GostSystem::A);
+    let _ = new_gost_translator(Language::Serbian, GostSystem::A);
+  }
+
+  #[test]
+  fn test_translator_round_trip_belarusian() {
+    assert_round_trip(Language::Belarusian, &STABLE_COMMON);
+    assert_round_trip(Language::Belarusian, &["uniq", "history", "python3", "cargo build", "docker ps", "sync"]);
+    assert_round_trip_unstable(Language::Belarusian, &UNSTABLE_COMMON);
+    assert_round_trip_unstable(Language::Belarusian, &["yes"]);
+  }
+
+  #[test]
+  fn test_translator_round_trip_bulgarian() {
+    assert_round_trip(Language::Bulgarian, &STABLE_COMMON);
+    assert_round_trip(Language::Bulgarian, &["uniq", "history", "python3", "cargo build", "docker ps", "sync"]);
+    assert_round_trip_unstable(Language::Bulgarian, &UNSTABLE_COMMON);
+    assert_round_trip_unstable(Language::Bulgarian, &["yes"]);
+  }
+
+  #[test]
+  fn test_translator_round_trip_macedonian() {
+    assert_round_trip(Language::Macedonian, &STABLE_COMMON);
+    assert_round_trip(Language::Macedonian, &["cd /tmp", "cat file.txt", "history", "python3", "sync", "yes"]);
+    assert_round_trip(Language::Macedonian, &UNSTABLE_COMMON);
+  }
+
+  #[test]
+  fn test_translator_round_trip_russian() {
+    assert_round_trip(Language::Russian, &STABLE_COMMON);
+    assert_round_trip(Language::Russian, &["uniq", "history", "python3", "cargo build", "docker ps", "sync"]);
+    assert_round_trip_unstable(Language::Russian, &UNSTABLE_COMMON);
+    assert_round_trip_unstable(Language::Russian, &["yes"]);
+  }
+
+  #[test]
+  fn test_translator_round_trip_serbian() {
+    assert_round_trip(Language::Serbian, &STABLE_COMMON);
+    assert_round_trip(Language::Serbian, &["mkdir -p build"]);
+    assert_round_trip_unstable(Language::Serbian, &UNSTABLE_COMMON);
+    assert_round_trip_unstable(Language::Serbian, &["cd /tmp", "cat file.txt", "history", "python3", "sync", "yes"]);
+  }
+
+  #[test]
+  fn test_translator_round_trip_ukrainian() {
+    assert_round_trip(Language::Ukrainian, &STABLE_COMMON);
+    assert_round_trip(Language::Ukrainian, &["yes", "uniq", "history", "python3", "cargo build", "docker ps", "sync"]);
+    assert_round_trip_unstable(Language::Ukrainian, &UNSTABLE_COMMON);
+  }
+
+  #[test]
+  fn test_translator_round_trip_nil() {
+    assert_round_trip(Language::Nil, &STABLE_COMMON);
+    assert_round_trip(Language::Nil, &UNSTABLE_COMMON);
+  }
+
+  /// Commands which are round-trip stable for every language
+  const STABLE_COMMON: [&str; 17] = [
+    "ls -l", "echo hello", "grep -r foo .", "make", "top", "ps aux", "df -h", "du -hs", "uname -a",
+    "find . -name", "tar -xvf a.tar", "vim", "chmod 755 run", "kill -9", "tail -f", "sudo", "npm install"
+  ];
+
+  /// Commands which are not round-trip stable for any cyrillic language
+  const UNSTABLE_COMMON: [&str; 8] = [
+    "pwd", "rm -rf target", "git status", "whoami", "wc -l", "which ls", "wget", "why"
+  ];
+
+  /// ### round_trip_deviations
+  ///
+  /// Returns the inputs for which `to_latin(to_cyrillic(x)) != x`, described as `x -> cyrillic -> latin`
+  fn round_trip_deviations(language: Language, corpus: &[&str]) -> Vec<String> {
+    let translator: Box<dyn Translator> = new_translator(language);
+    let mut deviations: Vec<String> = Vec::new();
+    for input in corpus.iter() {
+      let input: String = String::from(*input);
+      let cyrillic: String = translator.to_cyrillic(&input);
+      let latin: String = translator.to_latin(&cyrillic);
+      if latin != input {
+        deviations.push(format!("{} -> {} -> {}", input, cyrillic, latin));
+      }
+    }
+    deviations
+  }
+
+  /// ### assert_round_trip
+  ///
+  /// Assert that all the commands in corpus are round-trip stable for language
+  fn assert_round_trip(language: Language, corpus: &[&str]) {
+    let deviations: Vec<String> = round_trip_deviations(language, corpus);
+    assert!(deviations.is_empty(), "{:?} is not round-trip stable for: {:?}", language, deviations);
+  }
+
+  /// ### assert_round_trip_unstable
+  ///
+  /// Assert that none of the commands in corpus is round-trip stable for language
+  fn assert_round_trip_unstable(language: Language, corpus: &[&str]) {
+    let deviations: Vec<String> = round_trip_deviations(language, corpus);
+    assert_eq!(deviations.len(), corpus.len(), "{:?} has become round-trip stable for some of: {:?}", language, corpus);
   }
 
 }
@@ -0,0 +1,121 @@
+//! ## Ansi
+//!
+//! `ansi` contains utilities to handle ANSI escape sequences
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// ### strip_ansi
+///
+/// Remove all the ANSI escape sequences (CSI, such as colors and cursor movements, OSC, DCS, ...) from text.
+/// Incomplete sequences at the end of text are removed too
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped: String = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != ESC {
+            stripped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            //Control sequence: parameters and intermediate bytes, then the final byte
+            Some('[') => {
+                for ch in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            //Strings (OSC, DCS, SOS, PM, APC): terminated by BEL or ST (ESC \)
+            Some(']') | Some('P') | Some('X') | Some('^') | Some('_') => {
+                while let Some(ch) = chars.next() {
+                    if ch == BEL {
+                        break;
+                    }
+                    if ch == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            //Other escapes: intermediate bytes, then the final byte (e.g. ESC 7, ESC ( B)
+            Some(ch) if ('\x20'..='\x2f').contains(&ch) => {
+                for ch in chars.by_ref() {
+                    if !('\x20'..='\x2f').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            Some(_) | None => {}
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_ansi_strip_plain() {
+        assert_eq!(strip_ansi(""), String::from(""));
+        assert_eq!(strip_ansi("hello world\n"), String::from("hello world\n"));
+        assert_eq!(strip_ansi("привет\tмир\r\n"), String::from("привет\tмир\r\n"));
+    }
+
+    #[test]
+    fn test_utils_ansi_strip_sgr() {
+        assert_eq!(strip_ansi("\x1b[31mred\x1b[0m"), String::from("red"));
+        assert_eq!(strip_ansi("\x1b[1;4;38;5;208mbold\x1b[m text"), String::from("bold text"));
+        assert_eq!(strip_ansi("\x1b[38;2;255;0;0mtrue\x1b[39m color"), String::from("true color"));
+    }
+
+    #[test]
+    fn test_utils_ansi_strip_cursor() {
+        assert_eq!(strip_ansi("\x1b[H\x1b[2Jclear"), String::from("clear"));
+        assert_eq!(strip_ansi("a\x1b[2Ab\x1b[10;20Hc\x1b[Kd"), String::from("abcd"));
+        assert_eq!(strip_ansi("\x1b[?25lhidden\x1b[?25h"), String::from("hidden"));
+        assert_eq!(strip_ansi("\x1b7saved\x1b8"), String::from("saved"));
+        assert_eq!(strip_ansi("\x1b(Bcharset"), String::from("charset"));
+    }
+
+    #[test]
+    fn test_utils_ansi_strip_osc() {
+        //Terminated by BEL
+        assert_eq!(strip_ansi("\x1b]0;window title\x07prompt$ "), String::from("prompt$ "));
+        //Terminated by ST
+        assert_eq!(strip_ansi("\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x1b\\"), String::from("link"));
+        //DCS
+        assert_eq!(strip_ansi("\x1bPq#0;2;0;0;0\x1b\\text"), String::from("text"));
+    }
+
+    #[test]
+    fn test_utils_ansi_strip_incomplete() {
+        assert_eq!(strip_ansi("text\x1b"), String::from("text"));
+        assert_eq!(strip_ansi("text\x1b[31"), String::from("text"));
+        assert_eq!(strip_ansi("text\x1b]0;title"), String::from("text"));
+    }
+}
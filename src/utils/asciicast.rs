@@ -0,0 +1,97 @@
+//! ## Asciicast
+//!
+//! `asciicast` contains the writer used to record the session in asciicast v2 format
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// ## Asciicast
+///
+/// Asciicast writes the terminal output to a file in asciicast v2 format (asciinema).
+/// The header is written on creation, then each write is recorded as an output event,
+/// with the time elapsed since the start of the recording
+pub struct Asciicast {
+    file: File,
+    start: Instant,
+}
+
+impl Asciicast {
+    /// ### create
+    ///
+    /// Create a new recording at path, writing the header with the terminal size
+    pub fn create(path: &Path, width: usize, height: usize) -> io::Result<Asciicast> {
+        let mut file: File = File::create(path)?;
+        let timestamp: u64 = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(t) => t.as_secs(),
+            Err(_) => 0,
+        };
+        writeln!(
+            file,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            width, height, timestamp
+        )?;
+        Ok(Asciicast {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    /// ### write_output
+    ///
+    /// Record data written to the terminal as an output event.
+    /// Line feeds are recorded as the terminal outputs them (CRLF)
+    pub fn write_output(&mut self, data: &str) -> io::Result<()> {
+        let elapsed: f64 = self.start.elapsed().as_secs_f64();
+        let data: String = data.replace("\r\n", "\n").replace('\n', "\r\n");
+        writeln!(self.file, "[{:.6}, \"o\", {}]", elapsed, serde_json::to_string(&data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_asciicast_recording() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let path: std::path::PathBuf = tmpdir.path().join("session.cast");
+        let mut recording: Asciicast = Asciicast::create(path.as_path(), 80, 24).unwrap();
+        assert!(recording.write_output("привет\n").is_ok());
+        assert!(recording.write_output("\x1b[31m\"error\"\x1b[0m").is_ok());
+        let content: String = std::fs::read_to_string(path.as_path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        //Header
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": "));
+        assert!(lines[0].ends_with("}"));
+        //Events
+        assert!(lines[1].starts_with("[0."));
+        assert!(lines[1].ends_with(", \"o\", \"привет\\r\\n\"]"));
+        assert!(lines[2].ends_with(", \"o\", \"\\u001b[31m\\\"error\\\"\\u001b[0m\"]"));
+    }
+}
@@ -30,6 +30,69 @@ pub fn chars_to_string(buff: &Vec<char>) -> String {
     buff.iter().collect()
 }
 
+/// ### is_invisible_control_char
+/// 
+/// Returns whether the provided character is a zero-width or bidirectional control character,
+/// which can alter the way the input is displayed without being visible
+pub fn is_invisible_control_char(ch: char) -> bool {
+    match ch {
+        '\u{200B}'..='\u{200F}' => true, //Zero width space, ZWNJ, ZWJ, LRM, RLM
+        '\u{202A}'..='\u{202E}' => true, //Bidirectional embeddings and overrides
+        '\u{2060}' => true, //Word joiner
+        '\u{2066}'..='\u{2069}' => true, //Bidirectional isolates
+        '\u{FEFF}' => true, //Zero width no-break space
+        _ => false,
+    }
+}
+
+/// ### is_blank
+///
+/// Returns whether the provided text is made of whitespaces and invisible control characters only
+pub fn is_blank(text: &str) -> bool {
+    text.chars().all(|ch| ch.is_whitespace() || is_invisible_control_char(ch))
+}
+
+/// ### is_word_char
+///
+/// Returns whether the provided character is part of a word.
+/// If word_chars is None, words are separated by whitespaces only; otherwise words are made of alphanumeric characters and of the characters in word_chars
+pub fn is_word_char(ch: char, word_chars: Option<&str>) -> bool {
+    match word_chars {
+        None => !ch.is_whitespace(),
+        Some(word_chars) => ch.is_alphanumeric() || word_chars.contains(ch),
+    }
+}
+
+/// ### prev_word_boundary
+///
+/// Returns the index of the beginning of the word before cursor
+pub fn prev_word_boundary(buff: &[char], cursor: usize, word_chars: Option<&str>) -> usize {
+    let mut index: usize = cursor.min(buff.len());
+    //Skip separators, then the word
+    while index > 0 && !is_word_char(buff[index - 1], word_chars) {
+        index -= 1;
+    }
+    while index > 0 && is_word_char(buff[index - 1], word_chars) {
+        index -= 1;
+    }
+    index
+}
+
+/// ### next_word_boundary
+///
+/// Returns the index of the end of the word after cursor
+pub fn next_word_boundary(buff: &[char], cursor: usize, word_chars: Option<&str>) -> usize {
+    let mut index: usize = cursor.min(buff.len());
+    //Skip separators, then the word
+    while index < buff.len() && !is_word_char(buff[index], word_chars) {
+        index += 1;
+    }
+    while index < buff.len() && is_word_char(buff[index], word_chars) {
+        index += 1;
+    }
+    index
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +101,60 @@ mod tests {
     fn test_utils_buffer_chars_to_string() {
         assert_eq!(chars_to_string(&vec!['a', 'b', 'c', 'л']), String::from("abcл"));
     }
+
+    #[test]
+    fn test_utils_buffer_is_invisible_control_char() {
+        assert!(is_invisible_control_char('\u{200B}'));
+        assert!(is_invisible_control_char('\u{200D}'));
+        assert!(is_invisible_control_char('\u{202E}'));
+        assert!(is_invisible_control_char('\u{2066}'));
+        assert!(is_invisible_control_char('\u{FEFF}'));
+        assert!(!is_invisible_control_char('a'));
+        assert!(!is_invisible_control_char('л'));
+        assert!(!is_invisible_control_char(' '));
+    }
+
+    #[test]
+    fn test_utils_buffer_is_blank() {
+        assert!(is_blank(""));
+        assert!(is_blank(" \t\n"));
+        assert!(is_blank("\u{200B}\u{FEFF} \n"));
+        assert!(!is_blank(" ls\n"));
+        assert!(!is_blank("\"\"\n"));
+    }
+
+    #[test]
+    fn test_utils_buffer_word_boundaries() {
+        let buff: Vec<char> = "ls /usr/local/bin".chars().collect();
+        //Whitespace separated words
+        assert_eq!(prev_word_boundary(&buff, 17, None), 3);
+        assert_eq!(prev_word_boundary(&buff, 3, None), 0);
+        assert_eq!(next_word_boundary(&buff, 0, None), 2);
+        assert_eq!(next_word_boundary(&buff, 2, None), 17);
+        //Path separators break words
+        let word_chars: Option<&str> = Some("-_");
+        assert_eq!(prev_word_boundary(&buff, 17, word_chars), 14);
+        assert_eq!(prev_word_boundary(&buff, 14, word_chars), 8);
+        assert_eq!(prev_word_boundary(&buff, 8, word_chars), 4);
+        assert_eq!(prev_word_boundary(&buff, 4, word_chars), 0);
+        assert_eq!(prev_word_boundary(&buff, 0, word_chars), 0);
+        assert_eq!(next_word_boundary(&buff, 0, word_chars), 2);
+        assert_eq!(next_word_boundary(&buff, 2, word_chars), 7);
+        assert_eq!(next_word_boundary(&buff, 7, word_chars), 13);
+        assert_eq!(next_word_boundary(&buff, 13, word_chars), 17);
+        assert_eq!(next_word_boundary(&buff, 17, word_chars), 17);
+        //Option assignments
+        let buff: Vec<char> = "--opt=value".chars().collect();
+        assert_eq!(next_word_boundary(&buff, 0, word_chars), 5);
+        assert_eq!(next_word_boundary(&buff, 5, word_chars), 11);
+        assert_eq!(prev_word_boundary(&buff, 11, word_chars), 6);
+        assert_eq!(prev_word_boundary(&buff, 6, word_chars), 0);
+        assert_eq!(prev_word_boundary(&buff, 11, None), 0);
+        //Without '-', the dashes are separators
+        assert_eq!(next_word_boundary(&buff, 0, Some("")), 5);
+        assert_eq!(prev_word_boundary(&buff, 5, Some("")), 2);
+        //Cyrillic letters are alphanumeric
+        let buff: Vec<char> = "чд /тмп".chars().collect();
+        assert_eq!(prev_word_boundary(&buff, 7, word_chars), 4);
+    }
 }
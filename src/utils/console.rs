@@ -25,10 +25,18 @@
 extern crate nix;
 extern crate termios;
 
+use super::asciicast::Asciicast;
+
 use std::io::{self, Read, Write};
 use std::os::unix::io::RawFd;
+use std::sync::Mutex;
 
 const STDIN_FILENO: RawFd = 0;
+const STDOUT_FILENO: RawFd = 1;
+
+lazy_static! {
+    static ref RECORDING: Mutex<Option<Asciicast>> = Mutex::new(None);
+}
 
 /// ## InputEvent
 /// 
@@ -43,7 +51,9 @@ pub enum InputEvent {
     ArrowUp,
     ArrowLeft,
     ArrowRight,
-    ArrowDown
+    ArrowDown,
+    WordLeft,
+    WordRight
 }
 
 
@@ -70,11 +80,90 @@ pub fn carriage_return() {
     print(String::from("\r"));
 }
 
-/// ### clear
+/// ### clear_seq
+/// 
+/// Returns the escape sequence to erase the screen
+pub fn clear_seq() -> String {
+    String::from("\x1b[H\x1b[2J")
+}
+
+/// ### purge_seq
+/// 
+/// Returns the escape sequence to erase both the screen and the scrollback
+pub fn purge_seq() -> String {
+    String::from("\x1b[H\x1b[2J\x1b[3J")
+}
+
+/// ### scroll_up_seq
+/// 
+/// Returns the escape sequence to scroll the screen up by `rows` (the content is moved to the scrollback), then move the cursor to the top
+pub fn scroll_up_seq(rows: usize) -> String {
+    format!("\x1b[{}S\x1b[H", rows)
+}
+
+/// ### save_cursor
+/// 
+/// Save current cursor position
+pub fn save_cursor() {
+    print(String::from("\x1b7"));
+}
+
+/// ### restore_cursor
+/// 
+/// Restore the cursor position previously saved with `save_cursor`
+pub fn restore_cursor() {
+    print(String::from("\x1b8"));
+}
+
+/// ### cursor_to_seq
+/// 
+/// Returns the escape sequence to move the cursor at the provided position (1-indexed)
+pub fn cursor_to_seq(row: usize, col: usize) -> String {
+    format!("\x1b[{};{}H", row, col)
+}
+
+/// ### cursor_to_col_seq
 /// 
-/// Clear console
-pub fn clear() {
-    print(String::from("\x1b[H\x1b[2J"));
+/// Returns the escape sequence to move the cursor at the provided column of the current row (1-indexed)
+pub fn cursor_to_col_seq(col: usize) -> String {
+    format!("\x1b[{}G", col)
+}
+
+/// ### clear_line_seq
+/// 
+/// Returns the escape sequence to clear the entire current line
+pub fn clear_line_seq() -> String {
+    String::from("\x1b[2K")
+}
+
+/// ### scroll_region_seq
+/// 
+/// Returns the escape sequence to restrict scrolling between the rows `top` and `bottom` (1-indexed)
+pub fn scroll_region_seq(top: usize, bottom: usize) -> String {
+    format!("\x1b[{};{}r", top, bottom)
+}
+
+/// ### reset_scroll_region_seq
+/// 
+/// Returns the escape sequence to reset the scroll region to the entire screen
+pub fn reset_scroll_region_seq() -> String {
+    String::from("\x1b[r")
+}
+
+/// ### term_size
+/// 
+/// Get terminal size as (columns, rows). Returns None if stdout is not a terminal
+pub fn term_size() -> Option<(usize, usize)> {
+    let mut winsize: nix::libc::winsize = nix::libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0
+    };
+    match unsafe { nix::libc::ioctl(STDOUT_FILENO, nix::libc::TIOCGWINSZ, &mut winsize) } {
+        0 if winsize.ws_row > 0 && winsize.ws_col > 0 => Some((winsize.ws_col as usize, winsize.ws_row as usize)),
+        _ => None
+    }
 }
 
 /// ### read
@@ -108,9 +197,13 @@ fn to_input_event(ready_fn: &dyn Fn() -> bool, read_fn: &dyn Fn(&mut [u8]) -> io
                 10 => InputEvent::Enter,
                 13 => InputEvent::CarriageReturn,
                 0..=26 => InputEvent::Ctrl(key), //CTRL key (exclude 8, 10, 13)
-                27 => { //Is Arrow Key
-                    //Read twice
+                27 => { //Is Arrow Key or Alt + key
                     let _ = read_fn(&mut buf);
+                    match *buf.first().unwrap_or(&0) as char {
+                        'b' => return Some(InputEvent::WordLeft), //Alt + B
+                        'f' => return Some(InputEvent::WordRight), //Alt + F
+                        _ => {}
+                    }
                     let _ = read_fn(&mut buf);
                     let direction: char = *buf.get(0).unwrap_or(&0) as char;
                     match direction {
@@ -118,6 +211,16 @@ fn to_input_event(ready_fn: &dyn Fn() -> bool, read_fn: &dyn Fn(&mut [u8]) -> io
                         'B' => InputEvent::ArrowDown,
                         'C' => InputEvent::ArrowRight,
                         'D' => InputEvent::ArrowLeft,
+                        '1' => { //Modified arrow (e.g. Ctrl + Arrow is '1;5C')
+                            let _ = read_fn(&mut buf);
+                            let _ = read_fn(&mut buf);
+                            let _ = read_fn(&mut buf);
+                            match *buf.first().unwrap_or(&0) as char {
+                                'C' => InputEvent::WordRight,
+                                'D' => InputEvent::WordLeft,
+                                _ => return None //Unknown event
+                            }
+                        },
                         _ => return None //Unknown event
                     }
                 },
@@ -175,6 +278,7 @@ pub fn rewrite(row: String, len: usize) {
 pub fn print(row: String) {
     print!("{}", row);
     let _ = io::stdout().flush();
+    record(row.as_str());
 }
 
 /// ### println
@@ -182,6 +286,35 @@ pub fn print(row: String) {
 /// Print line and go to new line
 pub fn println(row: String) {
     println!("{}", row);
+    record(format!("{}\n", row).as_str());
+}
+
+/// ### eprintln
+/// 
+/// Print line to stderr and go to new line
+pub fn eprintln(row: String) {
+    eprintln!("{}", row);
+    record(format!("{}\n", row).as_str());
+}
+
+/// ### start_recording
+/// 
+/// Record everything printed to the console from now on
+pub fn start_recording(recording: Asciicast) {
+    if let Ok(mut current) = RECORDING.lock() {
+        *current = Some(recording);
+    }
+}
+
+/// ### record
+/// 
+/// Write output to recording, if any
+fn record(out: &str) {
+    if let Ok(mut recording) = RECORDING.lock() {
+        if let Some(recording) = recording.as_mut() {
+            let _ = recording.write_output(out);
+        }
+    }
 }
 
 /// ### input_ready
@@ -217,6 +350,8 @@ fn prepare_termios() {
     let _ = termios::tcgetattr(STDIN_FILENO, &mut term);
     term.c_lflag &= !termios::ICANON;
     term.c_lflag &= !termios::ECHO;
+    //Disable flow control, so that CTRL + S and CTRL + Q are read
+    term.c_iflag &= !termios::IXON;
     let _ = termios::tcsetattr(STDIN_FILENO, termios::TCSANOW, &term);
 }
 
@@ -228,6 +363,7 @@ fn reset_termios() {
     let _ = termios::tcgetattr(STDIN_FILENO, &mut term);
     term.c_lflag |= termios::ICANON;
     term.c_lflag &= termios::ECHO;
+    term.c_iflag |= termios::IXON;
     let _ = termios::tcsetattr(STDIN_FILENO, termios::TCSADRAIN, &term);
 }
 
@@ -240,6 +376,8 @@ pub fn input_event_to_string(ev: InputEvent) -> String {
         InputEvent::ArrowLeft => String::from("\x1b[D"),
         InputEvent::ArrowRight => String::from("\x1b[C"),
         InputEvent::ArrowUp => String::from("\x1b[A"),
+        InputEvent::WordLeft => String::from("\x1bb"),
+        InputEvent::WordRight => String::from("\x1bf"),
         InputEvent::Backspace => String::from("\x7F"),
         InputEvent::CarriageReturn => String::from("\x0D"),
         InputEvent::Ctrl(sig) => {
@@ -272,7 +410,32 @@ mod tests {
 
     #[test]
     fn test_utils_console_clear() {
-        clear();
+        assert_eq!(clear_seq(), String::from("\x1b[H\x1b[2J"));
+        assert_eq!(purge_seq(), String::from("\x1b[H\x1b[2J\x1b[3J"));
+        assert_eq!(scroll_up_seq(24), String::from("\x1b[24S\x1b[H"));
+    }
+
+    #[test]
+    fn test_utils_console_cursor_seq() {
+        save_cursor();
+        restore_cursor();
+        assert_eq!(cursor_to_seq(24, 1), String::from("\x1b[24;1H"));
+        assert_eq!(clear_line_seq(), String::from("\x1b[2K"));
+    }
+
+    #[test]
+    fn test_utils_console_scroll_region() {
+        assert_eq!(scroll_region_seq(1, 23), String::from("\x1b[1;23r"));
+        assert_eq!(reset_scroll_region_seq(), String::from("\x1b[r"));
+    }
+
+    #[test]
+    fn test_utils_console_term_size() {
+        //Stdout may not be a terminal while testing
+        if let Some((cols, rows)) = term_size() {
+            assert!(cols > 0);
+            assert!(rows > 0);
+        }
     }
 
     #[test]
@@ -388,6 +551,48 @@ mod tests {
             Ok(())
         };
         assert!(to_input_event(&ready_fn, &read_fn).is_none());
+        //Alt + B / Alt + F
+        let read_fn = |buff: &mut [u8]| -> io::Result<()> {
+            match buff[0] {
+                27 => buff[0] = b'b',
+                _ => buff[0] = 27
+            }
+            Ok(())
+        };
+        assert_eq!(to_input_event(&ready_fn, &read_fn).unwrap(), InputEvent::WordLeft);
+        let read_fn = |buff: &mut [u8]| -> io::Result<()> {
+            match buff[0] {
+                27 => buff[0] = b'f',
+                _ => buff[0] = 27
+            }
+            Ok(())
+        };
+        assert_eq!(to_input_event(&ready_fn, &read_fn).unwrap(), InputEvent::WordRight);
+        //Ctrl + Arrow ('\x1b[1;5C')
+        let read_fn = |buff: &mut [u8]| -> io::Result<()> {
+            match buff[0] {
+                27 => buff[0] = 91,
+                91 => buff[0] = b'1',
+                49 => buff[0] = b';',
+                59 => buff[0] = b'5',
+                53 => buff[0] = b'C',
+                _ => buff[0] = 27
+            }
+            Ok(())
+        };
+        assert_eq!(to_input_event(&ready_fn, &read_fn).unwrap(), InputEvent::WordRight);
+        let read_fn = |buff: &mut [u8]| -> io::Result<()> {
+            match buff[0] {
+                27 => buff[0] = 91,
+                91 => buff[0] = b'1',
+                49 => buff[0] = b';',
+                59 => buff[0] = b'5',
+                53 => buff[0] = b'D',
+                _ => buff[0] = 27
+            }
+            Ok(())
+        };
+        assert_eq!(to_input_event(&ready_fn, &read_fn).unwrap(), InputEvent::WordLeft);
         //Test read - ASCII key
         let read_fn = |buff: &mut [u8]| -> io::Result<()> {
             buff[0] = 'A' as u8;
@@ -436,6 +641,8 @@ mod tests {
         assert_eq!(input_event_to_string(InputEvent::ArrowLeft), String::from("\x1b[D"));
         assert_eq!(input_event_to_string(InputEvent::ArrowRight), String::from("\x1b[C"));
         assert_eq!(input_event_to_string(InputEvent::ArrowUp), String::from("\x1b[A"));
+        assert_eq!(input_event_to_string(InputEvent::WordLeft), String::from("\x1bb"));
+        assert_eq!(input_event_to_string(InputEvent::WordRight), String::from("\x1bf"));
         assert_eq!(input_event_to_string(InputEvent::Backspace), String::from("\x7F"));
         assert_eq!(input_event_to_string(InputEvent::CarriageReturn), String::from("\x0D"));
         assert_eq!(input_event_to_string(InputEvent::Ctrl(3)), String::from("\x03"));
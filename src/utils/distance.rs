@@ -0,0 +1,68 @@
+//! ## Distance
+//!
+//! `distance` contains functions to compute the edit distance between strings
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+/// ### levenshtein
+///
+/// Computes the Levenshtein distance (insertions, deletions and substitutions) between two strings
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    //Keep only the previous row of the matrix
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev_diag: usize = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost: usize = match ca == *cb {
+                true => 0,
+                false => 1,
+            };
+            let value: usize = std::cmp::min(std::cmp::min(row[j] + 1, row[j + 1] + 1), prev_diag + cost);
+            prev_diag = row[j + 1];
+            row[j + 1] = value;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_distance_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("ls", "ls"), 0);
+        assert_eq!(levenshtein("", "ls"), 2);
+        assert_eq!(levenshtein("ls", ""), 2);
+        assert_eq!(levenshtein("sl", "ls"), 2);
+        assert_eq!(levenshtein("gerp", "grep"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("pyhton", "python"), 2);
+        //Multi-byte chars count as one
+        assert_eq!(levenshtein("привет", "привед"), 1);
+    }
+}
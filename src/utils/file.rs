@@ -58,6 +58,25 @@ pub fn write_lines<P>(filename: P, lines: Vec<String>) -> io::Result<()> where P
     }
 }
 
+/// ### append_lines
+/// 
+/// Append lines to file; the file is created if it doesn't exist
+pub fn append_lines<P>(filename: P, lines: Vec<String>) -> io::Result<()> where P: AsRef<Path> {
+    let mut f: File = OpenOptions::new().create(true).append(true).open(filename)?;
+    for line in lines.iter() {
+        writeln!(f, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// ### read_first_line
+/// 
+/// Read the first line of file; None if the file is empty
+pub fn read_first_line<P>(filename: P) -> io::Result<Option<String>> where P: AsRef<Path> {
+    let file: File = File::open(filename)?;
+    io::BufReader::new(file).lines().next().transpose()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,6 +117,26 @@ mod tests {
         assert!(write_lines(Path::new("/sample.file1231234.txt"), in_lines).is_err());
     }
 
+    #[test]
+    fn test_utils_file_append_lines() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let path = tmpdir.path().join("history");
+        //File is created
+        assert!(append_lines(path.as_path(), vec![String::from("row 1")]).is_ok());
+        assert!(append_lines(path.as_path(), vec![String::from("row 2"), String::from("row 3")]).is_ok());
+        assert_eq!(read_lines(path.as_path()).unwrap(), vec![String::from("row 1"), String::from("row 2"), String::from("row 3")]);
+        assert!(append_lines(Path::new("/this/does/not/exist.txt"), vec![String::from("row 1")]).is_err());
+    }
+
+    #[test]
+    fn test_utils_file_read_first_line() {
+        let sample_file: tempfile::NamedTempFile = write_sample_file();
+        assert_eq!(read_first_line(sample_file.path()).unwrap(), Some(String::from("Lorem ipsum dolor sit amet, consectetur adipiscing elit.")));
+        let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        assert_eq!(read_first_line(tmpfile.path()).unwrap(), None);
+        assert!(read_first_line(Path::new("/sample.file123123.txt")).is_err());
+    }
+
     /// ### write_sample_file
     /// Write a sample file
     fn write_sample_file() -> tempfile::NamedTempFile {
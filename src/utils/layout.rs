@@ -0,0 +1,94 @@
+//! ## Layout
+//!
+//! `layout` contains the keyboard layout remap table, used to fix commands typed with the wrong keyboard layout
+
+
+/*
+*
+*   Copyright (C) 2020 Christian Visintin - christian.visintin1997@gmail.com
+*
+* 	This file is part of "Pyc"
+*
+*   Pyc is free software: you can redistribute it and/or modify
+*   it under the terms of the GNU General Public License as published by
+*   the Free Software Foundation, either version 3 of the License, or
+*   (at your option) any later version.
+*
+*   Pyc is distributed in the hope that it will be useful,
+*   but WITHOUT ANY WARRANTY; without even the implied warranty of
+*   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+*   GNU General Public License for more details.
+*
+*   You should have received a copy of the GNU General Public License
+*   along with Pyc.  If not, see <http://www.gnu.org/licenses/>.
+*
+*/
+
+/// Russian (ЙЦУКЕН) keys and the characters they produce on the latin (QWERTY) layout
+const LAYOUT_KEYS: [(char, char); 66] = [
+    ('й', 'q'), ('ц', 'w'), ('у', 'e'), ('к', 'r'), ('е', 't'), ('н', 'y'), ('г', 'u'), ('ш', 'i'), ('щ', 'o'), ('з', 'p'), ('х', '['), ('ъ', ']'),
+    ('ф', 'a'), ('ы', 's'), ('в', 'd'), ('а', 'f'), ('п', 'g'), ('р', 'h'), ('о', 'j'), ('л', 'k'), ('д', 'l'), ('ж', ';'), ('э', '\''),
+    ('я', 'z'), ('ч', 'x'), ('с', 'c'), ('м', 'v'), ('и', 'b'), ('т', 'n'), ('ь', 'm'), ('б', ','), ('ю', '.'), ('ё', '`'),
+    ('Й', 'Q'), ('Ц', 'W'), ('У', 'E'), ('К', 'R'), ('Е', 'T'), ('Н', 'Y'), ('Г', 'U'), ('Ш', 'I'), ('Щ', 'O'), ('З', 'P'), ('Х', '{'), ('Ъ', '}'),
+    ('Ф', 'A'), ('Ы', 'S'), ('В', 'D'), ('А', 'F'), ('П', 'G'), ('Р', 'H'), ('О', 'J'), ('Л', 'K'), ('Д', 'L'), ('Ж', ':'), ('Э', '"'),
+    ('Я', 'Z'), ('Ч', 'X'), ('С', 'C'), ('М', 'V'), ('И', 'B'), ('Т', 'N'), ('Ь', 'M'), ('Б', '<'), ('Ю', '>'), ('Ё', '~'),
+];
+
+/// ### switch_layout
+///
+/// Returns the text which would have been typed with the same keys on the other keyboard layout.
+/// Cyrillic characters are converted to the latin layout and latin letters to the cyrillic layout;
+/// any other character is kept as it is
+pub fn switch_layout(input: &str) -> String {
+    input
+        .chars()
+        .map(|ch| {
+            for (cyrillic, latin) in LAYOUT_KEYS.iter() {
+                if ch == *cyrillic {
+                    return *latin;
+                }
+                if ch == *latin && ch.is_ascii_alphabetic() {
+                    return *cyrillic;
+                }
+            }
+            ch
+        })
+        .collect()
+}
+
+/// ### is_cyrillic
+///
+/// Returns whether the text contains at least one character which is typed with the cyrillic layout
+pub fn is_cyrillic(input: &str) -> bool {
+    input.chars().any(|ch| LAYOUT_KEYS.iter().any(|(cyrillic, _)| ch == *cyrillic))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_layout_switch_layout() {
+        //Commands typed with the cyrillic layout
+        assert_eq!(switch_layout("ды -дф"), String::from("ls -la"));
+        assert_eq!(switch_layout("зцв"), String::from("pwd"));
+        assert_eq!(switch_layout("пше ыефегы"), String::from("git status"));
+        assert_eq!(switch_layout("сфе ^ЕЩВЩ"), String::from("cat ^TODO"));
+        assert_eq!(switch_layout("ёўё"), String::from("`ў`"));
+        //Commands typed with the latin layout
+        assert_eq!(switch_layout("ls"), String::from("ды"));
+        assert_eq!(switch_layout("ghbdtn"), String::from("привет"));
+        //Punctuation is kept
+        assert_eq!(switch_layout("./run.sh"), String::from("./кгт.ыр"));
+        assert_eq!(switch_layout(""), String::new());
+    }
+
+    #[test]
+    fn test_utils_layout_is_cyrillic() {
+        assert!(is_cyrillic("ды"));
+        assert!(is_cyrillic("lы"));
+        assert!(!is_cyrillic("ls -la"));
+        assert!(!is_cyrillic(""));
+    }
+}
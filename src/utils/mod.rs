@@ -23,6 +23,10 @@
 *
 */
 
+pub mod ansi;
+pub mod asciicast;
 pub mod buffer;
 pub mod console;
+pub mod distance;
 pub mod file;
+pub mod layout;